@@ -0,0 +1,15 @@
+CREATE TABLE `import_journal` (
+	`id` integer PRIMARY KEY NOT NULL,
+	`export_id` integer NOT NULL,
+	`archive_path` text NOT NULL,
+	`entry_name` text NOT NULL,
+	`thread_key` text NOT NULL,
+	`conversation_id` integer NOT NULL,
+	`messages_done` integer DEFAULT 0 NOT NULL,
+	`completed` integer DEFAULT false NOT NULL,
+	FOREIGN KEY (`export_id`) REFERENCES `export`(`id`) ON UPDATE no action ON DELETE cascade,
+	FOREIGN KEY (`conversation_id`) REFERENCES `conversation`(`id`) ON UPDATE no action ON DELETE cascade
+);
+--> statement-breakpoint
+CREATE UNIQUE INDEX `idx_import_journal_entry` ON `import_journal` (`export_id`,`archive_path`,`entry_name`);--> statement-breakpoint
+ALTER TABLE `export` ADD `status` text DEFAULT 'complete' NOT NULL;
\ No newline at end of file
@@ -0,0 +1,2 @@
+ALTER TABLE `message_audio` ADD `length_ms` integer;--> statement-breakpoint
+ALTER TABLE `message_audio` ADD `codec` text;
\ No newline at end of file
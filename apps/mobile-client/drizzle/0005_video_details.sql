@@ -0,0 +1,4 @@
+ALTER TABLE `message_video` ADD `length_ms` integer;--> statement-breakpoint
+ALTER TABLE `message_video` ADD `width` integer;--> statement-breakpoint
+ALTER TABLE `message_video` ADD `height` integer;--> statement-breakpoint
+ALTER TABLE `message_video` ADD `codec` text;
\ No newline at end of file
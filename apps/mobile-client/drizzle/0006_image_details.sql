@@ -0,0 +1,8 @@
+ALTER TABLE `message_image` ADD `format` text;--> statement-breakpoint
+ALTER TABLE `message_image` ADD `width` integer;--> statement-breakpoint
+ALTER TABLE `message_image` ADD `height` integer;--> statement-breakpoint
+ALTER TABLE `message_image` ADD `taken_at` text;--> statement-breakpoint
+ALTER TABLE `message_gif` ADD `width` integer;--> statement-breakpoint
+ALTER TABLE `message_gif` ADD `height` integer;--> statement-breakpoint
+ALTER TABLE `message_gif` ADD `frame_count` integer;--> statement-breakpoint
+ALTER TABLE `message_gif` ADD `length_ms` integer;
\ No newline at end of file
@@ -0,0 +1,4 @@
+ALTER TABLE `message_image` ADD `stored_path` text;--> statement-breakpoint
+ALTER TABLE `message_video` ADD `stored_path` text;--> statement-breakpoint
+ALTER TABLE `message_gif` ADD `stored_path` text;--> statement-breakpoint
+ALTER TABLE `message_audio` ADD `stored_path` text;
\ No newline at end of file
@@ -0,0 +1,8 @@
+CREATE TABLE `message_call` (
+	`id` integer PRIMARY KEY NOT NULL,
+	`message_id` integer NOT NULL,
+	`duration_seconds` integer DEFAULT 0 NOT NULL,
+	`missed` integer DEFAULT false NOT NULL,
+	`call_type` text DEFAULT 'audio' NOT NULL,
+	FOREIGN KEY (`message_id`) REFERENCES `message`(`id`) ON UPDATE no action ON DELETE cascade
+);
\ No newline at end of file
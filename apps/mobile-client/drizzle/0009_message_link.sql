@@ -0,0 +1,8 @@
+CREATE TABLE `message_link` (
+	`id` integer PRIMARY KEY NOT NULL,
+	`message_id` integer NOT NULL,
+	`url` text NOT NULL,
+	`share_text` text,
+	`domain` text,
+	FOREIGN KEY (`message_id`) REFERENCES `message`(`id`) ON UPDATE no action ON DELETE cascade
+);
\ No newline at end of file
@@ -0,0 +1,12 @@
+CREATE TABLE `message_sticker` (
+	`id` integer PRIMARY KEY NOT NULL,
+	`message_id` integer NOT NULL,
+	`sticker_uri` text,
+	`format` text,
+	`width` integer,
+	`height` integer,
+	`frame_count` integer,
+	`is_like` integer DEFAULT false NOT NULL,
+	`stored_path` text,
+	FOREIGN KEY (`message_id`) REFERENCES `message`(`id`) ON UPDATE no action ON DELETE cascade
+);
\ No newline at end of file
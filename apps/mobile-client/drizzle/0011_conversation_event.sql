@@ -0,0 +1,9 @@
+CREATE TABLE `conversation_event` (
+	`id` integer PRIMARY KEY NOT NULL,
+	`message_id` integer NOT NULL,
+	`kind` text NOT NULL,
+	`subject_name` text,
+	`value` text,
+	`raw_text` text,
+	FOREIGN KEY (`message_id`) REFERENCES `message`(`id`) ON UPDATE no action ON DELETE cascade
+);
\ No newline at end of file
@@ -0,0 +1,10 @@
+ALTER TABLE `person` ADD `is_participant` integer DEFAULT false NOT NULL;--> statement-breakpoint
+CREATE TABLE `person_membership` (
+	`id` integer PRIMARY KEY NOT NULL,
+	`person_id` integer NOT NULL,
+	`joined_at` integer,
+	`left_at` integer,
+	FOREIGN KEY (`person_id`) REFERENCES `person`(`id`) ON UPDATE no action ON DELETE cascade
+);
+--> statement-breakpoint
+CREATE INDEX `idx_person_membership_person` ON `person_membership` (`person_id`);
\ No newline at end of file
@@ -0,0 +1,4 @@
+ALTER TABLE `conversation` ADD `platform_thread_id` text;--> statement-breakpoint
+ALTER TABLE `conversation` ADD `owner_left` integer DEFAULT false NOT NULL;--> statement-breakpoint
+ALTER TABLE `conversation` ADD `invite_link` text;--> statement-breakpoint
+ALTER TABLE `conversation` ADD `archive_category` text;
\ No newline at end of file
@@ -0,0 +1,2 @@
+ALTER TABLE `message` ADD `reply_to_message_id` integer REFERENCES `message`(`id`) ON UPDATE no action ON DELETE set null;--> statement-breakpoint
+CREATE INDEX `idx_message_reply_to` ON `message` (`reply_to_message_id`);
\ No newline at end of file
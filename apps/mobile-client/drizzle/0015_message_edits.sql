@@ -0,0 +1,12 @@
+ALTER TABLE `message` ADD `edited` integer DEFAULT false NOT NULL;--> statement-breakpoint
+ALTER TABLE `message` ADD `edit_count` integer DEFAULT 0 NOT NULL;--> statement-breakpoint
+ALTER TABLE `message` ADD `unsent_reason` text;--> statement-breakpoint
+CREATE TABLE `message_edit` (
+	`id` integer PRIMARY KEY NOT NULL,
+	`message_id` integer NOT NULL,
+	`previous_text` text NOT NULL,
+	`sent_at` integer,
+	FOREIGN KEY (`message_id`) REFERENCES `message`(`id`) ON UPDATE no action ON DELETE cascade
+);
+--> statement-breakpoint
+CREATE INDEX `idx_message_edit_message` ON `message_edit` (`message_id`);
\ No newline at end of file
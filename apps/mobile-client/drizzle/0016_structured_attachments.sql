@@ -0,0 +1,43 @@
+CREATE TABLE `message_poll` (
+	`id` integer PRIMARY KEY NOT NULL,
+	`message_id` integer NOT NULL,
+	`question` text NOT NULL,
+	FOREIGN KEY (`message_id`) REFERENCES `message`(`id`) ON UPDATE no action ON DELETE cascade
+);
+--> statement-breakpoint
+CREATE TABLE `message_poll_option` (
+	`id` integer PRIMARY KEY NOT NULL,
+	`poll_id` integer NOT NULL,
+	`text` text NOT NULL,
+	`vote_count` integer DEFAULT 0 NOT NULL,
+	FOREIGN KEY (`poll_id`) REFERENCES `message_poll`(`id`) ON UPDATE no action ON DELETE cascade
+);
+--> statement-breakpoint
+CREATE INDEX `idx_message_poll_option_poll` ON `message_poll_option` (`poll_id`);--> statement-breakpoint
+CREATE TABLE `message_poll_vote` (
+	`id` integer PRIMARY KEY NOT NULL,
+	`option_id` integer NOT NULL,
+	`voter_id` integer NOT NULL,
+	FOREIGN KEY (`option_id`) REFERENCES `message_poll_option`(`id`) ON UPDATE no action ON DELETE cascade,
+	FOREIGN KEY (`voter_id`) REFERENCES `person`(`id`) ON UPDATE no action ON DELETE cascade
+);
+--> statement-breakpoint
+CREATE INDEX `idx_message_poll_vote_option` ON `message_poll_vote` (`option_id`);--> statement-breakpoint
+CREATE TABLE `message_location` (
+	`id` integer PRIMARY KEY NOT NULL,
+	`message_id` integer NOT NULL,
+	`latitude` real NOT NULL,
+	`longitude` real NOT NULL,
+	`name` text,
+	`is_live` integer DEFAULT false NOT NULL,
+	FOREIGN KEY (`message_id`) REFERENCES `message`(`id`) ON UPDATE no action ON DELETE cascade
+);
+--> statement-breakpoint
+CREATE TABLE `message_contact` (
+	`id` integer PRIMARY KEY NOT NULL,
+	`message_id` integer NOT NULL,
+	`name` text,
+	`phone_number` text,
+	`email` text,
+	FOREIGN KEY (`message_id`) REFERENCES `message`(`id`) ON UPDATE no action ON DELETE cascade
+);
\ No newline at end of file
@@ -0,0 +1,12 @@
+CREATE TABLE `message_source` (
+	`id` integer PRIMARY KEY NOT NULL,
+	`message_id` integer NOT NULL,
+	`export_id` integer NOT NULL,
+	`archive_path` text NOT NULL,
+	`entry_name` text NOT NULL,
+	`message_index` integer NOT NULL,
+	FOREIGN KEY (`message_id`) REFERENCES `message`(`id`) ON UPDATE no action ON DELETE cascade,
+	FOREIGN KEY (`export_id`) REFERENCES `export`(`id`) ON UPDATE no action ON DELETE cascade
+);
+--> statement-breakpoint
+CREATE UNIQUE INDEX `idx_message_source_message` ON `message_source` (`message_id`);
\ No newline at end of file
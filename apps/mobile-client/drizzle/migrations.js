@@ -4,6 +4,21 @@ import journal from './meta/_journal.json'
 import m0000 from './0000_lucky_harrier.sql'
 import m0001 from './0001_low_ben_urich.sql'
 import m0002 from './0002_big_vampiro.sql'
+import m0003 from './0003_import_journal.sql'
+import m0004 from './0004_audio_details.sql'
+import m0005 from './0005_video_details.sql'
+import m0006 from './0006_image_details.sql'
+import m0007 from './0007_media_store.sql'
+import m0008 from './0008_message_call.sql'
+import m0009 from './0009_message_link.sql'
+import m0010 from './0010_message_sticker.sql'
+import m0011 from './0011_conversation_event.sql'
+import m0012 from './0012_person_membership.sql'
+import m0013 from './0013_conversation_metadata.sql'
+import m0014 from './0014_message_reply.sql'
+import m0015 from './0015_message_edits.sql'
+import m0016 from './0016_structured_attachments.sql'
+import m0017 from './0017_message_source.sql'
 
 export default {
   journal,
@@ -11,5 +26,20 @@ export default {
     m0000,
     m0001,
     m0002,
+    m0003,
+    m0004,
+    m0005,
+    m0006,
+    m0007,
+    m0008,
+    m0009,
+    m0010,
+    m0011,
+    m0012,
+    m0013,
+    m0014,
+    m0015,
+    m0016,
+    m0017,
   },
 }
@@ -38,32 +38,80 @@ enum ProcessorBridgeError: Int, Error, LocalizedError {
 public class ProcessorBridgeModule: Module {
   private static var currentProgressModule: ProcessorBridgeModule?
 
-  private static let progressCallback: @convention(c) (UInt32, UInt32) -> Void = { processed, total in
-    ProcessorBridgeModule.handleProgress(processed: processed, total: total)
+  private static let progressEventCallback: @convention(c) (UnsafePointer<ProgressEvent>?) -> Void = { eventPtr in
+    guard let event = eventPtr?.pointee else {
+      return
+    }
+    // Strings are only valid during the callback, so copy everything before hopping threads.
+    var payload: [String: Any] = [
+      "processed": event.items_processed,
+      "total": event.items_total,
+      "phase": ProcessorBridgeModule.phaseName(event.phase),
+      "bytesProcessed": event.bytes_processed,
+      "bytesTotal": event.bytes_total,
+    ]
+    if let file = event.current_file {
+      payload["currentFile"] = String(cString: file)
+    }
+    if let thread = event.thread_name {
+      payload["threadName"] = String(cString: thread)
+    }
+    if event.eta_ms >= 0 {
+      payload["etaMs"] = event.eta_ms
+    }
+    ProcessorBridgeModule.handleProgress(payload)
+  }
+
+  private static func phaseName(_ phase: ImportPhase) -> String {
+    switch phase {
+    case ImportPhase_Scan: return "scan"
+    case ImportPhase_Index: return "index"
+    case ImportPhase_Parse: return "parse"
+    case ImportPhase_Insert: return "insert"
+    default: return "post_process"
+    }
   }
 
-  private static func handleProgress(processed: UInt32, total: UInt32) {
+  private static func handleProgress(_ payload: [String: Any]) {
     DispatchQueue.main.async {
       guard let module = ProcessorBridgeModule.currentProgressModule else {
         return
       }
 
-      module.sendEvent(
-        "onImportProgress",
-        [
-          "processed": processed,
-          "total": total,
-        ])
+      module.sendEvent("onImportProgress", payload)
     }
   }
 
+  /// Wraps a failed import status and the processor's JSON detail in an NSError so JS can
+  /// show a specific message and attach the detail to bug reports.
+  private static func importFailure(status: ProcessorImportStatus, detailJson: String?) -> NSError {
+    var userInfo: [String: Any] = [
+      NSLocalizedDescriptionKey: ProcessorBridgeError.processingFailed.errorDescription ?? "Import failed",
+      "importStatus": status.rawValue,
+    ]
+    if let detailJson = detailJson {
+      userInfo["detail"] = detailJson
+      if let data = detailJson.data(using: .utf8),
+        let detail = try? JSONSerialization.jsonObject(with: data) as? [String: Any],
+        let description = detail["description"] as? String
+      {
+        userInfo[NSLocalizedDescriptionKey] = description
+      }
+    }
+    return NSError(
+      domain: "ProcessorBridge",
+      code: ProcessorBridgeError.processingFailed.rawValue,
+      userInfo: userInfo
+    )
+  }
+
   private func beginProgressUpdates() {
     ProcessorBridgeModule.currentProgressModule = self
-    processor_set_progress_callback(ProcessorBridgeModule.progressCallback)
+    processor_set_progress_event_callback(ProcessorBridgeModule.progressEventCallback)
   }
 
   private func endProgressUpdates() {
-    processor_clear_progress_callback()
+    processor_clear_progress_event_callback()
     ProcessorBridgeModule.currentProgressModule = nil
   }
 
@@ -111,25 +159,26 @@ public class ProcessorBridgeModule: Module {
         throw ProcessorBridgeError.processingFailed
       }
 
-      let result = jsonString.withCString { filesPtr in
+      var errorDetailPtr: UnsafeMutablePointer<CChar>? = nil
+      let status = jsonString.withCString { filesPtr in
         dbPath.withCString { databasePtr in
-          processor_import_messenger_archives_json(filesPtr, databasePtr)
+          processor_import_messenger_archives_json_detailed(filesPtr, databasePtr, &errorDetailPtr)
         }
       }
 
-      guard let cString = result else {
-        throw ProcessorBridgeError.processingFailed
+      var errorDetail: String? = nil
+      if let detailPtr = errorDetailPtr {
+        errorDetail = String(cString: detailPtr)
+        processor_string_free(detailPtr)
       }
 
-      let status = String(cString: cString)
-
       switch status {
-      case "success":
-        return status
-      case "cancelled":
-        return status
+      case ProcessorImportStatus_Success:
+        return "success"
+      case ProcessorImportStatus_Cancelled:
+        return "cancelled"
       default:
-        throw ProcessorBridgeError.processingFailed
+        throw ProcessorBridgeModule.importFailure(status: status, detailJson: errorDetail)
       }
     }
 
@@ -13,9 +13,20 @@ export type ChangeEventPayload = {
   value: string
 }
 
+export type ImportPhase = 'scan' | 'index' | 'parse' | 'insert' | 'post_process'
+
 export type ImportProgressEventPayload = {
+  /** Thread files finished so far. */
   processed: number
+  /** Thread files in the whole import; fixed once the scan phase is over. */
   total: number
+  phase?: ImportPhase
+  currentFile?: string
+  threadName?: string
+  bytesProcessed?: number
+  bytesTotal?: number
+  /** Estimated milliseconds remaining; absent until it can be estimated. */
+  etaMs?: number
 }
 
 export type ProcessorBridgeViewProps = {
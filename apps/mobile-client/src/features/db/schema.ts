@@ -1,6 +1,15 @@
 import { getValues } from '@/common/helpers/object'
 import { relations, sql } from 'drizzle-orm'
-import { check, index, integer, sqliteTable, text } from 'drizzle-orm/sqlite-core'
+import {
+  type AnySQLiteColumn,
+  check,
+  index,
+  integer,
+  real,
+  sqliteTable,
+  text,
+  uniqueIndex,
+} from 'drizzle-orm/sqlite-core'
 import { EExportSource } from '../chatapps/constants'
 
 export enum EConversationType {
@@ -8,6 +17,33 @@ export enum EConversationType {
   GROUP = 'group',
 }
 
+// Messenger export folder a thread was found in
+export enum EArchiveCategory {
+  INBOX = 'inbox',
+  E2EE_CUTOVER = 'e2ee_cutover',
+  FILTERED_THREADS = 'filtered_threads',
+  MESSAGE_REQUESTS = 'message_requests',
+}
+
+export enum EConversationEventKind {
+  MEMBER_ADDED = 'member_added',
+  MEMBER_REMOVED = 'member_removed',
+  MEMBER_LEFT = 'member_left',
+  GROUP_RENAMED = 'group_renamed',
+  GROUP_PHOTO_CHANGED = 'group_photo_changed',
+  NICKNAME_SET = 'nickname_set',
+  NICKNAME_CLEARED = 'nickname_cleared',
+  THEME_CHANGED = 'theme_changed',
+  EMOJI_CHANGED = 'emoji_changed',
+  CALL_STARTED = 'call_started',
+  CALL_ENDED = 'call_ended',
+}
+
+export enum EUnsentReason {
+  SENDER = 'sender',
+  MESSENGER_KID_PARENT = 'messenger_kid_parent',
+}
+
 const tsEnumToDrizzleEnum = <T extends Record<string, unknown>>(
   myEnum: T,
 ): [T[keyof T], ...T[keyof T][]] => {
@@ -22,6 +58,10 @@ export const exportsTable = sqliteTable('export', {
     .notNull()
     .default(sql`(unixepoch('now'))`),
   metaJson: text('meta_json'),
+  // 'in_progress' while a checkpointed import is still running or was interrupted
+  status: text('status', { enum: ['in_progress', 'complete'] })
+    .notNull()
+    .default('complete'),
 })
 
 export const canonicalPeople = sqliteTable('canonical_person', {
@@ -59,6 +99,12 @@ export const conversations = sqliteTable(
     canonicalConversationId: integer('canonical_conversation_id')
       .notNull()
       .references(() => canonicalConversations.id),
+    // Thread id on the platform (Facebook thread_path, E2E threadName)
+    platformThreadId: text('platform_thread_id'),
+    // The export owner had left the conversation when exporting
+    ownerLeft: integer('owner_left', { mode: 'boolean' }).notNull().default(false),
+    inviteLink: text('invite_link'),
+    archiveCategory: text('archive_category', { enum: tsEnumToDrizzleEnum(EArchiveCategory) }),
   },
   (t) => [
     check('ck_conversation_type', sql`${t.type} in ('dm','group')`),
@@ -79,6 +125,8 @@ export const people = sqliteTable(
     canonicalPersonId: integer('canonical_person_id')
       .notNull()
       .references(() => canonicalPeople.id),
+    // Listed among the thread's participants when exported, i.e. still a member
+    isParticipant: integer('is_participant', { mode: 'boolean' }).notNull().default(false),
   },
   (t) => [
     index('idx_person_conversation').on(t.conversationId, t.id),
@@ -86,6 +134,21 @@ export const people = sqliteTable(
   ],
 )
 
+// When a person was a member of their conversation; null bounds are unknown (before the
+// export starts) or open (still a member)
+export const personMemberships = sqliteTable(
+  'person_membership',
+  {
+    id: integer('id').primaryKey(),
+    personId: integer('person_id')
+      .notNull()
+      .references(() => people.id, { onDelete: 'cascade' }),
+    joinedAt: integer('joined_at', { mode: 'number' }),
+    leftAt: integer('left_at', { mode: 'number' }),
+  },
+  (t) => [index('idx_person_membership_person').on(t.personId)],
+)
+
 export const messages = sqliteTable(
   'message',
   {
@@ -95,8 +158,32 @@ export const messages = sqliteTable(
       .references(() => people.id, { onDelete: 'cascade' }),
     sentAt: integer('sent_at', { mode: 'number' }).notNull(), // epoch seconds
     unsent: integer('unsent', { mode: 'boolean' }).notNull().default(false),
+    edited: integer('edited', { mode: 'boolean' }).notNull().default(false),
+    editCount: integer('edit_count', { mode: 'number' }).notNull().default(0),
+    unsentReason: text('unsent_reason', { enum: tsEnumToDrizzleEnum(EUnsentReason) }),
+    // Earlier message this one replies to, where the export records replies
+    replyToMessageId: integer('reply_to_message_id').references((): AnySQLiteColumn => messages.id, {
+      onDelete: 'set null',
+    }),
+  },
+  (t) => [
+    index('idx_message_sender_time').on(t.senderId, t.sentAt),
+    index('idx_message_reply_to').on(t.replyToMessageId),
+  ],
+)
+
+// Earlier versions of an edited message, oldest first
+export const messageEdits = sqliteTable(
+  'message_edit',
+  {
+    id: integer('id').primaryKey(),
+    messageId: integer('message_id')
+      .notNull()
+      .references(() => messages.id, { onDelete: 'cascade' }),
+    previousText: text('previous_text').notNull(),
+    sentAt: integer('sent_at', { mode: 'number' }), // epoch seconds, when that version was sent
   },
-  (t) => [index('idx_message_sender_time').on(t.senderId, t.sentAt)],
+  (t) => [index('idx_message_edit_message').on(t.messageId)],
 )
 
 export const messageTexts = sqliteTable('message_text', {
@@ -113,6 +200,11 @@ export const messageImages = sqliteTable('message_image', {
     .notNull()
     .references(() => messages.id, { onDelete: 'cascade' }),
   imageUri: text('image_uri'),
+  format: text('format'),
+  width: integer('width', { mode: 'number' }),
+  height: integer('height', { mode: 'number' }),
+  takenAt: text('taken_at'),
+  storedPath: text('stored_path'),
 })
 
 export const messageVideos = sqliteTable('message_video', {
@@ -121,6 +213,11 @@ export const messageVideos = sqliteTable('message_video', {
     .notNull()
     .references(() => messages.id, { onDelete: 'cascade' }),
   videoUri: text('video_uri'),
+  lengthMs: integer('length_ms', { mode: 'number' }),
+  width: integer('width', { mode: 'number' }),
+  height: integer('height', { mode: 'number' }),
+  codec: text('codec'),
+  storedPath: text('stored_path'),
 })
 
 export const messageGifs = sqliteTable('message_gif', {
@@ -129,6 +226,25 @@ export const messageGifs = sqliteTable('message_gif', {
     .notNull()
     .references(() => messages.id, { onDelete: 'cascade' }),
   gifUri: text('gif_uri'),
+  width: integer('width', { mode: 'number' }),
+  height: integer('height', { mode: 'number' }),
+  frameCount: integer('frame_count', { mode: 'number' }),
+  lengthMs: integer('length_ms', { mode: 'number' }),
+  storedPath: text('stored_path'),
+})
+
+export const messageStickers = sqliteTable('message_sticker', {
+  id: integer('id').primaryKey(),
+  messageId: integer('message_id')
+    .notNull()
+    .references(() => messages.id, { onDelete: 'cascade' }),
+  stickerUri: text('sticker_uri'),
+  format: text('format'),
+  width: integer('width', { mode: 'number' }),
+  height: integer('height', { mode: 'number' }),
+  frameCount: integer('frame_count', { mode: 'number' }),
+  isLike: integer('is_like', { mode: 'boolean' }).notNull().default(false),
+  storedPath: text('stored_path'),
 })
 
 export const messageAudios = sqliteTable('message_audio', {
@@ -138,6 +254,119 @@ export const messageAudios = sqliteTable('message_audio', {
     .references(() => messages.id, { onDelete: 'cascade' }),
   audioUri: text('audio_uri'),
   lengthSeconds: integer('length_seconds', { mode: 'number' }),
+  lengthMs: integer('length_ms', { mode: 'number' }),
+  codec: text('codec'),
+  storedPath: text('stored_path'),
+})
+
+export const messageCalls = sqliteTable('message_call', {
+  id: integer('id').primaryKey(),
+  messageId: integer('message_id')
+    .notNull()
+    .references(() => messages.id, { onDelete: 'cascade' }),
+  durationSeconds: integer('duration_seconds', { mode: 'number' })
+    .notNull()
+    .default(0),
+  missed: integer('missed', { mode: 'boolean' }).notNull().default(false),
+  callType: text('call_type', { enum: ['audio', 'video'] })
+    .notNull()
+    .default('audio'),
+})
+
+export const messageLinks = sqliteTable('message_link', {
+  id: integer('id').primaryKey(),
+  messageId: integer('message_id')
+    .notNull()
+    .references(() => messages.id, { onDelete: 'cascade' }),
+  url: text('url').notNull(),
+  shareText: text('share_text'),
+  domain: text('domain'),
+})
+
+export const messagePolls = sqliteTable('message_poll', {
+  id: integer('id').primaryKey(),
+  messageId: integer('message_id')
+    .notNull()
+    .references(() => messages.id, { onDelete: 'cascade' }),
+  question: text('question').notNull(),
+})
+
+export const messagePollOptions = sqliteTable(
+  'message_poll_option',
+  {
+    id: integer('id').primaryKey(),
+    pollId: integer('poll_id')
+      .notNull()
+      .references(() => messagePolls.id, { onDelete: 'cascade' }),
+    text: text('text').notNull(),
+    // At least the number of known voters; some exports only give a count
+    voteCount: integer('vote_count', { mode: 'number' }).notNull().default(0),
+  },
+  (t) => [index('idx_message_poll_option_poll').on(t.pollId)],
+)
+
+export const messagePollVotes = sqliteTable(
+  'message_poll_vote',
+  {
+    id: integer('id').primaryKey(),
+    optionId: integer('option_id')
+      .notNull()
+      .references(() => messagePollOptions.id, { onDelete: 'cascade' }),
+    voterId: integer('voter_id')
+      .notNull()
+      .references(() => people.id, { onDelete: 'cascade' }),
+  },
+  (t) => [index('idx_message_poll_vote_option').on(t.optionId)],
+)
+
+export const messageLocations = sqliteTable('message_location', {
+  id: integer('id').primaryKey(),
+  messageId: integer('message_id')
+    .notNull()
+    .references(() => messages.id, { onDelete: 'cascade' }),
+  latitude: real('latitude').notNull(),
+  longitude: real('longitude').notNull(),
+  name: text('name'),
+  isLive: integer('is_live', { mode: 'boolean' }).notNull().default(false),
+})
+
+export const messageContacts = sqliteTable('message_contact', {
+  id: integer('id').primaryKey(),
+  messageId: integer('message_id')
+    .notNull()
+    .references(() => messages.id, { onDelete: 'cascade' }),
+  name: text('name'),
+  phoneNumber: text('phone_number'),
+  email: text('email'),
+})
+
+// Where a message was read from; only written when the import records provenance
+export const messageSources = sqliteTable(
+  'message_source',
+  {
+    id: integer('id').primaryKey(),
+    messageId: integer('message_id')
+      .notNull()
+      .references(() => messages.id, { onDelete: 'cascade' }),
+    exportId: integer('export_id')
+      .notNull()
+      .references(() => exportsTable.id, { onDelete: 'cascade' }),
+    archivePath: text('archive_path').notNull(),
+    entryName: text('entry_name').notNull(),
+    messageIndex: integer('message_index', { mode: 'number' }).notNull(), // position in the entry's messages array
+  },
+  (t) => [uniqueIndex('idx_message_source_message').on(t.messageId)],
+)
+
+export const conversationEvents = sqliteTable('conversation_event', {
+  id: integer('id').primaryKey(),
+  messageId: integer('message_id')
+    .notNull()
+    .references(() => messages.id, { onDelete: 'cascade' }),
+  kind: text('kind', { enum: tsEnumToDrizzleEnum(EConversationEventKind) }).notNull(),
+  subjectName: text('subject_name'),
+  value: text('value'),
+  rawText: text('raw_text'),
 })
 
 export const reactions = sqliteTable(
@@ -155,6 +384,26 @@ export const reactions = sqliteTable(
   (t) => [index('idx_reaction_message').on(t.messageId)],
 )
 
+// Thread files already written by a checkpointed import, so it can resume after being killed
+export const importJournal = sqliteTable(
+  'import_journal',
+  {
+    id: integer('id').primaryKey(),
+    exportId: integer('export_id')
+      .notNull()
+      .references(() => exportsTable.id, { onDelete: 'cascade' }),
+    archivePath: text('archive_path').notNull(),
+    entryName: text('entry_name').notNull(),
+    threadKey: text('thread_key').notNull(),
+    conversationId: integer('conversation_id')
+      .notNull()
+      .references(() => conversations.id, { onDelete: 'cascade' }),
+    messagesDone: integer('messages_done', { mode: 'number' }).notNull().default(0),
+    completed: integer('completed', { mode: 'boolean' }).notNull().default(false),
+  },
+  (t) => [uniqueIndex('idx_import_journal_entry').on(t.exportId, t.archivePath, t.entryName)],
+)
+
 export const exportsRelations = relations(exportsTable, ({ many }) => ({
   conversations: many(conversations),
 }))
@@ -190,6 +439,15 @@ export const peopleRelations = relations(people, ({ one, many }) => ({
   }),
   messages: many(messages),
   reactions: many(reactions, { relationName: 'personReactions' }),
+  memberships: many(personMemberships),
+  pollVotes: many(messagePollVotes),
+}))
+
+export const personMembershipsRelations = relations(personMemberships, ({ one }) => ({
+  person: one(people, {
+    fields: [personMemberships.personId],
+    references: [people.id],
+  }),
 }))
 
 export const messagesRelations = relations(messages, ({ one, many }) => ({
@@ -197,14 +455,36 @@ export const messagesRelations = relations(messages, ({ one, many }) => ({
     fields: [messages.senderId],
     references: [people.id],
   }),
+  replyTo: one(messages, {
+    fields: [messages.replyToMessageId],
+    references: [messages.id],
+    relationName: 'messageReplies',
+  }),
+  replies: many(messages, { relationName: 'messageReplies' }),
+  edits: many(messageEdits),
   texts: many(messageTexts),
   images: many(messageImages),
   videos: many(messageVideos),
   gifs: many(messageGifs),
+  stickers: many(messageStickers),
   audios: many(messageAudios),
+  calls: many(messageCalls),
+  links: many(messageLinks),
+  polls: many(messagePolls),
+  locations: many(messageLocations),
+  contacts: many(messageContacts),
+  source: one(messageSources),
+  events: many(conversationEvents),
   reactions: many(reactions),
 }))
 
+export const messageEditsRelations = relations(messageEdits, ({ one }) => ({
+  message: one(messages, {
+    fields: [messageEdits.messageId],
+    references: [messages.id],
+  }),
+}))
+
 export const messageTextsRelations = relations(messageTexts, ({ one }) => ({
   message: one(messages, {
     fields: [messageTexts.messageId],
@@ -233,6 +513,13 @@ export const messageGifsRelations = relations(messageGifs, ({ one }) => ({
   }),
 }))
 
+export const messageStickersRelations = relations(messageStickers, ({ one }) => ({
+  message: one(messages, {
+    fields: [messageStickers.messageId],
+    references: [messages.id],
+  }),
+}))
+
 export const messageAudiosRelations = relations(messageAudios, ({ one }) => ({
   message: one(messages, {
     fields: [messageAudios.messageId],
@@ -240,6 +527,79 @@ export const messageAudiosRelations = relations(messageAudios, ({ one }) => ({
   }),
 }))
 
+export const messageCallsRelations = relations(messageCalls, ({ one }) => ({
+  message: one(messages, {
+    fields: [messageCalls.messageId],
+    references: [messages.id],
+  }),
+}))
+
+export const messageLinksRelations = relations(messageLinks, ({ one }) => ({
+  message: one(messages, {
+    fields: [messageLinks.messageId],
+    references: [messages.id],
+  }),
+}))
+
+export const messagePollsRelations = relations(messagePolls, ({ one, many }) => ({
+  message: one(messages, {
+    fields: [messagePolls.messageId],
+    references: [messages.id],
+  }),
+  options: many(messagePollOptions),
+}))
+
+export const messagePollOptionsRelations = relations(messagePollOptions, ({ one, many }) => ({
+  poll: one(messagePolls, {
+    fields: [messagePollOptions.pollId],
+    references: [messagePolls.id],
+  }),
+  votes: many(messagePollVotes),
+}))
+
+export const messagePollVotesRelations = relations(messagePollVotes, ({ one }) => ({
+  option: one(messagePollOptions, {
+    fields: [messagePollVotes.optionId],
+    references: [messagePollOptions.id],
+  }),
+  voter: one(people, {
+    fields: [messagePollVotes.voterId],
+    references: [people.id],
+  }),
+}))
+
+export const messageLocationsRelations = relations(messageLocations, ({ one }) => ({
+  message: one(messages, {
+    fields: [messageLocations.messageId],
+    references: [messages.id],
+  }),
+}))
+
+export const messageContactsRelations = relations(messageContacts, ({ one }) => ({
+  message: one(messages, {
+    fields: [messageContacts.messageId],
+    references: [messages.id],
+  }),
+}))
+
+export const messageSourcesRelations = relations(messageSources, ({ one }) => ({
+  message: one(messages, {
+    fields: [messageSources.messageId],
+    references: [messages.id],
+  }),
+  export: one(exportsTable, {
+    fields: [messageSources.exportId],
+    references: [exportsTable.id],
+  }),
+}))
+
+export const conversationEventsRelations = relations(conversationEvents, ({ one }) => ({
+  message: one(messages, {
+    fields: [conversationEvents.messageId],
+    references: [messages.id],
+  }),
+}))
+
 export const reactionsRelations = relations(reactions, ({ one }) => ({
   message: one(messages, {
     fields: [reactions.messageId],
@@ -257,23 +617,48 @@ export const schema = {
   canonicalConversations,
   conversations,
   people,
+  personMemberships,
   messages,
+  messageEdits,
   messageTexts,
   messageImages,
   messageVideos,
   messageGifs,
+  messageStickers,
   messageAudios,
+  messageCalls,
+  messageLinks,
+  messagePolls,
+  messagePollOptions,
+  messagePollVotes,
+  messageLocations,
+  messageContacts,
+  messageSources,
+  conversationEvents,
   reactions,
+  importJournal,
   exportsRelations,
   canonicalPeopleRelations,
   canonicalConversationsRelations,
   conversationsRelations,
   peopleRelations,
+  personMembershipsRelations,
   messagesRelations,
+  messageEditsRelations,
   messageTextsRelations,
   messageImagesRelations,
   messageVideosRelations,
   messageGifsRelations,
+  messageStickersRelations,
   messageAudiosRelations,
+  messageCallsRelations,
+  messageLinksRelations,
+  messagePollsRelations,
+  messagePollOptionsRelations,
+  messagePollVotesRelations,
+  messageLocationsRelations,
+  messageContactsRelations,
+  messageSourcesRelations,
+  conversationEventsRelations,
   reactionsRelations,
 }
@@ -1,7 +1,12 @@
 use clap::{Parser, Subcommand};
-use processor::{self, database::MessageDb, APP_NAME};
+use processor::analytics::{self, StatsFilter};
+use processor::importers::messenger::utils::MediaStoreOptions;
+use processor::{self, database::MessageDb, importers::messenger::ImportOptions, APP_NAME};
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+
+/// Missing attachments listed after an import; the rest are only counted.
+const MISSING_MEDIA_SHOWN: usize = 10;
 
 #[derive(Parser)]
 #[command(name = "processor-cli", about = APP_NAME, version)]
@@ -20,6 +25,87 @@ enum Commands {
         /// Input files: any mix of old ZIPs, new E2E ZIPs, or JSON files
         #[arg(value_name = "FILES", num_args = 1..)]
         files: Vec<PathBuf>,
+        /// Skip malformed threads/messages instead of aborting the import
+        #[arg(long)]
+        lenient: bool,
+        /// Commit after every thread file so an interrupted import can be resumed
+        #[arg(long)]
+        checkpoint: bool,
+        /// With --checkpoint, also commit every N messages within a thread
+        #[arg(long, value_name = "N", default_value_t = 0)]
+        checkpoint_messages: usize,
+        /// With --checkpoint, continue an unfinished import of the same files
+        #[arg(long, requires = "checkpoint")]
+        resume: bool,
+        /// Stream thread files message by message instead of loading each one whole
+        #[arg(long, conflicts_with = "jobs")]
+        streaming: bool,
+        /// Threads parsing thread files in parallel (defaults to the number of CPUs; 1 imports
+        /// sequentially)
+        #[arg(long)]
+        jobs: Option<usize>,
+        /// Copy referenced media into this content-addressed directory
+        #[arg(long, value_name = "DIR")]
+        media_dir: Option<PathBuf>,
+        /// With --media-dir, add at most this many bytes to the store (0: no limit)
+        #[arg(
+            long,
+            value_name = "BYTES",
+            default_value_t = 0,
+            requires = "media_dir"
+        )]
+        media_max_bytes: u64,
+        /// With --media-dir, only extract these kinds (image, gif, sticker, video, audio)
+        #[arg(
+            long,
+            value_name = "KINDS",
+            value_delimiter = ',',
+            requires = "media_dir"
+        )]
+        media_include: Vec<String>,
+        /// With --media-dir, never extract these kinds
+        #[arg(
+            long,
+            value_name = "KINDS",
+            value_delimiter = ',',
+            requires = "media_dir"
+        )]
+        media_exclude: Vec<String>,
+        /// Record the archive entry and position each message was read from (see show-source)
+        #[arg(long)]
+        record_sources: bool,
+    },
+    /// Delete an export and everything imported under it (e.g. an unfinished checkpointed import)
+    RollbackExport {
+        /// SQLite DB path to update
+        #[arg(long)]
+        db: PathBuf,
+        /// Export id to delete
+        export_id: i64,
+    },
+    /// Print the original export JSON of a message imported with --record-sources
+    ShowSource {
+        /// SQLite DB path to read
+        #[arg(long)]
+        db: PathBuf,
+        /// Message id
+        message_id: i64,
+    },
+    /// Print statistics of an imported DB
+    Stats {
+        /// SQLite DB path to read
+        #[arg(long)]
+        db: PathBuf,
+        /// Only count messages of this canonical conversation
+        #[arg(long, value_name = "ID")]
+        conversation: Option<i64>,
+        /// Entries listed per statistic
+        #[arg(long, value_name = "N", default_value_t = 10)]
+        top: usize,
+        /// Leave out threads from these export folders (inbox, e2ee_cutover,
+        /// filtered_threads, message_requests)
+        #[arg(long, value_name = "CATEGORIES", value_delimiter = ',')]
+        exclude_category: Vec<String>,
     },
 }
 
@@ -27,7 +113,21 @@ fn main() {
     let cli = Cli::parse();
 
     match cli.command {
-        Commands::NormalizeMessenger { db, files } => {
+        Commands::NormalizeMessenger {
+            db,
+            files,
+            lenient,
+            checkpoint,
+            checkpoint_messages,
+            resume,
+            streaming,
+            jobs,
+            media_dir,
+            media_max_bytes,
+            media_include,
+            media_exclude,
+            record_sources,
+        } => {
             if files.is_empty() {
                 eprintln!("No files provided.");
                 std::process::exit(2);
@@ -54,20 +154,73 @@ fn main() {
             }
 
             // Stage 1: Import everything into normalized DB with export_source
-            match processor::importers::messenger::import_messenger_exports(files, &db) {
-                Ok(export_ids) => {
-                    if export_ids.is_empty() {
+            let options = ImportOptions {
+                lenient,
+                checkpoint,
+                checkpoint_messages,
+                resume,
+                streaming,
+                parse_threads: jobs
+                    .unwrap_or_else(|| std::thread::available_parallelism().map_or(1, |n| n.get())),
+                media_store: media_dir.map(|dir| MediaStoreOptions {
+                    dir,
+                    max_bytes: media_max_bytes,
+                    include: media_include,
+                    exclude: media_exclude,
+                }),
+                record_sources,
+            };
+            match processor::importers::messenger::import_messenger_exports_with_options(
+                files, &db, &options,
+            ) {
+                Ok(report) => {
+                    if report.export_ids.is_empty() {
                         println!("Import completed but no exports were recorded.");
                     } else {
                         println!(
                             "Imported export IDs {:?} into DB {}",
-                            export_ids,
+                            report.export_ids,
                             db.display()
                         );
                     }
+                    if report.stored_media_bytes > 0 {
+                        println!(
+                            "Stored {} bytes of new media in the media directory.",
+                            report.stored_media_bytes
+                        );
+                    }
+                    for skipped in &report.skipped {
+                        match skipped.message_index {
+                            Some(index) => eprintln!(
+                                "  Skipped message #{} in {}: {}",
+                                index, skipped.path, skipped.error
+                            ),
+                            None => eprintln!("  Skipped {}: {}", skipped.path, skipped.error),
+                        }
+                    }
+                    if !report.missing_media.is_empty() {
+                        eprintln!(
+                            "  {} attachment(s) not found in the selected files:",
+                            report.missing_media.len()
+                        );
+                        for missing in report.missing_media.iter().take(MISSING_MEDIA_SHOWN) {
+                            eprintln!("    {} {}", missing.kind, missing.uri);
+                        }
+                        if report.missing_media.len() > MISSING_MEDIA_SHOWN {
+                            eprintln!(
+                                "    ... and {} more",
+                                report.missing_media.len() - MISSING_MEDIA_SHOWN
+                            );
+                        }
+                    }
                 }
                 Err(e) => {
                     eprintln!("Import failed: {}", e);
+                    if checkpoint {
+                        eprintln!(
+                            "Committed checkpoints were kept; rerun with --resume or use rollback-export."
+                        );
+                    }
                     std::process::exit(1);
                 }
             }
@@ -87,9 +240,148 @@ fn main() {
                 }
             }
         }
+        Commands::RollbackExport { db, export_id } => {
+            match processor::importers::messenger::rollback_export(&db, export_id) {
+                Ok(()) => println!("Deleted export {} from DB {}", export_id, db.display()),
+                Err(e) => {
+                    eprintln!("Rollback failed: {}", e);
+                    std::process::exit(1);
+                }
+            }
+        }
+        Commands::ShowSource { db, message_id } => {
+            match processor::importers::messenger::message_source_json(&db, message_id) {
+                Ok(Some(json)) => match serde_json::to_string_pretty(&json) {
+                    Ok(text) => println!("{}", text),
+                    Err(e) => {
+                        eprintln!("Show source failed: {}", e);
+                        std::process::exit(1);
+                    }
+                },
+                Ok(None) => {
+                    eprintln!(
+                        "Message {} has no recorded source; import with --record-sources.",
+                        message_id
+                    );
+                    std::process::exit(1);
+                }
+                Err(e) => {
+                    eprintln!("Show source failed: {}", e);
+                    std::process::exit(1);
+                }
+            }
+        }
+        Commands::Stats {
+            db,
+            conversation,
+            top,
+            exclude_category,
+        } => {
+            let exclude_categories = match exclude_category.iter().map(|c| c.parse()).collect() {
+                Ok(categories) => categories,
+                Err(e) => {
+                    eprintln!("Stats failed: {}", e);
+                    std::process::exit(1);
+                }
+            };
+            let filter = StatsFilter {
+                canonical_conversation_id: conversation,
+                exclude_categories,
+                ..Default::default()
+            };
+            if let Err(e) = print_stats(&db, &filter, top) {
+                eprintln!("Stats failed: {}", e);
+                std::process::exit(1);
+            }
+        }
     }
 }
 
+fn print_stats(
+    db_path: &Path,
+    filter: &StatsFilter,
+    top: usize,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let db = MessageDb::open(db_path)?;
+
+    println!("Most shared domains:");
+    for d in analytics::most_shared_domains(&db, filter, top)? {
+        println!(
+            "  {:<32} {} link(s) by {} sharer(s)",
+            d.domain, d.links, d.sharers
+        );
+    }
+    println!("Top link sharers:");
+    for s in analytics::top_link_sharers(&db, filter, top)? {
+        println!(
+            "  {:<32} {} link(s) to {} domain(s)",
+            s.name.as_deref().unwrap_or("(unknown)"),
+            s.links,
+            s.domains
+        );
+    }
+    println!("Media sent:");
+    for m in analytics::media_sent_by_person(&db, filter, top)? {
+        println!(
+            "  {:<32} {} photo(s), {} GIF(s), {} video(s), {} audio, {} sticker(s) ({} like), \
+             {} poll(s), {} location(s), {} contact(s)",
+            m.name.as_deref().unwrap_or("(unknown)"),
+            m.photos,
+            m.gifs,
+            m.videos,
+            m.audio,
+            m.stickers,
+            m.like_stickers,
+            m.polls,
+            m.locations,
+            m.contacts
+        );
+    }
+    println!("Share of messages while a member:");
+    for p in analytics::participation_rates(&db, filter, top)? {
+        println!(
+            "  {:<32} {:>5.1}% ({} of {} message(s))",
+            p.name.as_deref().unwrap_or("(unknown)"),
+            p.share * 100.0,
+            p.messages,
+            p.group_messages
+        );
+    }
+    println!("Most edited:");
+    for e in analytics::most_edited(&db, filter, top)? {
+        println!(
+            "  {:<32} {} message(s) edited {} time(s)",
+            e.name.as_deref().unwrap_or("(unknown)"),
+            e.edited_messages,
+            e.edits
+        );
+    }
+    println!("Most unsent:");
+    for u in analytics::most_unsent(&db, filter, top)? {
+        println!(
+            "  {:<32} {} of {} message(s)",
+            u.name.as_deref().unwrap_or("(unknown)"),
+            u.unsent,
+            u.messages
+        );
+    }
+    println!("Who replies to whom:");
+    for r in analytics::reply_pairs(&db, filter, top)? {
+        let from = r.from_name.as_deref().unwrap_or("(unknown)");
+        let to = r.to_name.as_deref().unwrap_or("(unknown)");
+        println!(
+            "  {:<32} {} repl(ies)",
+            format!("{} → {}", from, to),
+            r.replies
+        );
+    }
+    println!("Reply chains by depth:");
+    for d in analytics::reply_chain_depths(&db, filter)? {
+        println!("  {:>3} {} chain(s)", d.depth, d.chains);
+    }
+    Ok(())
+}
+
 /// Find and merge duplicate DM conversations based on heuristics.
 ///
 /// Only merges DM conversations since group messages only exist in Facebook format,
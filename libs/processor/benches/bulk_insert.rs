@@ -0,0 +1,130 @@
+//! Message insert throughput: one bulk call per message vs. chunked bulk calls.
+//!
+//! Run with `cargo bench -p processor --bench bulk_insert [-- <messages>]`.
+
+#[path = "../tests/common/mod.rs"]
+mod common;
+
+use std::time::{Duration, Instant};
+
+use processor::database::{
+    Attachment, ConversationMeta, ConversationType, MessageDb, MessageRecord, WriteBatch,
+};
+
+const DEFAULT_MESSAGES: usize = 200_000;
+const CHUNK: usize = 512;
+
+#[derive(Clone, Copy)]
+enum Mode {
+    RowByRow,
+    Bulk,
+    BulkTuned,
+}
+
+impl Mode {
+    fn label(self) -> &'static str {
+        match self {
+            Mode::RowByRow => "row by row",
+            Mode::Bulk => "bulk",
+            Mode::BulkTuned => "bulk + pragmas + deferred indexes",
+        }
+    }
+}
+
+fn setup(batch: &mut WriteBatch<'_>) -> (i64, i64) {
+    let export_id = batch
+        .insert_export("messenger:facebook", None, None)
+        .unwrap();
+    let canon = batch
+        .insert_canonical_conversation(ConversationType::DM, Some("Bob"))
+        .unwrap();
+    let conv = batch
+        .insert_conversation(
+            ConversationType::DM,
+            Some("Bob"),
+            &ConversationMeta::default(),
+            export_id,
+            canon,
+        )
+        .unwrap();
+    let mut person = |name: &str| {
+        let canon = batch.insert_canonical_person(Some(name), None).unwrap();
+        batch.insert_person(conv, Some(name), None, canon).unwrap()
+    };
+    (person("Alice"), person("Bob"))
+}
+
+/// A typical mix: every message has text, some have photos, a few have reactions.
+fn record(i: usize, alice: i64, bob: i64) -> MessageRecord {
+    MessageRecord {
+        sender_id: if i.is_multiple_of(2) { alice } else { bob },
+        sent_at: 1_700_000_000 + i as i64,
+        texts: vec![format!("message number {} with some words in it", i)],
+        images: if i.is_multiple_of(10) {
+            vec![Attachment::new(format!("photos/{}.jpg", i), None)]
+        } else {
+            Vec::new()
+        },
+        reactions: if i.is_multiple_of(4) {
+            vec![(bob, "\u{2764}".to_string())]
+        } else {
+            Vec::new()
+        },
+        ..Default::default()
+    }
+}
+
+fn run(mode: Mode, messages: usize) -> Duration {
+    let path = common::fresh_db(&format!("bench_bulk_insert_{}", mode as u8));
+    let mut db = MessageDb::open(&path).unwrap();
+    let started = Instant::now();
+    let tuning = matches!(mode, Mode::BulkTuned).then(|| db.tune_for_import().unwrap());
+    let mut batch = db.begin_write().unwrap();
+    let deferred = matches!(mode, Mode::BulkTuned).then(|| batch.defer_indexes().unwrap());
+    let (alice, bob) = setup(&mut batch);
+
+    let mut chunk = Vec::with_capacity(CHUNK);
+    for i in 0..messages {
+        let r = record(i, alice, bob);
+        match mode {
+            Mode::RowByRow => {
+                batch
+                    .insert_messages_bulk(std::slice::from_ref(&r))
+                    .unwrap();
+            }
+            Mode::Bulk | Mode::BulkTuned => {
+                chunk.push(r);
+                if chunk.len() == CHUNK {
+                    batch.insert_messages_bulk(&chunk).unwrap();
+                    chunk.clear();
+                }
+            }
+        }
+    }
+    batch.insert_messages_bulk(&chunk).unwrap();
+    if let Some(deferred) = deferred {
+        deferred.restore(&mut batch).unwrap();
+    }
+    batch.commit().unwrap();
+    if let Some(tuning) = tuning {
+        tuning.restore(&db).unwrap();
+    }
+    started.elapsed()
+}
+
+fn main() {
+    let messages = std::env::args()
+        .skip(1)
+        .find_map(|a| a.parse().ok())
+        .unwrap_or(DEFAULT_MESSAGES);
+    println!("inserting {} messages", messages);
+    for mode in [Mode::RowByRow, Mode::Bulk, Mode::BulkTuned] {
+        let elapsed = run(mode, messages);
+        println!(
+            "{:<36} {:>8.0} ms {:>10.0} messages/s",
+            mode.label(),
+            elapsed.as_secs_f64() * 1000.0,
+            messages as f64 / elapsed.as_secs_f64()
+        );
+    }
+}
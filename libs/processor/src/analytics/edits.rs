@@ -0,0 +1,85 @@
+//! Second thoughts: who edits their messages and who unsends them.
+
+use anyhow::Result;
+
+use super::{query, StatsFilter};
+use crate::database::MessageDb;
+
+/// Edited messages of one person.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EditCount {
+    pub canonical_person_id: i64,
+    pub name: Option<String>,
+    pub edited_messages: i64,
+    /// Edits over all those messages.
+    pub edits: i64,
+}
+
+/// Unsent messages of one person.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UnsentCount {
+    pub canonical_person_id: i64,
+    pub name: Option<String>,
+    pub unsent: i64,
+    /// All messages they sent, unsent ones included.
+    pub messages: i64,
+}
+
+/// People who edited the most messages, most first (ties by id); at most `limit`.
+pub fn most_edited(db: &MessageDb, filter: &StatsFilter, limit: usize) -> Result<Vec<EditCount>> {
+    query(
+        db,
+        filter,
+        &format!(
+            "SELECT cp.id, cp.display_name, COUNT(*), SUM(m.edit_count)
+               FROM message m
+               JOIN person p ON p.id = m.sender
+               JOIN canonical_person cp ON cp.id = p.canonical_person_id
+               JOIN conversation c ON c.id = p.conversation_id
+              WHERE m.edited AND {{filter}}
+              GROUP BY cp.id
+              ORDER BY 3 DESC, cp.id
+              LIMIT {}",
+            limit
+        ),
+        |r| {
+            Ok(EditCount {
+                canonical_person_id: r.get(0)?,
+                name: r.get(1)?,
+                edited_messages: r.get(2)?,
+                edits: r.get(3)?,
+            })
+        },
+    )
+}
+
+/// People who unsent the most messages, most first (ties by id); at most `limit`. Messages
+/// removed by someone else (a Messenger Kids parent) do not count.
+pub fn most_unsent(db: &MessageDb, filter: &StatsFilter, limit: usize) -> Result<Vec<UnsentCount>> {
+    query(
+        db,
+        filter,
+        &format!(
+            "SELECT cp.id, cp.display_name,
+                    SUM(m.unsent AND COALESCE(m.unsent_reason, 'sender') = 'sender'), COUNT(*)
+               FROM message m
+               JOIN person p ON p.id = m.sender
+               JOIN canonical_person cp ON cp.id = p.canonical_person_id
+               JOIN conversation c ON c.id = p.conversation_id
+              WHERE {{filter}}
+              GROUP BY cp.id
+             HAVING SUM(m.unsent AND COALESCE(m.unsent_reason, 'sender') = 'sender') > 0
+              ORDER BY 3 DESC, cp.id
+              LIMIT {}",
+            limit
+        ),
+        |r| {
+            Ok(UnsentCount {
+                canonical_person_id: r.get(0)?,
+                name: r.get(1)?,
+                unsent: r.get(2)?,
+                messages: r.get(3)?,
+            })
+        },
+    )
+}
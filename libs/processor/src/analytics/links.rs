@@ -0,0 +1,89 @@
+//! Shared links: which sites get shared and who shares them.
+
+use anyhow::Result;
+
+use super::{query, StatsFilter};
+use crate::database::MessageDb;
+
+/// Links shared to one domain.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DomainCount {
+    pub domain: String,
+    pub links: i64,
+    /// People who shared at least one of the links.
+    pub sharers: i64,
+}
+
+/// Links shared by one person.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LinkSharer {
+    pub canonical_person_id: i64,
+    pub name: Option<String>,
+    pub links: i64,
+    /// Distinct domains among the links.
+    pub domains: i64,
+}
+
+/// Domains with the most shared links, most first (ties by name); at most `limit`.
+pub fn most_shared_domains(
+    db: &MessageDb,
+    filter: &StatsFilter,
+    limit: usize,
+) -> Result<Vec<DomainCount>> {
+    query(
+        db,
+        filter,
+        &format!(
+            "SELECT l.domain, COUNT(*), COUNT(DISTINCT p.canonical_person_id)
+               FROM message_link l
+               JOIN message m ON m.id = l.message_id
+               JOIN person p ON p.id = m.sender
+               JOIN conversation c ON c.id = p.conversation_id
+              WHERE l.domain IS NOT NULL AND {{filter}}
+              GROUP BY l.domain
+              ORDER BY 2 DESC, l.domain
+              LIMIT {}",
+            limit
+        ),
+        |r| {
+            Ok(DomainCount {
+                domain: r.get(0)?,
+                links: r.get(1)?,
+                sharers: r.get(2)?,
+            })
+        },
+    )
+}
+
+/// People who shared the most links, most first (ties by id); at most `limit`.
+pub fn top_link_sharers(
+    db: &MessageDb,
+    filter: &StatsFilter,
+    limit: usize,
+) -> Result<Vec<LinkSharer>> {
+    query(
+        db,
+        filter,
+        &format!(
+            "SELECT cp.id, cp.display_name, COUNT(*), COUNT(DISTINCT l.domain)
+               FROM message_link l
+               JOIN message m ON m.id = l.message_id
+               JOIN person p ON p.id = m.sender
+               JOIN canonical_person cp ON cp.id = p.canonical_person_id
+               JOIN conversation c ON c.id = p.conversation_id
+              WHERE {{filter}}
+              GROUP BY cp.id
+              ORDER BY 3 DESC, cp.id
+              LIMIT {}",
+            limit
+        ),
+        |r| {
+            Ok(LinkSharer {
+                canonical_person_id: r.get(0)?,
+                name: r.get(1)?,
+                links: r.get(2)?,
+                domains: r.get(3)?,
+            })
+        },
+    )
+}
@@ -0,0 +1,80 @@
+//! Attachments sent per person, with stickers kept apart from photos and polls, locations
+//! and contact cards counted apart from text.
+
+use anyhow::Result;
+
+use super::{query, StatsFilter};
+use crate::database::MessageDb;
+
+/// Attachments one person sent, by kind.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct MediaSent {
+    pub canonical_person_id: i64,
+    pub name: Option<String>,
+    pub photos: i64,
+    pub gifs: i64,
+    pub videos: i64,
+    pub audio: i64,
+    /// Stickers, including the thumbs-up ones.
+    pub stickers: i64,
+    /// Thumbs-up "like" stickers, the sticker counterpart of a reaction.
+    pub like_stickers: i64,
+    pub polls: i64,
+    /// Location pins and live locations.
+    pub locations: i64,
+    pub contacts: i64,
+}
+
+/// People who sent the most attachments of any kind, most first (ties by id); at most
+/// `limit`.
+pub fn media_sent_by_person(
+    db: &MessageDb,
+    filter: &StatsFilter,
+    limit: usize,
+) -> Result<Vec<MediaSent>> {
+    query(
+        db,
+        filter,
+        &format!(
+            "WITH media(message_id, kind) AS (
+                 SELECT message_id, 'photo' FROM message_image
+                 UNION ALL SELECT message_id, 'gif' FROM message_gif
+                 UNION ALL SELECT message_id, 'video' FROM message_video
+                 UNION ALL SELECT message_id, 'audio' FROM message_audio
+                 UNION ALL SELECT message_id, CASE WHEN is_like THEN 'like' ELSE 'sticker' END
+                             FROM message_sticker
+                 UNION ALL SELECT message_id, 'poll' FROM message_poll
+                 UNION ALL SELECT message_id, 'location' FROM message_location
+                 UNION ALL SELECT message_id, 'contact' FROM message_contact)
+             SELECT cp.id, cp.display_name, SUM(a.kind = 'photo'), SUM(a.kind = 'gif'),
+                    SUM(a.kind = 'video'), SUM(a.kind = 'audio'),
+                    SUM(a.kind IN ('sticker', 'like')), SUM(a.kind = 'like'),
+                    SUM(a.kind = 'poll'), SUM(a.kind = 'location'), SUM(a.kind = 'contact')
+               FROM media a
+               JOIN message m ON m.id = a.message_id
+               JOIN person p ON p.id = m.sender
+               JOIN canonical_person cp ON cp.id = p.canonical_person_id
+               JOIN conversation c ON c.id = p.conversation_id
+              WHERE {{filter}}
+              GROUP BY cp.id
+              ORDER BY COUNT(*) DESC, cp.id
+              LIMIT {}",
+            limit
+        ),
+        |r| {
+            Ok(MediaSent {
+                canonical_person_id: r.get(0)?,
+                name: r.get(1)?,
+                photos: r.get(2)?,
+                gifs: r.get(3)?,
+                videos: r.get(4)?,
+                audio: r.get(5)?,
+                stickers: r.get(6)?,
+                like_stickers: r.get(7)?,
+                polls: r.get(8)?,
+                locations: r.get(9)?,
+                contacts: r.get(10)?,
+            })
+        },
+    )
+}
@@ -0,0 +1,104 @@
+//! Group membership over time, and message shares counted only while people were members.
+
+use anyhow::Result;
+use rusqlite::params;
+
+use super::{query, StatsFilter};
+use crate::database::MessageDb;
+
+/// One span during which a person was a member of a conversation.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MembershipInterval {
+    pub canonical_person_id: i64,
+    pub name: Option<String>,
+    /// Epoch seconds; `None` if already a member when the export starts.
+    pub joined_at: Option<i64>,
+    /// Epoch seconds; `None` if still a member.
+    pub left_at: Option<i64>,
+}
+
+/// A person's share of the messages sent while they were a member.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Participation {
+    pub canonical_person_id: i64,
+    pub name: Option<String>,
+    /// Messages they sent while a member.
+    pub messages: i64,
+    /// Messages anyone sent in their conversations while they were a member.
+    pub group_messages: i64,
+    /// `messages / group_messages`.
+    pub share: f64,
+}
+
+/// Membership intervals in a canonical conversation, by start (unknown first), then person.
+pub fn memberships(
+    db: &MessageDb,
+    canonical_conversation_id: i64,
+) -> Result<Vec<MembershipInterval>> {
+    let mut stmt = db.conn().prepare(
+        "SELECT cp.id, cp.display_name, pm.joined_at, pm.left_at
+           FROM person_membership pm
+           JOIN person p ON p.id = pm.person_id
+           JOIN canonical_person cp ON cp.id = p.canonical_person_id
+           JOIN conversation c ON c.id = p.conversation_id
+          WHERE c.canonical_conversation_id = ?1
+          ORDER BY pm.joined_at IS NOT NULL, pm.joined_at, cp.id, pm.id",
+    )?;
+    let rows = stmt
+        .query_map(params![canonical_conversation_id], |r| {
+            Ok(MembershipInterval {
+                canonical_person_id: r.get(0)?,
+                name: r.get(1)?,
+                joined_at: r.get(2)?,
+                left_at: r.get(3)?,
+            })
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    Ok(rows)
+}
+
+/// People by their share of the messages sent while they were members, highest first (ties
+/// by id); at most `limit`. Unlike a share of all messages, this does not penalize people
+/// who joined late or left early. System messages are not counted.
+pub fn participation_rates(
+    db: &MessageDb,
+    filter: &StatsFilter,
+    limit: usize,
+) -> Result<Vec<Participation>> {
+    query(
+        db,
+        filter,
+        &format!(
+            "WITH events AS (SELECT DISTINCT message_id FROM conversation_event),
+                  counted AS (
+                    SELECT m.sender, m.sent_at, p.conversation_id
+                      FROM message m
+                      JOIN person p ON p.id = m.sender
+                      JOIN conversation c ON c.id = p.conversation_id
+                      LEFT JOIN events e ON e.message_id = m.id
+                     WHERE e.message_id IS NULL AND {{filter}})
+             SELECT cp.id, cp.display_name, SUM(x.sender = p.id), COUNT(*)
+               FROM person_membership pm
+               JOIN person p ON p.id = pm.person_id
+               JOIN canonical_person cp ON cp.id = p.canonical_person_id
+               JOIN counted x ON x.conversation_id = p.conversation_id
+                AND (pm.joined_at IS NULL OR x.sent_at >= pm.joined_at)
+                AND (pm.left_at IS NULL OR x.sent_at <= pm.left_at)
+              GROUP BY cp.id
+              ORDER BY CAST(SUM(x.sender = p.id) AS REAL) / COUNT(*) DESC, cp.id
+              LIMIT {}",
+            limit
+        ),
+        |r| {
+            let messages: i64 = r.get(2)?;
+            let group_messages: i64 = r.get(3)?;
+            Ok(Participation {
+                canonical_person_id: r.get(0)?,
+                name: r.get(1)?,
+                messages,
+                group_messages,
+                share: messages as f64 / group_messages as f64,
+            })
+        },
+    )
+}
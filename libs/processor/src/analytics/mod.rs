@@ -0,0 +1,88 @@
+//! Statistics computed from the normalized database.
+//!
+//! Every query takes a [`StatsFilter`] narrowing it to part of the database; people and
+//! conversations are reported by their canonical ids, so duplicates merged across exports
+//! count once.
+
+pub mod edits;
+pub mod links;
+pub mod media;
+pub mod membership;
+pub mod replies;
+
+pub use edits::*;
+pub use links::*;
+pub use media::*;
+pub use membership::*;
+pub use replies::*;
+
+use anyhow::Result;
+use rusqlite::types::Value;
+use rusqlite::{params_from_iter, Row};
+
+use crate::database::{ArchiveCategory, MessageDb};
+
+/// Which messages a statistic covers. The default covers everything.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct StatsFilter {
+    /// Only messages of this canonical conversation.
+    pub canonical_conversation_id: Option<i64>,
+    /// Only messages sent at or after this time (epoch seconds).
+    pub since: Option<i64>,
+    /// Only messages sent before this time (epoch seconds).
+    pub until: Option<i64>,
+    /// Leave out conversations exported from these folders, e.g. message requests.
+    /// Conversations without a category (E2E exports) are always kept.
+    pub exclude_categories: Vec<ArchiveCategory>,
+}
+
+impl StatsFilter {
+    /// SQL condition over `m` (message) and `c` (its conversation) with its parameters.
+    fn condition(&self) -> (String, Vec<Value>) {
+        let mut clauses = vec!["1".to_string()];
+        let mut params = Vec::new();
+        let mut add = |clause: &str, value: i64| {
+            params.push(Value::Integer(value));
+            clauses.push(clause.replace('?', &format!("?{}", params.len())));
+        };
+        if let Some(id) = self.canonical_conversation_id {
+            add("c.canonical_conversation_id = ?", id);
+        }
+        if let Some(since) = self.since {
+            add("m.sent_at >= ?", since);
+        }
+        if let Some(until) = self.until {
+            add("m.sent_at < ?", until);
+        }
+        if !self.exclude_categories.is_empty() {
+            let placeholders: Vec<String> = self
+                .exclude_categories
+                .iter()
+                .map(|category| {
+                    params.push(Value::Text(category.as_str().to_string()));
+                    format!("?{}", params.len())
+                })
+                .collect();
+            clauses.push(format!(
+                "(c.archive_category IS NULL OR c.archive_category NOT IN ({}))",
+                placeholders.join(", ")
+            ));
+        }
+        (clauses.join(" AND "), params)
+    }
+}
+
+/// Run `sql`, where `{filter}` stands for the filter's condition, and map every row.
+fn query<T>(
+    db: &MessageDb,
+    filter: &StatsFilter,
+    sql: &str,
+    map: impl FnMut(&Row<'_>) -> rusqlite::Result<T>,
+) -> Result<Vec<T>> {
+    let (condition, params) = filter.condition();
+    let mut stmt = db.conn().prepare(&sql.replace("{filter}", &condition))?;
+    let rows = stmt
+        .query_map(params_from_iter(params), map)?
+        .collect::<rusqlite::Result<Vec<T>>>()?;
+    Ok(rows)
+}
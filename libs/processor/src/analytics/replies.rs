@@ -0,0 +1,88 @@
+//! Replies: who answers whom, and how long reply chains get.
+
+use anyhow::Result;
+
+use super::{query, StatsFilter};
+use crate::database::MessageDb;
+
+/// Direct replies from one person to another.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ReplyPair {
+    pub from_canonical_person_id: i64,
+    pub from_name: Option<String>,
+    pub to_canonical_person_id: i64,
+    pub to_name: Option<String>,
+    pub replies: i64,
+}
+
+/// Number of reply chains reaching one depth.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ChainDepth {
+    /// Replies on the longest path down from the chain's first message.
+    pub depth: i64,
+    pub chains: i64,
+}
+
+/// Pairs of people by how often the first replied to the second, most first (ties by ids);
+/// at most `limit`. Replies to one's own messages are left out.
+pub fn reply_pairs(db: &MessageDb, filter: &StatsFilter, limit: usize) -> Result<Vec<ReplyPair>> {
+    query(
+        db,
+        filter,
+        &format!(
+            "SELECT fp.id, fp.display_name, tp.id, tp.display_name, COUNT(*)
+               FROM message m
+               JOIN person p ON p.id = m.sender
+               JOIN conversation c ON c.id = p.conversation_id
+               JOIN canonical_person fp ON fp.id = p.canonical_person_id
+               JOIN message t ON t.id = m.reply_to_message_id
+               JOIN person tpp ON tpp.id = t.sender
+               JOIN canonical_person tp ON tp.id = tpp.canonical_person_id
+              WHERE fp.id != tp.id AND {{filter}}
+              GROUP BY fp.id, tp.id
+              ORDER BY 5 DESC, fp.id, tp.id
+              LIMIT {}",
+            limit
+        ),
+        |r| {
+            Ok(ReplyPair {
+                from_canonical_person_id: r.get(0)?,
+                from_name: r.get(1)?,
+                to_canonical_person_id: r.get(2)?,
+                to_name: r.get(3)?,
+                replies: r.get(4)?,
+            })
+        },
+    )
+}
+
+/// Reply chains counted by depth, shallowest first. A chain starts at a message that is
+/// not itself a reply and that got at least one; the filter applies to that message.
+pub fn reply_chain_depths(db: &MessageDb, filter: &StatsFilter) -> Result<Vec<ChainDepth>> {
+    query(
+        db,
+        filter,
+        "WITH RECURSIVE chain(root, id, depth) AS (
+             SELECT m.id, m.id, 0
+               FROM message m
+               JOIN person p ON p.id = m.sender
+               JOIN conversation c ON c.id = p.conversation_id
+              WHERE m.reply_to_message_id IS NULL AND {filter}
+                AND EXISTS (SELECT 1 FROM message r WHERE r.reply_to_message_id = m.id)
+             UNION ALL
+             SELECT chain.root, r.id, chain.depth + 1
+               FROM message r
+               JOIN chain ON r.reply_to_message_id = chain.id
+              WHERE chain.depth < 1000)
+         SELECT depth, COUNT(*)
+           FROM (SELECT root, MAX(depth) AS depth FROM chain GROUP BY root)
+          GROUP BY depth
+          ORDER BY depth",
+        |r| {
+            Ok(ChainDepth {
+                depth: r.get(0)?,
+                chains: r.get(1)?,
+            })
+        },
+    )
+}
@@ -0,0 +1,754 @@
+//! Bulk insert fast path for imports.
+//!
+//! Instead of one statement (and one `last_insert_rowid`) per row, messages are written from
+//! pre-built [`MessageRecord`]s: their ids are allocated as one contiguous range up front and
+//! each table is filled with multi-row `INSERT`s. [`ImportTuning`] and [`DeferredIndexes`]
+//! cover the connection-level side (cache size, index maintenance) for the duration of an
+//! import.
+
+use std::ops::Range;
+
+use anyhow::{Context, Result};
+use rusqlite::types::Value;
+use rusqlite::{params_from_iter, Connection};
+
+use super::{MessageDb, WriteBatch};
+use crate::utils::audio::AudioInfo;
+use crate::utils::image::ImageInfo;
+use crate::utils::links::url_domain;
+use crate::utils::video::VideoInfo;
+
+/// Rows per multi-row `INSERT`; keeps statements well below SQLite's bound-parameter limit.
+const ROWS_PER_STATEMENT: usize = 128;
+
+/// Cache size used while importing, in KiB (negative values are KiB for `PRAGMA cache_size`).
+const IMPORT_CACHE_KIB: i64 = 64 * 1024;
+
+/// Tables whose secondary indexes are worth rebuilding once instead of maintaining per row.
+const DEFERRED_INDEX_TABLES: &[&str] = &[
+    "message",
+    "message_text",
+    "message_image",
+    "message_video",
+    "message_gif",
+    "message_sticker",
+    "message_audio",
+    "message_call",
+    "message_link",
+    "message_edit",
+    "message_poll",
+    "message_poll_option",
+    "message_poll_vote",
+    "message_location",
+    "message_contact",
+    "conversation_event",
+    "reaction",
+];
+
+/// A file attached to a message.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Attachment<T> {
+    /// Path of the file inside the export, as written in the thread file.
+    pub uri: String,
+    /// Details probed from the file, if it was found and understood.
+    pub info: Option<T>,
+    /// Path of the extracted copy relative to the media store directory, if extracted.
+    pub stored_path: Option<String>,
+}
+
+impl<T> Attachment<T> {
+    /// An attachment that was not extracted.
+    pub fn new(uri: impl Into<String>, info: Option<T>) -> Self {
+        Self {
+            uri: uri.into(),
+            info,
+            stored_path: None,
+        }
+    }
+}
+
+/// A sticker sent in a message.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Sticker {
+    /// The sticker image with its probed format, size and animation.
+    pub image: Attachment<ImageInfo>,
+    /// The thumbs-up "like" sticker, a reaction-like gesture rather than content.
+    pub is_like: bool,
+}
+
+/// A call logged in a conversation.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Call {
+    /// Length of the call; 0 when it was not answered.
+    pub duration_seconds: i64,
+    pub missed: bool,
+    /// Video call rather than an audio-only one.
+    pub video: bool,
+}
+
+impl Call {
+    /// Value of the `call_type` column.
+    pub fn call_type(&self) -> &'static str {
+        if self.video {
+            "video"
+        } else {
+            "audio"
+        }
+    }
+}
+
+/// A link shared in a message.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Link {
+    pub url: String,
+    /// Preview text of a shared link (Facebook `share.share_text`).
+    pub share_text: Option<String>,
+    /// Normalized domain of `url`, see [`url_domain`].
+    pub domain: Option<String>,
+}
+
+impl Link {
+    /// A link to `url`, with its domain derived from it.
+    pub fn new(url: impl Into<String>, share_text: Option<String>) -> Self {
+        let url = url.into();
+        Self {
+            domain: url_domain(&url),
+            url,
+            share_text,
+        }
+    }
+}
+
+/// A poll created in a message.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Poll {
+    pub question: String,
+    pub options: Vec<PollOption>,
+}
+
+/// One answer of a [`Poll`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PollOption {
+    pub text: String,
+    /// Votes as the export counts them; raised to `voter_ids.len()` when stored.
+    pub vote_count: i64,
+    /// People known to have voted for it.
+    pub voter_ids: Vec<i64>,
+}
+
+impl PollOption {
+    /// Value of the `vote_count` column.
+    pub fn votes(&self) -> i64 {
+        self.vote_count.max(self.voter_ids.len() as i64)
+    }
+}
+
+/// A location pin or live location sent in a message.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Location {
+    pub latitude: f64,
+    pub longitude: f64,
+    /// Place name or address.
+    pub name: Option<String>,
+    /// Live location shared for a while rather than a fixed pin.
+    pub is_live: bool,
+}
+
+/// A contact card shared in a message.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Contact {
+    pub name: Option<String>,
+    pub phone_number: Option<String>,
+    pub email: Option<String>,
+}
+
+/// Where a message was read from, to trace its row back to the export JSON.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct MessageSource {
+    pub export_id: i64,
+    /// Archive as passed to the import.
+    pub archive_path: String,
+    /// Thread JSON entry inside the archive.
+    pub entry_name: String,
+    /// Position in the entry's `messages` array.
+    pub message_index: i64,
+}
+
+/// What a system message records.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EventKind {
+    MemberAdded,
+    MemberRemoved,
+    /// The subject left on their own.
+    MemberLeft,
+    GroupRenamed,
+    GroupPhotoChanged,
+    NicknameSet,
+    NicknameCleared,
+    ThemeChanged,
+    EmojiChanged,
+    CallStarted,
+    CallEnded,
+}
+
+impl EventKind {
+    /// Value of the `kind` column.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            EventKind::MemberAdded => "member_added",
+            EventKind::MemberRemoved => "member_removed",
+            EventKind::MemberLeft => "member_left",
+            EventKind::GroupRenamed => "group_renamed",
+            EventKind::GroupPhotoChanged => "group_photo_changed",
+            EventKind::NicknameSet => "nickname_set",
+            EventKind::NicknameCleared => "nickname_cleared",
+            EventKind::ThemeChanged => "theme_changed",
+            EventKind::EmojiChanged => "emoji_changed",
+            EventKind::CallStarted => "call_started",
+            EventKind::CallEnded => "call_ended",
+        }
+    }
+}
+
+/// A conversation event logged by a system message; its sender is the actor.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ConversationEvent {
+    pub kind: EventKind,
+    /// Person the event is about (added, removed, left, renamed); `None` for the export
+    /// owner and for events not about a person.
+    pub subject_name: Option<String>,
+    /// New group name, nickname, theme or emoji.
+    pub value: Option<String>,
+    /// Text of the system message.
+    pub raw_text: Option<String>,
+}
+
+/// Why a message was unsent.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum UnsentReason {
+    /// The sender took it back.
+    Sender,
+    /// A parent removed an image from a Messenger Kids conversation.
+    MessengerKidParent,
+}
+
+impl UnsentReason {
+    /// Value of the `unsent_reason` column.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            UnsentReason::Sender => "sender",
+            UnsentReason::MessengerKidParent => "messenger_kid_parent",
+        }
+    }
+}
+
+/// An earlier version of an edited message.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MessageEdit {
+    pub previous_text: String,
+    /// When this version was sent (epoch seconds), if known.
+    pub sent_at: Option<i64>,
+}
+
+/// A message with everything attached to it, ready for [`WriteBatch::insert_messages_bulk`].
+///
+/// Attachments of each kind are inserted in the order given.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MessageRecord {
+    pub sender_id: i64,
+    /// Epoch seconds.
+    pub sent_at: i64,
+    pub unsent: bool,
+    /// Why it was unsent, if it was and the export says.
+    pub unsent_reason: Option<UnsentReason>,
+    /// Times the message was edited; 0 if never.
+    pub edit_count: i64,
+    /// Earlier versions, oldest first, where the export keeps them.
+    pub edits: Vec<MessageEdit>,
+    /// Message this one replies to; it must already exist or be in the same insert.
+    pub reply_to_message_id: Option<i64>,
+    pub texts: Vec<String>,
+    /// Images with their probed format, size and capture time.
+    pub images: Vec<Attachment<ImageInfo>>,
+    /// Videos with their probed duration, size and codec.
+    pub videos: Vec<Attachment<VideoInfo>>,
+    /// GIFs with their probed size and animation.
+    pub gifs: Vec<Attachment<ImageInfo>>,
+    pub stickers: Vec<Sticker>,
+    /// Audio with its probed duration and codec.
+    pub audios: Vec<Attachment<AudioInfo>>,
+    /// The call this message logs, if any.
+    pub call: Option<Call>,
+    /// Shared links and URLs found in the text.
+    pub links: Vec<Link>,
+    pub poll: Option<Poll>,
+    pub location: Option<Location>,
+    pub contacts: Vec<Contact>,
+    /// Events a system message logs; such messages have no `texts`.
+    pub events: Vec<ConversationEvent>,
+    /// Reactor person id and reaction.
+    pub reactions: Vec<(i64, String)>,
+    /// Where the message was read from, when the import records it.
+    pub source: Option<MessageSource>,
+}
+
+/// Connection pragmas changed for an import; [`restore`](Self::restore) puts them back.
+#[derive(Debug)]
+pub struct ImportTuning {
+    cache_size: i64,
+    temp_store: i64,
+}
+
+/// Secondary indexes dropped for the rest of a write batch; [`restore`](Self::restore)
+/// recreates them from their original definitions.
+#[derive(Debug)]
+#[must_use = "dropped indexes are only recreated by `restore`"]
+pub struct DeferredIndexes {
+    definitions: Vec<String>,
+}
+
+impl MessageDb {
+    /// Switch the connection to import-time pragmas (a larger page cache, in-memory temp
+    /// storage) and return the previous settings.
+    pub fn tune_for_import(&self) -> Result<ImportTuning> {
+        let conn = self.conn();
+        let saved = ImportTuning {
+            cache_size: pragma_i64(conn, "cache_size")?,
+            temp_store: pragma_i64(conn, "temp_store")?,
+        };
+        conn.pragma_update(None, "cache_size", -IMPORT_CACHE_KIB)
+            .context("setting PRAGMA cache_size for import")?;
+        conn.pragma_update(None, "temp_store", "MEMORY")
+            .context("setting PRAGMA temp_store=MEMORY")?;
+        Ok(saved)
+    }
+}
+
+impl ImportTuning {
+    /// Restore the pragmas saved by [`MessageDb::tune_for_import`].
+    pub fn restore(self, db: &MessageDb) -> Result<()> {
+        let conn = db.conn();
+        conn.pragma_update(None, "cache_size", self.cache_size)
+            .context("restoring PRAGMA cache_size")?;
+        conn.pragma_update(None, "temp_store", self.temp_store)
+            .context("restoring PRAGMA temp_store")?;
+        Ok(())
+    }
+}
+
+fn pragma_i64(conn: &Connection, name: &str) -> Result<i64> {
+    conn.pragma_query_value(None, name, |r| r.get(0))
+        .with_context(|| format!("reading PRAGMA {}", name))
+}
+
+impl WriteBatch<'_> {
+    /// Whether every table [`defer_indexes`](Self::defer_indexes) touches is empty, i.e.
+    /// rebuilding their indexes costs no more than the rows about to be written.
+    pub fn message_tables_empty(&self) -> Result<bool> {
+        for table in DEFERRED_INDEX_TABLES {
+            let any: bool = self
+                .tx()
+                .query_row(
+                    &format!("SELECT EXISTS(SELECT 1 FROM {})", table),
+                    [],
+                    |r| r.get(0),
+                )
+                .with_context(|| format!("checking whether {} is empty", table))?;
+            if any {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    /// Drop the non-unique indexes of the message tables until [`DeferredIndexes::restore`].
+    ///
+    /// This runs inside the batch's transaction, so a rollback brings the indexes back too;
+    /// restore them before the next commit or checkpoint.
+    pub fn defer_indexes(&mut self) -> Result<DeferredIndexes> {
+        let tx = self.tx();
+        let mut definitions = Vec::new();
+        let mut names = Vec::new();
+        {
+            let tables = DEFERRED_INDEX_TABLES
+                .iter()
+                .map(|t| format!("'{}'", t))
+                .collect::<Vec<_>>()
+                .join(",");
+            let mut stmt = tx.prepare(&format!(
+                "SELECT name, sql FROM sqlite_master
+                  WHERE type = 'index' AND sql IS NOT NULL
+                    AND sql NOT LIKE 'CREATE UNIQUE%' AND tbl_name IN ({})
+                  ORDER BY name",
+                tables
+            ))?;
+            let rows = stmt.query_map([], |r| Ok((r.get::<_, String>(0)?, r.get(1)?)))?;
+            for row in rows {
+                let (name, sql) = row?;
+                names.push(name);
+                definitions.push(sql);
+            }
+        }
+        for name in names {
+            tx.execute(&format!("DROP INDEX \"{}\"", name), [])
+                .with_context(|| format!("dropping index {}", name))?;
+        }
+        Ok(DeferredIndexes { definitions })
+    }
+
+    /// Insert `records` under a freshly allocated, contiguous range of message ids (one per
+    /// record, in order), which is returned.
+    pub fn insert_messages_bulk(&mut self, records: &[MessageRecord]) -> Result<Range<i64>> {
+        let first_id = self.next_message_id()?;
+        self.insert_messages_bulk_at(first_id, records)?;
+        Ok(first_id..first_id + records.len() as i64)
+    }
+
+    /// Insert `records` under ids `first_id`, `first_id + 1`, …, which must be free.
+    pub fn insert_messages_bulk_at(
+        &mut self,
+        first_id: i64,
+        records: &[MessageRecord],
+    ) -> Result<()> {
+        if records.is_empty() {
+            return Ok(());
+        }
+        let mut messages = Vec::with_capacity(records.len() * 8);
+        let mut edits = Vec::new();
+        let mut texts = Vec::new();
+        let mut images = Vec::new();
+        let mut videos = Vec::new();
+        let mut gifs = Vec::new();
+        let mut stickers = Vec::new();
+        let mut audios = Vec::new();
+        let mut calls = Vec::new();
+        let mut links = Vec::new();
+        let mut polls = Vec::new();
+        let mut poll_options = Vec::new();
+        let mut poll_votes = Vec::new();
+        let mut locations = Vec::new();
+        let mut contacts = Vec::new();
+        let mut events = Vec::new();
+        let mut reactions = Vec::new();
+        let mut sources = Vec::new();
+        let int = |v: Option<i64>| v.map_or(Value::Null, Value::Integer);
+        let text = |v: Option<&str>| v.map_or(Value::Null, |t| Value::Text(t.to_string()));
+        // Poll options are referenced by votes, so polls and options get explicit ids too.
+        let (mut poll_id, mut option_id) = if records.iter().any(|r| r.poll.is_some()) {
+            let tx = self.tx();
+            (
+                next_id(tx, "message_poll")?,
+                next_id(tx, "message_poll_option")?,
+            )
+        } else {
+            (0, 0)
+        };
+        for (id, record) in (first_id..).zip(records) {
+            messages.extend([
+                Value::Integer(id),
+                Value::Integer(record.sender_id),
+                Value::Integer(record.sent_at),
+                Value::Integer(record.unsent as i64),
+                int(record.reply_to_message_id),
+                Value::Integer((record.edit_count > 0) as i64),
+                Value::Integer(record.edit_count),
+                text(record.unsent_reason.map(UnsentReason::as_str)),
+            ]);
+            for edit in &record.edits {
+                edits.extend([
+                    Value::Integer(id),
+                    Value::Text(edit.previous_text.clone()),
+                    int(edit.sent_at),
+                ]);
+            }
+            for text in &record.texts {
+                texts.extend([Value::Integer(id), Value::Text(text.clone())]);
+            }
+            for a in &record.images {
+                let info = a.info.as_ref();
+                images.extend([
+                    Value::Integer(id),
+                    Value::Text(a.uri.clone()),
+                    text(info.map(|i| i.format)),
+                    int(info.and_then(|i| i.width).map(i64::from)),
+                    int(info.and_then(|i| i.height).map(i64::from)),
+                    text(info.and_then(|i| i.taken_at.as_deref())),
+                    text(a.stored_path.as_deref()),
+                ]);
+            }
+            for a in &record.videos {
+                let info = a.info.as_ref();
+                videos.extend([
+                    Value::Integer(id),
+                    Value::Text(a.uri.clone()),
+                    int(info.map(|i| i.duration_ms)),
+                    int(info.and_then(|i| i.width).map(i64::from)),
+                    int(info.and_then(|i| i.height).map(i64::from)),
+                    text(info.and_then(|i| i.codec)),
+                    text(a.stored_path.as_deref()),
+                ]);
+            }
+            for a in &record.gifs {
+                let info = a.info.as_ref();
+                gifs.extend([
+                    Value::Integer(id),
+                    Value::Text(a.uri.clone()),
+                    int(info.and_then(|i| i.width).map(i64::from)),
+                    int(info.and_then(|i| i.height).map(i64::from)),
+                    int(info.and_then(|i| i.frame_count).map(i64::from)),
+                    int(info.and_then(|i| i.duration_ms)),
+                    text(a.stored_path.as_deref()),
+                ]);
+            }
+            for s in &record.stickers {
+                let info = s.image.info.as_ref();
+                stickers.extend([
+                    Value::Integer(id),
+                    Value::Text(s.image.uri.clone()),
+                    text(info.map(|i| i.format)),
+                    int(info.and_then(|i| i.width).map(i64::from)),
+                    int(info.and_then(|i| i.height).map(i64::from)),
+                    int(info.and_then(|i| i.frame_count).map(i64::from)),
+                    Value::Integer(s.is_like as i64),
+                    text(s.image.stored_path.as_deref()),
+                ]);
+            }
+            for a in &record.audios {
+                let info = a.info.as_ref();
+                audios.extend([
+                    Value::Integer(id),
+                    Value::Text(a.uri.clone()),
+                    info.and_then(AudioInfo::whole_seconds)
+                        .map_or(Value::Null, Value::Integer),
+                    info.map_or(Value::Null, |i| Value::Integer(i.duration_ms)),
+                    text(info.and_then(|i| i.codec)),
+                    text(a.stored_path.as_deref()),
+                ]);
+            }
+            if let Some(call) = &record.call {
+                calls.extend([
+                    Value::Integer(id),
+                    Value::Integer(call.duration_seconds),
+                    Value::Integer(call.missed as i64),
+                    Value::Text(call.call_type().to_string()),
+                ]);
+            }
+            for link in &record.links {
+                links.extend([
+                    Value::Integer(id),
+                    Value::Text(link.url.clone()),
+                    text(link.share_text.as_deref()),
+                    text(link.domain.as_deref()),
+                ]);
+            }
+            if let Some(poll) = &record.poll {
+                polls.extend([
+                    Value::Integer(poll_id),
+                    Value::Integer(id),
+                    Value::Text(poll.question.clone()),
+                ]);
+                for option in &poll.options {
+                    poll_options.extend([
+                        Value::Integer(option_id),
+                        Value::Integer(poll_id),
+                        Value::Text(option.text.clone()),
+                        Value::Integer(option.votes()),
+                    ]);
+                    for voter_id in &option.voter_ids {
+                        poll_votes.extend([Value::Integer(option_id), Value::Integer(*voter_id)]);
+                    }
+                    option_id += 1;
+                }
+                poll_id += 1;
+            }
+            if let Some(location) = &record.location {
+                locations.extend([
+                    Value::Integer(id),
+                    Value::Real(location.latitude),
+                    Value::Real(location.longitude),
+                    text(location.name.as_deref()),
+                    Value::Integer(location.is_live as i64),
+                ]);
+            }
+            for contact in &record.contacts {
+                contacts.extend([
+                    Value::Integer(id),
+                    text(contact.name.as_deref()),
+                    text(contact.phone_number.as_deref()),
+                    text(contact.email.as_deref()),
+                ]);
+            }
+            for event in &record.events {
+                events.extend([
+                    Value::Integer(id),
+                    Value::Text(event.kind.as_str().to_string()),
+                    text(event.subject_name.as_deref()),
+                    text(event.value.as_deref()),
+                    text(event.raw_text.as_deref()),
+                ]);
+            }
+            for (reactor_id, reaction) in &record.reactions {
+                reactions.extend([
+                    Value::Integer(*reactor_id),
+                    Value::Integer(id),
+                    Value::Text(reaction.clone()),
+                ]);
+            }
+            if let Some(source) = &record.source {
+                sources.extend([
+                    Value::Integer(id),
+                    Value::Integer(source.export_id),
+                    Value::Text(source.archive_path.clone()),
+                    Value::Text(source.entry_name.clone()),
+                    Value::Integer(source.message_index),
+                ]);
+            }
+        }
+
+        let tx = self.tx();
+        insert_rows(
+            tx,
+            "message(id, sender, sent_at, unsent, reply_to_message_id, edited, edit_count,
+                 unsent_reason)",
+            8,
+            &messages,
+        )
+        .context("bulk insert messages")?;
+        insert_rows(
+            tx,
+            "message_edit(message_id, previous_text, sent_at)",
+            3,
+            &edits,
+        )
+        .context("bulk insert message edits")?;
+        insert_rows(tx, "message_text(message_id, text)", 2, &texts)
+            .context("bulk insert message texts")?;
+        insert_rows(
+            tx,
+            "message_image(message_id, image_uri, format, width, height, taken_at, stored_path)",
+            7,
+            &images,
+        )
+        .context("bulk insert message images")?;
+        insert_rows(
+            tx,
+            "message_video(message_id, video_uri, length_ms, width, height, codec, stored_path)",
+            7,
+            &videos,
+        )
+        .context("bulk insert message videos")?;
+        insert_rows(
+            tx,
+            "message_gif(message_id, gif_uri, width, height, frame_count, length_ms, stored_path)",
+            7,
+            &gifs,
+        )
+        .context("bulk insert message gifs")?;
+        insert_rows(
+            tx,
+            "message_sticker(message_id, sticker_uri, format, width, height, frame_count, is_like,
+                 stored_path)",
+            8,
+            &stickers,
+        )
+        .context("bulk insert message stickers")?;
+        insert_rows(
+            tx,
+            "message_audio(message_id, audio_uri, length_seconds, length_ms, codec, stored_path)",
+            6,
+            &audios,
+        )
+        .context("bulk insert message audio")?;
+        insert_rows(
+            tx,
+            "message_call(message_id, duration_seconds, missed, call_type)",
+            4,
+            &calls,
+        )
+        .context("bulk insert message calls")?;
+        insert_rows(
+            tx,
+            "message_link(message_id, url, share_text, domain)",
+            4,
+            &links,
+        )
+        .context("bulk insert message links")?;
+        insert_rows(tx, "message_poll(id, message_id, question)", 3, &polls)
+            .context("bulk insert message polls")?;
+        insert_rows(
+            tx,
+            "message_poll_option(id, poll_id, text, vote_count)",
+            4,
+            &poll_options,
+        )
+        .context("bulk insert message poll options")?;
+        insert_rows(tx, "message_poll_vote(option_id, voter_id)", 2, &poll_votes)
+            .context("bulk insert message poll votes")?;
+        insert_rows(
+            tx,
+            "message_location(message_id, latitude, longitude, name, is_live)",
+            5,
+            &locations,
+        )
+        .context("bulk insert message locations")?;
+        insert_rows(
+            tx,
+            "message_contact(message_id, name, phone_number, email)",
+            4,
+            &contacts,
+        )
+        .context("bulk insert message contacts")?;
+        insert_rows(
+            tx,
+            "conversation_event(message_id, kind, subject_name, value, raw_text)",
+            5,
+            &events,
+        )
+        .context("bulk insert conversation events")?;
+        insert_rows(
+            tx,
+            "reaction(reactor_id, message_id, reaction)",
+            3,
+            &reactions,
+        )
+        .context("bulk insert reactions")?;
+        insert_rows(
+            tx,
+            "message_source(message_id, export_id, archive_path, entry_name, message_index)",
+            5,
+            &sources,
+        )
+        .context("bulk insert message sources")?;
+        Ok(())
+    }
+}
+
+impl DeferredIndexes {
+    /// Recreate the indexes dropped by [`WriteBatch::defer_indexes`].
+    pub fn restore(self, batch: &mut WriteBatch<'_>) -> Result<()> {
+        let tx = batch.tx();
+        for sql in &self.definitions {
+            tx.execute(sql, [])
+                .with_context(|| format!("recreating index: {}", sql))?;
+        }
+        Ok(())
+    }
+}
+
+/// First id above every existing one in `table`.
+fn next_id(conn: &Connection, table: &str) -> Result<i64> {
+    let sql = format!("SELECT COALESCE(MAX(id), 0) + 1 FROM {}", table);
+    Ok(conn.query_row(&sql, [], |r| r.get(0))?)
+}
+
+/// Insert `values` (row-major, `width` columns per row) into `target` with multi-row
+/// statements of up to [`ROWS_PER_STATEMENT`] rows.
+fn insert_rows(conn: &Connection, target: &str, width: usize, values: &[Value]) -> Result<()> {
+    let row = format!("({})", vec!["?"; width].join(","));
+    for chunk in values.chunks(ROWS_PER_STATEMENT * width) {
+        let rows = vec![row.as_str(); chunk.len() / width].join(",");
+        let mut stmt = conn.prepare_cached(&format!("INSERT INTO {} VALUES {}", target, rows))?;
+        stmt.execute(params_from_iter(chunk))?;
+    }
+    Ok(())
+}
@@ -0,0 +1,199 @@
+//! Group membership intervals per person.
+//!
+//! Exports only say who is in a thread when it was exported (the participant list) and, as
+//! system messages, who joined or left along the way. [`WriteBatch::rebuild_memberships`]
+//! walks each person's join and leave events oldest first and writes the spans in between
+//! to `person_membership`. A person listed as a participant stays a member past their last
+//! event; anyone else (including the export owner after leaving, which Facebook marks with
+//! `is_still_participant: false` and by dropping them from the list) is taken to have left
+//! after their last message or reaction. `NULL` bounds mean "before the export starts" and
+//! "still a member".
+
+use std::collections::HashMap;
+
+use anyhow::{Context, Result};
+use rusqlite::params;
+
+use super::WriteBatch;
+
+/// What is known about one person of a conversation.
+#[derive(Default)]
+struct PersonFacts {
+    conversation_id: i64,
+    is_participant: bool,
+    /// First and last time they sent or reacted to a message.
+    active: Option<(i64, i64)>,
+    /// Joins (`true`) and leaves (`false`), oldest first.
+    events: Vec<(i64, bool)>,
+}
+
+impl WriteBatch<'_> {
+    /// Mark a person as listed among their thread's participants.
+    pub fn mark_participant(&mut self, person_id: i64) -> Result<()> {
+        let mut stmt = self
+            .tx()
+            .prepare_cached("UPDATE person SET is_participant = 1 WHERE id = ?1")?;
+        stmt.execute(params![person_id])?;
+        Ok(())
+    }
+
+    /// Derive the membership intervals of everyone in an export's conversations, replacing
+    /// any derived before.
+    pub fn rebuild_memberships(&mut self, export_id: i64) -> Result<()> {
+        let tx = self.tx();
+        tx.execute(
+            "DELETE FROM person_membership WHERE person_id IN (
+               SELECT p.id FROM person p JOIN conversation c ON c.id = p.conversation_id
+                WHERE c.export_id = ?1)",
+            params![export_id],
+        )
+        .context("clear person_membership")?;
+
+        let mut people: HashMap<i64, PersonFacts> = HashMap::new();
+        let mut by_name: HashMap<(i64, String), i64> = HashMap::new();
+        {
+            let mut stmt = tx.prepare(
+                "SELECT p.id, p.conversation_id, p.name, p.is_participant
+                   FROM person p JOIN conversation c ON c.id = p.conversation_id
+                  WHERE c.export_id = ?1",
+            )?;
+            let mut rows = stmt.query(params![export_id])?;
+            while let Some(r) = rows.next()? {
+                let id: i64 = r.get(0)?;
+                let conversation_id: i64 = r.get(1)?;
+                if let Some(name) = r.get::<_, Option<String>>(2)? {
+                    by_name.insert((conversation_id, name), id);
+                }
+                people.insert(
+                    id,
+                    PersonFacts {
+                        conversation_id,
+                        is_participant: r.get(3)?,
+                        ..Default::default()
+                    },
+                );
+            }
+        }
+
+        // First and last activity: messages sent, then messages reacted to.
+        for sql in [
+            "SELECT m.sender, MIN(m.sent_at), MAX(m.sent_at)
+               FROM message m
+               JOIN person p ON p.id = m.sender
+               JOIN conversation c ON c.id = p.conversation_id
+              WHERE c.export_id = ?1
+              GROUP BY m.sender",
+            "SELECT r.reactor_id, MIN(m.sent_at), MAX(m.sent_at)
+               FROM reaction r
+               JOIN message m ON m.id = r.message_id
+               JOIN person p ON p.id = r.reactor_id
+               JOIN conversation c ON c.id = p.conversation_id
+              WHERE c.export_id = ?1
+              GROUP BY r.reactor_id",
+        ] {
+            let mut stmt = tx.prepare(sql)?;
+            let mut rows = stmt.query(params![export_id])?;
+            while let Some(r) = rows.next()? {
+                let (id, first, last): (i64, i64, i64) = (r.get(0)?, r.get(1)?, r.get(2)?);
+                if let Some(person) = people.get_mut(&id) {
+                    person.active = Some(match person.active {
+                        Some((a, b)) => (a.min(first), b.max(last)),
+                        None => (first, last),
+                    });
+                }
+            }
+        }
+
+        // Join and leave events, attributed by name within the conversation. Names the
+        // export never uses for a person (inflected Polish ones, say) are dropped.
+        {
+            let mut stmt = tx.prepare(
+                "SELECT p.conversation_id, e.subject_name, e.kind, m.sent_at
+                   FROM conversation_event e
+                   JOIN message m ON m.id = e.message_id
+                   JOIN person p ON p.id = m.sender
+                   JOIN conversation c ON c.id = p.conversation_id
+                  WHERE c.export_id = ?1 AND e.subject_name IS NOT NULL
+                    AND e.kind IN ('member_added', 'member_removed', 'member_left')
+                  ORDER BY m.sent_at, m.id, e.id",
+            )?;
+            let mut rows = stmt.query(params![export_id])?;
+            while let Some(r) = rows.next()? {
+                let conversation_id: i64 = r.get(0)?;
+                let subject: String = r.get(1)?;
+                let kind: String = r.get(2)?;
+                let at: i64 = r.get(3)?;
+                if let Some(person) = by_name
+                    .get(&(conversation_id, subject))
+                    .and_then(|id| people.get_mut(id))
+                {
+                    person.events.push((at, kind == "member_added"));
+                }
+            }
+        }
+
+        // Without a participant list, everyone counts as still a member.
+        let mut has_list: HashMap<i64, bool> = HashMap::new();
+        for person in people.values() {
+            *has_list.entry(person.conversation_id).or_default() |= person.is_participant;
+        }
+
+        let mut ids: Vec<i64> = people.keys().copied().collect();
+        ids.sort_unstable();
+        let mut stmt = tx.prepare_cached(
+            "INSERT INTO person_membership(person_id, joined_at, left_at) VALUES (?1, ?2, ?3)",
+        )?;
+        for id in ids {
+            let person = &people[&id];
+            let current = person.is_participant || !has_list[&person.conversation_id];
+            for (joined_at, left_at) in intervals(&person.events, person.active, current) {
+                stmt.execute(params![id, joined_at, left_at])
+                    .context("insert person_membership")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Membership intervals of one person from their joins and leaves (oldest first), the span
+/// of their activity and whether they were still a member when exported.
+fn intervals(
+    events: &[(i64, bool)],
+    active: Option<(i64, i64)>,
+    current: bool,
+) -> Vec<(Option<i64>, Option<i64>)> {
+    let mut intervals = Vec::new();
+    // Start of the interval in progress; `Some(None)` if it began before the export.
+    let mut open: Option<Option<i64>> = events.is_empty().then_some(None);
+    for &(at, joined) in events {
+        match (joined, open) {
+            (true, None) => {
+                // Active before their first join: an earlier leave went unrecorded, so
+                // count them as a member from the start.
+                let earlier = intervals.is_empty() && active.is_some_and(|(first, _)| first < at);
+                open = Some(if earlier { None } else { Some(at) });
+            }
+            (false, Some(start)) => {
+                intervals.push((start, Some(at)));
+                open = None;
+            }
+            (false, None) if intervals.is_empty() => intervals.push((None, Some(at))),
+            _ => {}
+        }
+    }
+    if let Some(start) = open {
+        if current {
+            intervals.push((start, None));
+        } else {
+            let last = active.map(|(_, last)| last);
+            let end = match (start, last) {
+                (Some(start), Some(last)) => Some(start.max(last)),
+                (start, last) => start.or(last),
+            };
+            if let Some(end) = end {
+                intervals.push((start, Some(end)));
+            }
+        }
+    }
+    intervals
+}
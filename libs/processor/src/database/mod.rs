@@ -3,6 +3,14 @@
 //! This module handles SQLite database operations, schema management,
 //! and provides a transactional API for importing chat data.
 
+pub mod bulk;
+pub mod membership;
 pub mod schema;
 
-pub use schema::{ConversationType, MessageDb, WriteBatch};
+pub use bulk::{
+    Attachment, Call, Contact, ConversationEvent, DeferredIndexes, EventKind, ImportTuning, Link,
+    Location, MessageEdit, MessageRecord, MessageSource, Poll, PollOption, Sticker, UnsentReason,
+};
+pub use schema::{
+    ArchiveCategory, ConversationMeta, ConversationType, JournalEntry, MessageDb, WriteBatch,
+};
@@ -1,9 +1,13 @@
 use std::path::{Path, PathBuf};
 
 use anyhow::{bail, Context, Result};
-use rusqlite::{params, Connection, OpenFlags, Transaction};
+use rusqlite::{
+    params, Connection, OpenFlags, OptionalExtension, Transaction, TransactionBehavior,
+};
 use serde::{Deserialize, Serialize};
 
+use super::MessageSource;
+
 /// Thin wrapper around a `rusqlite` connection for message database access.
 pub struct MessageDb {
     path: PathBuf,
@@ -11,10 +15,26 @@ pub struct MessageDb {
 }
 
 /// Transactional writer (RAII). Commit via `commit(self)`; rolls back on drop if not committed.
+///
+/// [`checkpoint`](WriteBatch::checkpoint) commits the work so far and keeps writing in a fresh
+/// transaction; only the part after the last checkpoint is rolled back on drop.
 pub struct WriteBatch<'c> {
+    conn: &'c Connection,
     tx: Option<Transaction<'c>>,
 }
 
+/// Import-journal row of one thread file (see [`WriteBatch::record_journal_entry`]).
+#[derive(Clone, Debug, PartialEq)]
+pub struct JournalEntry {
+    pub archive_path: String,
+    pub entry_name: String,
+    pub thread_key: String,
+    pub conversation_id: i64,
+    /// Messages of the entry written so far, in import order.
+    pub messages_done: usize,
+    pub completed: bool,
+}
+
 #[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
 pub enum ConversationType {
     #[serde(rename = "dm")]
@@ -32,6 +52,63 @@ impl ConversationType {
     }
 }
 
+/// Messenger export folder a thread was found in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ArchiveCategory {
+    Inbox,
+    /// Threads moved over when a chat switched to end-to-end encryption.
+    E2eeCutover,
+    /// Threads Messenger filtered as likely spam.
+    FilteredThreads,
+    /// Threads the owner never accepted.
+    MessageRequests,
+}
+
+impl ArchiveCategory {
+    pub const ALL: [ArchiveCategory; 4] = [
+        ArchiveCategory::Inbox,
+        ArchiveCategory::E2eeCutover,
+        ArchiveCategory::FilteredThreads,
+        ArchiveCategory::MessageRequests,
+    ];
+
+    /// Folder name of the category, as stored in `conversation.archive_category`.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ArchiveCategory::Inbox => "inbox",
+            ArchiveCategory::E2eeCutover => "e2ee_cutover",
+            ArchiveCategory::FilteredThreads => "filtered_threads",
+            ArchiveCategory::MessageRequests => "message_requests",
+        }
+    }
+}
+
+impl std::str::FromStr for ArchiveCategory {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|c| c.as_str() == s)
+            .with_context(|| format!("unknown archive category: {}", s))
+    }
+}
+
+/// Optional details of a conversation instance.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ConversationMeta {
+    pub image_uri: Option<String>,
+    /// Thread id on the platform: Facebook's `thread_path`, the E2E `threadName`.
+    pub platform_thread_id: Option<String>,
+    /// The export owner had left the conversation when exporting.
+    pub owner_left: bool,
+    /// Link anyone can join the group with.
+    pub invite_link: Option<String>,
+    /// Folder of the export the thread was found in.
+    pub archive_category: Option<ArchiveCategory>,
+}
+
 impl MessageDb {
     /// Open an existing SQLite database at `db_path` and configure connection pragmas.
     pub fn open(db_path: impl AsRef<Path>) -> Result<Self> {
@@ -59,8 +136,84 @@ impl MessageDb {
 
     /// Start a write batch (single transaction). Commit with `commit(self)`.
     pub fn begin_write(&mut self) -> Result<WriteBatch<'_>> {
+        let conn = &self.conn;
+        let tx = Transaction::new_unchecked(conn, TransactionBehavior::Deferred)?;
+        Ok(WriteBatch { conn, tx: Some(tx) })
+    }
+
+    /// Ids of exports whose checkpointed import never finished, oldest first.
+    pub fn unfinished_exports(&self) -> Result<Vec<i64>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT id FROM export WHERE status = 'in_progress' ORDER BY id")?;
+        let ids = stmt
+            .query_map([], |r| r.get(0))?
+            .collect::<rusqlite::Result<Vec<i64>>>()?;
+        Ok(ids)
+    }
+
+    /// Where a message was read from, if its import recorded provenance.
+    pub fn message_source(&self, message_id: i64) -> Result<Option<MessageSource>> {
+        let source = self
+            .conn
+            .query_row(
+                "SELECT export_id, archive_path, entry_name, message_index
+                   FROM message_source WHERE message_id = ?1",
+                params![message_id],
+                |r| {
+                    Ok(MessageSource {
+                        export_id: r.get(0)?,
+                        archive_path: r.get(1)?,
+                        entry_name: r.get(2)?,
+                        message_index: r.get(3)?,
+                    })
+                },
+            )
+            .optional()?;
+        Ok(source)
+    }
+
+    /// Delete an export with everything imported under it.
+    ///
+    /// Conversations, people, messages and journal rows go via `ON DELETE CASCADE`; canonical
+    /// rows that only this export referenced are removed as well.
+    pub fn delete_export(&mut self, export_id: i64) -> Result<()> {
         let tx = self.conn.transaction()?;
-        Ok(WriteBatch { tx: Some(tx) })
+        let canonical_ids = |sql: &str| -> Result<Vec<i64>> {
+            let mut stmt = tx.prepare(sql)?;
+            let ids = stmt
+                .query_map(params![export_id], |r| r.get(0))?
+                .collect::<rusqlite::Result<Vec<i64>>>()?;
+            Ok(ids)
+        };
+        let conversations = canonical_ids(
+            "SELECT DISTINCT canonical_conversation_id FROM conversation WHERE export_id = ?1",
+        )?;
+        let people = canonical_ids(
+            "SELECT DISTINCT p.canonical_person_id
+               FROM person p JOIN conversation c ON c.id = p.conversation_id
+              WHERE c.export_id = ?1",
+        )?;
+
+        if tx.execute("DELETE FROM export WHERE id = ?1", params![export_id])? == 0 {
+            bail!("export {} does not exist", export_id);
+        }
+        for id in conversations {
+            tx.execute(
+                "DELETE FROM canonical_conversation WHERE id = ?1
+                   AND NOT EXISTS (SELECT 1 FROM conversation WHERE canonical_conversation_id = ?1)",
+                params![id],
+            )?;
+        }
+        for id in people {
+            tx.execute(
+                "DELETE FROM canonical_person WHERE id = ?1
+                   AND NOT EXISTS (SELECT 1 FROM person WHERE canonical_person_id = ?1)",
+                params![id],
+            )?;
+        }
+        tx.commit()?;
+        Ok(())
     }
 
     /// Borrow the inner connection for ad-hoc work (autocommit mode).
@@ -83,6 +236,42 @@ impl<'c> WriteBatch<'c> {
         Ok(())
     }
 
+    /// Commit everything written so far and continue in a new transaction.
+    pub fn checkpoint(&mut self) -> Result<()> {
+        if let Some(tx) = self.tx.take() {
+            tx.commit()?;
+        }
+        self.tx = Some(Transaction::new_unchecked(
+            self.conn,
+            TransactionBehavior::Deferred,
+        )?);
+        Ok(())
+    }
+
+    /// Open a savepoint named `name` inside the current transaction.
+    pub fn savepoint(&mut self, name: &str) -> Result<()> {
+        self.tx().execute_batch(&format!("SAVEPOINT {}", name))?;
+        Ok(())
+    }
+
+    /// Keep what was written since the savepoint `name` and close it.
+    pub fn release_savepoint(&mut self, name: &str) -> Result<()> {
+        self.tx().execute_batch(&format!("RELEASE {}", name))?;
+        Ok(())
+    }
+
+    /// Undo everything written since the savepoint `name` and close it.
+    pub fn rollback_to_savepoint(&mut self, name: &str) -> Result<()> {
+        self.tx()
+            .execute_batch(&format!("ROLLBACK TO {0}; RELEASE {0}", name))?;
+        Ok(())
+    }
+
+    /// The open transaction.
+    pub(super) fn tx(&self) -> &Transaction<'c> {
+        self.tx.as_ref().unwrap()
+    }
+
     // -----------------------------
     // Insert helpers (epoch seconds in)
     // -----------------------------
@@ -101,6 +290,102 @@ impl<'c> WriteBatch<'c> {
         Ok(tx.last_insert_rowid())
     }
 
+    /// Insert an export whose checkpointed import has not finished yet.
+    pub fn insert_export_in_progress(
+        &mut self,
+        source: &str,
+        meta_json: Option<&str>,
+    ) -> Result<i64> {
+        let tx = self.tx.as_mut().unwrap();
+        let mut stmt = tx.prepare_cached(
+            "INSERT INTO export(source, meta_json, status) VALUES (?1, ?2, 'in_progress')",
+        )?;
+        stmt.execute(params![source, meta_json])?;
+        Ok(tx.last_insert_rowid())
+    }
+
+    /// Find an unfinished export with the same source and meta JSON, to resume into.
+    pub fn find_unfinished_export(&mut self, source: &str, meta_json: &str) -> Result<Option<i64>> {
+        let tx = self.tx.as_mut().unwrap();
+        let mut stmt = tx.prepare_cached(
+            "SELECT id FROM export
+              WHERE status = 'in_progress' AND source = ?1 AND meta_json = ?2
+              ORDER BY id DESC LIMIT 1",
+        )?;
+        Ok(stmt
+            .query_row(params![source, meta_json], |r| r.get(0))
+            .optional()?)
+    }
+
+    /// Mark a checkpointed export as fully imported.
+    pub fn mark_export_complete(&mut self, export_id: i64) -> Result<()> {
+        let tx = self.tx.as_mut().unwrap();
+        let mut stmt = tx.prepare_cached("UPDATE export SET status = 'complete' WHERE id = ?1")?;
+        stmt.execute(params![export_id])?;
+        Ok(())
+    }
+
+    /// Insert or update the journal row of one thread file.
+    pub fn record_journal_entry(&mut self, export_id: i64, entry: &JournalEntry) -> Result<()> {
+        let tx = self.tx.as_mut().unwrap();
+        let mut stmt = tx.prepare_cached(
+            "INSERT INTO import_journal(export_id, archive_path, entry_name, thread_key,
+                                        conversation_id, messages_done, completed)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+             ON CONFLICT(export_id, archive_path, entry_name) DO UPDATE SET
+               messages_done = excluded.messages_done,
+               completed = excluded.completed",
+        )?;
+        stmt.execute(params![
+            export_id,
+            entry.archive_path,
+            entry.entry_name,
+            entry.thread_key,
+            entry.conversation_id,
+            entry.messages_done as i64,
+            entry.completed
+        ])?;
+        Ok(())
+    }
+
+    /// All journal rows of an export.
+    pub fn journal_entries(&mut self, export_id: i64) -> Result<Vec<JournalEntry>> {
+        let tx = self.tx.as_mut().unwrap();
+        let mut stmt = tx.prepare_cached(
+            "SELECT archive_path, entry_name, thread_key, conversation_id, messages_done, completed
+               FROM import_journal WHERE export_id = ?1 ORDER BY id",
+        )?;
+        let rows = stmt
+            .query_map(params![export_id], |r| {
+                Ok(JournalEntry {
+                    archive_path: r.get(0)?,
+                    entry_name: r.get(1)?,
+                    thread_key: r.get(2)?,
+                    conversation_id: r.get(3)?,
+                    messages_done: r.get::<_, i64>(4)? as usize,
+                    completed: r.get(5)?,
+                })
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(rows)
+    }
+
+    /// `(conversation_id, name, person_id)` of every person in an export's conversations.
+    pub fn people_of_export(&mut self, export_id: i64) -> Result<Vec<(i64, String, i64)>> {
+        let tx = self.tx.as_mut().unwrap();
+        let mut stmt = tx.prepare_cached(
+            "SELECT p.conversation_id, p.name, p.id
+               FROM person p JOIN conversation c ON c.id = p.conversation_id
+              WHERE c.export_id = ?1 AND p.name IS NOT NULL",
+        )?;
+        let rows = stmt
+            .query_map(params![export_id], |r| {
+                Ok((r.get(0)?, r.get(1)?, r.get(2)?))
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(rows)
+    }
+
     pub fn insert_canonical_person(
         &mut self,
         display_name: Option<&str>,
@@ -130,22 +415,28 @@ impl<'c> WriteBatch<'c> {
     pub fn insert_conversation(
         &mut self,
         ctype: ConversationType,
-        image_uri: Option<&str>,
         name: Option<&str>,
+        meta: &ConversationMeta,
         export_id: i64,
         canonical_conversation_id: i64,
     ) -> Result<i64> {
         let tx = self.tx.as_mut().unwrap();
         let mut stmt = tx.prepare_cached(
-            "INSERT INTO conversation(type, image_uri, name, export_id, canonical_conversation_id)
-             VALUES (?1, ?2, ?3, ?4, ?5)",
+            "INSERT INTO conversation(type, image_uri, name, export_id, canonical_conversation_id,
+                                      platform_thread_id, owner_left, invite_link,
+                                      archive_category)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
         )?;
         stmt.execute(params![
             ctype.as_str(),
-            image_uri,
+            meta.image_uri,
             name,
             export_id,
-            canonical_conversation_id
+            canonical_conversation_id,
+            meta.platform_thread_id,
+            meta.owner_left,
+            meta.invite_link,
+            meta.archive_category.map(ArchiveCategory::as_str)
         ])?;
         Ok(tx.last_insert_rowid())
     }
@@ -190,6 +481,16 @@ impl<'c> WriteBatch<'c> {
         Ok(tx.last_insert_rowid())
     }
 
+    /// First message id above every existing one (see
+    /// [`insert_messages_bulk_at`](Self::insert_messages_bulk_at)).
+    pub fn next_message_id(&mut self) -> Result<i64> {
+        let tx = self.tx.as_mut().unwrap();
+        let id = tx.query_row("SELECT COALESCE(MAX(id), 0) + 1 FROM message", [], |r| {
+            r.get(0)
+        })?;
+        Ok(id)
+    }
+
     /// Add text content to an existing message.
     pub fn add_message_text(&mut self, message_id: i64, text: &str) -> Result<()> {
         let tx = self.tx.as_mut().unwrap();
@@ -199,49 +500,6 @@ impl<'c> WriteBatch<'c> {
         Ok(())
     }
 
-    /// Add an image attachment to an existing message.
-    pub fn add_message_image(&mut self, message_id: i64, image_uri: &str) -> Result<()> {
-        let tx = self.tx.as_mut().unwrap();
-        let mut stmt =
-            tx.prepare_cached("INSERT INTO message_image(message_id, image_uri) VALUES (?1, ?2)")?;
-        stmt.execute(params![message_id, image_uri])?;
-        Ok(())
-    }
-
-    /// Add a video attachment to an existing message.
-    pub fn add_message_video(&mut self, message_id: i64, video_uri: &str) -> Result<()> {
-        let tx = self.tx.as_mut().unwrap();
-        let mut stmt =
-            tx.prepare_cached("INSERT INTO message_video(message_id, video_uri) VALUES (?1, ?2)")?;
-        stmt.execute(params![message_id, video_uri])?;
-        Ok(())
-    }
-
-    /// Add a GIF attachment to an existing message.
-    pub fn add_message_gif(&mut self, message_id: i64, gif_uri: &str) -> Result<()> {
-        let tx = self.tx.as_mut().unwrap();
-        let mut stmt =
-            tx.prepare_cached("INSERT INTO message_gif(message_id, gif_uri) VALUES (?1, ?2)")?;
-        stmt.execute(params![message_id, gif_uri])?;
-        Ok(())
-    }
-
-    /// Add an audio attachment to an existing message.
-    pub fn add_message_audio(
-        &mut self,
-        message_id: i64,
-        audio_uri: &str,
-        length_seconds: Option<i64>,
-    ) -> Result<()> {
-        let tx = self.tx.as_mut().unwrap();
-        let mut stmt = tx.prepare_cached(
-            "INSERT INTO message_audio(message_id, audio_uri, length_seconds)
-             VALUES (?1, ?2, ?3)",
-        )?;
-        stmt.execute(params![message_id, audio_uri, length_seconds])?;
-        Ok(())
-    }
-
     /// Insert a reaction.
     pub fn insert_reaction(
         &mut self,
@@ -0,0 +1,302 @@
+//! Typed import errors.
+//!
+//! Importers work with `anyhow` internally and attach an [`ImportError`] at the
+//! point of failure where the cause is known (opening a ZIP, parsing a thread JSON,
+//! talking to SQLite). The public entry points convert the resulting chain back
+//! into an [`ImportError`] so hosts can branch on [`ImportErrorKind`] instead of
+//! matching on message strings.
+
+use std::fmt;
+use std::path::Path;
+
+use serde::Serialize;
+
+use crate::progress::ImportCancelled;
+
+/// Failure categories exposed to hosts (and over FFI).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ImportErrorKind {
+    Io,
+    Zip,
+    JsonParse,
+    SchemaMismatch,
+    Cancelled,
+    Database,
+    InvalidArgument,
+    Other,
+}
+
+impl ImportErrorKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ImportErrorKind::Io => "io",
+            ImportErrorKind::Zip => "zip",
+            ImportErrorKind::JsonParse => "json_parse",
+            ImportErrorKind::SchemaMismatch => "schema_mismatch",
+            ImportErrorKind::Cancelled => "cancelled",
+            ImportErrorKind::Database => "database",
+            ImportErrorKind::InvalidArgument => "invalid_argument",
+            ImportErrorKind::Other => "other",
+        }
+    }
+}
+
+/// An import failure with enough detail to show an actionable message.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ImportError {
+    /// Reading a file (or a ZIP entry stream) failed.
+    Io {
+        path: Option<String>,
+        message: String,
+    },
+    /// The file is not a readable ZIP archive, or an entry is missing/corrupt.
+    Zip {
+        path: Option<String>,
+        message: String,
+    },
+    /// A JSON file is not syntactically valid.
+    JsonParse {
+        path: String,
+        line: usize,
+        column: usize,
+        message: String,
+    },
+    /// A JSON file is valid but does not match the expected export schema.
+    SchemaMismatch {
+        path: String,
+        line: usize,
+        column: usize,
+        message: String,
+    },
+    /// The host requested cancellation.
+    Cancelled,
+    /// The database is missing or a statement failed.
+    Database {
+        path: Option<String>,
+        message: String,
+    },
+    /// The caller passed arguments we could not use (bad UTF-8, bad JSON list, ...).
+    InvalidArgument { message: String },
+    /// Anything we could not classify.
+    Other { message: String },
+}
+
+impl ImportError {
+    pub fn io(path: impl AsRef<Path>, err: impl fmt::Display) -> Self {
+        ImportError::Io {
+            path: Some(path.as_ref().to_string_lossy().into_owned()),
+            message: err.to_string(),
+        }
+    }
+
+    pub fn zip(path: impl AsRef<Path>, err: impl fmt::Display) -> Self {
+        ImportError::Zip {
+            path: Some(path.as_ref().to_string_lossy().into_owned()),
+            message: err.to_string(),
+        }
+    }
+
+    pub fn database(path: impl AsRef<Path>, err: impl fmt::Display) -> Self {
+        ImportError::Database {
+            path: Some(path.as_ref().to_string_lossy().into_owned()),
+            message: err.to_string(),
+        }
+    }
+
+    pub fn invalid_argument(message: impl Into<String>) -> Self {
+        ImportError::InvalidArgument {
+            message: message.into(),
+        }
+    }
+
+    /// Classify a `serde_json` failure for the file at `path`.
+    ///
+    /// Syntax/EOF errors become [`ImportError::JsonParse`]; data errors (wrong type,
+    /// missing field) become [`ImportError::SchemaMismatch`].
+    pub fn json(path: impl AsRef<Path>, err: serde_json::Error) -> Self {
+        use serde_json::error::Category;
+
+        let path = path.as_ref().to_string_lossy().into_owned();
+        let (line, column, message) = (err.line(), err.column(), err.to_string());
+        match err.classify() {
+            Category::Data => ImportError::SchemaMismatch {
+                path,
+                line,
+                column,
+                message,
+            },
+            Category::Syntax | Category::Eof => ImportError::JsonParse {
+                path,
+                line,
+                column,
+                message,
+            },
+            Category::Io => ImportError::Io {
+                path: Some(path),
+                message,
+            },
+        }
+    }
+
+    pub fn kind(&self) -> ImportErrorKind {
+        match self {
+            ImportError::Io { .. } => ImportErrorKind::Io,
+            ImportError::Zip { .. } => ImportErrorKind::Zip,
+            ImportError::JsonParse { .. } => ImportErrorKind::JsonParse,
+            ImportError::SchemaMismatch { .. } => ImportErrorKind::SchemaMismatch,
+            ImportError::Cancelled => ImportErrorKind::Cancelled,
+            ImportError::Database { .. } => ImportErrorKind::Database,
+            ImportError::InvalidArgument { .. } => ImportErrorKind::InvalidArgument,
+            ImportError::Other { .. } => ImportErrorKind::Other,
+        }
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        matches!(self, ImportError::Cancelled)
+    }
+
+    /// JSON detail object for hosts: `kind`, the variant fields and a readable `description`.
+    pub fn to_json(&self) -> String {
+        let mut value = serde_json::to_value(self)
+            .unwrap_or_else(|_| serde_json::json!({ "kind": self.kind().as_str() }));
+        if let Some(obj) = value.as_object_mut() {
+            obj.insert("description".into(), self.to_string().into());
+        }
+        value.to_string()
+    }
+}
+
+impl fmt::Display for ImportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ImportError::Io {
+                path: Some(path),
+                message,
+            } => write!(f, "I/O error reading {}: {}", path, message),
+            ImportError::Io {
+                path: None,
+                message,
+            } => write!(f, "I/O error: {}", message),
+            ImportError::Zip {
+                path: Some(path),
+                message,
+            } => write!(f, "invalid ZIP archive {}: {}", path, message),
+            ImportError::Zip {
+                path: None,
+                message,
+            } => write!(f, "invalid ZIP archive: {}", message),
+            ImportError::JsonParse { path, message, .. } => {
+                write!(f, "malformed JSON in {}: {}", path, message)
+            }
+            ImportError::SchemaMismatch { path, message, .. } => {
+                write!(f, "unexpected export schema in {}: {}", path, message)
+            }
+            ImportError::Cancelled => write!(f, "import cancelled"),
+            ImportError::Database {
+                path: Some(path),
+                message,
+            } => write!(f, "database error ({}): {}", path, message),
+            ImportError::Database {
+                path: None,
+                message,
+            } => write!(f, "database error: {}", message),
+            ImportError::InvalidArgument { message } => write!(f, "invalid argument: {}", message),
+            ImportError::Other { message } => f.write_str(message),
+        }
+    }
+}
+
+impl std::error::Error for ImportError {}
+
+impl From<ImportCancelled> for ImportError {
+    fn from(_: ImportCancelled) -> Self {
+        ImportError::Cancelled
+    }
+}
+
+impl From<anyhow::Error> for ImportError {
+    /// Recover the most specific [`ImportError`] from an `anyhow` chain.
+    ///
+    /// Errors that were tagged at the source are returned as-is; otherwise the chain is
+    /// searched for well-known library errors and the full context chain becomes the message.
+    fn from(err: anyhow::Error) -> Self {
+        let message = format!("{:#}", err);
+        let err = match err.downcast::<ImportError>() {
+            Ok(typed) => return typed,
+            Err(err) => err,
+        };
+
+        for cause in err.chain() {
+            if cause.is::<ImportCancelled>() {
+                return ImportError::Cancelled;
+            }
+            if cause.is::<rusqlite::Error>() {
+                return ImportError::Database {
+                    path: None,
+                    message,
+                };
+            }
+            if cause.is::<zip::result::ZipError>() {
+                return ImportError::Zip {
+                    path: None,
+                    message,
+                };
+            }
+            if cause.is::<std::io::Error>() {
+                return ImportError::Io {
+                    path: None,
+                    message,
+                };
+            }
+        }
+        ImportError::Other { message }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn json_errors_are_split_into_syntax_and_schema() {
+        let syntax = serde_json::from_str::<serde_json::Value>("{\n  \"a\": ").unwrap_err();
+        match ImportError::json("inbox/x/message_1.json", syntax) {
+            ImportError::JsonParse { path, line, .. } => {
+                assert_eq!(path, "inbox/x/message_1.json");
+                assert_eq!(line, 2);
+            }
+            other => panic!("expected JsonParse, got {:?}", other),
+        }
+
+        let schema = serde_json::from_str::<Vec<u32>>("[\"x\"]").unwrap_err();
+        assert_eq!(
+            ImportError::json("a.json", schema).kind(),
+            ImportErrorKind::SchemaMismatch
+        );
+    }
+
+    #[test]
+    fn typed_errors_survive_anyhow_context() {
+        let err = anyhow::Error::new(ImportError::zip("a.zip", "bad central directory"))
+            .context("importing a.zip");
+        let typed = ImportError::from(err);
+        assert_eq!(typed.kind(), ImportErrorKind::Zip);
+
+        let cancelled = anyhow::Error::new(ImportCancelled).context("parsing thread");
+        assert!(ImportError::from(cancelled).is_cancelled());
+    }
+
+    #[test]
+    fn json_detail_carries_kind_and_message() {
+        let detail = ImportError::io("/tmp/a.zip", "permission denied").to_json();
+        let value: serde_json::Value = serde_json::from_str(&detail).unwrap();
+        assert_eq!(value["kind"], "io");
+        assert_eq!(value["path"], "/tmp/a.zip");
+        assert_eq!(value["message"], "permission denied");
+        assert!(value["description"]
+            .as_str()
+            .unwrap()
+            .contains("/tmp/a.zip"));
+    }
+}
@@ -0,0 +1,142 @@
+use std::ffi::CString;
+use std::os::raw::c_char;
+use std::path::PathBuf;
+
+use super::messenger::{c_str_arg, file_list_arg, options_arg};
+use crate::error::ImportError;
+use crate::importers::messenger::{ImportJob, JobState, JobStatus};
+use crate::progress::ProgressSnapshot;
+
+/// State of an import job as returned by [`processor_import_job_poll`].
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ProcessorJobState {
+    Running = 0,
+    Succeeded = 1,
+    Cancelled = 2,
+    Failed = 3,
+}
+
+impl From<JobState> for ProcessorJobState {
+    fn from(state: JobState) -> Self {
+        match state {
+            JobState::Running => ProcessorJobState::Running,
+            JobState::Succeeded => ProcessorJobState::Succeeded,
+            JobState::Cancelled => ProcessorJobState::Cancelled,
+            JobState::Failed => ProcessorJobState::Failed,
+        }
+    }
+}
+
+/// Start importing Messenger exports on a background thread.
+///
+/// Arguments match
+/// [`processor_import_messenger_archives_json_with_options`](crate::ffi::processor_import_messenger_archives_json_with_options).
+/// The job has its own cancellation flag and progress state; it does not use the
+/// process-wide progress callbacks or [`processor_request_cancel_import`](crate::ffi::processor_request_cancel_import).
+///
+/// Returns an opaque handle that must be released with [`processor_import_job_free`], or
+/// null if the arguments are invalid, in which case `*out_error` receives error detail JSON
+/// (free with [`processor_string_free`](crate::ffi::processor_string_free)).
+///
+/// # Safety
+/// - `file_list_json` and `db_path` must be valid pointers to null-terminated C strings.
+/// - `options_json` must be null or a valid null-terminated C string.
+/// - `out_error` must be null or point to writable storage for one pointer.
+#[no_mangle]
+pub unsafe extern "C" fn processor_import_job_start(
+    file_list_json: *const c_char,
+    db_path: *const c_char,
+    options_json: *const c_char,
+    out_error: *mut *mut c_char,
+) -> *mut ImportJob {
+    if !out_error.is_null() {
+        *out_error = std::ptr::null_mut();
+    }
+    let started = (|| {
+        let paths = file_list_arg(file_list_json)?;
+        let db_path = PathBuf::from(c_str_arg(db_path, "db_path")?);
+        let options = options_arg(options_json)?;
+        ImportJob::start(paths, db_path, options)
+    })();
+
+    match started {
+        Ok(job) => Box::into_raw(Box::new(job)),
+        Err(err) => {
+            if !out_error.is_null() {
+                if let Ok(detail) = CString::new(err.to_json()) {
+                    *out_error = detail.into_raw();
+                }
+            }
+            std::ptr::null_mut()
+        }
+    }
+}
+
+/// Report the state of a job.
+///
+/// `*out_status` receives a JSON object
+/// `{"state":..,"progress":{"phase":..,"items_processed":..,..},"report":..,"error":..}`
+/// where `report` is set once the job succeeded and `error` once it failed. The caller owns
+/// that string and must release it with
+/// [`processor_string_free`](crate::ffi::processor_string_free).
+///
+/// A null `job` is reported as `Failed` with an invalid-argument error.
+///
+/// # Safety
+/// - `job` must be null or a handle returned by [`processor_import_job_start`] and not yet
+///   freed.
+/// - `out_status` must be null or point to writable storage for one pointer.
+#[no_mangle]
+pub unsafe extern "C" fn processor_import_job_poll(
+    job: *const ImportJob,
+    out_status: *mut *mut c_char,
+) -> ProcessorJobState {
+    if !out_status.is_null() {
+        *out_status = std::ptr::null_mut();
+    }
+    let status = match job.as_ref() {
+        Some(job) => job.poll(),
+        None => JobStatus {
+            state: JobState::Failed,
+            progress: ProgressSnapshot::default(),
+            report: None,
+            error: Some(ImportError::invalid_argument("job is null")),
+        },
+    };
+    if !out_status.is_null() {
+        if let Ok(json) = serde_json::to_string(&status) {
+            if let Ok(json) = CString::new(json) {
+                *out_status = json.into_raw();
+            }
+        }
+    }
+    status.state.into()
+}
+
+/// Ask a job to stop as soon as practical. Without checkpoints nothing from a cancelled job
+/// is committed; in checkpoint mode the threads committed so far persist under an unfinished
+/// export, which can be resumed or deleted with
+/// [`processor_rollback_export`](super::processor_rollback_export).
+///
+/// # Safety
+/// - `job` must be null (a no-op) or a handle returned by [`processor_import_job_start`] and
+///   not yet freed.
+#[no_mangle]
+pub unsafe extern "C" fn processor_import_job_cancel(job: *const ImportJob) {
+    if let Some(job) = job.as_ref() {
+        job.cancel();
+    }
+}
+
+/// Release a job handle. A still-running job is cancelled and waited for first.
+///
+/// # Safety
+/// - `job` must be null or a handle returned by [`processor_import_job_start`]; it must not
+///   be used again afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn processor_import_job_free(job: *mut ImportJob) {
+    if !job.is_null() {
+        drop(Box::from_raw(job));
+    }
+}
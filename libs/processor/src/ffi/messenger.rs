@@ -1,19 +1,152 @@
-use std::ffi::CStr;
+use std::ffi::{CStr, CString};
 use std::os::raw::c_char;
 use std::path::{Path, PathBuf};
 
-use crate::importers::messenger::import_messenger_exports;
+use crate::error::{ImportError, ImportErrorKind};
+use crate::importers::messenger::{
+    import_messenger_exports_with_options, rollback_export, unfinished_exports, ImportOptions,
+    ImportReport,
+};
 use crate::progress;
 
-const STATUS_SUCCESS: &CStr = unsafe { CStr::from_bytes_with_nul_unchecked(b"success\0") };
-const STATUS_CANCELLED: &CStr = unsafe { CStr::from_bytes_with_nul_unchecked(b"cancelled\0") };
-const STATUS_ERROR: &CStr = unsafe { CStr::from_bytes_with_nul_unchecked(b"error\0") };
+const STATUS_SUCCESS: &CStr = c"success";
+const STATUS_CANCELLED: &CStr = c"cancelled";
+const STATUS_ERROR: &CStr = c"error";
 
 #[inline]
 fn status_ptr(status: &'static CStr) -> *const c_char {
     status.as_ptr()
 }
 
+/// Result code of a detailed import call.
+///
+/// `Success` and `Cancelled` carry no detail; every other code comes with a JSON
+/// detail object (see [`processor_import_messenger_archives_json_detailed`]).
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ProcessorImportStatus {
+    Success = 0,
+    Cancelled = 1,
+    IoError = 2,
+    ZipError = 3,
+    JsonParseError = 4,
+    SchemaMismatch = 5,
+    DatabaseError = 6,
+    InvalidArgument = 7,
+    UnknownError = 8,
+}
+
+impl From<ImportErrorKind> for ProcessorImportStatus {
+    fn from(kind: ImportErrorKind) -> Self {
+        match kind {
+            ImportErrorKind::Io => ProcessorImportStatus::IoError,
+            ImportErrorKind::Zip => ProcessorImportStatus::ZipError,
+            ImportErrorKind::JsonParse => ProcessorImportStatus::JsonParseError,
+            ImportErrorKind::SchemaMismatch => ProcessorImportStatus::SchemaMismatch,
+            ImportErrorKind::Cancelled => ProcessorImportStatus::Cancelled,
+            ImportErrorKind::Database => ProcessorImportStatus::DatabaseError,
+            ImportErrorKind::InvalidArgument => ProcessorImportStatus::InvalidArgument,
+            ImportErrorKind::Other => ProcessorImportStatus::UnknownError,
+        }
+    }
+}
+
+pub(super) unsafe fn c_str_arg<'a>(ptr: *const c_char, name: &str) -> Result<&'a str, ImportError> {
+    if ptr.is_null() {
+        return Err(ImportError::invalid_argument(format!("{} is null", name)));
+    }
+    CStr::from_ptr(ptr)
+        .to_str()
+        .map_err(|_| ImportError::invalid_argument(format!("{} is not valid UTF-8", name)))
+}
+
+/// Run an import with a fresh process-wide cancellation flag.
+fn run_import(
+    paths: Vec<PathBuf>,
+    db_path: &Path,
+    options: &ImportOptions,
+) -> Result<ImportReport, ImportError> {
+    progress::clear_cancel();
+    let result = import_messenger_exports_with_options(paths, db_path, options);
+    progress::clear_cancel();
+    result
+}
+
+fn legacy_status(result: Result<ImportReport, ImportError>) -> *const c_char {
+    match result {
+        Ok(_) => status_ptr(STATUS_SUCCESS),
+        Err(err) if err.is_cancelled() => status_ptr(STATUS_CANCELLED),
+        Err(_) => status_ptr(STATUS_ERROR),
+    }
+}
+
+/// Convert an import result into a status code, writing error detail JSON to `out_error`.
+unsafe fn detailed_status<T>(
+    result: Result<T, ImportError>,
+    out_error: *mut *mut c_char,
+) -> ProcessorImportStatus {
+    if !out_error.is_null() {
+        *out_error = std::ptr::null_mut();
+    }
+    match result {
+        Ok(_) => ProcessorImportStatus::Success,
+        Err(err) => {
+            if !out_error.is_null() && !err.is_cancelled() {
+                if let Ok(detail) = CString::new(err.to_json()) {
+                    *out_error = detail.into_raw();
+                }
+            }
+            err.kind().into()
+        }
+    }
+}
+
+unsafe fn import_file(
+    file_path: *const c_char,
+    db_path: *const c_char,
+) -> Result<ImportReport, ImportError> {
+    let file_path = c_str_arg(file_path, "file_path")?;
+    let db_path = c_str_arg(db_path, "db_path")?;
+    run_import(
+        vec![file_path.into()],
+        Path::new(db_path),
+        &ImportOptions::default(),
+    )
+}
+
+unsafe fn import_archives_json(
+    file_list_json: *const c_char,
+    db_path: *const c_char,
+    options: &ImportOptions,
+) -> Result<ImportReport, ImportError> {
+    let path_bufs = file_list_arg(file_list_json)?;
+    let db_path = c_str_arg(db_path, "db_path")?;
+    run_import(path_bufs, Path::new(db_path), options)
+}
+
+/// Decode a JSON array of file paths.
+pub(super) unsafe fn file_list_arg(
+    file_list_json: *const c_char,
+) -> Result<Vec<PathBuf>, ImportError> {
+    let file_list_json = c_str_arg(file_list_json, "file_list_json")?;
+    let file_paths: Vec<String> = serde_json::from_str(file_list_json).map_err(|e| {
+        ImportError::invalid_argument(format!("file list is not a JSON string array: {}", e))
+    })?;
+    Ok(file_paths.into_iter().map(PathBuf::from).collect())
+}
+
+/// Parse an optional options JSON object; null means defaults.
+pub(super) unsafe fn options_arg(
+    options_json: *const c_char,
+) -> Result<ImportOptions, ImportError> {
+    if options_json.is_null() {
+        return Ok(ImportOptions::default());
+    }
+    let options_json = c_str_arg(options_json, "options_json")?;
+    serde_json::from_str(options_json)
+        .map_err(|e| ImportError::invalid_argument(format!("invalid import options: {}", e)))
+}
+
 /// Import a single Messenger export file (ZIP or JSON) into a SQLite database.
 ///
 /// Returns a pointer to a static null-terminated status string:
@@ -21,6 +154,9 @@ fn status_ptr(status: &'static CStr) -> *const c_char {
 /// - `"cancelled"` if the host requested cancellation
 /// - `"error"` when any other failure occurs
 ///
+/// Prefer [`processor_import_messenger_file_detailed`] when the caller needs to know
+/// why an import failed.
+///
 /// # Safety
 /// - `file_path` and `db_path` must be valid pointers to null-terminated C strings.
 /// - Pointers must remain valid for the duration of the call.
@@ -29,37 +165,7 @@ pub unsafe extern "C" fn processor_import_messenger_file(
     file_path: *const c_char,
     db_path: *const c_char,
 ) -> *const c_char {
-    let file_path = match CStr::from_ptr(file_path).to_str() {
-        Ok(s) => s,
-        Err(_) => return status_ptr(STATUS_ERROR),
-    };
-
-    let db_path = match CStr::from_ptr(db_path).to_str() {
-        Ok(s) => s,
-        Err(_) => return status_ptr(STATUS_ERROR),
-    };
-
-    progress::clear_cancel();
-    let result = import_messenger_exports(vec![file_path.into()], Path::new(db_path));
-    let cancelled = progress::cancellation_requested();
-    if cancelled {
-        progress::clear_cancel();
-    }
-
-    match result {
-        Ok(_) => {
-            progress::clear_cancel();
-            status_ptr(STATUS_SUCCESS)
-        }
-        Err(_) if cancelled => {
-            progress::clear_cancel();
-            status_ptr(STATUS_CANCELLED)
-        }
-        Err(_) => {
-            progress::clear_cancel();
-            status_ptr(STATUS_ERROR)
-        }
-    }
+    legacy_status(import_file(file_path, db_path))
 }
 
 /// Import multiple Messenger export files described by a JSON array.
@@ -75,42 +181,129 @@ pub unsafe extern "C" fn processor_import_messenger_archives_json(
     file_list_json: *const c_char,
     db_path: *const c_char,
 ) -> *const c_char {
-    let file_list_json = match CStr::from_ptr(file_list_json).to_str() {
-        Ok(s) => s,
-        Err(_) => return status_ptr(STATUS_ERROR),
-    };
-
-    let db_path = match CStr::from_ptr(db_path).to_str() {
-        Ok(s) => s,
-        Err(_) => return status_ptr(STATUS_ERROR),
-    };
+    legacy_status(import_archives_json(
+        file_list_json,
+        db_path,
+        &ImportOptions::default(),
+    ))
+}
 
-    let file_paths: Vec<String> = match serde_json::from_str(file_list_json) {
-        Ok(paths) => paths,
-        Err(_) => return status_ptr(STATUS_ERROR),
-    };
+/// Import a single Messenger export file, reporting failures as a status code plus detail.
+///
+/// On failure (any status other than `Success`/`Cancelled`), `*out_error` receives a JSON
+/// object such as `{"kind":"json_parse","path":"...","line":3,"column":17,"message":"...",
+/// "description":"..."}`. The caller owns that string and must release it with
+/// [`processor_string_free`](crate::ffi::processor_string_free). Otherwise `*out_error` is
+/// set to null.
+///
+/// # Safety
+/// - `file_path` and `db_path` must be valid pointers to null-terminated C strings.
+/// - `out_error` must be null or point to writable storage for one pointer.
+#[no_mangle]
+pub unsafe extern "C" fn processor_import_messenger_file_detailed(
+    file_path: *const c_char,
+    db_path: *const c_char,
+    out_error: *mut *mut c_char,
+) -> ProcessorImportStatus {
+    detailed_status(import_file(file_path, db_path), out_error)
+}
 
-    let path_bufs: Vec<PathBuf> = file_paths.into_iter().map(PathBuf::from).collect();
+/// Import multiple Messenger export files (JSON array of paths) with detailed errors.
+///
+/// Error reporting follows [`processor_import_messenger_file_detailed`].
+///
+/// # Safety
+/// - `file_list_json` and `db_path` must be valid pointers to null-terminated C strings.
+/// - `out_error` must be null or point to writable storage for one pointer.
+#[no_mangle]
+pub unsafe extern "C" fn processor_import_messenger_archives_json_detailed(
+    file_list_json: *const c_char,
+    db_path: *const c_char,
+    out_error: *mut *mut c_char,
+) -> ProcessorImportStatus {
+    detailed_status(
+        import_archives_json(file_list_json, db_path, &ImportOptions::default()),
+        out_error,
+    )
+}
 
-    progress::clear_cancel();
-    let result = import_messenger_exports(path_bufs, Path::new(db_path));
-    let cancelled = progress::cancellation_requested();
-    if cancelled {
-        progress::clear_cancel();
+/// Import multiple Messenger export files with options, returning the import report.
+///
+/// `options_json` may be null (defaults) or a JSON object mirroring [`ImportOptions`],
+/// e.g. `{"lenient": true}`. On `Success`, `*out_report` receives the [`ImportReport`] as
+/// JSON (`{"export_ids":[..],"skipped":[{"path":..,"message_index":..,"error":{..}}]}`).
+/// Error reporting follows [`processor_import_messenger_file_detailed`]. Both strings are
+/// owned by the caller and must be released with
+/// [`processor_string_free`](crate::ffi::processor_string_free).
+///
+/// # Safety
+/// - `file_list_json` and `db_path` must be valid pointers to null-terminated C strings.
+/// - `options_json` must be null or a valid null-terminated C string.
+/// - `out_report` and `out_error` must each be null or point to writable storage for one pointer.
+#[no_mangle]
+pub unsafe extern "C" fn processor_import_messenger_archives_json_with_options(
+    file_list_json: *const c_char,
+    db_path: *const c_char,
+    options_json: *const c_char,
+    out_report: *mut *mut c_char,
+    out_error: *mut *mut c_char,
+) -> ProcessorImportStatus {
+    if !out_report.is_null() {
+        *out_report = std::ptr::null_mut();
     }
-
-    match result {
-        Ok(_) => {
-            progress::clear_cancel();
-            status_ptr(STATUS_SUCCESS)
-        }
-        Err(_) if cancelled => {
-            progress::clear_cancel();
-            status_ptr(STATUS_CANCELLED)
+    let result = options_arg(options_json)
+        .and_then(|options| import_archives_json(file_list_json, db_path, &options));
+    if let (Ok(report), false) = (&result, out_report.is_null()) {
+        if let Ok(json) = serde_json::to_string(report) {
+            if let Ok(json) = CString::new(json) {
+                *out_report = json.into_raw();
+            }
         }
-        Err(_) => {
-            progress::clear_cancel();
-            status_ptr(STATUS_ERROR)
+    }
+    detailed_status(result, out_error)
+}
+
+/// Delete an export and everything imported under it, e.g. an unfinished checkpointed
+/// import the user cancelled. Error reporting follows
+/// [`processor_import_messenger_file_detailed`].
+///
+/// # Safety
+/// - `db_path` must be a valid pointer to a null-terminated C string.
+/// - `out_error` must be null or point to writable storage for one pointer.
+#[no_mangle]
+pub unsafe extern "C" fn processor_rollback_export(
+    db_path: *const c_char,
+    export_id: i64,
+    out_error: *mut *mut c_char,
+) -> ProcessorImportStatus {
+    let result =
+        c_str_arg(db_path, "db_path").and_then(|db| rollback_export(Path::new(db), export_id));
+    detailed_status(result, out_error)
+}
+
+/// List exports left unfinished by interrupted checkpointed imports.
+///
+/// On `Success`, `*out_ids` receives a JSON array of export ids owned by the caller (release
+/// with [`processor_string_free`](crate::ffi::processor_string_free)). Error reporting follows
+/// [`processor_import_messenger_file_detailed`].
+///
+/// # Safety
+/// - `db_path` must be a valid pointer to a null-terminated C string.
+/// - `out_ids` and `out_error` must each be null or point to writable storage for one pointer.
+#[no_mangle]
+pub unsafe extern "C" fn processor_unfinished_exports_json(
+    db_path: *const c_char,
+    out_ids: *mut *mut c_char,
+    out_error: *mut *mut c_char,
+) -> ProcessorImportStatus {
+    if !out_ids.is_null() {
+        *out_ids = std::ptr::null_mut();
+    }
+    let result = c_str_arg(db_path, "db_path").and_then(|db| unfinished_exports(Path::new(db)));
+    if let (Ok(ids), false) = (&result, out_ids.is_null()) {
+        if let Ok(json) = CString::new(serde_json::json!(ids).to_string()) {
+            *out_ids = json.into_raw();
         }
     }
+    detailed_status(result, out_error)
 }
@@ -2,10 +2,12 @@
 //!
 //! Organized into submodules so importer-specific surfaces remain isolated.
 
+mod import_job;
 mod messenger;
 mod progress_callbacks;
 mod utilities;
 
+pub use import_job::*;
 pub use messenger::*;
 pub use progress_callbacks::*;
 pub use utilities::*;
@@ -1,18 +1,50 @@
-use crate::progress::{self, ProgressCallback};
+use crate::progress::{self, ProgressCallback, ProgressEventCallback};
 
 /// Register a callback to receive import progress updates.
+///
+/// # Safety
+/// - `callback` must remain callable for as long as it is registered; it may be invoked
+///   from the thread running the import.
 #[no_mangle]
 pub unsafe extern "C" fn processor_set_progress_callback(callback: ProgressCallback) {
     progress::set_progress_callback(Some(callback));
 }
 
 /// Clear any registered progress callback.
+///
+/// # Safety
+/// Always safe to call; marked `unsafe` for symmetry with the other FFI entry points.
 #[no_mangle]
 pub unsafe extern "C" fn processor_clear_progress_callback() {
     progress::set_progress_callback(None);
 }
 
+/// Register a callback to receive structured, phase-aware progress events.
+///
+/// Independent of [`processor_set_progress_callback`]; both may be registered at once.
+///
+/// # Safety
+/// - `callback` must remain callable for as long as it is registered; it may be invoked
+///   from the thread running the import.
+/// - The event pointer and its strings must not be retained past the callback.
+#[no_mangle]
+pub unsafe extern "C" fn processor_set_progress_event_callback(callback: ProgressEventCallback) {
+    progress::set_progress_event_callback(Some(callback));
+}
+
+/// Clear any registered progress event callback.
+///
+/// # Safety
+/// Always safe to call; marked `unsafe` for symmetry with the other FFI entry points.
+#[no_mangle]
+pub unsafe extern "C" fn processor_clear_progress_event_callback() {
+    progress::set_progress_event_callback(None);
+}
+
 /// Signal that the in-flight import should cancel as soon as practical.
+///
+/// # Safety
+/// Always safe to call; marked `unsafe` for symmetry with the other FFI entry points.
 #[no_mangle]
 pub unsafe extern "C" fn processor_request_cancel_import() {
     progress::request_cancel();
@@ -0,0 +1,214 @@
+//! Checkpointed imports.
+//!
+//! With [`ImportOptions::checkpoint`](super::ImportOptions::checkpoint) set, the import commits
+//! after every thread file (and optionally every N messages) and records what it finished in
+//! the `import_journal` table, in the same transaction as the data. The export stays
+//! `in_progress` until the end, so an interrupted run can be resumed
+//! ([`ImportOptions::resume`](super::ImportOptions::resume)) or rolled back with
+//! [`rollback_export`](super::rollback_export).
+
+use std::collections::HashMap;
+
+use anyhow::{Context, Result};
+
+use crate::database::{JournalEntry, WriteBatch};
+use crate::importers::messenger::utils::flush_messages;
+use crate::importers::messenger::ImportState;
+
+/// Journal of the export currently being imported in checkpoint mode.
+#[derive(Debug)]
+pub struct Checkpointer {
+    export_id: i64,
+    every_messages: usize,
+    /// Journal rows keyed by `(archive_path, entry_name)`.
+    entries: HashMap<(String, String), JournalEntry>,
+}
+
+/// Where to pick up a thread file.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EntryResume {
+    /// Not started; import from the first message.
+    Fresh,
+    /// Partly written; skip this many messages (in import order).
+    Partial { messages_done: usize },
+    /// Already fully imported.
+    Done,
+}
+
+/// Create (or, when resuming, reopen) the export for one group of archives.
+///
+/// Outside checkpoint mode this is a plain export insert.
+pub fn begin_export(
+    batch: &mut WriteBatch<'_>,
+    state: &mut ImportState,
+    source: &str,
+    meta_json: &str,
+) -> Result<i64> {
+    if !state.options.checkpoint {
+        state.checkpoint = None;
+        return batch.insert_export(source, None, Some(meta_json));
+    }
+
+    let resumed = if state.options.resume {
+        batch.find_unfinished_export(source, meta_json)?
+    } else {
+        None
+    };
+    let (export_id, journal) = match resumed {
+        Some(export_id) => {
+            let journal = batch
+                .journal_entries(export_id)
+                .context("load import journal")?;
+            restore_state(batch, state, export_id, &journal)?;
+            (export_id, journal)
+        }
+        None => {
+            let export_id = batch.insert_export_in_progress(source, Some(meta_json))?;
+            (export_id, Vec::new())
+        }
+    };
+    batch.checkpoint().context("commit export row")?;
+
+    state.checkpoint = Some(Checkpointer {
+        export_id,
+        every_messages: state.options.checkpoint_messages,
+        entries: journal
+            .into_iter()
+            .map(|e| ((e.archive_path.clone(), e.entry_name.clone()), e))
+            .collect(),
+    });
+    Ok(export_id)
+}
+
+/// Rebuild the per-run lookups (threads and people) from what an interrupted run committed.
+fn restore_state(
+    batch: &mut WriteBatch<'_>,
+    state: &mut ImportState,
+    export_id: i64,
+    journal: &[JournalEntry],
+) -> Result<()> {
+    for entry in journal {
+        state
+            .folder_names_to_conv_ids
+            .insert(entry.thread_key.clone(), entry.conversation_id);
+    }
+    for (conversation_id, name, person_id) in batch.people_of_export(export_id)? {
+        state
+            .person_ids_by_conversation
+            .entry(conversation_id)
+            .or_default()
+            .insert(name, person_id);
+    }
+    Ok(())
+}
+
+/// Mark the current export complete and commit.
+pub fn finish_export(batch: &mut WriteBatch<'_>, state: &mut ImportState) -> Result<()> {
+    if let Some(cp) = state.checkpoint.take() {
+        batch.mark_export_complete(cp.export_id)?;
+        batch.checkpoint().context("commit finished export")?;
+    }
+    Ok(())
+}
+
+impl ImportState {
+    /// How far a previous run got with `entry_name` of the current archive.
+    pub fn entry_resume(&self, entry_name: &str) -> EntryResume {
+        let Some(cp) = &self.checkpoint else {
+            return EntryResume::Fresh;
+        };
+        match cp
+            .entries
+            .get(&(self.archive_path.clone(), entry_name.to_string()))
+        {
+            None => EntryResume::Fresh,
+            Some(e) if e.completed => EntryResume::Done,
+            Some(e) => EntryResume::Partial {
+                messages_done: e.messages_done,
+            },
+        }
+    }
+
+    /// Called after each message; commits every `checkpoint_messages` messages.
+    pub fn message_checkpoint(
+        &mut self,
+        batch: &mut WriteBatch<'_>,
+        entry_name: &str,
+        thread_key: &str,
+        conversation_id: i64,
+        messages_done: usize,
+    ) -> Result<()> {
+        let due = match &self.checkpoint {
+            Some(cp) => cp.every_messages > 0 && messages_done.is_multiple_of(cp.every_messages),
+            None => false,
+        };
+        if due {
+            self.record_entry(
+                batch,
+                entry_name,
+                thread_key,
+                conversation_id,
+                messages_done,
+                false,
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Called after a thread file is fully built; writes its queued messages and, in
+    /// checkpoint mode, journals it as completed and commits.
+    pub fn entry_checkpoint(
+        &mut self,
+        batch: &mut WriteBatch<'_>,
+        entry_name: &str,
+        thread_key: &str,
+        conversation_id: i64,
+        messages_done: usize,
+    ) -> Result<()> {
+        flush_messages(batch, self)?;
+        if self.checkpoint.is_some() {
+            self.record_entry(
+                batch,
+                entry_name,
+                thread_key,
+                conversation_id,
+                messages_done,
+                true,
+            )?;
+        }
+        Ok(())
+    }
+
+    fn record_entry(
+        &mut self,
+        batch: &mut WriteBatch<'_>,
+        entry_name: &str,
+        thread_key: &str,
+        conversation_id: i64,
+        messages_done: usize,
+        completed: bool,
+    ) -> Result<()> {
+        flush_messages(batch, self)?;
+        let archive_path = self.archive_path.clone();
+        let Some(cp) = self.checkpoint.as_mut() else {
+            return Ok(());
+        };
+        let entry = JournalEntry {
+            archive_path,
+            entry_name: entry_name.to_string(),
+            thread_key: thread_key.to_string(),
+            conversation_id,
+            messages_done,
+            completed,
+        };
+        batch
+            .record_journal_entry(cp.export_id, &entry)
+            .context("record import journal")?;
+        batch.checkpoint().context("commit checkpoint")?;
+        cp.entries.insert(
+            (entry.archive_path.clone(), entry.entry_name.clone()),
+            entry,
+        );
+        Ok(())
+    }
+}
@@ -1,24 +1,57 @@
+//! Serde model of E2E export thread files.
+//!
+//! Only `senderName` and `timestamp` are required on a message; other fields default
+//! when absent and unknown fields are ignored.
+
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct E2eExportRoot {
+    #[serde(default)]
     pub participants: Vec<String>,
-    #[serde(rename = "threadName")]
+    #[serde(rename = "threadName", default)]
     pub thread_name: String,
+    #[serde(default)]
     pub messages: Vec<Message>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Message {
-    #[serde(rename = "isUnsent")]
+    #[serde(rename = "isUnsent", default)]
     pub is_unsent: bool,
+    #[serde(rename = "isEdited", default)]
+    pub is_edited: bool,
+    /// Earlier versions of an edited message, oldest first (newer exports only).
+    #[serde(rename = "editHistory", default)]
+    pub edit_history: Vec<EditedVersion>,
+    #[serde(default)]
     pub media: Vec<Media>,
+    #[serde(default)]
     pub reactions: Vec<Reaction>,
     #[serde(rename = "senderName")]
     pub sender_name: String,
+    #[serde(default)]
     pub text: String,
     pub timestamp: i64,
+    #[serde(default)]
     pub r#type: String,
+    /// A poll created by this message.
+    #[serde(default)]
+    pub poll: Option<Poll>,
+    /// A location pin or live location.
+    #[serde(default)]
+    pub location: Option<Location>,
+    /// Shared contact cards.
+    #[serde(default)]
+    pub contacts: Vec<Contact>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EditedVersion {
+    #[serde(default)]
+    pub text: String,
+    /// When this version was sent.
+    pub timestamp: Option<i64>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -26,6 +59,48 @@ pub struct Media {
     pub uri: String,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Poll {
+    #[serde(default)]
+    pub question: String,
+    #[serde(default)]
+    pub options: Vec<PollOption>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PollOption {
+    #[serde(default)]
+    pub text: String,
+    /// Names of the people who voted for it.
+    #[serde(default)]
+    pub voters: Vec<String>,
+    /// Vote count, when given without (all) the voters.
+    #[serde(rename = "voteCount", default)]
+    pub vote_count: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Location {
+    pub latitude: f64,
+    pub longitude: f64,
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub address: Option<String>,
+    #[serde(rename = "isLive", default)]
+    pub is_live: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Contact {
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(rename = "phoneNumber", default)]
+    pub phone_number: Option<String>,
+    #[serde(default)]
+    pub email: Option<String>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Reaction {
     pub actor: String,
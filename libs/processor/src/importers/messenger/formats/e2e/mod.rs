@@ -3,25 +3,46 @@
 //! Handles the newer Facebook Messenger end-to-end encrypted export format,
 //! which uses a different structure and JSON schema than the legacy format.
 
-use crate::importers::messenger::utils::{ensure_conversation, ensure_person_in_conversation};
-use crate::progress::{ensure_not_cancelled, ImportCancelled, ImportProgressTracker};
-use crate::utils::audio::detect_duration_seconds;
+use crate::error::ImportError;
+use crate::importers::messenger::pipeline::{
+    attachment, audio_info, gif_info, image_info, parse_in_parallel, probe_media, sticker_info,
+    video_info, ArchiveMedia, MediaLookup, NoMedia, ProbedMedia,
+};
+use crate::importers::messenger::utils::{
+    classify_event, ensure_conversation, ensure_person_in_conversation, is_like_sticker,
+    is_sticker_uri, is_unreadable_thread, parse_thread_lenient, parsed_message_positions,
+    queue_message, read_thread_header, stream_thread, HeaderAfterMessages, ThreadUndo,
+};
+use crate::progress::{
+    ensure_not_cancelled, CancellationToken, ImportCancelled, ImportProgressTracker,
+};
 use crate::{
-    database::WriteBatch, importers::messenger::formats::e2e::json::E2eExportRoot,
-    importers::messenger::ImportState,
+    database::{
+        Contact, ConversationMeta, Link, Location, MessageEdit, MessageRecord, MessageSource, Poll,
+        PollOption, Sticker, UnsentReason, WriteBatch,
+    },
+    importers::messenger::checkpoint::EntryResume,
+    importers::messenger::formats::e2e::json::{E2eExportRoot, Message},
+    importers::messenger::{entry_size, ImportState, SkippedItem},
+    utils::links::find_urls,
 };
-use anyhow::{Context, Result};
+use anyhow::Result;
+use std::fs::File;
 use std::io::{Read, Seek};
+use std::path::Path;
 use zip::read::ZipArchive;
 
 pub mod json;
 
 /// Detect if a ZIP archive is the new E2E format: root contains json files and a `media/` dir.
-pub fn is_e2e_archive<R: Seek + Read>(archive: &ZipArchive<R>) -> Result<bool, ImportCancelled> {
+pub fn is_e2e_archive<R: Seek + Read>(
+    archive: &ZipArchive<R>,
+    cancel: &CancellationToken,
+) -> Result<bool, ImportCancelled> {
     let mut has_media = false;
     let mut has_root_json = false;
     for name in archive.file_names() {
-        ensure_not_cancelled()?;
+        ensure_not_cancelled(cancel)?;
         if name.starts_with("media/") {
             has_media = true;
         }
@@ -43,30 +64,183 @@ pub fn import_e2e_archive<R: Seek + Read>(
     state: &mut ImportState,
     progress: &mut ImportProgressTracker,
 ) -> Result<()> {
-    // Iterate root-level JSON files.
-    let root_jsons: Vec<String> = archive
+    for json_path in collect_thread_entries(archive) {
+        ensure_not_cancelled(&state.cancel)?;
+        progress.begin_file(&json_path);
+        let bytes = entry_size(archive, &json_path);
+        if state.entry_resume(&json_path) == EntryResume::Done {
+            progress.advance_bytes(bytes);
+            progress.advance(1);
+            continue;
+        }
+        if state.options.streaming {
+            import_e2e_thread_streaming(archive, &json_path, export_id, batch, state, progress)?;
+        } else {
+            let parsed = read_thread(archive, &json_path, state.options.lenient);
+            write_thread(
+                &json_path,
+                parsed,
+                &mut ArchiveMedia(archive),
+                export_id,
+                batch,
+                state,
+                progress,
+            )?;
+        }
+        progress.advance_bytes(bytes);
+        progress.advance(1);
+    }
+    Ok(())
+}
+
+/// Import an E2E-format ZIP archive, parsing thread files on `workers` threads.
+///
+/// Only the calling thread writes, in the same order as [`import_e2e_archive`].
+pub fn import_e2e_archive_parallel(
+    archive_path: &Path,
+    archive: &mut ZipArchive<File>,
+    workers: usize,
+    export_id: i64,
+    batch: &mut WriteBatch<'_>,
+    state: &mut ImportState,
+    progress: &mut ImportProgressTracker,
+) -> Result<()> {
+    let entries = collect_thread_entries(archive);
+    let done: Vec<bool> = entries
+        .iter()
+        .map(|e| state.entry_resume(e) == EntryResume::Done)
+        .collect();
+    let lenient = state.options.lenient;
+    let cancel = state.cancel.clone();
+
+    parse_in_parallel(
+        archive_path,
+        &entries,
+        workers,
+        &cancel,
+        |worker_archive, index, json_path| {
+            if done[index] {
+                return None;
+            }
+            Some(
+                read_thread(worker_archive, json_path, lenient).map(|(root, skipped)| {
+                    let uris = || {
+                        root.messages
+                            .iter()
+                            .flat_map(|m| &m.media)
+                            .map(|media| media.uri.as_str())
+                    };
+                    let probed = probe_media(
+                        worker_archive,
+                        uris().filter(|uri| classify_media(uri) == "audio"),
+                        uris().filter(|uri| classify_media(uri) == "video"),
+                        uris().filter(|uri| matches!(classify_media(uri), "image" | "gif")),
+                    );
+                    (root, skipped, probed)
+                }),
+            )
+        },
+        |_, json_path, parsed| {
+            ensure_not_cancelled(&state.cancel)?;
+            progress.begin_file(json_path);
+            let bytes = entry_size(archive, json_path);
+            if let Some(parsed) = parsed {
+                let (parsed, mut probed) = match parsed {
+                    Ok((root, skipped, probed)) => (Ok((root, skipped)), probed),
+                    Err(e) => (Err(e), ProbedMedia::default()),
+                };
+                write_thread(
+                    json_path,
+                    parsed,
+                    &mut probed,
+                    export_id,
+                    batch,
+                    state,
+                    progress,
+                )?;
+            }
+            progress.advance_bytes(bytes);
+            progress.advance(1);
+            Ok(())
+        },
+    )
+}
+
+/// Write a thread file read by [`read_thread`]; in lenient mode an unreadable one is
+/// recorded as skipped instead.
+fn write_thread(
+    json_path: &str,
+    parsed: Result<(E2eExportRoot, Vec<SkippedItem>), ImportError>,
+    media: &mut dyn MediaLookup,
+    export_id: i64,
+    batch: &mut WriteBatch<'_>,
+    state: &mut ImportState,
+    progress: &mut ImportProgressTracker,
+) -> Result<()> {
+    let parsed = match parsed {
+        Ok((parsed, skipped)) => {
+            state.message_positions = parsed_message_positions(parsed.messages.len(), &skipped);
+            state.skipped.extend(skipped);
+            parsed
+        }
+        Err(err) if state.options.lenient => {
+            state.skipped.push(SkippedItem {
+                path: json_path.to_string(),
+                message_index: None,
+                error: err,
+            });
+            return Ok(());
+        }
+        Err(err) => return Err(err.into()),
+    };
+    progress.begin_insert(&parsed.thread_name);
+    import_e2e_thread(media, json_path, parsed, export_id, batch, state)
+}
+
+/// Root-level JSON entries of an E2E archive; each is one thread.
+pub fn collect_thread_entries<R: Seek + Read>(archive: &ZipArchive<R>) -> Vec<String> {
+    archive
         .file_names()
         .filter(|name| !name.contains('/') && name.ends_with(".json"))
         .map(|s| s.to_string())
-        .collect();
+        .collect()
+}
 
-    progress.add_total(root_jsons.len() as u32);
-
-    for json_path in root_jsons {
-        ensure_not_cancelled()?;
-        let mut json_content = String::new();
-        {
-            let mut f = archive
-                .by_name(&json_path)
-                .with_context(|| format!("open {}", json_path))?;
-            f.read_to_string(&mut json_content)
-                .with_context(|| format!("read {}", json_path))?;
-        }
+/// Read one root-level thread JSON entry and parse it.
+fn read_thread<R: Seek + Read>(
+    archive: &mut ZipArchive<R>,
+    json_path: &str,
+    lenient: bool,
+) -> Result<(E2eExportRoot, Vec<SkippedItem>), ImportError> {
+    let mut json_content = String::new();
+    {
+        let mut f = archive
+            .by_name(json_path)
+            .map_err(|e| ImportError::zip(json_path, e))?;
+        f.read_to_string(&mut json_content)
+            .map_err(|e| ImportError::io(json_path, e))?;
+    }
+    parse_e2e_json(json_path, &json_content, lenient)
+}
 
-        import_e2e_json(archive, &json_content, export_id, batch, state)?;
-        progress.advance(1);
+/// Deserialize E2E thread JSON, falling back to per-message parsing with `lenient`.
+///
+/// Messages skipped by the fallback are returned alongside the thread.
+fn parse_e2e_json(
+    json_path: &str,
+    json_content: &str,
+    lenient: bool,
+) -> Result<(E2eExportRoot, Vec<SkippedItem>), ImportError> {
+    match serde_json::from_str::<E2eExportRoot>(json_content) {
+        Ok(parsed) => Ok((parsed, Vec::new())),
+        Err(_) if lenient => {
+            let (mut root, messages, skipped) =
+                parse_thread_lenient::<E2eExportRoot, Message>(json_path, json_content)?;
+            root.messages = messages;
+            Ok((root, skipped))
+        }
+        Err(e) => Err(ImportError::json(json_path, e)),
     }
-    Ok(())
 }
 
 /// Classify media by file extension.
@@ -76,118 +250,403 @@ fn classify_media(uri: &str) -> &'static str {
         .and_then(|s| s.to_str())
         .map(|s| s.to_ascii_lowercase());
     match ext.as_deref() {
-        Some("mp3" | "m4a" | "aac" | "wav" | "ogg" | "oga" | "opus" | "flac") => "audio",
-        Some("mp4" | "mov" | "mkv" | "webm") => "video",
+        Some(
+            "mp3" | "m4a" | "aac" | "wav" | "ogg" | "oga" | "opus" | "flac" | "amr" | "awb" | "caf"
+            | "mka" | "3gp" | "3gpp",
+        ) => "audio",
+        Some("mp4" | "mov" | "m4v" | "mkv" | "webm") => "video",
         Some("gif") => "gif",
-        Some("jpg" | "jpeg" | "png" | "webp" | "heic" | "heif") => "image",
+        Some("jpg" | "jpeg" | "png" | "webp" | "heic" | "heif" | "avif") => "image",
         _ => "image",
     }
 }
 
-/// Import a single E2E JSON content.
+/// Attachment kind of a media entry of a message of `message_type`: images of sticker
+/// messages and images under sticker paths are stickers, the rest go by
+/// [`classify_media`].
+fn media_kind(message_type: &str, uri: &str) -> &'static str {
+    match classify_media(uri) {
+        "image" | "gif" if message_type.eq_ignore_ascii_case("sticker") || is_sticker_uri(uri) => {
+            "sticker"
+        }
+        kind => kind,
+    }
+}
+
+/// Import a single E2E JSON content. `json_path` names the entry in error reports.
 pub fn import_e2e_json<R: Seek + Read>(
     archive: &mut ZipArchive<R>,
+    json_path: &str,
     json_content: &str,
     export_id: i64,
     batch: &mut WriteBatch<'_>,
     state: &mut ImportState,
 ) -> Result<()> {
-    let parsed: E2eExportRoot = serde_json::from_str(json_content).context("parsing e2e json")?;
+    let (parsed, skipped) = parse_e2e_json(json_path, json_content, state.options.lenient)?;
+    state.message_positions = parsed_message_positions(parsed.messages.len(), &skipped);
+    state.skipped.extend(skipped);
+    import_e2e_thread(
+        &mut ArchiveMedia(archive),
+        json_path,
+        parsed,
+        export_id,
+        batch,
+        state,
+    )
+}
+
+/// Import an already parsed E2E thread, skipping messages a checkpointed run already wrote.
+fn import_e2e_thread(
+    media: &mut dyn MediaLookup,
+    json_path: &str,
+    parsed: E2eExportRoot,
+    export_id: i64,
+    batch: &mut WriteBatch<'_>,
+    state: &mut ImportState,
+) -> Result<()> {
+    let conv_id = begin_e2e_thread(&parsed, export_id, batch, state)?;
 
+    let messages_done = match state.entry_resume(json_path) {
+        EntryResume::Partial { messages_done } => messages_done,
+        _ => 0,
+    };
+    let message_count = parsed.messages.len();
+    for (index, m) in parsed.messages.into_iter().enumerate() {
+        if index < messages_done {
+            continue;
+        }
+        ensure_not_cancelled(&state.cancel)?;
+        let source = state.message_source(export_id, json_path, state.message_position(index));
+        queue_e2e_message(media, m, conv_id, source, batch, state)?;
+        state.message_checkpoint(batch, json_path, &parsed.thread_name, conv_id, index + 1)?;
+    }
+    state.entry_checkpoint(
+        batch,
+        json_path,
+        &parsed.thread_name,
+        conv_id,
+        message_count,
+    )?;
+
+    Ok(())
+}
+
+/// Create the conversation of a thread and its participants; returns the conversation id.
+fn begin_e2e_thread(
+    parsed: &E2eExportRoot,
+    export_id: i64,
+    batch: &mut WriteBatch<'_>,
+    state: &mut ImportState,
+) -> Result<i64> {
     // Create conversation (and canonical) and ensure users per conversation
     // Thread names have the "Name Surname_X" format, where X is some number. We want
-    // to remove the _X.
+    // to remove the _X from the name; the whole thread name is kept as the thread id.
     let thread_name = parsed
         .thread_name
         .split('_')
         .next()
         .unwrap_or(&parsed.thread_name);
+    let meta = ConversationMeta {
+        platform_thread_id: Some(parsed.thread_name.clone()).filter(|t| !t.is_empty()),
+        ..Default::default()
+    };
     let conv_id = ensure_conversation(
         batch,
         state,
         &parsed.thread_name,
         parsed.participants.len(),
-        None,
         Some(thread_name),
+        &meta,
         export_id,
     )?;
     for name in &parsed.participants {
-        ensure_person_in_conversation(batch, state, conv_id, name)?;
+        let person_id = ensure_person_in_conversation(batch, state, conv_id, name)?;
+        batch.mark_participant(person_id)?;
+    }
+    Ok(conv_id)
+}
+
+/// Seconds since the epoch of an E2E timestamp, which may be in seconds or milliseconds.
+fn epoch_seconds(timestamp: i64) -> i64 {
+    if timestamp > 1_000_000_000_000 {
+        timestamp / 1000
+    } else {
+        timestamp
+    }
+}
+
+/// Build one message and queue it with its source.
+fn queue_e2e_message(
+    lookup: &mut dyn MediaLookup,
+    m: Message,
+    conv_id: i64,
+    source: Option<MessageSource>,
+    batch: &mut WriteBatch<'_>,
+    state: &mut ImportState,
+) -> Result<()> {
+    let mut record = build_e2e_message(lookup, m, conv_id, batch, state)?;
+    record.source = source;
+    queue_message(batch, state, record)
+}
+
+/// Build the row of one message with its attachments and reactions, ensuring the people
+/// involved exist.
+///
+/// Audio and video details come from `lookup`, falling back to the global media index for
+/// URIs it does not know.
+fn build_e2e_message(
+    lookup: &mut dyn MediaLookup,
+    mut m: Message,
+    conv_id: i64,
+    batch: &mut WriteBatch<'_>,
+    state: &mut ImportState,
+) -> Result<MessageRecord> {
+    // Sender (per-conversation person)
+    let sender_id = ensure_person_in_conversation(batch, state, conv_id, &m.sender_name)?;
+
+    let edits: Vec<MessageEdit> = m
+        .edit_history
+        .into_iter()
+        .map(|v| MessageEdit {
+            previous_text: v.text,
+            sent_at: v.timestamp.map(epoch_seconds),
+        })
+        .collect();
+    let mut record = MessageRecord {
+        sender_id,
+        sent_at: epoch_seconds(m.timestamp),
+        unsent: m.is_unsent,
+        unsent_reason: m.is_unsent.then_some(UnsentReason::Sender),
+        // Exports that mark edits without keeping the history still count one.
+        edit_count: (edits.len() as i64).max(m.is_edited as i64),
+        edits,
+        ..Default::default()
+    };
+    let structured = m.poll.is_some() || m.location.is_some() || !m.contacts.is_empty();
+    if m.media.is_empty() && !structured {
+        record.events = classify_event(&m.sender_name, Some(&m.r#type), &[], Some(&m.text));
+    }
+    // The text of a poll message is its question.
+    let poll_text = m.poll.is_some().then(|| std::mem::take(&mut m.text));
+    if !m.text.trim().is_empty() && record.events.is_empty() {
+        record.links = find_urls(&m.text)
+            .into_iter()
+            .map(|url| Link::new(url, None))
+            .collect();
+        record.texts.push(m.text);
+    }
+    for media in m.media {
+        match media_kind(&m.r#type, &media.uri) {
+            "audio" => {
+                let info = audio_info(lookup, &mut state.file_index, &media.uri);
+                record
+                    .audios
+                    .push(attachment(state, "audio", &media.uri, info)?);
+            }
+            "video" => {
+                let info = video_info(lookup, &mut state.file_index, &media.uri);
+                record
+                    .videos
+                    .push(attachment(state, "video", &media.uri, info)?);
+            }
+            "gif" => {
+                let info = gif_info(lookup, &mut state.file_index, &media.uri);
+                record
+                    .gifs
+                    .push(attachment(state, "gif", &media.uri, info)?);
+            }
+            "sticker" => {
+                let info = sticker_info(lookup, &mut state.file_index, &media.uri);
+                record.stickers.push(Sticker {
+                    image: attachment(state, "sticker", &media.uri, info)?,
+                    is_like: is_like_sticker(&media.uri),
+                });
+            }
+            _ => {
+                let info = image_info(lookup, &mut state.file_index, &media.uri);
+                record
+                    .images
+                    .push(attachment(state, "image", &media.uri, info)?);
+            }
+        }
     }
 
-    // Collect audio URIs (if we can probe durations from zip)
-    let mut audio_uris: Vec<String> = Vec::new();
-    for m in &parsed.messages {
-        for media in &m.media {
-            if classify_media(&media.uri) == "audio" {
-                audio_uris.push(media.uri.clone());
+    if let Some(poll) = m.poll {
+        let mut options = Vec::with_capacity(poll.options.len());
+        for option in poll.options {
+            let mut voter_ids = Vec::with_capacity(option.voters.len());
+            for voter in &option.voters {
+                voter_ids.push(ensure_person_in_conversation(batch, state, conv_id, voter)?);
             }
+            options.push(PollOption {
+                text: option.text,
+                vote_count: option.vote_count,
+                voter_ids,
+            });
         }
+        let question = if poll.question.trim().is_empty() {
+            poll_text.unwrap_or_default()
+        } else {
+            poll.question
+        };
+        record.poll = Some(Poll { question, options });
     }
+    record.location = m.location.map(|l| Location {
+        latitude: l.latitude,
+        longitude: l.longitude,
+        name: l.name.or(l.address),
+        is_live: l.is_live,
+    });
+    record.contacts = m
+        .contacts
+        .into_iter()
+        .map(|c| Contact {
+            name: c.name,
+            phone_number: c.phone_number,
+            email: c.email,
+        })
+        .collect();
 
-    for m in parsed.messages {
-        ensure_not_cancelled()?;
-        // Sender (per-conversation person)
-        let sender_id = ensure_person_in_conversation(batch, state, conv_id, &m.sender_name)?;
+    for r in m.reactions {
+        let reactor_id = ensure_person_in_conversation(batch, state, conv_id, &r.actor)?;
+        record.reactions.push((reactor_id, r.reaction));
+    }
+    Ok(record)
+}
 
-        // Timestamp: detect seconds vs ms
-        let mut sent_at = m.timestamp;
-        if sent_at > 1_000_000_000_000 {
-            // treat as ms
-            sent_at /= 1000;
-        }
+/// Savepoint a thread is streamed under in lenient mode.
+const STREAM_SAVEPOINT: &str = "streamed_thread";
 
-        let is_unsent = m.is_unsent;
-        let msg_id = batch
-            .insert_message(sender_id, sent_at, is_unsent)
-            .context("insert e2e msg")?;
+/// Stream one thread entry, inserting messages as they are read without holding them in
+/// memory. Output matches [`import_e2e_thread`].
+///
+/// The thread is begun at the first message from the header fields before the `messages`
+/// array; a file with its header after the messages has it read first, in a pass that skips
+/// over the messages. In lenient mode the thread is written under a savepoint and without
+/// message checkpoints, so a file found malformed halfway is dropped whole, as the buffered
+/// importer does.
+fn import_e2e_thread_streaming<R: Seek + Read>(
+    archive: &mut ZipArchive<R>,
+    json_path: &str,
+    export_id: i64,
+    batch: &mut WriteBatch<'_>,
+    state: &mut ImportState,
+    progress: &mut ImportProgressTracker,
+) -> Result<()> {
+    let lenient = state.options.lenient;
+    let messages_done = match state.entry_resume(json_path) {
+        EntryResume::Partial { messages_done } => messages_done,
+        _ => 0,
+    };
+    if lenient {
+        batch.savepoint(STREAM_SAVEPOINT)?;
+    }
 
-        if !m.text.trim().is_empty() {
-            batch
-                .add_message_text(msg_id, &m.text)
-                .context("attach text")?;
-        }
-        for media in m.media {
-            match classify_media(&media.uri) {
-                "audio" => {
-                    // Prefer current ZIP; fall back to global media index by full pathname
-                    let len_opt = if let Ok(mut f) = archive.by_name(&media.uri) {
-                        detect_duration_seconds(&media.uri, &mut f)
-                    } else {
-                        state
-                            .file_index
-                            .with_file(&media.uri, |r| detect_duration_seconds(&media.uri, r))
-                            .unwrap_or(None)
+    let mut known_header: Option<E2eExportRoot> = None;
+    let mut skipped = Vec::new();
+    // Conversation and thread key of the thread once begun.
+    let mut thread: Option<(i64, String)> = None;
+    let mut undo = None;
+    // Index among messages that parsed, matching the non-streaming path in lenient mode.
+    let mut valid_index = 0;
+    let streamed = loop {
+        skipped.clear();
+        let entry = archive
+            .by_name(json_path)
+            .map_err(|e| ImportError::zip(json_path, e))?;
+        let streamed = stream_thread::<E2eExportRoot, Message, _, _>(
+            json_path,
+            entry,
+            lenient,
+            |fields, file_index, m| {
+                let m = match m {
+                    Ok(m) => m,
+                    Err(error) => {
+                        skipped.push(SkippedItem {
+                            path: json_path.to_string(),
+                            message_index: Some(file_index),
+                            error,
+                        });
+                        return Ok(());
+                    }
+                };
+                if thread.is_none() {
+                    let read_header;
+                    let header = match &known_header {
+                        Some(header) => header,
+                        None if fields.contains_key("participants")
+                            && fields.contains_key("threadName") =>
+                        {
+                            read_header = serde_json::from_value::<E2eExportRoot>(
+                                serde_json::Value::Object(fields.clone()),
+                            )
+                            .map_err(|e| ImportError::json(json_path, e))?;
+                            &read_header
+                        }
+                        None => return Err(HeaderAfterMessages.into()),
                     };
-                    batch
-                        .add_message_audio(msg_id, &media.uri, len_opt)
-                        .context("attach audio")?;
+                    progress.begin_insert(&header.thread_name);
+                    undo = Some(ThreadUndo::new(state, &header.thread_name));
+                    let conv_id = begin_e2e_thread(header, export_id, batch, state)?;
+                    thread = Some((conv_id, header.thread_name.clone()));
                 }
-                "video" => {
-                    batch
-                        .add_message_video(msg_id, &media.uri)
-                        .context("attach video")?;
+                let (conv_id, thread_key) = thread.as_ref().unwrap();
+                let index = valid_index;
+                valid_index += 1;
+                if index < messages_done {
+                    return Ok(());
                 }
-                "gif" => {
-                    batch
-                        .add_message_gif(msg_id, &media.uri)
-                        .context("attach gif")?;
+                ensure_not_cancelled(&state.cancel)?;
+                let source = state.message_source(export_id, json_path, file_index);
+                queue_e2e_message(&mut NoMedia, m, *conv_id, source, batch, state)?;
+                if lenient {
+                    return Ok(());
                 }
-                _ => {
-                    batch
-                        .add_message_image(msg_id, &media.uri)
-                        .context("attach image")?;
+                state.message_checkpoint(batch, json_path, thread_key, *conv_id, index + 1)
+            },
+        );
+        match streamed {
+            Err(err) if err.is::<HeaderAfterMessages>() => {
+                let entry = archive
+                    .by_name(json_path)
+                    .map_err(|e| ImportError::zip(json_path, e))?;
+                match read_thread_header(json_path, entry) {
+                    Ok(header) => known_header = Some(header),
+                    Err(err) => break Err(err),
                 }
             }
+            streamed => break streamed,
         }
+    };
+    let header = match streamed {
+        Ok(header) => header,
+        Err(err) if lenient && is_unreadable_thread(&err) => {
+            batch.rollback_to_savepoint(STREAM_SAVEPOINT)?;
+            if let Some(undo) = undo {
+                undo.undo(state);
+            }
+            state.skipped.push(SkippedItem {
+                path: json_path.to_string(),
+                message_index: None,
+                error: err.into(),
+            });
+            return Ok(());
+        }
+        Err(err) => return Err(err),
+    };
+    state.skipped.extend(skipped);
 
-        for r in m.reactions {
-            let reactor_id = ensure_person_in_conversation(batch, state, conv_id, &r.actor)?;
-            batch
-                .insert_reaction(reactor_id, msg_id, &r.reaction)
-                .context("insert reaction")?;
+    let (conv_id, thread_key) = match thread {
+        Some(thread) => thread,
+        None => {
+            progress.begin_insert(&header.thread_name);
+            let conv_id = begin_e2e_thread(&header, export_id, batch, state)?;
+            (conv_id, header.thread_name)
         }
+    };
+    if lenient {
+        batch.release_savepoint(STREAM_SAVEPOINT)?;
     }
-
+    state.entry_checkpoint(batch, json_path, &thread_key, conv_id, valid_index)?;
     Ok(())
 }
@@ -0,0 +1,27 @@
+//! Call log messages of Facebook Messenger exports.
+//!
+//! Facebook marks call messages only with `call_duration`; whether the call was a video
+//! call or was missed is told by the generated message text ("Bob missed your call.",
+//! "The video chat ended.", "Nieodebrane połączenie wideo od Boba.", …), in the language
+//! of the account that requested the export.
+
+use crate::database::Call;
+
+/// Fragments of the generated text of video calls (English, Polish).
+const VIDEO_MARKERS: &[&str] = &["video", "wideo"];
+
+/// Fragments of the generated text of missed calls (English, Polish).
+const MISSED_MARKERS: &[&str] = &["missed", "nieodebran", "nie odebra"];
+
+/// Classify a call message from its text and `call_duration` (seconds). A call that
+/// lasted no time at all counts as missed whatever its text says.
+pub fn classify_call(content: Option<&str>, duration_seconds: i64) -> Call {
+    let text = content.unwrap_or_default().to_lowercase();
+    let mentions = |markers: &[&str]| markers.iter().any(|m| text.contains(m));
+    let duration_seconds = duration_seconds.max(0);
+    Call {
+        duration_seconds,
+        missed: duration_seconds == 0 || mentions(MISSED_MARKERS),
+        video: mentions(VIDEO_MARKERS),
+    }
+}
@@ -1,11 +1,26 @@
+//! Serde model of Facebook `message_N.json` files.
+//!
+//! Only `sender_name` and `timestamp_ms` are required on a message; everything else
+//! defaults when absent so new or dropped Meta fields do not fail the whole thread.
+//! Unknown fields are ignored.
+
 use serde::{Deserialize, Serialize};
 
+fn default_true() -> bool {
+    true
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FacebookExportRoot {
+    #[serde(default)]
     pub participants: Vec<Participant>,
+    #[serde(default)]
     pub messages: Vec<Message>,
+    #[serde(default)]
     pub title: String,
+    #[serde(default = "default_true")]
     pub is_still_participant: bool,
+    #[serde(default)]
     pub thread_path: String,
     // pub magic_words: Vec<serde_json::Value>, // won't be used
     pub image: Option<Image>,
@@ -22,7 +37,9 @@ pub struct Message {
     pub sender_name: String,
     pub timestamp_ms: i64,
     pub content: Option<String>,
+    #[serde(default)]
     pub is_geoblocked_for_viewer: bool,
+    /// A parent removed the image from a Messenger Kids conversation.
     pub is_unsent_image_by_messenger_kid_parent: Option<bool>,
     pub audio_files: Option<Vec<AudioFile>>,
     pub reactions: Option<Vec<Reaction>>,
@@ -33,11 +50,17 @@ pub struct Message {
     pub gifs: Option<Vec<Gif>>,
     pub sticker: Option<Sticker>,
     pub call_duration: Option<i64>,
+    /// `"Generic"`, `"Share"`, `"Call"`, `"Subscribe"`, `"Unsubscribe"`, … (older exports).
+    #[serde(rename = "type")]
+    pub message_type: Option<String>,
+    /// People added or removed by a `Subscribe`/`Unsubscribe` message.
+    pub users: Option<Vec<Participant>>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AudioFile {
     pub uri: String,
+    #[serde(default)]
     pub creation_timestamp: i64,
 }
 
@@ -50,12 +73,14 @@ pub struct Reaction {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Video {
     pub uri: String,
+    #[serde(default)]
     pub creation_timestamp: i64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Photo {
     pub uri: String,
+    #[serde(default)]
     pub creation_timestamp: i64,
 }
 
@@ -79,11 +104,14 @@ pub struct Sticker {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Image {
     pub uri: String,
+    #[serde(default)]
     pub creation_timestamp: i64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct JoinableMode {
+    #[serde(default)]
     pub mode: i64,
+    #[serde(default)]
     pub link: String,
 }
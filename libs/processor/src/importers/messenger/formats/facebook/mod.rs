@@ -3,23 +3,39 @@
 //! Handles the legacy Facebook Messenger export format, including
 //! encoding fixes and thread import functionality.
 
+use std::collections::HashMap;
+use std::fs::File;
 use std::io::Read;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
-use anyhow::{Context, Result};
-use serde_json;
+use anyhow::Result;
 use zip::ZipArchive;
 
-use crate::database::WriteBatch;
-use crate::importers::messenger::utils::{ensure_conversation, ensure_person_in_conversation};
-use crate::importers::messenger::ImportState;
+use crate::database::{
+    Call, ConversationEvent, ConversationMeta, Link, Location, MessageRecord, Poll, Sticker,
+    UnsentReason, WriteBatch,
+};
+use crate::error::ImportError;
+use crate::importers::messenger::checkpoint::EntryResume;
+use crate::importers::messenger::pipeline::{
+    attachment, audio_info, gif_info, image_info, parse_in_parallel, probe_media, sticker_info,
+    video_info, ArchiveMedia, MediaLookup, NoMedia, ProbedMedia,
+};
+use crate::importers::messenger::utils::{
+    classify_event,
+    encoding::{fix_encoding, fix_message_encoding},
+    ensure_conversation, ensure_person_in_conversation, is_like_sticker, parse_thread_lenient,
+    parsed_message_positions, queue_message, stream_thread, MESSAGE_FLUSH_ROWS,
+};
+use crate::importers::messenger::{entry_size, ImportState, SkippedItem};
 use crate::progress::{ensure_not_cancelled, ImportProgressTracker};
-use crate::utils::audio::detect_duration_seconds;
 
+pub mod calls;
 pub mod json;
 pub mod paths;
+pub mod shares;
 
-use json::FacebookExportRoot;
+use json::{FacebookExportRoot, Message};
 
 /// Import a Facebook Messenger ZIP archive.
 pub fn import_facebook_archive<R: std::io::Seek + std::io::Read>(
@@ -31,180 +47,594 @@ pub fn import_facebook_archive<R: std::io::Seek + std::io::Read>(
 ) -> Result<()> {
     let is_messages_re = &paths::MESSAGES_RE;
     let entries = paths::collect_message_entries(archive, is_messages_re);
-    progress.add_total(entries.len() as u32);
     for (_thread_dir, _num, json_path) in entries.into_iter() {
-        ensure_not_cancelled()?;
-        let mut json_content = String::new();
-        {
-            let mut file = archive
-                .by_name(&json_path)
-                .with_context(|| format!("opening {}", json_path))?;
-            file.read_to_string(&mut json_content)
-                .with_context(|| format!("reading {}", json_path))?;
+        ensure_not_cancelled(&state.cancel)?;
+        progress.begin_file(&json_path);
+        let bytes = entry_size(archive, &json_path);
+        if state.entry_resume(&json_path) == EntryResume::Done {
+            progress.advance_bytes(bytes);
+            progress.advance(1);
+            continue;
         }
-
-        let parsed: FacebookExportRoot = serde_json::from_str(&json_content)
-            .with_context(|| format!("parsing {}", json_path))?;
-        let parsed = crate::importers::messenger::utils::encoding::fix_encoding(parsed);
-
-        let path: PathBuf = json_path.clone().into();
-        let folder_name_cow = path
-            .parent()
-            .unwrap()
-            .file_name()
-            .unwrap()
-            .to_string_lossy();
-
-        let folder_name = folder_name_cow.as_ref();
-        let thread_dir_path = path.parent().unwrap().to_string_lossy();
-        import_thread(
-            archive,
-            folder_name,
-            &thread_dir_path,
-            &parsed,
-            export_id,
-            batch,
-            state,
-        )?;
-
+        if state.options.streaming && state.entry_resume(&json_path) == EntryResume::Fresh {
+            import_thread_streaming(archive, &json_path, export_id, batch, state, progress)?;
+        } else {
+            let parsed = read_thread(archive, &json_path, state.options.lenient);
+            write_thread(
+                &json_path,
+                parsed,
+                &mut ArchiveMedia(archive),
+                export_id,
+                batch,
+                state,
+                progress,
+            )?;
+        }
+        progress.advance_bytes(bytes);
         progress.advance(1);
     }
     Ok(())
 }
 
-/// Import a single Facebook Messenger thread.
-pub fn import_thread<R: std::io::Seek + std::io::Read>(
+/// Import a Facebook Messenger ZIP archive, parsing thread files on `workers` threads.
+///
+/// Only the calling thread writes, in the same order as [`import_facebook_archive`].
+pub fn import_facebook_archive_parallel(
+    archive_path: &Path,
+    archive: &mut ZipArchive<File>,
+    workers: usize,
+    export_id: i64,
+    batch: &mut WriteBatch<'_>,
+    state: &mut ImportState,
+    progress: &mut ImportProgressTracker,
+) -> Result<()> {
+    let entries: Vec<String> = paths::collect_message_entries(archive, &paths::MESSAGES_RE)
+        .into_iter()
+        .map(|(_, _, json_path)| json_path)
+        .collect();
+    let done: Vec<bool> = entries
+        .iter()
+        .map(|e| state.entry_resume(e) == EntryResume::Done)
+        .collect();
+    let lenient = state.options.lenient;
+    let cancel = state.cancel.clone();
+
+    parse_in_parallel(
+        archive_path,
+        &entries,
+        workers,
+        &cancel,
+        |worker_archive, index, json_path| {
+            if done[index] {
+                return None;
+            }
+            Some(
+                read_thread(worker_archive, json_path, lenient).map(|(root, skipped)| {
+                    let audio_uris = root
+                        .messages
+                        .iter()
+                        .flat_map(|m| m.audio_files.iter().flatten())
+                        .map(|a| a.uri.as_str());
+                    let video_uris = root
+                        .messages
+                        .iter()
+                        .flat_map(|m| m.videos.iter().flatten())
+                        .map(|v| v.uri.as_str());
+                    let image_uris = root.messages.iter().flat_map(|m| {
+                        let photos = m.photos.iter().flatten().map(|p| p.uri.as_str());
+                        let gifs = m.gifs.iter().flatten().map(|g| g.uri.as_str());
+                        m.sticker
+                            .iter()
+                            .map(|s| s.uri.as_str())
+                            .chain(photos)
+                            .chain(gifs)
+                    });
+                    let probed = probe_media(worker_archive, audio_uris, video_uris, image_uris);
+                    (root, skipped, probed)
+                }),
+            )
+        },
+        |_, json_path, parsed| {
+            ensure_not_cancelled(&state.cancel)?;
+            progress.begin_file(json_path);
+            let bytes = entry_size(archive, json_path);
+            if let Some(parsed) = parsed {
+                let (parsed, mut probed) = match parsed {
+                    Ok((root, skipped, probed)) => (Ok((root, skipped)), probed),
+                    Err(e) => (Err(e), ProbedMedia::default()),
+                };
+                write_thread(
+                    json_path,
+                    parsed,
+                    &mut probed,
+                    export_id,
+                    batch,
+                    state,
+                    progress,
+                )?;
+            }
+            progress.advance_bytes(bytes);
+            progress.advance(1);
+            Ok(())
+        },
+    )
+}
+
+/// Write a thread file read by [`read_thread`]; in lenient mode an unreadable one is
+/// recorded as skipped instead.
+fn write_thread(
+    json_path: &str,
+    parsed: Result<(FacebookExportRoot, Vec<SkippedItem>), ImportError>,
+    media: &mut dyn MediaLookup,
+    export_id: i64,
+    batch: &mut WriteBatch<'_>,
+    state: &mut ImportState,
+    progress: &mut ImportProgressTracker,
+) -> Result<()> {
+    let parsed = match parsed {
+        Ok((parsed, skipped)) => {
+            state.message_positions = parsed_message_positions(parsed.messages.len(), &skipped);
+            state.skipped.extend(skipped);
+            parsed
+        }
+        Err(err) if state.options.lenient => {
+            state.skipped.push(SkippedItem {
+                path: json_path.to_string(),
+                message_index: None,
+                error: err,
+            });
+            return Ok(());
+        }
+        Err(err) => return Err(err.into()),
+    };
+    progress.begin_insert(&parsed.title);
+
+    let folder_name = thread_folder_name(json_path);
+    import_thread(
+        media,
+        &folder_name,
+        json_path,
+        &parsed,
+        export_id,
+        batch,
+        state,
+    )
+}
+
+/// Name of the thread folder a `message_N.json` entry lives in.
+fn thread_folder_name(json_path: &str) -> String {
+    let path: PathBuf = json_path.into();
+    path.parent()
+        .unwrap()
+        .file_name()
+        .unwrap()
+        .to_string_lossy()
+        .into_owned()
+}
+
+/// Read, deserialize and fix the encoding of one `message_N.json` entry.
+///
+/// With `lenient`, a thread that fails strict parsing is re-parsed message by message;
+/// messages that still fail are returned alongside it.
+fn read_thread<R: std::io::Seek + std::io::Read>(
     archive: &mut ZipArchive<R>,
+    json_path: &str,
+    lenient: bool,
+) -> Result<(FacebookExportRoot, Vec<SkippedItem>), ImportError> {
+    let mut json_content = String::new();
+    {
+        let mut file = archive
+            .by_name(json_path)
+            .map_err(|e| ImportError::zip(json_path, e))?;
+        file.read_to_string(&mut json_content)
+            .map_err(|e| ImportError::io(json_path, e))?;
+    }
+
+    let (root, skipped) = match serde_json::from_str::<FacebookExportRoot>(&json_content) {
+        Ok(parsed) => (parsed, Vec::new()),
+        Err(_) if lenient => {
+            let (mut root, messages, skipped) =
+                parse_thread_lenient::<FacebookExportRoot, Message>(json_path, &json_content)?;
+            root.messages = messages;
+            (root, skipped)
+        }
+        Err(e) => return Err(ImportError::json(json_path, e)),
+    };
+    Ok((fix_encoding(root), skipped))
+}
+
+/// Import a single Facebook Messenger thread file (`json_path` is its entry name).
+///
+/// In checkpoint mode, messages a previous run already committed are skipped.
+pub fn import_thread(
+    media: &mut dyn MediaLookup,
     folder_name: &str,
-    _thread_dir_path: &str,
+    json_path: &str,
     parsed: &FacebookExportRoot,
     export_id: i64,
     batch: &mut WriteBatch<'_>,
     state: &mut crate::importers::messenger::ImportState,
 ) -> Result<()> {
+    let conv_id = begin_thread(folder_name, json_path, parsed, export_id, batch, state)?;
+
+    // Messages
+    let messages_done = match state.entry_resume(json_path) {
+        EntryResume::Partial { messages_done } => messages_done,
+        _ => 0,
+    };
+    let message_count = parsed.messages.len();
+    for (index, m) in parsed.messages.iter().rev().enumerate().skip(messages_done) {
+        ensure_not_cancelled(&state.cancel)?;
+        if let Some(mut record) = build_message(media, m, conv_id, batch, state)? {
+            let position = state.message_position(message_count - 1 - index);
+            record.source = state.message_source(export_id, json_path, position);
+            queue_message(batch, state, record)?;
+        }
+        state.message_checkpoint(batch, json_path, folder_name, conv_id, index + 1)?;
+    }
+    state.entry_checkpoint(
+        batch,
+        json_path,
+        folder_name,
+        conv_id,
+        parsed.messages.len(),
+    )?;
+    Ok(())
+}
+
+/// Create the conversation of a thread and its participants; returns the conversation id.
+fn begin_thread(
+    folder_name: &str,
+    json_path: &str,
+    parsed: &FacebookExportRoot,
+    export_id: i64,
+    batch: &mut WriteBatch<'_>,
+    state: &mut ImportState,
+) -> Result<i64> {
     // Create conversation (and canonical) and ensure users (participants) per conversation
-    let image_uri = parsed.image.as_ref().map(|i| i.uri.as_str());
+    let meta = ConversationMeta {
+        image_uri: parsed.image.as_ref().map(|i| i.uri.clone()),
+        platform_thread_id: Some(parsed.thread_path.clone()).filter(|p| !p.is_empty()),
+        owner_left: !parsed.is_still_participant,
+        invite_link: parsed
+            .joinable_mode
+            .as_ref()
+            .map(|j| j.link.clone())
+            .filter(|link| !link.is_empty()),
+        archive_category: paths::archive_category(json_path),
+    };
     let conv_id = ensure_conversation(
         batch,
         state,
         folder_name,
         parsed.participants.len(),
-        image_uri,
         Some(&parsed.title),
+        &meta,
         export_id,
     )?;
     for p in &parsed.participants {
-        ensure_person_in_conversation(batch, state, conv_id, p.name.as_str())?;
+        let person_id = ensure_person_in_conversation(batch, state, conv_id, p.name.as_str())?;
+        batch.mark_participant(person_id)?;
     }
+    Ok(conv_id)
+}
 
-    // Messages
-    for m in parsed.messages.iter().rev() {
-        ensure_not_cancelled()?;
-        if m.is_geoblocked_for_viewer {
-            continue;
+/// Stream one thread file in two passes without holding its messages in memory.
+///
+/// Facebook lists messages newest first while the buffered importer writes them oldest
+/// first, so the first pass validates the file, reads the header and records, per message,
+/// whether it gets a row and where each person first shows up chronologically. That counts
+/// the rows exactly; a pass skipping over the messages could not tell which ones get a row
+/// or in which order people first appear. The second pass writes messages as they come,
+/// under ids counted down from the last row so they end up in chronological order, exactly
+/// as the buffered importer numbers them. Only whole threads are checkpointed: an entry a
+/// previous run left half-written goes through [`import_thread`] instead.
+fn import_thread_streaming<R: std::io::Seek + std::io::Read>(
+    archive: &mut ZipArchive<R>,
+    json_path: &str,
+    export_id: i64,
+    batch: &mut WriteBatch<'_>,
+    state: &mut ImportState,
+    progress: &mut ImportProgressTracker,
+) -> Result<()> {
+    let lenient = state.options.lenient;
+    let mut skipped = Vec::new();
+    let mut writes_row: Vec<bool> = Vec::new();
+    // Person name -> (latest file position, position within that message).
+    let mut first_seen: HashMap<String, (usize, usize)> = HashMap::new();
+    let header = {
+        let entry = archive
+            .by_name(json_path)
+            .map_err(|e| ImportError::zip(json_path, e))?;
+        stream_thread::<FacebookExportRoot, Message, _, _>(
+            json_path,
+            entry,
+            lenient,
+            |_, index, m| {
+                let mut m = match m {
+                    Ok(m) => m,
+                    Err(error) => {
+                        skipped.push(SkippedItem {
+                            path: json_path.to_string(),
+                            message_index: Some(index),
+                            error,
+                        });
+                        return Ok(());
+                    }
+                };
+                fix_message_encoding(&mut m);
+                let position = writes_row.len();
+                let writes = writes_row_for(&m);
+                writes_row.push(writes);
+                if m.is_geoblocked_for_viewer {
+                    return Ok(());
+                }
+                let mut names = vec![m.sender_name];
+                if writes {
+                    names.extend(m.reactions.into_iter().flatten().map(|r| r.actor));
+                }
+                for (slot, name) in names.into_iter().enumerate() {
+                    let seen = first_seen.entry(name).or_insert((position, slot));
+                    if seen.0 < position {
+                        *seen = (position, slot);
+                    }
+                }
+                Ok(())
+            },
+        )
+    };
+    let header = match header {
+        Ok(header) => fix_encoding(header),
+        Err(err) if lenient => {
+            state.skipped.push(SkippedItem {
+                path: json_path.to_string(),
+                message_index: None,
+                error: err.into(),
+            });
+            return Ok(());
         }
+        Err(err) => return Err(err),
+    };
+    state.skipped.extend(skipped);
 
-        // sender id (per-conversation)
-        let sender_id = ensure_person_in_conversation(batch, state, conv_id, &m.sender_name)?;
-
-        let sent_at = m.timestamp_ms / 1000;
+    progress.begin_insert(&header.title);
+    let folder_name = thread_folder_name(json_path);
+    let conv_id = begin_thread(&folder_name, json_path, &header, export_id, batch, state)?;
+    let mut people: Vec<_> = first_seen.into_iter().collect();
+    people.sort_by(|(_, a), (_, b)| b.0.cmp(&a.0).then(a.1.cmp(&b.1)));
+    for (name, _) in people {
+        ensure_person_in_conversation(batch, state, conv_id, &name)?;
+    }
 
-        enum Variant<'a> {
-            Text(&'a str),
-            Image(&'a str),
-            Gif(&'a str),
-            Audio(&'a str),
-            Video(&'a str),
-        }
-        let mut variants: Vec<Variant<'_>> = Vec::new();
+    let rows = writes_row.iter().filter(|w| **w).count() as i64;
+    let first_id = batch.next_message_id()?;
 
-        let is_unsent = m.is_unsent.unwrap_or(false);
-        if let Some(text) = m.content.as_deref() {
-            if !text.trim().is_empty() {
-                variants.push(Variant::Text(text));
-            }
-        }
-        if let Some(sticker) = m.sticker.as_ref() {
-            variants.push(Variant::Image(sticker.uri.as_str()));
-        }
-        if let Some(photos) = m.photos.as_ref() {
-            for p in photos {
-                variants.push(Variant::Image(p.uri.as_str()));
+    // Rows arrive newest first, i.e. with descending ids; each chunk is written reversed.
+    let mut written = 0;
+    let mut chunk: Vec<MessageRecord> = Vec::new();
+    let flush = |chunk: &mut Vec<MessageRecord>, lowest_id: i64, batch: &mut WriteBatch<'_>| {
+        chunk.reverse();
+        let result = batch.insert_messages_bulk_at(lowest_id, chunk);
+        chunk.clear();
+        result
+    };
+    let entry = archive
+        .by_name(json_path)
+        .map_err(|e| ImportError::zip(json_path, e))?;
+    stream_thread::<serde::de::IgnoredAny, Message, _, _>(
+        json_path,
+        entry,
+        lenient,
+        |_, file_index, m| {
+            let Ok(mut m) = m else {
+                return Ok(());
+            };
+            ensure_not_cancelled(&state.cancel)?;
+            fix_message_encoding(&mut m);
+            if let Some(mut record) = build_message(&mut NoMedia, &m, conv_id, batch, state)? {
+                record.source = state.message_source(export_id, json_path, file_index);
+                written += 1;
+                chunk.push(record);
+                if chunk.len() >= MESSAGE_FLUSH_ROWS {
+                    flush(&mut chunk, first_id + rows - written, batch)?;
+                }
             }
+            Ok(())
+        },
+    )?;
+    flush(&mut chunk, first_id + rows - written, batch)?;
+    state.entry_checkpoint(batch, json_path, &folder_name, conv_id, writes_row.len())?;
+    Ok(())
+}
+
+/// A piece of message content, stored as one attachment row.
+enum Variant<'a> {
+    Text(&'a str),
+    Image(&'a str),
+    Gif(&'a str),
+    Sticker(&'a str),
+    Audio(&'a str),
+    Video(&'a str),
+    Call(Call),
+    Link {
+        url: &'a str,
+        share_text: Option<&'a str>,
+    },
+    Events(Vec<ConversationEvent>),
+    Poll(Poll),
+    Location(Location),
+}
+
+/// Content rows of a message, in insertion order.
+///
+/// The text of system messages, calls and new polls is not kept as text; their events, the
+/// call and the poll are recorded instead.
+fn message_variants(m: &Message) -> Vec<Variant<'_>> {
+    let mut variants: Vec<Variant<'_>> = Vec::new();
+
+    let events = if has_attachments(m) || m.call_duration.is_some() {
+        Vec::new()
+    } else {
+        let users: Vec<&str> = m.users.iter().flatten().map(|u| u.name.as_str()).collect();
+        classify_event(
+            &m.sender_name,
+            m.message_type.as_deref(),
+            &users,
+            m.content.as_deref(),
+        )
+    };
+    let poll = match m.content.as_deref() {
+        Some(text) if !has_attachments(m) => shares::created_poll(&m.sender_name, text),
+        _ => None,
+    };
+    if !events.is_empty() {
+        variants.push(Variant::Events(events));
+    } else if let Some(poll) = poll {
+        variants.push(Variant::Poll(poll));
+    } else if let Some(text) = m.content.as_deref() {
+        if !text.trim().is_empty() && m.call_duration.is_none() {
+            variants.push(Variant::Text(text));
         }
-        if let Some(videos) = m.videos.as_ref() {
-            for v in videos {
-                variants.push(Variant::Video(v.uri.as_str()));
-            }
+    }
+    if let Some(sticker) = m.sticker.as_ref() {
+        variants.push(Variant::Sticker(sticker.uri.as_str()));
+    }
+    if let Some(photos) = m.photos.as_ref() {
+        for p in photos {
+            variants.push(Variant::Image(p.uri.as_str()));
         }
-        if let Some(gifs) = m.gifs.as_ref() {
-            for g in gifs {
-                variants.push(Variant::Gif(g.uri.as_str()));
-            }
+    }
+    if let Some(videos) = m.videos.as_ref() {
+        for v in videos {
+            variants.push(Variant::Video(v.uri.as_str()));
         }
-        if let Some(audios) = m.audio_files.as_ref() {
-            for a in audios {
-                variants.push(Variant::Audio(a.uri.as_str()));
-            }
+    }
+    if let Some(gifs) = m.gifs.as_ref() {
+        for g in gifs {
+            variants.push(Variant::Gif(g.uri.as_str()));
         }
-        if let Some(share) = m.share.as_ref() {
-            if let Some(text) = share.share_text.as_deref() {
-                if !text.trim().is_empty() {
-                    variants.push(Variant::Text(text));
-                }
-            } else if let Some(link) = share.link.as_deref() {
-                variants.push(Variant::Text(link));
-            }
+    }
+    if let Some(audios) = m.audio_files.as_ref() {
+        for a in audios {
+            variants.push(Variant::Audio(a.uri.as_str()));
         }
-        if variants.is_empty() && !is_unsent {
-            continue;
+    }
+    if let Some(duration) = m.call_duration {
+        variants.push(Variant::Call(calls::classify_call(
+            m.content.as_deref(),
+            duration,
+        )));
+    }
+    if let Some(location) = m.share.as_ref().and_then(shares::shared_location) {
+        variants.push(Variant::Location(location));
+    } else if let Some(share) = m.share.as_ref() {
+        let share_text = share.share_text.as_deref().filter(|t| !t.trim().is_empty());
+        match share.link.as_deref().filter(|l| !l.trim().is_empty()) {
+            Some(url) => variants.push(Variant::Link { url, share_text }),
+            // Shared content without a link, e.g. a location or a post.
+            None => variants.extend(share_text.map(Variant::Text)),
         }
+    }
+    variants
+}
 
-        // Create a single base message row.
-        let msg_id = batch
-            .insert_message(sender_id, sent_at, is_unsent)
-            .with_context(|| format!("insert msg conv_id {} (unsent={})", conv_id, is_unsent))?;
-
-        // Attach all variants to this message.
-        for v in variants.iter() {
-            match v {
-                Variant::Text(t) => batch
-                    .add_message_text(msg_id, t)
-                    .with_context(|| format!("attach text to msg {}", msg_id))?,
-                Variant::Image(u) => batch
-                    .add_message_image(msg_id, u)
-                    .with_context(|| format!("attach image to msg {}", msg_id))?,
-                Variant::Gif(u) => batch
-                    .add_message_gif(msg_id, u)
-                    .with_context(|| format!("attach gif to msg {}", msg_id))?,
-                Variant::Audio(u) => {
-                    // Prefer current ZIP; fall back to global media index by full pathname
-                    let len_opt = if let Ok(mut f) = archive.by_name(u) {
-                        detect_duration_seconds(u, &mut f)
-                    } else {
-                        state
-                            .file_index
-                            .with_file(u, |r| detect_duration_seconds(u, r))
-                            .unwrap_or(None)
-                    };
-                    batch
-                        .add_message_audio(msg_id, u, len_opt)
-                        .with_context(|| format!("attach audio to msg {}", msg_id))?
-                }
-                Variant::Video(u) => batch
-                    .add_message_video(msg_id, u)
-                    .with_context(|| format!("attach video to msg {}", msg_id))?,
-            }
-        }
+/// Whether a message carries anything besides text (which rules out a system message).
+fn has_attachments(m: &Message) -> bool {
+    m.sticker.is_some()
+        || m.share.is_some()
+        || m.photos.as_ref().is_some_and(|v| !v.is_empty())
+        || m.videos.as_ref().is_some_and(|v| !v.is_empty())
+        || m.gifs.as_ref().is_some_and(|v| !v.is_empty())
+        || m.audio_files.as_ref().is_some_and(|v| !v.is_empty())
+}
+
+/// Why `m` was unsent, if it was.
+fn unsent_reason(m: &Message) -> Option<UnsentReason> {
+    if m.is_unsent.unwrap_or(false) {
+        Some(UnsentReason::Sender)
+    } else if m.is_unsent_image_by_messenger_kid_parent.unwrap_or(false) {
+        Some(UnsentReason::MessengerKidParent)
+    } else {
+        None
+    }
+}
+
+/// Whether [`build_message`] builds a message row for `m`.
+fn writes_row_for(m: &Message) -> bool {
+    !m.is_geoblocked_for_viewer && (unsent_reason(m).is_some() || !message_variants(m).is_empty())
+}
 
-        if let (pid, Some(reactions)) = (msg_id, m.reactions.as_ref()) {
-            for r in reactions {
-                let reactor_id = ensure_person_in_conversation(batch, state, conv_id, &r.actor)?;
-                batch
-                    .insert_reaction(reactor_id, pid, &r.reaction)
-                    .with_context(|| format!("insert reaction on msg {}", pid))?;
+/// Build the row of one message with its attachments and reactions, ensuring the people
+/// involved exist. Returns `None` for messages that get no row.
+///
+/// Attachment details come from `media`, falling back to the global media index for URIs
+/// it does not know.
+fn build_message(
+    media: &mut dyn MediaLookup,
+    m: &Message,
+    conv_id: i64,
+    batch: &mut WriteBatch<'_>,
+    state: &mut ImportState,
+) -> Result<Option<MessageRecord>> {
+    if m.is_geoblocked_for_viewer {
+        return Ok(None);
+    }
+
+    // sender id (per-conversation)
+    let sender_id = ensure_person_in_conversation(batch, state, conv_id, &m.sender_name)?;
+
+    let unsent_reason = unsent_reason(m);
+    let variants = message_variants(m);
+    if variants.is_empty() && unsent_reason.is_none() {
+        return Ok(None);
+    }
+
+    let mut record = MessageRecord {
+        sender_id,
+        sent_at: m.timestamp_ms / 1000,
+        unsent: unsent_reason.is_some(),
+        unsent_reason,
+        ..Default::default()
+    };
+    for v in variants {
+        match v {
+            Variant::Text(t) => record.texts.push(t.to_string()),
+            Variant::Image(u) => {
+                let info = image_info(media, &mut state.file_index, u);
+                record.images.push(attachment(state, "image", u, info)?);
             }
+            Variant::Gif(u) => {
+                let info = gif_info(media, &mut state.file_index, u);
+                record.gifs.push(attachment(state, "gif", u, info)?);
+            }
+            Variant::Sticker(u) => {
+                let info = sticker_info(media, &mut state.file_index, u);
+                record.stickers.push(Sticker {
+                    image: attachment(state, "sticker", u, info)?,
+                    is_like: is_like_sticker(u),
+                });
+            }
+            Variant::Audio(u) => {
+                let info = audio_info(media, &mut state.file_index, u);
+                record.audios.push(attachment(state, "audio", u, info)?);
+            }
+            Variant::Video(u) => {
+                let info = video_info(media, &mut state.file_index, u);
+                record.videos.push(attachment(state, "video", u, info)?);
+            }
+            Variant::Call(call) => record.call = Some(call),
+            Variant::Events(events) => record.events = events,
+            Variant::Poll(poll) => record.poll = Some(poll),
+            Variant::Location(location) => record.location = Some(location),
+            Variant::Link { url, share_text } => record
+                .links
+                .push(Link::new(url, share_text.map(str::to_string))),
         }
     }
-    Ok(())
+
+    for r in m.reactions.iter().flatten() {
+        let reactor_id = ensure_person_in_conversation(batch, state, conv_id, &r.actor)?;
+        record.reactions.push((reactor_id, r.reaction.clone()));
+    }
+    Ok(Some(record))
 }
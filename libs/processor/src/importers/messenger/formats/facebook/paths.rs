@@ -8,6 +8,8 @@ use regex::Regex;
 use std::io::{Read, Seek};
 use zip::read::ZipArchive;
 
+use crate::database::ArchiveCategory;
+
 /// Facebook Messenger export directory names.
 pub const DIRECTORIES: [&str; 4] = [
     "inbox",
@@ -25,6 +27,13 @@ pub static MESSAGES_RE: Lazy<Regex> = Lazy::new(|| {
     Regex::new(&pattern).expect("valid regex")
 });
 
+/// Export folder (one of [`DIRECTORIES`]) of a message file path.
+pub fn archive_category(path: &str) -> Option<ArchiveCategory> {
+    MESSAGES_RE
+        .captures(path)
+        .and_then(|caps| caps.get(1)?.as_str().parse().ok())
+}
+
 /// Parse the message number from a Facebook message file path.
 fn parse_message_number_from_path(path: &str) -> i64 {
     path.rsplit_once("message_")
@@ -0,0 +1,49 @@
+//! Polls and locations in Facebook Messenger exports.
+//!
+//! Facebook has no structured fields for either. A shared location is a `share` whose link
+//! points at a map with the coordinates in it (Bing Maps, `where1=lat, lon`), with the
+//! place name as `share_text`. A new poll is a generated message ("Ann created a poll:
+//! Lunch?.", "Ann utworzyła ankietę: Obiad?.") that names only the question; options and
+//! votes are not exported.
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+use crate::database::{Location, Poll};
+use crate::importers::messenger::formats::facebook::json::Share;
+use crate::utils::links::map_coordinates;
+
+/// `<sender or You> created a poll: <question>.` (English, Polish).
+static POLL_CREATED_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^(.+?) (?:created a poll|utworzył(?:a|\(a\))? ankietę): (.+?)\.?$")
+        .expect("poll regex")
+});
+
+/// The location a share points at, if its link is a map link with coordinates.
+pub fn shared_location(share: &Share) -> Option<Location> {
+    let (latitude, longitude) = map_coordinates(share.link.as_deref()?)?;
+    Some(Location {
+        latitude,
+        longitude,
+        name: share
+            .share_text
+            .as_deref()
+            .map(str::trim)
+            .filter(|t| !t.is_empty())
+            .map(str::to_string),
+        is_live: false,
+    })
+}
+
+/// The poll a generated "created a poll" message from `sender` announces.
+pub fn created_poll(sender: &str, content: &str) -> Option<Poll> {
+    let captures = POLL_CREATED_RE.captures(content.trim())?;
+    let actor = &captures[1];
+    if actor != sender && actor != "You" && actor != "Ty" {
+        return None;
+    }
+    Some(Poll {
+        question: captures[2].to_string(),
+        options: Vec::new(),
+    })
+}
@@ -0,0 +1,166 @@
+//! Background import jobs.
+//!
+//! An [`ImportJob`] runs one import on its own thread with its own cancellation token and
+//! progress state, so several imports (or an import and analytics queries) can run side by
+//! side without going through the process-wide cancel flag and callbacks.
+
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+
+use serde::Serialize;
+
+use super::{import_messenger_exports_with_control, ImportOptions, ImportReport};
+use crate::error::ImportError;
+use crate::progress::{CancellationToken, ProgressSink, ProgressSnapshot};
+
+/// Lifecycle of an [`ImportJob`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobState {
+    Running,
+    Succeeded,
+    Cancelled,
+    Failed,
+}
+
+/// Point-in-time view of a job, as returned by [`ImportJob::poll`].
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct JobStatus {
+    pub state: JobState,
+    pub progress: ProgressSnapshot,
+    /// Set once the job succeeded.
+    pub report: Option<ImportReport>,
+    /// Set once the job failed.
+    pub error: Option<ImportError>,
+}
+
+/// Sink that keeps only the latest snapshot, for polling.
+#[derive(Default)]
+struct LatestProgress(Mutex<ProgressSnapshot>);
+
+impl ProgressSink for LatestProgress {
+    fn report(&self, snapshot: &ProgressSnapshot) {
+        *self.0.lock().expect("job progress mutex poisoned") = snapshot.clone();
+    }
+}
+
+type Outcome = Arc<Mutex<Option<Result<ImportReport, ImportError>>>>;
+
+fn panicked() -> ImportError {
+    ImportError::Other {
+        message: "import thread panicked".into(),
+    }
+}
+
+/// Handle to an import running on a background thread.
+///
+/// Dropping the handle cancels the job (if still running) and waits for its thread to stop.
+pub struct ImportJob {
+    cancel: CancellationToken,
+    progress: Arc<LatestProgress>,
+    outcome: Outcome,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl ImportJob {
+    /// Spawn a thread importing `paths` into `db_path`.
+    pub fn start(
+        paths: Vec<PathBuf>,
+        db_path: PathBuf,
+        options: ImportOptions,
+    ) -> Result<Self, ImportError> {
+        let cancel = CancellationToken::new();
+        let progress = Arc::new(LatestProgress::default());
+        let outcome: Outcome = Arc::new(Mutex::new(None));
+
+        let thread = {
+            let cancel = cancel.clone();
+            let sink: Arc<dyn ProgressSink> = progress.clone();
+            let outcome = outcome.clone();
+            std::thread::Builder::new()
+                .name("messenger-import".into())
+                .spawn(move || {
+                    let result = catch_unwind(AssertUnwindSafe(|| {
+                        import_messenger_exports_with_control(
+                            paths, &db_path, &options, &cancel, sink,
+                        )
+                    }))
+                    .unwrap_or_else(|_| Err(panicked()));
+                    *outcome.lock().expect("job outcome mutex poisoned") = Some(result);
+                })
+                .map_err(|e| ImportError::Io {
+                    path: None,
+                    message: format!("failed to spawn import thread: {}", e),
+                })?
+        };
+
+        Ok(Self {
+            cancel,
+            progress,
+            outcome,
+            thread: Some(thread),
+        })
+    }
+
+    /// Ask the job to stop at the next cancellation check. Without checkpoints nothing is
+    /// committed; with [`ImportOptions::checkpoint`] the threads committed so far persist
+    /// under an unfinished export, to resume or to delete with
+    /// [`rollback_export`](super::rollback_export).
+    pub fn cancel(&self) {
+        self.cancel.cancel();
+    }
+
+    /// Current state and progress, plus the report or error once finished.
+    pub fn poll(&self) -> JobStatus {
+        let progress = self
+            .progress
+            .0
+            .lock()
+            .expect("job progress mutex poisoned")
+            .clone();
+        // Checked before reading the outcome, which the thread sets before it finishes: a
+        // finished thread with no outcome died without recording one.
+        let finished = self.thread.as_ref().is_none_or(|t| t.is_finished());
+        let outcome = self.outcome.lock().expect("job outcome mutex poisoned");
+        let (state, report, error) = match outcome.as_ref() {
+            None if finished => (JobState::Failed, None, Some(panicked())),
+            None => (JobState::Running, None, None),
+            Some(Ok(report)) => (JobState::Succeeded, Some(report.clone()), None),
+            Some(Err(ImportError::Cancelled)) => (JobState::Cancelled, None, None),
+            Some(Err(err)) => (JobState::Failed, None, Some(err.clone())),
+        };
+        JobStatus {
+            state,
+            progress,
+            report,
+            error,
+        }
+    }
+
+    /// Block until the job finishes and return its outcome.
+    pub fn wait(mut self) -> Result<ImportReport, ImportError> {
+        self.join();
+        self.outcome
+            .lock()
+            .expect("job outcome mutex poisoned")
+            .take()
+            .unwrap_or_else(|| Err(panicked()))
+    }
+
+    fn join(&mut self) {
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+impl Drop for ImportJob {
+    fn drop(&mut self) {
+        if self.thread.as_ref().is_some_and(|t| !t.is_finished()) {
+            self.cancel();
+        }
+        self.join();
+    }
+}
@@ -8,28 +8,94 @@
 //! Post-import merging is handled non-destructively by assigning canonical IDs.
 
 use anyhow::{Context, Result};
+use serde::Deserialize;
 use std::{
     collections::HashMap,
     fs::File,
+    io::{Read, Seek},
     path::{Path, PathBuf},
     slice,
+    sync::Arc,
 };
 use zip::read::ZipArchive;
 
-use crate::database::{MessageDb, WriteBatch};
-use crate::progress::{ensure_not_cancelled, ImportProgressTracker};
+use crate::database::{MessageDb, MessageRecord, MessageSource, WriteBatch};
+use crate::error::ImportError;
+use crate::progress::{
+    ensure_not_cancelled, global_cancel_token, CancellationToken, GlobalCallbacks, ImportPhase,
+    ImportProgressTracker, ProgressSink,
+};
 
+pub mod checkpoint;
 pub mod formats;
+pub mod job;
+pub mod pipeline;
+pub mod report;
 pub mod utils;
 
+pub use job::{ImportJob, JobState, JobStatus};
+pub use report::{ImportReport, MissingMedia, SkippedItem};
+
+/// Knobs for a single import run. Deserializable so FFI hosts can pass them as JSON.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct ImportOptions {
+    /// Skip thread files or messages that fail to parse (recording them in the
+    /// [`ImportReport`]) instead of aborting the whole import.
+    pub lenient: bool,
+    /// Commit after every thread file and journal progress, so a killed import can be
+    /// resumed and a cancelled one rolled back (see [`checkpoint`]).
+    pub checkpoint: bool,
+    /// In checkpoint mode, also commit every this many messages within a thread (0: only
+    /// per thread file). Not applied to streamed Facebook thread files, nor to streamed E2E
+    /// ones in lenient mode; those are committed whole (see `streaming`).
+    pub checkpoint_messages: usize,
+    /// In checkpoint mode, continue an unfinished export of the same files instead of
+    /// starting a new one.
+    pub resume: bool,
+    /// Stream thread files message by message instead of loading each one whole, keeping
+    /// only one message in memory; the result is the same. Facebook thread files are read
+    /// twice and checkpointed whole; E2E ones are read once unless their header follows the
+    /// messages. In lenient mode a streamed E2E thread file is written under one savepoint,
+    /// so one found malformed halfway is dropped whole and `checkpoint_messages` does not
+    /// commit within it.
+    pub streaming: bool,
+    /// Worker threads decompressing and parsing thread files while this thread writes;
+    /// 0 or 1 imports sequentially. Ignored with `streaming`. The result is the same.
+    pub parse_threads: usize,
+    /// Copy referenced media out of the archives into a content-addressed store and record
+    /// the copies in the `stored_path` columns. `None` leaves media in the archives.
+    pub media_store: Option<utils::MediaStoreOptions>,
+    /// Record in `message_source` which archive entry and `messages` array position every
+    /// message was read from, so rows can be traced back to their JSON.
+    pub record_sources: bool,
+}
+
 /// Importer state shared across multiple files/zips in a run.
 pub struct ImportState {
     /// Map from thread folder name to conversation id, to dedupe within a run.
     pub folder_names_to_conv_ids: HashMap<String, i64>,
     /// Map of per-conversation participant name -> per-conversation person id.
     pub person_ids_by_conversation: HashMap<i64, HashMap<String, i64>>,
-    /// Global media index across all selected paths for duration probing.
+    /// Global media resolver across all selected paths; also collects missing media.
     pub file_index: utils::file_index::FileIndex,
+    /// Options this run was started with.
+    pub options: ImportOptions,
+    /// Items quarantined so far (lenient mode).
+    pub skipped: Vec<SkippedItem>,
+    /// Cancellation token of the run; checked between threads and messages.
+    pub cancel: CancellationToken,
+    /// Path of the archive being imported, as passed in by the caller.
+    pub archive_path: String,
+    /// Journal of the current export in checkpoint mode.
+    pub checkpoint: Option<checkpoint::Checkpointer>,
+    /// Messages built but not yet written (see [`utils::queue_message`]).
+    pub queued_messages: Vec<MessageRecord>,
+    /// Store extracted media is copied into, when the options ask for one.
+    pub media_store: Option<utils::MediaStore>,
+    /// Position in the `messages` array of each parsed message of the thread entry being
+    /// imported; lenient parsing leaves out the messages it skips.
+    pub message_positions: Vec<usize>,
 }
 
 impl Default for ImportState {
@@ -40,12 +106,46 @@ impl Default for ImportState {
 
 impl ImportState {
     pub fn new() -> Self {
+        Self::with_options(ImportOptions::default())
+    }
+
+    pub fn with_options(options: ImportOptions) -> Self {
         Self {
             folder_names_to_conv_ids: HashMap::new(),
             person_ids_by_conversation: HashMap::new(),
             file_index: utils::file_index::FileIndex::default(),
+            options,
+            skipped: Vec::new(),
+            cancel: CancellationToken::new(),
+            archive_path: String::new(),
+            checkpoint: None,
+            queued_messages: Vec::new(),
+            media_store: None,
+            message_positions: Vec::new(),
         }
     }
+
+    /// Position in the `messages` array of the `index`th parsed message of the thread entry
+    /// being imported.
+    pub fn message_position(&self, index: usize) -> usize {
+        self.message_positions.get(index).copied().unwrap_or(index)
+    }
+
+    /// Where the message at `position` in the `messages` array of `entry_name` in the
+    /// current archive came from, if the run records it.
+    pub fn message_source(
+        &self,
+        export_id: i64,
+        entry_name: &str,
+        position: usize,
+    ) -> Option<MessageSource> {
+        self.options.record_sources.then(|| MessageSource {
+            export_id,
+            archive_path: self.archive_path.clone(),
+            entry_name: entry_name.to_string(),
+            message_index: position as i64,
+        })
+    }
 }
 
 /// The file format of an export file.
@@ -62,19 +162,60 @@ pub enum ExportFormat {
     E2E,
 }
 
-/// Determine the export format of a ZIP archive.
-fn determine_zip_format(path: &Path) -> Result<ExportFormat> {
-    ensure_not_cancelled()?;
-    let file =
-        File::open(path).with_context(|| format!("Failed to open ZIP file: {}", path.display()))?;
-    let archive = ZipArchive::new(file)
-        .with_context(|| format!("Failed to read ZIP archive: {}", path.display()))?;
+/// What the scan phase learned about one input archive.
+struct ScannedArchive {
+    path: PathBuf,
+    format: ExportFormat,
+    /// Number of thread JSON entries that will be imported.
+    thread_files: u32,
+    /// Sum of their uncompressed sizes.
+    thread_bytes: u64,
+}
+
+/// Determine the export format of a ZIP archive and size its thread files.
+fn scan_archive(path: PathBuf, cancel: &CancellationToken) -> Result<ScannedArchive> {
+    ensure_not_cancelled(cancel)?;
+    let mut archive = open_zip(&path)?;
 
-    if formats::e2e::is_e2e_archive(&archive)? {
-        Ok(ExportFormat::E2E)
+    let (format, entries) = if formats::e2e::is_e2e_archive(&archive, cancel)? {
+        (
+            ExportFormat::E2E,
+            formats::e2e::collect_thread_entries(&archive),
+        )
     } else {
-        Ok(ExportFormat::Facebook)
+        let entries = formats::facebook::paths::collect_message_entries(
+            &archive,
+            &formats::facebook::paths::MESSAGES_RE,
+        );
+        (
+            ExportFormat::Facebook,
+            entries.into_iter().map(|(_, _, path)| path).collect(),
+        )
+    };
+
+    let mut thread_bytes = 0u64;
+    for name in &entries {
+        ensure_not_cancelled(cancel)?;
+        thread_bytes += entry_size(&mut archive, name);
     }
+    Ok(ScannedArchive {
+        path,
+        format,
+        thread_files: entries.len() as u32,
+        thread_bytes,
+    })
+}
+
+/// Uncompressed size of a ZIP entry, or 0 if it cannot be read.
+pub(crate) fn entry_size<R: Read + Seek>(archive: &mut ZipArchive<R>, name: &str) -> u64 {
+    archive.by_name(name).map(|f| f.size()).unwrap_or(0)
+}
+
+/// Open a ZIP archive, tagging failures with the archive path.
+fn open_zip(path: &Path) -> Result<ZipArchive<File>> {
+    let file = File::open(path).map_err(|e| ImportError::io(path, e))?;
+    let archive = ZipArchive::new(file).map_err(|e| ImportError::zip(path, e))?;
+    Ok(archive)
 }
 
 /// Import multiple chat export files into a SQLite database.
@@ -82,60 +223,206 @@ fn determine_zip_format(path: &Path) -> Result<ExportFormat> {
 /// # Arguments
 /// * `paths` - List of file paths to import (ZIP archives or JSON files)
 /// * `db_path` - Path to the pre-initialized SQLite database to update
-pub fn import_messenger_exports(paths: Vec<PathBuf>, db_path: &Path) -> Result<Vec<i64>> {
-    let mut db = MessageDb::open(db_path)
-        .with_context(|| format!("Failed to open SQLite database: {}", db_path.display()))?;
+///
+/// # Errors
+/// Returns an [`ImportError`] describing the first failure; nothing is committed in that case.
+pub fn import_messenger_exports(
+    paths: Vec<PathBuf>,
+    db_path: &Path,
+) -> Result<Vec<i64>, ImportError> {
+    import_messenger_exports_with_options(paths, db_path, &ImportOptions::default())
+        .map(|report| report.export_ids)
+}
+
+/// Like [`import_messenger_exports`], but configurable and returning the full [`ImportReport`].
+///
+/// Uses the process-wide cancellation flag and progress callbacks; see
+/// [`import_messenger_exports_with_control`] for imports that must not share them.
+pub fn import_messenger_exports_with_options(
+    paths: Vec<PathBuf>,
+    db_path: &Path,
+    options: &ImportOptions,
+) -> Result<ImportReport, ImportError> {
+    import_messenger_exports_with_control(
+        paths,
+        db_path,
+        options,
+        &global_cancel_token(),
+        Arc::new(GlobalCallbacks),
+    )
+}
+
+/// Run an import observing its own cancellation token and reporting to its own sink.
+///
+/// Any failure after `cancel` fired is reported as [`ImportError::Cancelled`].
+pub fn import_messenger_exports_with_control(
+    paths: Vec<PathBuf>,
+    db_path: &Path,
+    options: &ImportOptions,
+    cancel: &CancellationToken,
+    sink: Arc<dyn ProgressSink>,
+) -> Result<ImportReport, ImportError> {
+    let mut state = ImportState::with_options(options.clone());
+    state.cancel = cancel.clone();
+    let progress = ImportProgressTracker::with_sink(sink);
+    match import_messenger_exports_inner(paths, db_path, state, progress) {
+        Ok(report) => Ok(report),
+        Err(_) if cancel.is_cancelled() => Err(ImportError::Cancelled),
+        Err(err) => Err(err.into()),
+    }
+}
+
+fn import_messenger_exports_inner(
+    paths: Vec<PathBuf>,
+    db_path: &Path,
+    mut state: ImportState,
+    mut progress: ImportProgressTracker,
+) -> Result<ImportReport> {
+    let mut db = MessageDb::open(db_path).map_err(|e| ImportError::database(db_path, e))?;
+    let tuning = db.tune_for_import()?;
     let mut batch = db
         .begin_write()
         .context("Failed to begin database write transaction")?;
+    // Rebuilding indexes once is cheaper than maintaining them per row, but only possible
+    // when nothing is committed before the end of the import, and only worth it into an
+    // empty database: otherwise the rebuild would cover every earlier import too.
+    let deferred_indexes = if !state.options.checkpoint && batch.message_tables_empty()? {
+        Some(batch.defer_indexes()?)
+    } else {
+        None
+    };
 
-    let mut state = ImportState::new();
-    // Build a global media index so we can resolve audio across ZIPs by full pathname.
-    state.file_index = utils::file_index::build_file_index(&paths)?;
-
-    let mut progress = ImportProgressTracker::new();
     progress.reset();
-    ensure_not_cancelled()?;
+    ensure_not_cancelled(&state.cancel)?;
 
-    // Partition selected paths by export format (zip-based detection)
+    // Scan: partition selected paths by export format (zip-based detection) and size the
+    // work up front so reported totals stay fixed for the whole run.
     let mut facebook_paths: Vec<PathBuf> = Vec::new();
     let mut e2e_paths: Vec<PathBuf> = Vec::new();
-    for path in paths.into_iter() {
-        ensure_not_cancelled()?;
-        match determine_zip_format(&path)? {
-            ExportFormat::Facebook => facebook_paths.push(path),
-            ExportFormat::E2E => e2e_paths.push(path),
+    let (mut total_files, mut total_bytes) = (0u32, 0u64);
+    for path in paths.iter().cloned() {
+        ensure_not_cancelled(&state.cancel)?;
+        let scanned = scan_archive(path, &state.cancel)?;
+        total_files += scanned.thread_files;
+        total_bytes += scanned.thread_bytes;
+        match scanned.format {
+            ExportFormat::Facebook => facebook_paths.push(scanned.path),
+            ExportFormat::E2E => e2e_paths.push(scanned.path),
         }
     }
+    progress.set_totals(total_files, total_bytes);
+
+    // Build a global media index so we can resolve attachments across ZIPs.
+    progress.set_phase(ImportPhase::Index);
+    state.file_index = utils::file_index::build_file_index(&paths, &state.cancel)?;
+    if let Some(options) = state.options.media_store.clone() {
+        state.media_store = Some(utils::MediaStore::open(options)?);
+    }
 
     let mut export_ids: Vec<i64> = Vec::new();
 
     // Facebook: one export across all selected FB zips
     if !facebook_paths.is_empty() {
-        ensure_not_cancelled()?;
+        ensure_not_cancelled(&state.cancel)?;
         let fb_meta_json = compute_group_meta(&facebook_paths);
-        let export_id = batch.insert_export("messenger:facebook", None, Some(&fb_meta_json))?;
+        let export_id =
+            checkpoint::begin_export(&mut batch, &mut state, "messenger:facebook", &fb_meta_json)?;
         export_ids.push(export_id);
 
         for path in facebook_paths {
-            ensure_not_cancelled()?;
+            ensure_not_cancelled(&state.cancel)?;
             import_facebook_zip(&path, export_id, &mut batch, &mut state, &mut progress)?;
         }
+        checkpoint::finish_export(&mut batch, &mut state)?;
     }
 
     // E2E: one export per zip
     for path in e2e_paths {
-        ensure_not_cancelled()?;
+        ensure_not_cancelled(&state.cancel)?;
         let meta_json = compute_group_meta(slice::from_ref(&path));
-        let export_id = batch.insert_export("messenger:e2e", None, Some(&meta_json))?;
+        let export_id =
+            checkpoint::begin_export(&mut batch, &mut state, "messenger:e2e", &meta_json)?;
         export_ids.push(export_id);
         import_e2e_zip(&path, export_id, &mut batch, &mut state, &mut progress)?;
+        checkpoint::finish_export(&mut batch, &mut state)?;
     }
 
+    progress.set_phase(ImportPhase::PostProcess);
+    utils::flush_messages(&mut batch, &mut state)?;
+    if let Some(indexes) = deferred_indexes {
+        indexes.restore(&mut batch)?;
+    }
+    for &export_id in &export_ids {
+        batch
+            .rebuild_memberships(export_id)
+            .context("Failed to derive group memberships")?;
+    }
     batch
         .commit()
         .context("Failed to commit database transaction")?;
-    Ok(export_ids)
+    tuning.restore(&db)?;
+    Ok(ImportReport {
+        export_ids,
+        skipped: state.skipped,
+        missing_media: state.file_index.take_missing(),
+        stored_media_bytes: state.media_store.as_ref().map_or(0, |s| s.added_bytes()),
+    })
+}
+
+/// Delete an export (typically an unfinished checkpointed one) and everything imported under it.
+pub fn rollback_export(db_path: &Path, export_id: i64) -> Result<(), ImportError> {
+    let mut db = MessageDb::open(db_path).map_err(|e| ImportError::database(db_path, e))?;
+    db.delete_export(export_id)
+        .map_err(|e| ImportError::database(db_path, format!("{:#}", e)))
+}
+
+/// Ids of exports left `in_progress` by interrupted checkpointed imports.
+pub fn unfinished_exports(db_path: &Path) -> Result<Vec<i64>, ImportError> {
+    let db = MessageDb::open(db_path).map_err(|e| ImportError::database(db_path, e))?;
+    db.unfinished_exports()
+        .map_err(|e| ImportError::database(db_path, format!("{:#}", e)))
+}
+
+/// The JSON a message was imported from, re-read from its archive: the element of the
+/// entry's `messages` array recorded in `message_source`. `None` if the message has no
+/// recorded source (it was imported without [`ImportOptions::record_sources`]).
+pub fn message_source_json(
+    db_path: &Path,
+    message_id: i64,
+) -> Result<Option<serde_json::Value>, ImportError> {
+    let db = MessageDb::open(db_path).map_err(|e| ImportError::database(db_path, e))?;
+    let source = db
+        .message_source(message_id)
+        .map_err(|e| ImportError::database(db_path, format!("{:#}", e)))?;
+    let Some(source) = source else {
+        return Ok(None);
+    };
+    read_source_json(&source)
+        .map(Some)
+        .map_err(ImportError::from)
+}
+
+/// Read the message `source` points at out of its archive.
+fn read_source_json(source: &MessageSource) -> Result<serde_json::Value> {
+    let mut archive = open_zip(Path::new(&source.archive_path))?;
+    let mut json_content = String::new();
+    archive
+        .by_name(&source.entry_name)
+        .map_err(|e| ImportError::zip(&source.entry_name, e))?
+        .read_to_string(&mut json_content)
+        .map_err(|e| ImportError::io(&source.entry_name, e))?;
+    let mut root: serde_json::Value = serde_json::from_str(&json_content)
+        .map_err(|e| ImportError::json(&source.entry_name, e))?;
+    let message = usize::try_from(source.message_index)
+        .ok()
+        .and_then(|index| root.get_mut("messages")?.get_mut(index))
+        .map(serde_json::Value::take);
+    message.with_context(|| {
+        format!(
+            "{} in {} has no message #{}",
+            source.entry_name, source.archive_path, source.message_index
+        )
+    })
 }
 
 /// Import Facebook conversations from a ZIP archive.
@@ -146,11 +433,28 @@ fn import_facebook_zip(
     state: &mut ImportState,
     progress: &mut ImportProgressTracker,
 ) -> Result<()> {
-    let file =
-        File::open(path).with_context(|| format!("Failed to open ZIP file: {}", path.display()))?;
-    let mut archive = ZipArchive::new(file)
-        .with_context(|| format!("Failed to read ZIP archive: {}", path.display()))?;
-    formats::facebook::import_facebook_archive(&mut archive, export_id, batch, state, progress)
+    let mut archive = open_zip(path)?;
+    state.archive_path = path.to_string_lossy().into_owned();
+    match state.options.parse_threads {
+        workers if workers > 1 && !state.options.streaming => {
+            formats::facebook::import_facebook_archive_parallel(
+                path,
+                &mut archive,
+                workers,
+                export_id,
+                batch,
+                state,
+                progress,
+            )
+        }
+        _ => formats::facebook::import_facebook_archive(
+            &mut archive,
+            export_id,
+            batch,
+            state,
+            progress,
+        ),
+    }
 }
 
 /// Import E2E conversations from a ZIP archive.
@@ -161,11 +465,22 @@ fn import_e2e_zip(
     state: &mut ImportState,
     progress: &mut ImportProgressTracker,
 ) -> Result<()> {
-    let file =
-        File::open(path).with_context(|| format!("Failed to open ZIP file: {}", path.display()))?;
-    let mut archive = ZipArchive::new(file)
-        .with_context(|| format!("Failed to read ZIP archive: {}", path.display()))?;
-    formats::e2e::import_e2e_archive(&mut archive, export_id, batch, state, progress)
+    let mut archive = open_zip(path)?;
+    state.archive_path = path.to_string_lossy().into_owned();
+    match state.options.parse_threads {
+        workers if workers > 1 && !state.options.streaming => {
+            formats::e2e::import_e2e_archive_parallel(
+                path,
+                &mut archive,
+                workers,
+                export_id,
+                batch,
+                state,
+                progress,
+            )
+        }
+        _ => formats::e2e::import_e2e_archive(&mut archive, export_id, batch, state, progress),
+    }
 }
 
 /// Build a small meta JSON listing file paths. Kept lightweight to avoid I/O hashing.
@@ -0,0 +1,330 @@
+//! Parallel parsing of thread files with a single writer.
+//!
+//! Worker threads open their own handle on the archive, then decompress and parse thread
+//! entries (and probe audio, video and images) concurrently. The calling thread stays the only
+//! one touching the database: it receives the results and hands them to the writer callback
+//! strictly in entry order, so the database ends up exactly as after a sequential import.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc::sync_channel;
+use std::sync::{Condvar, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use anyhow::Result;
+use zip::ZipArchive;
+
+use crate::database::Attachment;
+use crate::error::ImportError;
+use crate::progress::{ensure_not_cancelled, CancellationToken};
+use crate::utils::audio::{detect_audio_info, AudioInfo};
+use crate::utils::image::{detect_image_info, ImageInfo};
+use crate::utils::video::{detect_video_info, VideoInfo};
+
+use super::utils::file_index::FileIndex;
+use super::ImportState;
+
+/// Media details probed by a worker, keyed by URI. URIs missing here were not found in the
+/// thread's own archive and are resolved through the global file index by the writer.
+#[derive(Debug, Default)]
+pub struct ProbedMedia {
+    pub audio: HashMap<String, Option<AudioInfo>>,
+    pub video: HashMap<String, Option<VideoInfo>>,
+    pub image: HashMap<String, Option<ImageInfo>>,
+}
+
+/// Parse `entries` of the archive at `archive_path` on `workers` threads.
+///
+/// `parse(archive, index, entry)` runs on a worker with that worker's archive handle;
+/// `write(index, entry, parsed)` runs on the calling thread once per entry, in entry order.
+/// At most a few results per worker are buffered ahead of the writer. The run stops early
+/// when `cancel` fires or `write` fails; a worker that fails to open the archive reports
+/// the error through the writer.
+pub fn parse_in_parallel<T, P, W>(
+    archive_path: &Path,
+    entries: &[String],
+    workers: usize,
+    cancel: &CancellationToken,
+    parse: P,
+    mut write: W,
+) -> Result<()>
+where
+    T: Send,
+    P: Fn(&mut ZipArchive<File>, usize, &str) -> T + Sync,
+    W: FnMut(usize, &str, T) -> Result<()>,
+{
+    let workers = workers.max(1).min(entries.len().max(1));
+    let window = workers * 2;
+    let next = AtomicUsize::new(0);
+    let stop = AtomicBool::new(false);
+    // Index of the next entry the writer is waiting for.
+    let written = Mutex::new(0usize);
+    let advanced = Condvar::new();
+    let (tx, rx) = sync_channel::<(usize, Result<T, ImportError>)>(window);
+
+    thread::scope(|scope| {
+        for _ in 0..workers {
+            let tx = tx.clone();
+            let (next, stop, written, advanced, parse) =
+                (&next, &stop, &written, &advanced, &parse);
+            scope.spawn(move || {
+                let mut archive = match File::open(archive_path)
+                    .map_err(|e| ImportError::io(archive_path, e))
+                    .and_then(|f| ZipArchive::new(f).map_err(|e| ImportError::zip(archive_path, e)))
+                {
+                    Ok(archive) => archive,
+                    Err(e) => {
+                        stop.store(true, Ordering::SeqCst);
+                        let _ = tx.send((usize::MAX, Err(e)));
+                        return;
+                    }
+                };
+                loop {
+                    if stop.load(Ordering::SeqCst) || cancel.is_cancelled() {
+                        return;
+                    }
+                    let index = next.fetch_add(1, Ordering::SeqCst);
+                    if index >= entries.len() {
+                        return;
+                    }
+                    // Don't run further ahead of the writer than the window allows.
+                    let mut done = written.lock().unwrap();
+                    while index >= *done + window {
+                        if stop.load(Ordering::SeqCst) || cancel.is_cancelled() {
+                            return;
+                        }
+                        done = advanced
+                            .wait_timeout(done, Duration::from_millis(50))
+                            .unwrap()
+                            .0;
+                    }
+                    drop(done);
+                    let parsed = parse(&mut archive, index, &entries[index]);
+                    if tx.send((index, Ok(parsed))).is_err() {
+                        return;
+                    }
+                }
+            });
+        }
+        drop(tx);
+
+        let result = (|| -> Result<()> {
+            let mut pending = BTreeMap::new();
+            let mut expected = 0;
+            while expected < entries.len() {
+                ensure_not_cancelled(cancel)?;
+                while let Some(parsed) = pending.remove(&expected) {
+                    write(expected, &entries[expected], parsed)?;
+                    expected += 1;
+                    *written.lock().unwrap() = expected;
+                    advanced.notify_all();
+                }
+                if expected >= entries.len() {
+                    break;
+                }
+                match rx.recv_timeout(Duration::from_millis(50)) {
+                    Ok((_, Err(e))) => return Err(e.into()),
+                    Ok((index, Ok(parsed))) => {
+                        pending.insert(index, parsed);
+                    }
+                    Err(std::sync::mpsc::RecvTimeoutError::Timeout) => {}
+                    Err(std::sync::mpsc::RecvTimeoutError::Disconnected) => {
+                        // Workers only exit early on cancellation.
+                        ensure_not_cancelled(cancel)?;
+                        anyhow::bail!("parse workers exited early");
+                    }
+                }
+            }
+            Ok(())
+        })();
+
+        stop.store(true, Ordering::SeqCst);
+        advanced.notify_all();
+        // Unblock workers waiting on a full channel.
+        drop(rx);
+        result
+    })
+}
+
+/// Looks up the details of media URIs; `None` if the source does not have the file.
+pub trait MediaLookup {
+    fn audio(&mut self, uri: &str) -> Option<Option<AudioInfo>>;
+    fn video(&mut self, uri: &str) -> Option<Option<VideoInfo>>;
+    fn image(&mut self, uri: &str) -> Option<Option<ImageInfo>>;
+}
+
+impl MediaLookup for ProbedMedia {
+    fn audio(&mut self, uri: &str) -> Option<Option<AudioInfo>> {
+        self.audio.get(uri).copied()
+    }
+
+    fn video(&mut self, uri: &str) -> Option<Option<VideoInfo>> {
+        self.video.get(uri).copied()
+    }
+
+    fn image(&mut self, uri: &str) -> Option<Option<ImageInfo>> {
+        self.image.get(uri).cloned()
+    }
+}
+
+/// A lookup without files of its own: everything resolves through the global file index.
+pub struct NoMedia;
+
+impl MediaLookup for NoMedia {
+    fn audio(&mut self, _uri: &str) -> Option<Option<AudioInfo>> {
+        None
+    }
+
+    fn video(&mut self, _uri: &str) -> Option<Option<VideoInfo>> {
+        None
+    }
+
+    fn image(&mut self, _uri: &str) -> Option<Option<ImageInfo>> {
+        None
+    }
+}
+
+/// Media lookup reading straight from the thread's own archive.
+pub struct ArchiveMedia<'a, R>(pub &'a mut ZipArchive<R>);
+
+impl<R: std::io::Seek + Read> MediaLookup for ArchiveMedia<'_, R> {
+    fn audio(&mut self, uri: &str) -> Option<Option<AudioInfo>> {
+        let mut f = self.0.by_name(uri).ok()?;
+        Some(detect_audio_info(uri, &mut f))
+    }
+
+    fn video(&mut self, uri: &str) -> Option<Option<VideoInfo>> {
+        let mut f = self.0.by_name(uri).ok()?;
+        Some(detect_video_info(&mut f))
+    }
+
+    fn image(&mut self, uri: &str) -> Option<Option<ImageInfo>> {
+        let mut f = self.0.by_name(uri).ok()?;
+        Some(detect_image_info(&mut f))
+    }
+}
+
+/// Probe the details of every `audio`, `video` and `image` entry present in `archive`.
+pub fn probe_media<'a, R: std::io::Seek + Read>(
+    archive: &mut ZipArchive<R>,
+    audio: impl IntoIterator<Item = &'a str>,
+    video: impl IntoIterator<Item = &'a str>,
+    image: impl IntoIterator<Item = &'a str>,
+) -> ProbedMedia {
+    let mut probed = ProbedMedia::default();
+    let mut lookup = ArchiveMedia(archive);
+    for uri in audio {
+        if !probed.audio.contains_key(uri) {
+            if let Some(info) = lookup.audio(uri) {
+                probed.audio.insert(uri.to_string(), info);
+            }
+        }
+    }
+    for uri in video {
+        if !probed.video.contains_key(uri) {
+            if let Some(info) = lookup.video(uri) {
+                probed.video.insert(uri.to_string(), info);
+            }
+        }
+    }
+    for uri in image {
+        if !probed.image.contains_key(uri) {
+            if let Some(info) = lookup.image(uri) {
+                probed.image.insert(uri.to_string(), info);
+            }
+        }
+    }
+    probed
+}
+
+/// Probe `uri` through the global file index, recording it as missing media of `kind`
+/// when no archive has it.
+fn probe_indexed<T>(
+    index: &mut FileIndex,
+    kind: &'static str,
+    uri: &str,
+    probe: impl FnOnce(&mut dyn Read) -> Option<T>,
+) -> Option<T> {
+    match index.with_file(uri, probe) {
+        Some(info) => info,
+        None => {
+            index.report_missing(kind, uri);
+            None
+        }
+    }
+}
+
+/// Audio details of `uri`: from `media` when it has the file, else through the global
+/// file index.
+pub fn audio_info(
+    media: &mut dyn MediaLookup,
+    index: &mut FileIndex,
+    uri: &str,
+) -> Option<AudioInfo> {
+    media
+        .audio(uri)
+        .unwrap_or_else(|| probe_indexed(index, "audio", uri, |r| detect_audio_info(uri, r)))
+}
+
+/// Video details of `uri`, resolved like [`audio_info`].
+pub fn video_info(
+    media: &mut dyn MediaLookup,
+    index: &mut FileIndex,
+    uri: &str,
+) -> Option<VideoInfo> {
+    media
+        .video(uri)
+        .unwrap_or_else(|| probe_indexed(index, "video", uri, |r| detect_video_info(r)))
+}
+
+/// Image details (format, size, capture time) of `uri`, resolved like [`audio_info`].
+pub fn image_info(
+    media: &mut dyn MediaLookup,
+    index: &mut FileIndex,
+    uri: &str,
+) -> Option<ImageInfo> {
+    media
+        .image(uri)
+        .unwrap_or_else(|| probe_indexed(index, "image", uri, |r| detect_image_info(r)))
+}
+
+/// GIF details (size, frames, animation length) of `uri`, resolved like [`audio_info`].
+pub fn gif_info(
+    media: &mut dyn MediaLookup,
+    index: &mut FileIndex,
+    uri: &str,
+) -> Option<ImageInfo> {
+    media
+        .image(uri)
+        .unwrap_or_else(|| probe_indexed(index, "gif", uri, |r| detect_image_info(r)))
+}
+
+/// Sticker details (format, size, animation) of `uri`, resolved like [`audio_info`].
+pub fn sticker_info(
+    media: &mut dyn MediaLookup,
+    index: &mut FileIndex,
+    uri: &str,
+) -> Option<ImageInfo> {
+    media
+        .image(uri)
+        .unwrap_or_else(|| probe_indexed(index, "sticker", uri, |r| detect_image_info(r)))
+}
+
+/// An attachment of `kind` with its probed `info`, copied into the media store when the
+/// run has one.
+pub fn attachment<T>(
+    state: &mut ImportState,
+    kind: &'static str,
+    uri: &str,
+    info: Option<T>,
+) -> Result<Attachment<T>> {
+    let mut attachment = Attachment::new(uri, info);
+    if let Some(store) = state.media_store.as_mut() {
+        attachment.stored_path = store.extract(&mut state.file_index, kind, uri)?;
+    }
+    Ok(attachment)
+}
@@ -0,0 +1,42 @@
+//! Summary of a finished import run.
+//!
+//! Returned by [`import_messenger_exports_with_options`](super::import_messenger_exports_with_options)
+//! and serialized as-is for FFI hosts.
+
+use serde::Serialize;
+
+use crate::error::ImportError;
+
+/// An input that was left out of the import instead of aborting it (lenient mode only).
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct SkippedItem {
+    /// Entry path inside the archive (e.g. `your_facebook_activity/messages/inbox/x/message_1.json`).
+    pub path: String,
+    /// Index within the `messages` array when a single message was skipped;
+    /// `None` when the whole thread file was skipped.
+    pub message_index: Option<usize>,
+    pub error: ImportError,
+}
+
+/// An attachment referenced by a message whose file is in none of the selected archives.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct MissingMedia {
+    /// URI as written in the thread file.
+    pub uri: String,
+    /// `"image"`, `"gif"`, `"sticker"`, `"video"` or `"audio"`.
+    pub kind: &'static str,
+}
+
+/// Outcome of an import run.
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct ImportReport {
+    /// Export ids created by this run, in insertion order.
+    pub export_ids: Vec<i64>,
+    /// Threads or messages quarantined by lenient parsing.
+    pub skipped: Vec<SkippedItem>,
+    /// Attachments whose files were not found, each URI once. Their rows are still
+    /// imported, without probed details.
+    pub missing_media: Vec<MissingMedia>,
+    /// Bytes of media this run copied into the media store (0 without one).
+    pub stored_media_bytes: u64,
+}
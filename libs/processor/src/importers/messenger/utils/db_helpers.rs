@@ -1,10 +1,13 @@
 //! Database helper functions for messenger imports.
 //!
-//! Contains helpers to create conversations and per-conversation persons with canonical links.
+//! Contains helpers to create conversations and per-conversation persons with canonical links,
+//! the queue that writes messages in bulk, and the undo of a streamed thread.
+
+use std::collections::HashMap;
 
 use anyhow::{Context, Result};
 
-use crate::database::{ConversationType, WriteBatch};
+use crate::database::{ConversationMeta, ConversationType, MessageRecord, WriteBatch};
 use crate::importers::messenger::ImportState;
 
 /// Create or get a conversation by folder/thread name within a single import run.
@@ -13,8 +16,8 @@ pub fn ensure_conversation(
     state: &mut ImportState,
     folder_name: &str,
     participant_count: usize,
-    image_uri: Option<&str>,
     title: Option<&str>,
+    meta: &ConversationMeta,
     export_id: i64,
 ) -> Result<i64> {
     if let Some(&conv_id) = state.folder_names_to_conv_ids.get(folder_name) {
@@ -31,7 +34,7 @@ pub fn ensure_conversation(
         .insert_canonical_conversation(ctype, title)
         .context("insert canonical_conversation")?;
     let conv_id = batch
-        .insert_conversation(ctype, image_uri, title, export_id, canon_id)
+        .insert_conversation(ctype, title, meta, export_id, canon_id)
         .context("insert conversation")?;
 
     state
@@ -40,6 +43,47 @@ pub fn ensure_conversation(
     Ok(conv_id)
 }
 
+/// What the import state knew about a thread before it was streamed inside a savepoint, so
+/// that rolling the savepoint back can forget the conversation and people it added.
+pub struct ThreadUndo {
+    key: String,
+    /// The existing conversation and its people, for a thread continued from an earlier file.
+    existing: Option<(i64, HashMap<String, i64>)>,
+}
+
+impl ThreadUndo {
+    /// Remember the state of the thread `key` (as passed to [`ensure_conversation`]).
+    pub fn new(state: &ImportState, key: &str) -> Self {
+        let existing = state.folder_names_to_conv_ids.get(key).map(|&conv_id| {
+            let people = state
+                .person_ids_by_conversation
+                .get(&conv_id)
+                .cloned()
+                .unwrap_or_default();
+            (conv_id, people)
+        });
+        Self {
+            key: key.to_string(),
+            existing,
+        }
+    }
+
+    /// Restore the state remembered by [`ThreadUndo::new`] after the rows were rolled back.
+    pub fn undo(self, state: &mut ImportState) {
+        match self.existing {
+            Some((conv_id, people)) => {
+                state.person_ids_by_conversation.insert(conv_id, people);
+            }
+            None => {
+                if let Some(conv_id) = state.folder_names_to_conv_ids.remove(&self.key) {
+                    state.person_ids_by_conversation.remove(&conv_id);
+                }
+            }
+        }
+        state.queued_messages.clear();
+    }
+}
+
 /// Create or get a per-conversation person by name.
 pub fn ensure_person_in_conversation(
     batch: &mut WriteBatch<'_>,
@@ -64,3 +108,32 @@ pub fn ensure_person_in_conversation(
     entry.insert(user_name.to_string(), person_id);
     Ok(person_id)
 }
+
+/// Queued messages written per bulk insert.
+pub const MESSAGE_FLUSH_ROWS: usize = 512;
+
+/// Queue a built message for a bulk insert, writing the queue once it is full.
+///
+/// Queued messages get their ids when written, in queue order.
+pub fn queue_message(
+    batch: &mut WriteBatch<'_>,
+    state: &mut ImportState,
+    record: MessageRecord,
+) -> Result<()> {
+    state.queued_messages.push(record);
+    if state.queued_messages.len() >= MESSAGE_FLUSH_ROWS {
+        flush_messages(batch, state)?;
+    }
+    Ok(())
+}
+
+/// Write all queued messages.
+pub fn flush_messages(batch: &mut WriteBatch<'_>, state: &mut ImportState) -> Result<()> {
+    if !state.queued_messages.is_empty() {
+        batch
+            .insert_messages_bulk(&state.queued_messages)
+            .context("bulk insert queued messages")?;
+        state.queued_messages.clear();
+    }
+    Ok(())
+}
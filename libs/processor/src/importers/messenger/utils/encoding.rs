@@ -1,8 +1,8 @@
-use crate::importers::messenger::formats::facebook::json::FacebookExportRoot;
+use crate::importers::messenger::formats::facebook::json::{FacebookExportRoot, Message};
 
 /// Interpret a Unicode string as if each character were a single ISO-8859-1 byte,
 /// then decode those bytes as UTF-8. This reverses typical "Ã©"-style mojibake.
-fn fix_latin1_mojibake(s: &str) -> String {
+pub(crate) fn fix_latin1_mojibake(s: &str) -> String {
     // Map each Unicode scalar's lower 8 bits to a byte, similar to Node's Buffer(..., 'latin1').
     let bytes: Vec<u8> = s.chars().map(|ch| (ch as u32) as u8).collect();
     match String::from_utf8(bytes) {
@@ -31,55 +31,66 @@ pub fn fix_encoding(mut root: FacebookExportRoot) -> FacebookExportRoot {
     }
 
     for m in root.messages.iter_mut() {
-        m.sender_name = fix_latin1_mojibake(&m.sender_name);
-        if let Some(ref content) = m.content {
-            m.content = Some(fix_latin1_mojibake(content));
-        }
+        fix_message_encoding(m);
+    }
+
+    root
+}
+
+/// Fix mojibake in a single message (used when messages are streamed one at a time).
+pub fn fix_message_encoding(m: &mut Message) {
+    m.sender_name = fix_latin1_mojibake(&m.sender_name);
+    if let Some(ref content) = m.content {
+        m.content = Some(fix_latin1_mojibake(content));
+    }
 
-        if let Some(ref mut afs) = m.audio_files {
-            for a in afs.iter_mut() {
-                a.uri = fix_latin1_mojibake(&a.uri);
-            }
+    if let Some(ref mut afs) = m.audio_files {
+        for a in afs.iter_mut() {
+            a.uri = fix_latin1_mojibake(&a.uri);
         }
+    }
 
-        if let Some(ref mut rs) = m.reactions {
-            for r in rs.iter_mut() {
-                r.reaction = fix_latin1_mojibake(&r.reaction);
-                r.actor = fix_latin1_mojibake(&r.actor);
-            }
+    if let Some(ref mut us) = m.users {
+        for u in us.iter_mut() {
+            u.name = fix_latin1_mojibake(&u.name);
         }
+    }
 
-        if let Some(ref mut vs) = m.videos {
-            for v in vs.iter_mut() {
-                v.uri = fix_latin1_mojibake(&v.uri);
-            }
+    if let Some(ref mut rs) = m.reactions {
+        for r in rs.iter_mut() {
+            r.reaction = fix_latin1_mojibake(&r.reaction);
+            r.actor = fix_latin1_mojibake(&r.actor);
         }
+    }
 
-        if let Some(ref mut ps) = m.photos {
-            for p in ps.iter_mut() {
-                p.uri = fix_latin1_mojibake(&p.uri);
-            }
+    if let Some(ref mut vs) = m.videos {
+        for v in vs.iter_mut() {
+            v.uri = fix_latin1_mojibake(&v.uri);
         }
+    }
 
-        if let Some(ref mut sh) = m.share {
-            if let Some(ref link) = sh.link {
-                sh.link = Some(fix_latin1_mojibake(link));
-            }
-            if let Some(ref text) = sh.share_text {
-                sh.share_text = Some(fix_latin1_mojibake(text));
-            }
+    if let Some(ref mut ps) = m.photos {
+        for p in ps.iter_mut() {
+            p.uri = fix_latin1_mojibake(&p.uri);
         }
+    }
 
-        if let Some(ref mut gs) = m.gifs {
-            for g in gs.iter_mut() {
-                g.uri = fix_latin1_mojibake(&g.uri);
-            }
+    if let Some(ref mut sh) = m.share {
+        if let Some(ref link) = sh.link {
+            sh.link = Some(fix_latin1_mojibake(link));
         }
+        if let Some(ref text) = sh.share_text {
+            sh.share_text = Some(fix_latin1_mojibake(text));
+        }
+    }
 
-        if let Some(ref mut st) = m.sticker {
-            st.uri = fix_latin1_mojibake(&st.uri);
+    if let Some(ref mut gs) = m.gifs {
+        for g in gs.iter_mut() {
+            g.uri = fix_latin1_mojibake(&g.uri);
         }
     }
 
-    root
+    if let Some(ref mut st) = m.sticker {
+        st.uri = fix_latin1_mojibake(&st.uri);
+    }
 }
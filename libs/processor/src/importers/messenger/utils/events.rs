@@ -0,0 +1,297 @@
+//! System messages and the conversation events they log.
+//!
+//! Messenger writes events (members joining or leaving, renames, nicknames, themes, calls)
+//! as ordinary messages of the actor, in the language of the account that requested the
+//! export: "Bob added Carol to the group.", "Ola zmieniła motyw na Miłość.". Facebook
+//! exports also mark member changes with `type: "Subscribe"`/`"Unsubscribe"` and list the
+//! people in `users`. English and Polish templates are recognized; text is matched after
+//! the actor's name (or "You"/"Ty") is stripped off, so names with spaces are no problem.
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+use crate::database::{ConversationEvent, EventKind};
+
+/// Who a template's event is about when it has no `subject` capture.
+#[derive(Clone, Copy)]
+enum Subject {
+    /// Not about a person.
+    Nobody,
+    /// The actor themselves.
+    Actor,
+    /// The export owner ("you").
+    Owner,
+}
+
+struct Template {
+    kind: EventKind,
+    subject: Subject,
+    re: Regex,
+}
+
+fn template(kind: EventKind, subject: Subject, pattern: &str) -> Template {
+    // Polish verbs come in masculine, feminine, "(a)" and second-person forms.
+    let pattern = pattern
+        .replace("{ł}", r"ł(?:a|\(a\)|eś|aś)?")
+        .replace("{ął}", r"(?:ął|ęła|ął\(ęła\)|ąłeś|ęłaś)");
+    Template {
+        kind,
+        subject,
+        re: Regex::new(&format!(r"(?i)^{}\.?$", pattern)).expect("event template"),
+    }
+}
+
+/// Templates of the text following the actor's name.
+static ACTOR_TEMPLATES: Lazy<Vec<Template>> = Lazy::new(|| {
+    use EventKind::*;
+    use Subject::*;
+    vec![
+        // English
+        template(MemberAdded, Nobody, r"added (?P<subject>.+) to the group"),
+        template(
+            MemberRemoved,
+            Nobody,
+            r"removed (?P<subject>.+) from the group",
+        ),
+        template(MemberLeft, Actor, r"left the group"),
+        template(MemberAdded, Actor, r"joined the group(?: .+)?"),
+        template(GroupRenamed, Nobody, r"removed the group name"),
+        template(
+            GroupRenamed,
+            Nobody,
+            r"(?:named the group|changed the group name to) (?P<value>.+?)",
+        ),
+        template(
+            GroupPhotoChanged,
+            Nobody,
+            r"changed the group (?:photo|picture|image)",
+        ),
+        template(NicknameSet, Owner, r"set your nickname to (?P<value>.+?)"),
+        template(
+            NicknameSet,
+            Actor,
+            r"set (?:his|her|their) own nickname to (?P<value>.+?)",
+        ),
+        template(
+            NicknameSet,
+            Nobody,
+            r"set the nickname for (?P<subject>.+?) to (?P<value>.+?)",
+        ),
+        template(NicknameCleared, Owner, r"cleared your nickname"),
+        template(
+            NicknameCleared,
+            Actor,
+            r"cleared (?:his|her|their) own nickname",
+        ),
+        template(
+            NicknameCleared,
+            Nobody,
+            r"cleared the nickname for (?P<subject>.+?)",
+        ),
+        template(
+            ThemeChanged,
+            Nobody,
+            r"changed the (?:chat )?theme(?: to (?P<value>.+?))?",
+        ),
+        template(
+            EmojiChanged,
+            Nobody,
+            r"(?:set|changed) the (?:emoji|quick reaction) to (?P<value>.+?)",
+        ),
+        template(
+            CallStarted,
+            Nobody,
+            r"started (?:a |an )?(?:audio |video |group )?(?:call|chat)|started sharing video",
+        ),
+        // Polish
+        template(
+            MemberAdded,
+            Nobody,
+            r"doda{ł} (?P<subject>.+) do (?:grupy|konwersacji)",
+        ),
+        template(
+            MemberRemoved,
+            Nobody,
+            r"usun{ął} (?P<subject>.+) z (?:grupy|konwersacji)",
+        ),
+        template(MemberLeft, Actor, r"opuści{ł} (?:grupę|konwersację)"),
+        template(
+            MemberAdded,
+            Actor,
+            r"dołączy{ł} do (?:grupy|konwersacji)(?: .+)?",
+        ),
+        template(GroupRenamed, Nobody, r"usun{ął} nazwę grupy"),
+        template(
+            GroupRenamed,
+            Nobody,
+            r"(?:nazwa{ł} grupę|zmieni{ł} nazwę grupy na) (?P<value>.+?)",
+        ),
+        template(GroupPhotoChanged, Nobody, r"zmieni{ł} zdjęcie grupy"),
+        template(
+            NicknameSet,
+            Owner,
+            r"ustawi{ł} (?:twój|ci) pseudonim na (?P<value>.+?)",
+        ),
+        template(
+            NicknameSet,
+            Actor,
+            r"ustawi{ł} (?:swój|sobie) pseudonim na (?P<value>.+?)",
+        ),
+        template(
+            NicknameSet,
+            Nobody,
+            r"ustawi{ł} pseudonim (?:użytkownika |dla )?(?P<subject>.+?) na (?P<value>.+?)",
+        ),
+        template(NicknameCleared, Owner, r"usun{ął} (?:twój|ci) pseudonim"),
+        template(NicknameCleared, Actor, r"usun{ął} swój pseudonim"),
+        template(
+            NicknameCleared,
+            Nobody,
+            r"usun{ął} pseudonim (?:użytkownika |dla )?(?P<subject>.+?)",
+        ),
+        template(
+            ThemeChanged,
+            Nobody,
+            r"zmieni{ł} motyw(?: czatu)?(?: na (?P<value>.+?))?",
+        ),
+        template(
+            EmojiChanged,
+            Nobody,
+            r"(?:ustawi|zmieni){ł} (?:emoji|szybką reakcję) na (?P<value>.+?)",
+        ),
+        template(
+            CallStarted,
+            Nobody,
+            r"rozpocz{ął} (?:połączenie|rozmowę)(?: wideo| głosowe| grupowe)?",
+        ),
+    ]
+});
+
+/// Templates of whole messages without an actor.
+static PLAIN_TEMPLATES: Lazy<Vec<Template>> = Lazy::new(|| {
+    vec![
+        template(
+            EventKind::CallEnded,
+            Subject::Nobody,
+            r"the (?:audio |video |group )?(?:call|chat) (?:has )?ended",
+        ),
+        template(
+            EventKind::CallEnded,
+            Subject::Nobody,
+            r"(?:połączenie|rozmowa)(?: wideo| głosowe| grupowe)? (?:zakończył[oa] się|zakończon[ae])",
+        ),
+    ]
+});
+
+/// "N others" at the end of a list of added or removed people.
+static OTHERS_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?i)^\d+ (?:others?|other people|inn[eay]+ (?:osob[ay]|osób)|innych osób)$")
+        .expect("others regex")
+});
+
+/// Words standing for the export owner where a name would be.
+const OWNER_WORDS: &[&str] = &["you", "ciebie", "cię", "cie", "ci", "ty"];
+
+/// Prefixes standing for the actor when the export owner is the sender.
+const OWNER_PREFIXES: &[&str] = &["You ", "Ty "];
+
+/// Events logged by a message of `sender` with the given text, type and `users` (Facebook
+/// `Subscribe`/`Unsubscribe` messages). Empty for ordinary messages.
+///
+/// Only call this for messages without attachments; text with media is never a system
+/// message.
+pub fn classify_event(
+    sender: &str,
+    message_type: Option<&str>,
+    users: &[&str],
+    text: Option<&str>,
+) -> Vec<ConversationEvent> {
+    let text = text.map(str::trim).filter(|t| !t.is_empty());
+    let raw_text = text.map(str::to_string);
+    let event = |kind, subject_name: Option<String>, value: Option<String>| ConversationEvent {
+        kind,
+        subject_name,
+        value,
+        raw_text: raw_text.clone(),
+    };
+
+    let membership = match message_type.map(str::to_ascii_lowercase).as_deref() {
+        Some("subscribe") => Some(EventKind::MemberAdded),
+        Some("unsubscribe") => Some(EventKind::MemberRemoved),
+        _ => None,
+    };
+    if let (Some(kind), false) = (membership, users.is_empty()) {
+        return users
+            .iter()
+            .map(|&user| {
+                let kind = if kind == EventKind::MemberRemoved && user == sender {
+                    EventKind::MemberLeft
+                } else {
+                    kind
+                };
+                event(kind, Some(user.to_string()), None)
+            })
+            .collect();
+    }
+
+    let Some(text) = text else {
+        return Vec::new();
+    };
+    let after_actor = std::iter::once(sender)
+        .filter(|s| !s.is_empty())
+        .map(|s| format!("{} ", s))
+        .chain(OWNER_PREFIXES.iter().map(|p| p.to_string()))
+        .find_map(|prefix| text.strip_prefix(prefix.as_str()));
+    let matched = after_actor
+        .and_then(|rest| match_template(&ACTOR_TEMPLATES, rest))
+        .or_else(|| match_template(&PLAIN_TEMPLATES, text));
+    let Some((template, subject, value)) = matched else {
+        return Vec::new();
+    };
+
+    let value = value.map(|v| v.trim().to_string());
+    match subject {
+        Some(names) => split_names(names)
+            .into_iter()
+            .map(|name| {
+                let subject = (!is_owner_word(name)).then(|| name.to_string());
+                event(template.kind, subject, value.clone())
+            })
+            .collect(),
+        None => {
+            let subject = match template.subject {
+                Subject::Actor => Some(sender.to_string()),
+                Subject::Owner | Subject::Nobody => None,
+            };
+            vec![event(template.kind, subject, value)]
+        }
+    }
+}
+
+/// The first template matching `text`, with its `subject` and `value` captures.
+fn match_template<'t, 's>(
+    templates: &'t [Template],
+    text: &'s str,
+) -> Option<(&'t Template, Option<&'s str>, Option<&'s str>)> {
+    templates.iter().find_map(|t| {
+        let caps = t.re.captures(text)?;
+        let capture = |name| caps.name(name).map(|m| m.as_str());
+        Some((t, capture("subject"), capture("value")))
+    })
+}
+
+/// Names of a list like "Ann, Bob and 2 others" ("Ann, Bob i Cezary" in Polish); the
+/// uncounted "others" are dropped.
+fn split_names(list: &str) -> Vec<&str> {
+    list.split(", ")
+        .flat_map(|part| part.split(" and "))
+        .flat_map(|part| part.split(" i "))
+        .flat_map(|part| part.split(" oraz "))
+        .map(str::trim)
+        .filter(|name| !name.is_empty() && !OTHERS_RE.is_match(name))
+        .collect()
+}
+
+fn is_owner_word(name: &str) -> bool {
+    OWNER_WORDS.contains(&name.to_lowercase().as_str())
+}
@@ -1,96 +1,206 @@
-//! Global file index for cross-ZIP resolution (pre-opens ZIPs). For now, indexes audio files.
+//! Global media resolver for cross-ZIP attachment lookup.
+//!
+//! Every attachment-like entry of the selected archives is indexed by name up front; the
+//! archives themselves are opened lazily on lookup, keeping at most [`MAX_OPEN_ARCHIVES`]
+//! handles open (least recently used ones are closed first).
+//!
+//! Lookups normalize URIs: a leading `./` is ignored, a name with Latin-1 mojibake is
+//! retried fixed, and a name that matches nothing exactly is retried case-insensitively.
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fs::File;
 use std::io::Read;
 use std::path::{Path, PathBuf};
 use zip::ZipArchive;
 
-use crate::progress::{ensure_not_cancelled, ImportCancelled};
+use super::encoding::fix_latin1_mojibake;
+use crate::importers::messenger::report::MissingMedia;
+use crate::progress::{ensure_not_cancelled, CancellationToken, ImportCancelled};
+
+/// Archives kept open at once; others are reopened when a lookup needs them.
+pub const MAX_OPEN_ARCHIVES: usize = 4;
 
 #[derive(Clone, Debug)]
 pub struct FileLocation {
-    pub zip_path: PathBuf,
+    /// Archive the entry is in; see [`FileIndex::archive_path`].
+    pub archive: usize,
     pub entry_name: String,
+    /// Uncompressed size in bytes.
+    pub size: u64,
 }
 
 #[derive(Default)]
 pub struct FileIndex {
-    by_full: HashMap<String, FileLocation>,
-    zips: HashMap<PathBuf, ZipArchive<File>>, // pre-opened ZIPs
+    archives: Vec<PathBuf>,
+    locations: Vec<FileLocation>,
+    /// Normalized entry name -> location.
+    by_name: HashMap<String, usize>,
+    /// Lowercased normalized name -> location, only for names that are not lowercase
+    /// already (those are found through `by_name`).
+    by_lowercase: HashMap<String, usize>,
+    /// Open archives, least recently used first.
+    open: Vec<(usize, ZipArchive<File>)>,
+    missing: Vec<MissingMedia>,
+    reported: HashSet<String>,
 }
 
-/// Build a file index from a set of ZIP paths. Indexes audio-like files by full path.
-pub fn build_file_index(paths: &[PathBuf]) -> Result<FileIndex, ImportCancelled> {
+/// Build a file index from a set of ZIP paths. Indexes every attachment-like entry; each
+/// archive is read once and stays open while the LRU limit allows.
+pub fn build_file_index(
+    paths: &[PathBuf],
+    cancel: &CancellationToken,
+) -> Result<FileIndex, ImportCancelled> {
     let mut idx = FileIndex::default();
 
     for zp in paths {
-        ensure_not_cancelled()?;
-        if let Ok(f) = File::open(zp) {
-            if let Ok(archive) = ZipArchive::new(f) {
-                // Build index entries for this archive
-                for name in archive.file_names() {
-                    ensure_not_cancelled()?;
-                    if is_audio_like(name) {
-                        let loc = FileLocation {
-                            zip_path: zp.clone(),
-                            entry_name: name.to_string(),
-                        };
-                        idx.by_full.entry(name.to_string()).or_insert(loc.clone());
-                        if !name.starts_with("./") {
-                            let dot = format!("./{}", name);
-                            idx.by_full.entry(dot).or_insert(loc);
-                        }
-                    }
-                }
-                // Store the opened archive for fast future access
-                // Re-open to move into our map (ZipArchive is not Clone)
-                if let Ok(f2) = File::open(zp) {
-                    if let Ok(ar2) = ZipArchive::new(f2) {
-                        idx.zips.insert(zp.clone(), ar2);
-                    }
-                }
+        ensure_not_cancelled(cancel)?;
+        let Some(mut archive) = File::open(zp).ok().and_then(|f| ZipArchive::new(f).ok()) else {
+            continue;
+        };
+        let archive_id = idx.archives.len();
+        idx.archives.push(zp.clone());
+        for i in 0..archive.len() {
+            ensure_not_cancelled(cancel)?;
+            // Raw access reads the header only, without decompressing anything.
+            let Ok(entry) = archive.by_index_raw(i) else {
+                continue;
+            };
+            if is_attachment_like(entry.name()) {
+                idx.insert(archive_id, entry.name(), entry.size());
             }
         }
+        idx.keep_open(archive_id, archive);
     }
 
     Ok(idx)
 }
 
+/// Name without a leading `./`, as used for keys and lookups.
+fn normalize(name: &str) -> &str {
+    name.trim_start_matches("./")
+}
+
 impl FileIndex {
-    /// Execute a closure with a Read handle to the given full path inside a pre-opened ZIP.
-    /// The handle does not escape this method (avoids lifetime issues with ZipArchive).
-    pub fn with_file<F, R>(&mut self, full_path: &str, f: F) -> Option<R>
+    /// Number of indexed files.
+    pub fn len(&self) -> usize {
+        self.locations.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.locations.is_empty()
+    }
+
+    /// Path of the archive a location points into.
+    pub fn archive_path(&self, loc: &FileLocation) -> &Path {
+        &self.archives[loc.archive]
+    }
+
+    fn insert(&mut self, archive: usize, name: &str, size: u64) {
+        let key = normalize(name);
+        if self.by_name.contains_key(key) {
+            // The first archive listing a name wins.
+            return;
+        }
+        let id = self.locations.len();
+        self.locations.push(FileLocation {
+            archive,
+            entry_name: name.to_string(),
+            size,
+        });
+        self.by_name.insert(key.to_string(), id);
+        let lower = key.to_lowercase();
+        if lower != key {
+            self.by_lowercase.entry(lower).or_insert(id);
+        }
+    }
+
+    /// Location of the file a URI refers to, trying the URI as written, then with mojibake
+    /// fixed, then case-insensitively.
+    pub fn resolve(&self, uri: &str) -> Option<&FileLocation> {
+        let exact = |name: &str| self.by_name.get(normalize(name)).copied();
+        let any_case = |name: &str| {
+            let lower = normalize(name).to_lowercase();
+            self.by_lowercase
+                .get(&lower)
+                .or_else(|| self.by_name.get(&lower))
+                .copied()
+        };
+        let id = match exact(uri) {
+            Some(id) => id,
+            None => {
+                let fixed = fix_latin1_mojibake(uri);
+                exact(&fixed)
+                    .or_else(|| any_case(uri))
+                    .or_else(|| any_case(&fixed))?
+            }
+        };
+        Some(&self.locations[id])
+    }
+
+    /// Whether a URI resolves to an indexed file.
+    pub fn contains(&self, uri: &str) -> bool {
+        self.resolve(uri).is_some()
+    }
+
+    /// Execute a closure with a Read handle to the file a URI resolves to, opening its
+    /// archive if needed. The handle does not escape this method (avoids lifetime issues
+    /// with ZipArchive).
+    pub fn with_file<F, R>(&mut self, uri: &str, f: F) -> Option<R>
     where
         F: FnOnce(&mut dyn Read) -> R,
     {
-        let loc = self.by_full.get(full_path)?;
-        let zip = self.zips.get_mut(&loc.zip_path)?;
+        let loc = self.resolve(uri)?.clone();
+        let zip = self.archive(loc.archive)?;
         let mut zf = zip.by_name(&loc.entry_name).ok()?;
         Some(f(&mut zf))
     }
+
+    /// The open handle of an archive, opening it (and closing the least recently used one
+    /// over the limit) when needed.
+    fn archive(&mut self, archive: usize) -> Option<&mut ZipArchive<File>> {
+        if let Some(at) = self.open.iter().position(|(id, _)| *id == archive) {
+            let entry = self.open.remove(at);
+            self.open.push(entry);
+        } else {
+            let zip = ZipArchive::new(File::open(&self.archives[archive]).ok()?).ok()?;
+            self.keep_open(archive, zip);
+        }
+        self.open.last_mut().map(|(_, zip)| zip)
+    }
+
+    fn keep_open(&mut self, archive: usize, zip: ZipArchive<File>) {
+        self.open.push((archive, zip));
+        if self.open.len() > MAX_OPEN_ARCHIVES {
+            self.open.remove(0);
+        }
+    }
+
+    /// Record an attachment whose file is in none of the indexed archives. Each URI is
+    /// recorded once.
+    pub fn report_missing(&mut self, kind: &'static str, uri: &str) {
+        if self.reported.insert(uri.to_string()) {
+            self.missing.push(MissingMedia {
+                uri: uri.to_string(),
+                kind,
+            });
+        }
+    }
+
+    /// Attachments reported missing so far, in the order they were first seen.
+    pub fn take_missing(&mut self) -> Vec<MissingMedia> {
+        self.reported.clear();
+        std::mem::take(&mut self.missing)
+    }
 }
 
-fn is_audio_like(name: &str) -> bool {
+/// Whether an entry can be a message attachment: any file except thread JSON.
+fn is_attachment_like(name: &str) -> bool {
+    if name.ends_with('/') {
+        return false;
+    }
     let ext = Path::new(name)
         .extension()
         .and_then(|s| s.to_str())
         .map(|s| s.to_ascii_lowercase());
-    matches!(
-        ext.as_deref(),
-        Some(
-            "mp3"
-                | "m4a"
-                | "aac"
-                | "wav"
-                | "ogg"
-                | "oga"
-                | "opus"
-                | "flac"
-                | "mp4"
-                | "mov"
-                | "3gp"
-                | "3gpp"
-        )
-    )
+    ext.as_deref() != Some("json")
 }
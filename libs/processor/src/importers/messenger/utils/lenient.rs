@@ -0,0 +1,62 @@
+//! Lenient JSON parsing for thread files.
+//!
+//! Used when strict deserialization of a whole thread fails: the thread metadata and
+//! each message are deserialized separately so one malformed message does not take
+//! the rest of the thread down with it.
+
+use std::collections::HashSet;
+
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+
+use crate::error::ImportError;
+use crate::importers::messenger::report::SkippedItem;
+
+/// Parse a thread JSON whose root holds a `messages` array, skipping bad messages.
+///
+/// Returns the root (deserialized with an empty `messages` array), the messages that
+/// deserialized cleanly, and a [`SkippedItem`] for every message that did not.
+/// Fails only if the document is not JSON at all or the thread metadata itself is unusable.
+pub fn parse_thread_lenient<T, M>(
+    json_path: &str,
+    json_content: &str,
+) -> Result<(T, Vec<M>, Vec<SkippedItem>), ImportError>
+where
+    T: DeserializeOwned,
+    M: DeserializeOwned,
+{
+    let mut root: Value =
+        serde_json::from_str(json_content).map_err(|e| ImportError::json(json_path, e))?;
+    let raw_messages = match root.get_mut("messages").map(Value::take) {
+        Some(Value::Array(items)) => items,
+        _ => Vec::new(),
+    };
+    if let Some(obj) = root.as_object_mut() {
+        obj.insert("messages".into(), Value::Array(Vec::new()));
+    }
+    let root: T = serde_json::from_value(root).map_err(|e| ImportError::json(json_path, e))?;
+
+    let mut messages = Vec::with_capacity(raw_messages.len());
+    let mut skipped = Vec::new();
+    for (index, raw) in raw_messages.into_iter().enumerate() {
+        match serde_json::from_value::<M>(raw) {
+            Ok(m) => messages.push(m),
+            Err(e) => skipped.push(SkippedItem {
+                path: json_path.to_string(),
+                message_index: Some(index),
+                error: ImportError::json(json_path, e),
+            }),
+        }
+    }
+    Ok((root, messages, skipped))
+}
+
+/// Positions in the `messages` array of the `parsed` messages [`parse_thread_lenient`] kept,
+/// given the ones it `skipped`.
+pub fn parsed_message_positions(parsed: usize, skipped: &[SkippedItem]) -> Vec<usize> {
+    let skipped: HashSet<usize> = skipped.iter().filter_map(|s| s.message_index).collect();
+    (0..)
+        .filter(|position| !skipped.contains(position))
+        .take(parsed)
+        .collect()
+}
@@ -0,0 +1,201 @@
+//! Content-addressed copies of attachments, so media outlives the export archives.
+//!
+//! Files are named by the SHA-256 of their content plus the original extension, sharded by
+//! the first two hex digits (`ab/ab12…ef.jpg`). A file already in the store is never
+//! written again, so a photo sent in many threads or exports is kept once.
+
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{ErrorKind, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use anyhow::{bail, Context, Result};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+
+use super::file_index::FileIndex;
+
+/// Attachment kinds accepted by [`MediaStoreOptions::include`] and
+/// [`MediaStoreOptions::exclude`].
+pub const MEDIA_KINDS: &[&str] = &["image", "gif", "sticker", "video", "audio"];
+
+/// Suffix of in-flight temporary files, unique within the process.
+static TEMP_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// Where and what to extract. Deserializable as part of the import options.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct MediaStoreOptions {
+    /// Store directory; created if missing.
+    pub dir: PathBuf,
+    /// Most bytes one import may add to the store (0: no limit). Attachments that no
+    /// longer fit keep only their archive URI, unless an identical file is stored already.
+    pub max_bytes: u64,
+    /// Kinds to extract (see [`MEDIA_KINDS`]); empty means all of them.
+    pub include: Vec<String>,
+    /// Kinds never extracted, even when included.
+    pub exclude: Vec<String>,
+}
+
+impl MediaStoreOptions {
+    /// Whether attachments of `kind` are extracted.
+    pub fn wants(&self, kind: &str) -> bool {
+        (self.include.is_empty() || self.include.iter().any(|k| k == kind))
+            && !self.exclude.iter().any(|k| k == kind)
+    }
+}
+
+/// The media store of one import run.
+pub struct MediaStore {
+    options: MediaStoreOptions,
+    /// Bytes this run added to the store.
+    added_bytes: u64,
+    /// Outcome per URI, so an attachment referenced many times is read once.
+    stored: HashMap<String, Option<String>>,
+}
+
+impl MediaStore {
+    /// Open (creating if needed) the store directory of `options`.
+    pub fn open(options: MediaStoreOptions) -> Result<Self> {
+        for kind in options.include.iter().chain(&options.exclude) {
+            if !MEDIA_KINDS.contains(&kind.as_str()) {
+                bail!(
+                    "Unknown media kind '{}' (expected one of {})",
+                    kind,
+                    MEDIA_KINDS.join(", ")
+                );
+            }
+        }
+        fs::create_dir_all(&options.dir)
+            .with_context(|| format!("Failed to create media store {}", options.dir.display()))?;
+        Ok(Self {
+            options,
+            added_bytes: 0,
+            stored: HashMap::new(),
+        })
+    }
+
+    /// Bytes this run added to the store so far.
+    pub fn added_bytes(&self) -> u64 {
+        self.added_bytes
+    }
+
+    /// Copy the file `uri` resolves to into the store and return its path relative to the
+    /// store directory. `None` when `kind` is not extracted, the file is missing or
+    /// unreadable, or it does not fit the budget.
+    pub fn extract(
+        &mut self,
+        index: &mut FileIndex,
+        kind: &str,
+        uri: &str,
+    ) -> Result<Option<String>> {
+        if !self.options.wants(kind) {
+            return Ok(None);
+        }
+        if let Some(stored) = self.stored.get(uri) {
+            return Ok(stored.clone());
+        }
+        let Some(loc) = index.resolve(uri) else {
+            return Ok(None);
+        };
+        let fits =
+            self.options.max_bytes == 0 || self.added_bytes + loc.size <= self.options.max_bytes;
+        let extension = extension(uri);
+        let stored = match index.with_file(uri, |r| self.put(r, &extension, fits)) {
+            Some(stored) => stored?,
+            None => return Ok(None),
+        };
+        self.stored.insert(uri.to_string(), stored.clone());
+        Ok(stored)
+    }
+
+    /// Hash `reader` into the store. When it does not `fit`, nothing is written and the
+    /// path is returned only if an identical file is stored already.
+    fn put(
+        &mut self,
+        reader: &mut dyn Read,
+        extension: &str,
+        fits: bool,
+    ) -> Result<Option<String>> {
+        let dir = &self.options.dir;
+        let temp = dir.join(format!(
+            ".incoming-{}-{}",
+            std::process::id(),
+            TEMP_COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+        let mut out = if fits {
+            Some(
+                File::create(&temp)
+                    .with_context(|| format!("Failed to create {}", temp.display()))?,
+            )
+        } else {
+            None
+        };
+
+        let mut hasher = Sha256::new();
+        let mut written = 0u64;
+        let mut buf = vec![0u8; 64 * 1024];
+        loop {
+            let n = match reader.read(&mut buf) {
+                Ok(0) => break,
+                Ok(n) => n,
+                Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+                // A damaged entry is left in the archive, like a missing one.
+                Err(_) => {
+                    drop(out);
+                    remove_temp(&temp, fits);
+                    return Ok(None);
+                }
+            };
+            hasher.update(&buf[..n]);
+            if let Some(out) = out.as_mut() {
+                if let Err(e) = out.write_all(&buf[..n]) {
+                    let _ = fs::remove_file(&temp);
+                    return Err(e).with_context(|| format!("Failed to write {}", temp.display()));
+                }
+            }
+            written += n as u64;
+        }
+        drop(out);
+
+        let hash: String = hasher
+            .finalize()
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect();
+        let relative = format!("{}/{}{}", &hash[..2], hash, extension);
+        let dest = dir.join(&relative);
+        if dest.exists() {
+            remove_temp(&temp, fits);
+            return Ok(Some(relative));
+        }
+        if !fits {
+            return Ok(None);
+        }
+        let shard = dest.parent().unwrap_or(dir);
+        fs::create_dir_all(shard)
+            .with_context(|| format!("Failed to create {}", shard.display()))?;
+        fs::rename(&temp, &dest)
+            .with_context(|| format!("Failed to move media to {}", dest.display()))?;
+        self.added_bytes += written;
+        Ok(Some(relative))
+    }
+}
+
+fn remove_temp(temp: &Path, created: bool) {
+    if created {
+        let _ = fs::remove_file(temp);
+    }
+}
+
+/// Lowercased extension of `uri` with its dot, or nothing when it has none or it looks
+/// unusual (stored names stay predictable).
+fn extension(uri: &str) -> String {
+    Path::new(uri)
+        .extension()
+        .and_then(|e| e.to_str())
+        .filter(|e| e.len() <= 8 && e.chars().all(|c| c.is_ascii_alphanumeric()))
+        .map(|e| format!(".{}", e.to_ascii_lowercase()))
+        .unwrap_or_default()
+}
@@ -5,8 +5,18 @@
 
 pub mod db_helpers;
 pub mod encoding;
+pub mod events;
 pub mod file_index;
+pub mod lenient;
+pub mod media_store;
+pub mod stickers;
+pub mod streaming;
 
 pub use db_helpers::*;
 pub use encoding::*;
+pub use events::*;
 pub use file_index::*;
+pub use lenient::*;
+pub use media_store::*;
+pub use stickers::*;
+pub use streaming::*;
@@ -0,0 +1,34 @@
+//! Sticker recognition for Messenger attachments.
+//!
+//! Facebook exports keep stickers under `messages/stickers_used/`; E2E exports mark
+//! sticker messages with `type: "sticker"` and may name the files after the sticker as
+//! well. The file names of Facebook stickers contain the sticker id, which is how the
+//! thumbs-up "like" sticker is told apart.
+
+use std::path::Path;
+
+/// Ids of the thumbs-up sticker (small, medium, large) and of its image file.
+const LIKE_STICKER_IDS: &[&str] = &[
+    "369239263222822",
+    "369239343222814",
+    "369239383222810",
+    "369239266556155",
+];
+
+/// Whether the path of an attachment marks it as a sticker: a directory or file name
+/// starting with `sticker`.
+pub fn is_sticker_uri(uri: &str) -> bool {
+    Path::new(uri).iter().any(|part| {
+        part.to_str()
+            .is_some_and(|p| p.to_ascii_lowercase().starts_with("sticker"))
+    })
+}
+
+/// Whether a sticker file is the thumbs-up "like" sticker.
+pub fn is_like_sticker(uri: &str) -> bool {
+    let Some(name) = Path::new(uri).file_stem().and_then(|n| n.to_str()) else {
+        return false;
+    };
+    name.split(['_', '-', '.'])
+        .any(|token| LIKE_STICKER_IDS.contains(&token))
+}
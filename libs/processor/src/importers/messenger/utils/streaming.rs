@@ -0,0 +1,201 @@
+//! Streaming deserialization of thread files.
+//!
+//! Instead of materializing a whole thread root with its `Vec<Message>`, the root object is
+//! walked key by key: every field except `messages` is buffered (they are small), and each
+//! element of `messages` is deserialized and handed to a callback on its own, so only one
+//! message is alive at a time. The callback also sees the fields read before the array, so a
+//! thread whose header comes first can be written in the same pass.
+
+use std::fmt;
+use std::io::{BufReader, Read};
+use std::marker::PhantomData;
+
+use anyhow::Result;
+use serde::de::{self, DeserializeOwned, DeserializeSeed, MapAccess, SeqAccess, Visitor};
+use serde_json::{Map, Value};
+
+use crate::error::ImportError;
+
+/// Stream a thread JSON document from `reader`.
+///
+/// `on_message(header, index, message)` is called for every element of the root `messages`
+/// array in file order, with the root fields that precede the array. In strict mode a
+/// malformed message aborts the stream with a positioned [`ImportError`]; with `lenient` each
+/// element is decoded on its own and failures are passed to the callback as `Err` instead. An
+/// error returned by the callback stops the stream and is returned unchanged.
+///
+/// Returns the root deserialized from the remaining fields, with an empty `messages` array.
+pub fn stream_thread<T, M, R, F>(
+    json_path: &str,
+    reader: R,
+    lenient: bool,
+    mut on_message: F,
+) -> Result<T>
+where
+    T: DeserializeOwned,
+    M: DeserializeOwned,
+    R: Read,
+    F: FnMut(&Map<String, Value>, usize, Result<M, ImportError>) -> Result<()>,
+{
+    let mut failure: Option<anyhow::Error> = None;
+    let mut de = serde_json::Deserializer::from_reader(BufReader::new(reader));
+    let visited = de::Deserializer::deserialize_map(
+        &mut de,
+        RootVisitor {
+            json_path,
+            lenient,
+            on_message: &mut on_message,
+            failure: &mut failure,
+            _message: PhantomData::<M>,
+        },
+    )
+    .and_then(|fields| de.end().map(|_| fields));
+
+    let fields = match visited {
+        Ok(fields) => fields,
+        Err(e) => {
+            return Err(match failure {
+                Some(callback_error) => callback_error,
+                None => ImportError::json(json_path, e).into(),
+            })
+        }
+    };
+    serde_json::from_value(Value::Object(fields))
+        .map_err(|e| ImportError::json(json_path, e).into())
+}
+
+/// Read only the root fields of a thread, skipping over its messages without deserializing
+/// them. Used when a thread's header follows its messages.
+pub fn read_thread_header<T, R>(json_path: &str, reader: R) -> Result<T>
+where
+    T: DeserializeOwned,
+    R: Read,
+{
+    stream_thread::<T, de::IgnoredAny, R, _>(json_path, reader, false, |_, _, _| Ok(()))
+}
+
+/// Returned by a streaming importer's message callback when the header fields it needs to
+/// begin the thread were not read before the first message; the caller then reads the header
+/// with [`read_thread_header`] and streams the thread again.
+#[derive(Debug)]
+pub struct HeaderAfterMessages;
+
+impl fmt::Display for HeaderAfterMessages {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("thread header follows its messages")
+    }
+}
+
+impl std::error::Error for HeaderAfterMessages {}
+
+/// Whether `err` means the thread file itself is unreadable or malformed, which lenient mode
+/// records as a skipped thread, rather than a failure of the import.
+pub fn is_unreadable_thread(err: &anyhow::Error) -> bool {
+    matches!(
+        err.downcast_ref::<ImportError>(),
+        Some(
+            ImportError::Io { .. }
+                | ImportError::Zip { .. }
+                | ImportError::JsonParse { .. }
+                | ImportError::SchemaMismatch { .. }
+        )
+    )
+}
+
+struct RootVisitor<'a, M, F> {
+    json_path: &'a str,
+    lenient: bool,
+    on_message: &'a mut F,
+    failure: &'a mut Option<anyhow::Error>,
+    _message: PhantomData<M>,
+}
+
+impl<'de, M, F> Visitor<'de> for RootVisitor<'_, M, F>
+where
+    M: DeserializeOwned,
+    F: FnMut(&Map<String, Value>, usize, Result<M, ImportError>) -> Result<()>,
+{
+    type Value = Map<String, Value>;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a thread object")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        let mut fields = Map::new();
+        while let Some(key) = map.next_key::<String>()? {
+            if key == "messages" {
+                map.next_value_seed(MessagesSeed {
+                    json_path: self.json_path,
+                    header: &fields,
+                    lenient: self.lenient,
+                    on_message: &mut *self.on_message,
+                    failure: &mut *self.failure,
+                    _message: PhantomData::<M>,
+                })?;
+                fields.insert(key, Value::Array(Vec::new()));
+            } else {
+                let value: Value = map.next_value()?;
+                fields.insert(key, value);
+            }
+        }
+        Ok(fields)
+    }
+}
+
+struct MessagesSeed<'a, M, F> {
+    json_path: &'a str,
+    header: &'a Map<String, Value>,
+    lenient: bool,
+    on_message: &'a mut F,
+    failure: &'a mut Option<anyhow::Error>,
+    _message: PhantomData<M>,
+}
+
+impl<'de, M, F> DeserializeSeed<'de> for MessagesSeed<'_, M, F>
+where
+    M: DeserializeOwned,
+    F: FnMut(&Map<String, Value>, usize, Result<M, ImportError>) -> Result<()>,
+{
+    type Value = ();
+
+    fn deserialize<D: de::Deserializer<'de>>(self, deserializer: D) -> Result<(), D::Error> {
+        deserializer.deserialize_seq(self)
+    }
+}
+
+impl<'de, M, F> Visitor<'de> for MessagesSeed<'_, M, F>
+where
+    M: DeserializeOwned,
+    F: FnMut(&Map<String, Value>, usize, Result<M, ImportError>) -> Result<()>,
+{
+    type Value = ();
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("an array of messages")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<(), A::Error> {
+        let mut index = 0;
+        loop {
+            let message = if self.lenient {
+                match seq.next_element::<Value>()? {
+                    Some(raw) => serde_json::from_value::<M>(raw)
+                        .map_err(|e| ImportError::json(self.json_path, e)),
+                    None => break,
+                }
+            } else {
+                match seq.next_element::<M>()? {
+                    Some(message) => Ok(message),
+                    None => break,
+                }
+            };
+            if let Err(e) = (self.on_message)(self.header, index, message) {
+                *self.failure = Some(e);
+                return Err(de::Error::custom("message callback failed"));
+            }
+            index += 1;
+        }
+        Ok(())
+    }
+}
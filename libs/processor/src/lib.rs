@@ -6,12 +6,16 @@
 //!
 //! ## Main Components
 //!
+//! - [`analytics`] - Statistics queries over the normalized database
 //! - [`database`] - SQLite schema and database operations
+//! - [`error`] - Typed import errors shared by the Rust API and FFI
 //! - [`importers`] - Chat format parsers and import logic  
 //! - [`ffi`] - C-compatible functions for native bridges
 //! - [`utils`] - Shared utilities and helper functions
 
+pub mod analytics;
 pub mod database;
+pub mod error;
 pub mod ffi;
 pub mod importers;
 pub mod progress;
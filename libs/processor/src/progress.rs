@@ -1,13 +1,183 @@
 use once_cell::sync::Lazy;
+use serde::Serialize;
+use std::ffi::CString;
+use std::os::raw::c_char;
 use std::sync::{
     atomic::{AtomicBool, Ordering},
-    Mutex,
+    Arc, Mutex,
 };
+use std::time::Instant;
 
 pub type ProgressCallback = unsafe extern "C" fn(processed: u32, total: u32);
 
+/// Receives a structured [`ProgressEvent`]. The pointer and the strings it references are
+/// only valid for the duration of the call.
+pub type ProgressEventCallback = unsafe extern "C" fn(event: *const ProgressEvent);
+
 static PROGRESS_CALLBACK: Lazy<Mutex<Option<ProgressCallback>>> = Lazy::new(|| Mutex::new(None));
-static CANCEL_REQUESTED: AtomicBool = AtomicBool::new(false);
+static PROGRESS_EVENT_CALLBACK: Lazy<Mutex<Option<ProgressEventCallback>>> =
+    Lazy::new(|| Mutex::new(None));
+/// Token behind the legacy process-wide cancel functions.
+static GLOBAL_CANCEL: Lazy<CancellationToken> = Lazy::new(CancellationToken::new);
+
+/// Shared cancellation flag for one import. Clones observe the same flag.
+#[derive(Clone, Debug, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::SeqCst)
+    }
+
+    fn reset(&self) {
+        self.0.store(false, Ordering::SeqCst);
+    }
+}
+
+/// Stage of an import run, in the order they occur.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ImportPhase {
+    /// Opening inputs, detecting their format and sizing the work.
+    Scan = 0,
+    /// Building the cross-archive media index.
+    Index = 1,
+    /// Reading and deserializing a thread file.
+    Parse = 2,
+    /// Writing a parsed thread (including media probing) to the database.
+    Insert = 3,
+    /// Committing and any work after the last thread.
+    PostProcess = 4,
+}
+
+/// Structured progress delivered to [`ProgressEventCallback`]; the C view of a
+/// [`ProgressSnapshot`].
+#[repr(C)]
+#[derive(Debug)]
+pub struct ProgressEvent {
+    pub phase: ImportPhase,
+    /// Entry path of the thread file being handled, or null.
+    pub current_file: *const c_char,
+    /// Display name of the thread being handled, or null.
+    pub thread_name: *const c_char,
+    /// Thread files finished so far / in total (known after the scan phase).
+    pub items_processed: u32,
+    pub items_total: u32,
+    /// Uncompressed bytes of thread JSON finished so far / in total.
+    pub bytes_processed: u64,
+    pub bytes_total: u64,
+    /// Estimated milliseconds remaining, or -1 when not yet known.
+    pub eta_ms: i64,
+}
+
+/// Owned progress state of an import, as reported to a [`ProgressSink`].
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct ProgressSnapshot {
+    #[serde(serialize_with = "serialize_phase")]
+    pub phase: ImportPhase,
+    pub current_file: Option<String>,
+    pub thread_name: Option<String>,
+    pub items_processed: u32,
+    pub items_total: u32,
+    pub bytes_processed: u64,
+    pub bytes_total: u64,
+    /// Estimated milliseconds remaining, or -1 when not yet known.
+    pub eta_ms: i64,
+}
+
+impl Default for ProgressSnapshot {
+    fn default() -> Self {
+        Self {
+            phase: ImportPhase::Scan,
+            current_file: None,
+            thread_name: None,
+            items_processed: 0,
+            items_total: 0,
+            bytes_processed: 0,
+            bytes_total: 0,
+            eta_ms: -1,
+        }
+    }
+}
+
+impl ImportPhase {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ImportPhase::Scan => "scan",
+            ImportPhase::Index => "index",
+            ImportPhase::Parse => "parse",
+            ImportPhase::Insert => "insert",
+            ImportPhase::PostProcess => "post_process",
+        }
+    }
+}
+
+fn serialize_phase<S: serde::Serializer>(phase: &ImportPhase, s: S) -> Result<S::Ok, S::Error> {
+    s.serialize_str(phase.as_str())
+}
+
+/// Destination for the progress of one import.
+pub trait ProgressSink: Send + Sync {
+    fn report(&self, snapshot: &ProgressSnapshot);
+}
+
+/// Sink forwarding to the process-wide callbacks registered over FFI.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct GlobalCallbacks;
+
+impl ProgressSink for GlobalCallbacks {
+    fn report(&self, snapshot: &ProgressSnapshot) {
+        report_progress(snapshot.items_processed, snapshot.items_total);
+
+        let callback_opt = {
+            let guard = PROGRESS_EVENT_CALLBACK
+                .lock()
+                .expect("progress event callback mutex poisoned");
+            *guard
+        };
+        let Some(cb) = callback_opt else {
+            return;
+        };
+        let current_file = snapshot
+            .current_file
+            .as_deref()
+            .and_then(|s| CString::new(s).ok());
+        let thread_name = snapshot
+            .thread_name
+            .as_deref()
+            .and_then(|s| CString::new(s).ok());
+        let event = ProgressEvent {
+            phase: snapshot.phase,
+            current_file: current_file
+                .as_ref()
+                .map_or(std::ptr::null(), |s| s.as_ptr()),
+            thread_name: thread_name
+                .as_ref()
+                .map_or(std::ptr::null(), |s| s.as_ptr()),
+            items_processed: snapshot.items_processed,
+            items_total: snapshot.items_total,
+            bytes_processed: snapshot.bytes_processed,
+            bytes_total: snapshot.bytes_total,
+            eta_ms: snapshot.eta_ms,
+        };
+        unsafe { cb(&event) };
+    }
+}
+
+pub fn set_progress_event_callback(callback: Option<ProgressEventCallback>) {
+    let mut guard = PROGRESS_EVENT_CALLBACK
+        .lock()
+        .expect("progress event callback mutex poisoned");
+    *guard = callback;
+}
 
 pub fn set_progress_callback(callback: Option<ProgressCallback>) {
     let mut guard = PROGRESS_CALLBACK
@@ -38,19 +208,24 @@ pub fn report_progress(processed: u32, total: u32) {
     });
 }
 
+/// The token used by imports started without their own (legacy FFI entry points).
+pub fn global_cancel_token() -> CancellationToken {
+    GLOBAL_CANCEL.clone()
+}
+
 /// Signal that the current import should cancel as soon as possible.
 pub fn request_cancel() {
-    CANCEL_REQUESTED.store(true, Ordering::SeqCst);
+    GLOBAL_CANCEL.cancel();
 }
 
 /// Clear any pending cancellation signal - typically called before a new import.
 pub fn clear_cancel() {
-    CANCEL_REQUESTED.store(false, Ordering::SeqCst);
+    GLOBAL_CANCEL.reset();
 }
 
 /// Returns true if a cancellation was requested by the host application.
 pub fn cancellation_requested() -> bool {
-    CANCEL_REQUESTED.load(Ordering::SeqCst)
+    GLOBAL_CANCEL.is_cancelled()
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -64,19 +239,38 @@ impl std::fmt::Display for ImportCancelled {
 
 impl std::error::Error for ImportCancelled {}
 
-/// Helper to bail out early when a cancellation has been requested.
-pub fn ensure_not_cancelled() -> Result<(), ImportCancelled> {
-    if cancellation_requested() {
+/// Helper to bail out early when cancellation has been requested on `token`.
+pub fn ensure_not_cancelled(token: &CancellationToken) -> Result<(), ImportCancelled> {
+    if token.is_cancelled() {
         Err(ImportCancelled)
     } else {
         Ok(())
     }
 }
 
-#[derive(Debug, Default)]
+/// Tracks progress of one import run and reports it to a [`ProgressSink`].
+///
+/// With the default [`GlobalCallbacks`] sink the legacy `(processed, total)` callback keeps
+/// receiving thread-file counts, while the structured callback additionally gets the phase,
+/// current file/thread, byte counts and an ETA derived from the byte throughput so far.
 pub struct ImportProgressTracker {
-    processed: u32,
-    total: u32,
+    snapshot: ProgressSnapshot,
+    started_at: Option<Instant>,
+    sink: Arc<dyn ProgressSink>,
+}
+
+impl Default for ImportProgressTracker {
+    fn default() -> Self {
+        Self::with_sink(Arc::new(GlobalCallbacks))
+    }
+}
+
+impl std::fmt::Debug for ImportProgressTracker {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ImportProgressTracker")
+            .field("snapshot", &self.snapshot)
+            .finish_non_exhaustive()
+    }
 }
 
 impl ImportProgressTracker {
@@ -84,25 +278,81 @@ impl ImportProgressTracker {
         Self::default()
     }
 
+    pub fn with_sink(sink: Arc<dyn ProgressSink>) -> Self {
+        Self {
+            snapshot: ProgressSnapshot::default(),
+            started_at: None,
+            sink,
+        }
+    }
+
     pub fn reset(&mut self) {
-        self.processed = 0;
-        self.total = 0;
-        report_progress(0, 0);
+        self.snapshot = ProgressSnapshot::default();
+        self.started_at = None;
+        self.emit();
     }
 
-    pub fn add_total(&mut self, additional: u32) {
-        if additional == 0 {
-            return;
+    /// Set the full amount of work up front so totals do not jump mid-import.
+    pub fn set_totals(&mut self, items: u32, bytes: u64) {
+        self.snapshot.items_total = items;
+        self.snapshot.bytes_total = bytes;
+        self.started_at = Some(Instant::now());
+        self.emit();
+    }
+
+    /// Move to `phase`, clearing the current file/thread when leaving per-thread phases.
+    pub fn set_phase(&mut self, phase: ImportPhase) {
+        self.snapshot.phase = phase;
+        if !matches!(phase, ImportPhase::Parse | ImportPhase::Insert) {
+            self.snapshot.current_file = None;
+            self.snapshot.thread_name = None;
         }
-        self.total = self.total.saturating_add(additional);
-        report_progress(self.processed, self.total);
+        self.emit();
+    }
+
+    /// Announce the thread file about to be parsed.
+    pub fn begin_file(&mut self, file: &str) {
+        self.snapshot.phase = ImportPhase::Parse;
+        self.snapshot.current_file = Some(file.to_string());
+        self.snapshot.thread_name = None;
+        self.emit();
+    }
+
+    /// Announce that the current file parsed into `thread_name` and is being inserted.
+    pub fn begin_insert(&mut self, thread_name: &str) {
+        self.snapshot.phase = ImportPhase::Insert;
+        self.snapshot.thread_name = Some(thread_name.to_string());
+        self.emit();
     }
 
     pub fn advance(&mut self, delta: u32) {
         if delta == 0 {
             return;
         }
-        self.processed = self.processed.saturating_add(delta);
-        report_progress(self.processed, self.total);
+        self.snapshot.items_processed = self.snapshot.items_processed.saturating_add(delta);
+        self.emit();
+    }
+
+    /// Count `bytes` of thread JSON as done.
+    pub fn advance_bytes(&mut self, bytes: u64) {
+        self.snapshot.bytes_processed = self.snapshot.bytes_processed.saturating_add(bytes);
+    }
+
+    fn eta_ms(&self) -> i64 {
+        let s = &self.snapshot;
+        match self.started_at {
+            _ if s.bytes_total > 0 && s.bytes_processed >= s.bytes_total => 0,
+            Some(started_at) if s.bytes_processed > 0 => {
+                let elapsed_ms = started_at.elapsed().as_millis() as f64;
+                let remaining = (s.bytes_total - s.bytes_processed) as f64;
+                (elapsed_ms * remaining / s.bytes_processed as f64) as i64
+            }
+            _ => -1,
+        }
+    }
+
+    fn emit(&mut self) {
+        self.snapshot.eta_ms = self.eta_ms();
+        self.sink.report(&self.snapshot);
     }
 }
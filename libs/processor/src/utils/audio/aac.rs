@@ -0,0 +1,84 @@
+//! Raw AAC in ADTS framing: frame headers are read one by one, payloads skipped.
+
+use std::io::{Read, Seek};
+
+use super::reader::{find_forward, reaches, read_array, seek_to, skip_id3v2};
+use super::{average_bitrate, Probe};
+
+// sampling frequency table
+const SR: [u32; 13] = [
+    96000, 88200, 64000, 48000, 44100, 32000, 24000, 22050, 16000, 12000, 11025, 8000, 7350,
+];
+
+pub(super) fn is_adts(head: &[u8]) -> bool {
+    head.len() >= 7 && head[0] == 0xFF && (head[1] & 0xF0) == 0xF0
+}
+
+// AAC ADTS duration: iterate ADTS frames via header, summing samples per frame.
+pub(super) fn parse_aac_adts<R: Read + Seek + ?Sized>(r: &mut R) -> Option<Probe> {
+    // Skip ID3v2 if present
+    let mut i = skip_id3v2(r)?;
+
+    let mut total_samples: u64 = 0;
+    let mut sample_rate: Option<u32> = None;
+    let mut frames: u64 = 0;
+    let mut channels: Option<u16> = None;
+    let mut bytes: u64 = 0;
+
+    loop {
+        seek_to(r, i)?;
+        let Some(h) = read_array::<7, _>(r) else {
+            break;
+        };
+        if !is_adts(&h) {
+            // Resync on the next syncword.
+            seek_to(r, i)?;
+            match find_forward(r, 7, is_adts) {
+                Some(at) => i = at,
+                None => break,
+            }
+            continue;
+        }
+        let sf_index = ((h[2] >> 2) & 0x0F) as usize;
+        if sf_index >= SR.len() {
+            i += 1;
+            continue;
+        }
+        let sr = SR[sf_index];
+        if sample_rate.is_none() {
+            sample_rate = Some(sr);
+            // channel_configuration: 1 bit in byte 2, 2 bits in byte 3 (7 means 8 channels)
+            channels = match ((h[2] & 0x01) << 2) | (h[3] >> 6) {
+                0 => None,
+                7 => Some(8),
+                c => Some(c as u16),
+            };
+        }
+        let frame_length: u64 =
+            (((h[3] & 0x03) as u64) << 11) | ((h[4] as u64) << 3) | (((h[5] >> 5) & 0x07) as u64);
+        if frame_length < 7 {
+            i += 1;
+            continue;
+        }
+        // A truncated last frame is not counted.
+        if !reaches(r, i + frame_length) {
+            break;
+        }
+        let num_raw_blocks = (h[6] & 0x03) as u64; // 0 => 1 block
+        total_samples = total_samples.saturating_add(1024 * (num_raw_blocks + 1));
+        frames += 1;
+        bytes += frame_length;
+        i += frame_length;
+    }
+    let sr = sample_rate?;
+    if frames == 0 || sr == 0 {
+        return None;
+    }
+    let seconds = total_samples as f64 / sr as f64;
+    Some(Probe {
+        sample_rate: Some(sr),
+        channels,
+        bitrate: average_bitrate(bytes, seconds),
+        ..Probe::new(seconds, "aac")
+    })
+}
@@ -0,0 +1,56 @@
+//! AMR-NB/WB storage format (RFC 4867 §5): a magic line, then 20 ms frames whose size
+//! follows from the frame type in each one-byte frame header.
+
+use std::io::{Read, Seek};
+
+use super::reader::{read_array, read_up_to, seek_to};
+use super::{average_bitrate, Probe};
+
+const MAGIC_NB: &[u8] = b"#!AMR\n";
+const MAGIC_WB: &[u8] = b"#!AMR-WB\n";
+
+/// Speech bytes after the header byte, by frame type (NB: 4.75–12.2 kbit/s, SID, no data).
+const NB_FRAME_BYTES: [u8; 16] = [12, 13, 15, 17, 19, 20, 26, 31, 5, 0, 0, 0, 0, 0, 0, 0];
+/// Same for AMR-WB (6.6–23.85 kbit/s, SID, speech lost / no data).
+const WB_FRAME_BYTES: [u8; 16] = [17, 23, 32, 36, 40, 46, 50, 58, 60, 5, 0, 0, 0, 0, 0, 0];
+
+const FRAME_SECONDS: f64 = 0.020;
+
+pub(super) fn is_amr(head: &[u8]) -> bool {
+    head.starts_with(MAGIC_NB) || head.starts_with(MAGIC_WB)
+}
+
+// AMR duration: count frames, 20 ms each
+pub(super) fn parse_amr<R: Read + Seek + ?Sized>(r: &mut R) -> Option<Probe> {
+    seek_to(r, 0)?;
+    let head = read_up_to(r, MAGIC_WB.len() as u64)?;
+    let (mut pos, sizes, codec, sample_rate) = if head.starts_with(MAGIC_WB) {
+        (MAGIC_WB.len() as u64, &WB_FRAME_BYTES, "amr_wb", 16000)
+    } else if head.starts_with(MAGIC_NB) {
+        (MAGIC_NB.len() as u64, &NB_FRAME_BYTES, "amr_nb", 8000)
+    } else {
+        return None;
+    };
+
+    let start = pos;
+    let mut frames: u64 = 0;
+    loop {
+        seek_to(r, pos)?;
+        let Some([header]) = read_array::<1, _>(r) else {
+            break;
+        };
+        let frame_type = ((header >> 3) & 0x0F) as usize;
+        frames += 1;
+        pos += 1 + sizes[frame_type] as u64;
+    }
+    if frames == 0 {
+        return None;
+    }
+    let seconds = frames as f64 * FRAME_SECONDS;
+    Some(Probe {
+        sample_rate: Some(sample_rate),
+        channels: Some(1),
+        bitrate: average_bitrate(pos - start, seconds),
+        ..Probe::new(seconds, codec)
+    })
+}
@@ -0,0 +1,102 @@
+//! Core Audio Format: `desc` for the sample rate and packet layout, then the valid frame
+//! count from `pakt` or, for constant-size packets, the `data` chunk size. The codec and
+//! channel count come from `desc` too.
+
+use std::io::{Read, Seek};
+
+use super::reader::{position, read_array, seek_to};
+use super::Probe;
+
+pub(super) fn is_caf(head: &[u8]) -> bool {
+    head.starts_with(b"caff")
+}
+
+// CAF duration: pakt valid frames (or data size / bytes per packet) / sample rate
+pub(super) fn parse_caf<R: Read + Seek + ?Sized>(r: &mut R) -> Option<Probe> {
+    seek_to(r, 0)?;
+    let header = read_array::<8, _>(r)?;
+    if !is_caf(&header) {
+        return None;
+    }
+
+    let mut sample_rate: Option<f64> = None;
+    let mut format_id = [0u8; 4];
+    let mut channels: u32 = 0;
+    let mut bytes_per_packet: u32 = 0;
+    let mut frames_per_packet: u32 = 0;
+    let mut valid_frames: Option<i64> = None;
+    let mut data_size: Option<i64> = None;
+
+    while let Some(chunk) = read_array::<12, _>(r) {
+        let typ = &chunk[0..4];
+        let size = i64::from_be_bytes(chunk[4..12].try_into().ok()?);
+        let body = position(r)?;
+        match typ {
+            b"desc" => {
+                let d = read_array::<32, _>(r)?;
+                sample_rate = Some(f64::from_be_bytes(d[0..8].try_into().ok()?));
+                format_id.copy_from_slice(&d[8..12]);
+                bytes_per_packet = u32::from_be_bytes(d[16..20].try_into().ok()?);
+                frames_per_packet = u32::from_be_bytes(d[20..24].try_into().ok()?);
+                channels = u32::from_be_bytes(d[24..28].try_into().ok()?);
+            }
+            b"pakt" => {
+                let p = read_array::<16, _>(r)?;
+                valid_frames = Some(i64::from_be_bytes(p[8..16].try_into().ok()?));
+            }
+            b"data" => {
+                // -1 means the data runs to the end of the file; it's the last chunk then.
+                if size >= 0 {
+                    data_size = Some(size);
+                }
+                if valid_frames.is_some() || size < 0 {
+                    break;
+                }
+            }
+            _ => {}
+        }
+        if size < 0 {
+            break;
+        }
+        seek_to(r, body + size as u64)?;
+    }
+
+    let sr = sample_rate.filter(|sr| sr.is_finite() && *sr > 0.0)?;
+    let frames = match valid_frames {
+        Some(frames) => frames,
+        None if bytes_per_packet > 0 && frames_per_packet > 0 => {
+            // The data chunk starts with a 4-byte edit count.
+            let packets = (data_size? - 4).max(0) / bytes_per_packet as i64;
+            packets * frames_per_packet as i64
+        }
+        None => return None,
+    };
+    if frames <= 0 {
+        return None;
+    }
+    // Constant bit rate formats (LPCM) have fixed-size packets.
+    let bitrate = (bytes_per_packet > 0 && frames_per_packet > 0)
+        .then(|| (bytes_per_packet as f64 * 8.0 * sr / frames_per_packet as f64).round() as u32);
+    Some(Probe {
+        seconds: frames as f64 / sr,
+        codec: codec_for_format(&format_id),
+        sample_rate: Some(sr.round() as u32),
+        channels: u16::try_from(channels).ok(),
+        bitrate,
+    })
+}
+
+fn codec_for_format(id: &[u8; 4]) -> Option<&'static str> {
+    Some(match id {
+        b"aac " => "aac",
+        b"lpcm" => "pcm",
+        b"alac" => "alac",
+        b"opus" => "opus",
+        b"samr" => "amr_nb",
+        b"sawb" => "amr_wb",
+        b".mp3" => "mp3",
+        b"ulaw" => "pcm_mulaw",
+        b"alaw" => "pcm_alaw",
+        _ => return None,
+    })
+}
@@ -0,0 +1,42 @@
+//! FLAC: total samples and sample rate from the STREAMINFO metadata block.
+
+use std::io::{Read, Seek};
+
+use super::reader::{read_array, seek_to, skip_id3v2};
+use super::Probe;
+
+pub(super) fn is_flac(head: &[u8]) -> bool {
+    head.starts_with(b"fLaC")
+}
+
+// FLAC duration: STREAMINFO total samples / sample rate
+pub(super) fn parse_flac<R: Read + Seek + ?Sized>(r: &mut R) -> Option<Probe> {
+    // Some encoders prepend an ID3v2 tag
+    let start = skip_id3v2(r)?;
+    seek_to(r, start)?;
+    if !is_flac(&read_array::<4, _>(r)?) {
+        return None;
+    }
+    // STREAMINFO is mandatory and always the first metadata block (type 0, 34 bytes).
+    let block = read_array::<4, _>(r)?;
+    let length = u32::from_be_bytes([0, block[1], block[2], block[3]]);
+    if (block[0] & 0x7F) != 0 || length < 34 {
+        return None;
+    }
+    let info = read_array::<34, _>(r)?;
+    // 20 bits sample rate, 3 bits channels-1, 5 bits bits-per-sample-1, 36 bits total samples
+    let sample_rate =
+        ((info[10] as u32) << 12) | ((info[11] as u32) << 4) | ((info[12] as u32) >> 4);
+    let total_samples = (((info[13] & 0x0F) as u64) << 32)
+        | u32::from_be_bytes([info[14], info[15], info[16], info[17]]) as u64;
+    // Zero total samples means "unknown".
+    if sample_rate == 0 || total_samples == 0 {
+        return None;
+    }
+    let channels = ((info[12] >> 1) & 0x07) as u16 + 1;
+    Some(Probe {
+        sample_rate: Some(sample_rate),
+        channels: Some(channels),
+        ..Probe::new(total_samples as f64 / sample_rate as f64, "flac")
+    })
+}
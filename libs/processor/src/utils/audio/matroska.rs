@@ -0,0 +1,226 @@
+//! WebM/Matroska: `Segment/Info/Duration`, scaled by `TimecodeScale`, plus the first audio
+//! track's codec, sampling frequency and channels from `Segment/Tracks`.
+//!
+//! Only element headers are read until `Info` and `Tracks`, which muxers write before the
+//! clusters.
+
+use std::io::{Read, Seek};
+
+use super::reader::{position, read_array, read_up_to, seek_to};
+use super::Probe;
+
+const EBML: u32 = 0x1A45_DFA3;
+const SEGMENT: u32 = 0x1853_8067;
+const INFO: u32 = 0x1549_A966;
+const TIMECODE_SCALE: u32 = 0x2A_D7B1;
+const DURATION: u32 = 0x4489;
+const TRACKS: u32 = 0x1654_AE6B;
+const TRACK_ENTRY: u32 = 0xAE;
+const TRACK_TYPE: u32 = 0x83;
+const CODEC_ID: u32 = 0x86;
+const AUDIO: u32 = 0xE1;
+const SAMPLING_FREQUENCY: u32 = 0xB5;
+const CHANNELS: u32 = 0x9F;
+const CLUSTER: u32 = 0x1F43_B675;
+
+/// `TrackType` of audio tracks.
+const TRACK_TYPE_AUDIO: u64 = 2;
+
+/// Default `TimecodeScale`: timestamps in milliseconds.
+const DEFAULT_TIMECODE_SCALE: u64 = 1_000_000;
+
+pub(super) fn is_matroska(head: &[u8]) -> bool {
+    head.starts_with(&EBML.to_be_bytes())
+}
+
+/// Element header: its ID (marker bits kept, as IDs are usually written) and payload range;
+/// `end` is `None` for unknown-size elements.
+struct Element {
+    id: u32,
+    body: u64,
+    end: Option<u64>,
+}
+
+/// A variable-length integer: `(length, value without the length marker)`.
+fn read_vint<R: Read + ?Sized>(r: &mut R, max_len: u32) -> Option<(u32, u64)> {
+    let [first] = read_array::<1, _>(r)?;
+    let len = first.leading_zeros() + 1;
+    if len > max_len {
+        return None;
+    }
+    let mut value = (first as u64) & (0xFF >> len);
+    for _ in 1..len {
+        let [b] = read_array::<1, _>(r)?;
+        value = (value << 8) | b as u64;
+    }
+    Some((len, value))
+}
+
+fn next_element<R: Read + Seek + ?Sized>(r: &mut R, limit: Option<u64>) -> Option<Element> {
+    let start = position(r)?;
+    if limit.is_some_and(|l| start >= l) {
+        return None;
+    }
+    let (id_len, id_value) = read_vint(r, 4)?;
+    // Put the marker bit back: IDs are compared in their encoded form.
+    let id = (id_value | 1 << (7 * id_len)) as u32;
+    let (size_len, size) = read_vint(r, 8)?;
+    let body = position(r)?;
+    // All value bits set means "unknown size".
+    let unknown = size == (1u64 << (7 * size_len)) - 1;
+    let end = if unknown { None } else { Some(body + size) };
+    Some(Element { id, body, end })
+}
+
+fn read_uint(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0, |acc, &b| (acc << 8) | b as u64)
+}
+
+fn read_float(bytes: &[u8]) -> Option<f64> {
+    match bytes.len() {
+        4 => Some(f32::from_be_bytes(bytes.try_into().ok()?) as f64),
+        8 => Some(f64::from_be_bytes(bytes.try_into().ok()?)),
+        _ => None,
+    }
+}
+
+// Matroska duration: Info/Duration * TimecodeScale nanoseconds
+pub(super) fn parse_matroska<R: Read + Seek + ?Sized>(r: &mut R) -> Option<Probe> {
+    seek_to(r, 0)?;
+    let header = next_element(r, None)?;
+    if header.id != EBML {
+        return None;
+    }
+    seek_to(r, header.end?)?;
+    let segment = next_element(r, None)?;
+    if segment.id != SEGMENT {
+        return None;
+    }
+    let mut seconds = None;
+    let mut probe = Probe::default();
+    seek_to(r, segment.body)?;
+    while let Some(child) = next_element(r, segment.end) {
+        match child.id {
+            INFO => seconds = parse_info(r, &child),
+            TRACKS => parse_tracks(r, &child, &mut probe),
+            // Media data: everything we read comes before it.
+            CLUSTER => break,
+            _ => {}
+        }
+        // Anything else of unknown size can't be skipped.
+        match child.end {
+            Some(end) if seek_to(r, end).is_some() => {}
+            _ => break,
+        }
+    }
+    probe.seconds = seconds?;
+    Some(probe)
+}
+
+fn parse_info<R: Read + Seek + ?Sized>(r: &mut R, info: &Element) -> Option<f64> {
+    let mut scale = DEFAULT_TIMECODE_SCALE;
+    let mut duration = None;
+    seek_to(r, info.body)?;
+    while let Some(el) = next_element(r, info.end) {
+        let end = el.end?;
+        if el.id == TIMECODE_SCALE || el.id == DURATION {
+            let bytes = read_up_to(r, (end - el.body).min(8))?;
+            if el.id == TIMECODE_SCALE {
+                scale = read_uint(&bytes);
+            } else {
+                duration = read_float(&bytes);
+            }
+        }
+        seek_to(r, end)?;
+    }
+    let duration = duration.filter(|d| d.is_finite() && *d > 0.0)?;
+    if scale == 0 {
+        return None;
+    }
+    Some(duration * scale as f64 / 1e9)
+}
+
+/// Fill codec, sampling frequency and channels from the first audio `TrackEntry`.
+fn parse_tracks<R: Read + Seek + ?Sized>(r: &mut R, tracks: &Element, probe: &mut Probe) {
+    let mut entries = Vec::new();
+    if seek_to(r, tracks.body).is_none() {
+        return;
+    }
+    while let Some(entry) = next_element(r, tracks.end) {
+        let Some(end) = entry.end else {
+            return;
+        };
+        if entry.id == TRACK_ENTRY {
+            entries.push(entry);
+        }
+        if seek_to(r, end).is_none() {
+            return;
+        }
+    }
+    for entry in entries {
+        if parse_track_entry(r, &entry, probe) == Some(true) {
+            return;
+        }
+    }
+}
+
+/// `Some(true)` if `entry` is an audio track (and `probe` was filled from it).
+fn parse_track_entry<R: Read + Seek + ?Sized>(
+    r: &mut R,
+    entry: &Element,
+    probe: &mut Probe,
+) -> Option<bool> {
+    let mut is_audio = false;
+    let mut codec = None;
+    let mut audio = None;
+    seek_to(r, entry.body)?;
+    while let Some(el) = next_element(r, entry.end) {
+        let end = el.end?;
+        match el.id {
+            TRACK_TYPE => is_audio = read_uint(&read_up_to(r, end - el.body)?) == TRACK_TYPE_AUDIO,
+            CODEC_ID => codec = Some(read_up_to(r, (end - el.body).min(64))?),
+            AUDIO => audio = Some(el),
+            _ => {}
+        }
+        seek_to(r, end)?;
+    }
+    if !is_audio {
+        return Some(false);
+    }
+    probe.codec = codec.as_deref().and_then(codec_for_id);
+    if let Some(audio) = audio {
+        seek_to(r, audio.body)?;
+        while let Some(el) = next_element(r, audio.end) {
+            let end = el.end?;
+            let bytes = read_up_to(r, (end - el.body).min(8))?;
+            match el.id {
+                SAMPLING_FREQUENCY => {
+                    probe.sample_rate = read_float(&bytes)
+                        .filter(|f| f.is_finite() && *f >= 1.0)
+                        .map(|f| f.round() as u32);
+                }
+                CHANNELS => probe.channels = Some(read_uint(&bytes) as u16),
+                _ => {}
+            }
+            seek_to(r, end)?;
+        }
+    }
+    Some(true)
+}
+
+fn codec_for_id(id: &[u8]) -> Option<&'static str> {
+    let id = std::str::from_utf8(id).ok()?.trim_end_matches('\0');
+    Some(match id {
+        "A_OPUS" => "opus",
+        "A_VORBIS" => "vorbis",
+        "A_FLAC" => "flac",
+        "A_MPEG/L3" => "mp3",
+        "A_MPEG/L2" => "mp2",
+        "A_AC3" => "ac3",
+        "A_EAC3" => "eac3",
+        _ if id.starts_with("A_AAC") => "aac",
+        _ if id.starts_with("A_PCM/FLOAT") => "pcm_float",
+        _ if id.starts_with("A_PCM") => "pcm",
+        _ => return None,
+    })
+}
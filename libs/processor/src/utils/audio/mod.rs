@@ -0,0 +1,369 @@
+//! Audio duration and stream details without external dependencies.
+//!
+//! This module provides utilities for extracting audio duration (and, where the headers
+//! carry them, codec, sample rate, channels and bitrate) from common formats using
+//! byte-level parsing only (std). Supported formats include:
+//! - WAV (RIFF/WAVE)
+//! - MP4/M4A (ISO BMFF) via `mvhd`/`mdhd`
+//! - MP3 (frames + optional Xing/Info/VBRI)
+//! - AAC (ADTS frames)
+//! - Ogg Opus/Vorbis (granule position & pre-skip)
+//! - AMR-NB/WB (frame count)
+//! - FLAC (STREAMINFO)
+//! - WebM/Matroska (EBML `Duration`)
+//! - CAF (`desc` + `pakt`/`data`)
+//!
+//! Parsers read headers and skip payloads, so memory use does not grow with file size.
+//! They work on `Read + Seek`; forward-only streams such as compressed ZIP entries are
+//! wrapped in a [`ForwardSeek`].
+
+mod aac;
+mod amr;
+mod caf;
+mod flac;
+mod matroska;
+mod mp3;
+pub(crate) mod mp4;
+mod ogg;
+pub(crate) mod reader;
+mod wav;
+
+use std::io::{Read, Seek};
+
+pub use reader::ForwardSeek;
+
+/// Bytes looked at when sniffing the format from the stream start.
+const SNIFF_BYTES: u64 = 16;
+
+/// Duration and stream details of an audio file, as far as its headers tell.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct AudioInfo {
+    /// Duration in milliseconds (rounded), always positive.
+    pub duration_ms: i64,
+    /// Codec, e.g. `"aac"`, `"opus"`, `"mp3"`, `"amr_nb"` or `"pcm"`.
+    pub codec: Option<&'static str>,
+    /// Sample rate in Hz.
+    pub sample_rate: Option<u32>,
+    pub channels: Option<u16>,
+    /// Average bitrate in bits per second.
+    pub bitrate: Option<u32>,
+}
+
+impl AudioInfo {
+    /// Duration in whole seconds (rounded) as stored in `message_audio.length_seconds`;
+    /// `None` under half a second, like [`detect_duration_seconds`].
+    pub fn whole_seconds(&self) -> Option<i64> {
+        Some((self.duration_ms + 500) / 1000).filter(|&s| s > 0)
+    }
+}
+
+/// What a format parser found: the exact duration plus whatever its headers carry.
+#[derive(Clone, Debug, Default)]
+struct Probe {
+    seconds: f64,
+    codec: Option<&'static str>,
+    sample_rate: Option<u32>,
+    channels: Option<u16>,
+    bitrate: Option<u32>,
+}
+
+impl Probe {
+    fn new(seconds: f64, codec: &'static str) -> Probe {
+        Probe {
+            seconds,
+            codec: Some(codec),
+            ..Probe::default()
+        }
+    }
+}
+
+/// Average bitrate of `bytes` of audio data lasting `seconds`.
+fn average_bitrate(bytes: u64, seconds: f64) -> Option<u32> {
+    if seconds <= 0.0 {
+        return None;
+    }
+    let bps = (bytes as f64 * 8.0 / seconds).round();
+    (bps >= 1.0 && bps <= u32::MAX as f64).then_some(bps as u32)
+}
+
+/// Detect the duration of audio from a forward-only stream (e.g. a ZIP entry).
+///
+/// The stream is wrapped in a [`ForwardSeek`]: seeking forward skips bytes, so formats
+/// whose index sits at the end (MP4 `moov`, the last Ogg page) are still found, at the
+/// cost of reading through the file. Prefer [`detect_duration_seconds_seekable`] when the
+/// reader can seek.
+///
+/// # Arguments
+/// - `path_hint`: File path or name used for extension hinting.
+/// - `reader`: Input stream with audio bytes.
+///
+/// # Returns
+/// - `Some(duration_seconds)` rounded to whole seconds
+/// - `None` if format unsupported or parsing fails
+///
+/// # Example
+/// ```rust,no_run
+/// use std::fs::File;
+/// use processor::utils::audio::detect_duration_seconds;
+///
+/// let mut file = File::open("audio.mp3").unwrap();
+/// if let Some(duration) = detect_duration_seconds("audio.mp3", &mut file) {
+///     println!("Duration: {} seconds", duration);
+/// }
+/// ```
+pub fn detect_duration_seconds<R: Read + ?Sized>(path_hint: &str, reader: &mut R) -> Option<i64> {
+    let mut reader = ForwardSeek::new(reader).ok()?;
+    detect_duration_seconds_seekable(path_hint, &mut reader)
+}
+
+/// Detect the duration of audio from a seekable reader, reading only headers, frame headers
+/// and (for Ogg) the file's tail.
+///
+/// Same results as [`detect_duration_seconds`].
+pub fn detect_duration_seconds_seekable<R: Read + Seek + ?Sized>(
+    path_hint: &str,
+    reader: &mut R,
+) -> Option<i64> {
+    let probe = probe(path_hint, reader)?;
+    Some(probe.seconds.round() as i64).filter(|&s| s > 0)
+}
+
+/// Detect duration (in milliseconds) and stream details of audio from a forward-only
+/// stream; see [`detect_duration_seconds`] for how it is read.
+///
+/// Returns `None` if the format is unsupported, parsing fails or the duration is under
+/// half a millisecond. Details the headers don't carry are left `None`.
+pub fn detect_audio_info<R: Read + ?Sized>(path_hint: &str, reader: &mut R) -> Option<AudioInfo> {
+    let mut reader = ForwardSeek::new(reader).ok()?;
+    detect_audio_info_seekable(path_hint, &mut reader)
+}
+
+/// [`detect_audio_info`] for seekable readers.
+pub fn detect_audio_info_seekable<R: Read + Seek + ?Sized>(
+    path_hint: &str,
+    reader: &mut R,
+) -> Option<AudioInfo> {
+    let probe = probe(path_hint, reader)?;
+    let duration_ms = (probe.seconds * 1000.0).round() as i64;
+    if duration_ms <= 0 {
+        return None;
+    }
+    Some(AudioInfo {
+        duration_ms,
+        codec: probe.codec,
+        sample_rate: probe.sample_rate.filter(|&sr| sr > 0),
+        channels: probe.channels.filter(|&c| c > 0),
+        bitrate: probe.bitrate.filter(|&b| b > 0),
+    })
+}
+
+fn probe<R: Read + Seek + ?Sized>(path_hint: &str, reader: &mut R) -> Option<Probe> {
+    reader::seek_to(reader, 0)?;
+    let head = reader::read_up_to(reader, SNIFF_BYTES)?;
+    if head.is_empty() {
+        return None;
+    }
+
+    let ext = path_extension_lower(path_hint);
+
+    // Try by extension first
+    let probe = match ext.as_deref() {
+        Some("wav") => wav::parse_wav(reader),
+        Some("m4a") | Some("mp4") | Some("mov") | Some("3gp") | Some("3gpp") => {
+            mp4::parse_mp4(reader)
+        }
+        Some("aac") => aac::parse_aac_adts(reader),
+        Some("mp3") => mp3::parse_mp3(reader),
+        Some("opus") | Some("oga") | Some("ogg") => ogg::parse_ogg(reader),
+        Some("amr") | Some("awb") => amr::parse_amr(reader),
+        Some("flac") => flac::parse_flac(reader),
+        Some("webm") | Some("mka") | Some("mkv") => matroska::parse_matroska(reader),
+        Some("caf") => caf::parse_caf(reader),
+        _ => None,
+    };
+
+    // Fallback: sniff by header if extension didn't help
+    probe.or_else(|| sniff_and_parse(&head, reader))
+}
+
+/// Alternative function that takes a path and handles file opening internally.
+///
+/// This is a convenience wrapper around `detect_duration_seconds_seekable` for cases
+/// where you have a file path rather than an open reader.
+///
+/// # Arguments
+/// * `path` - Path to the audio file
+///
+/// # Returns
+/// * `Some(duration_seconds)` - Duration in whole seconds (rounded)
+/// * `None` - If the file cannot be opened or duration cannot be determined
+pub fn detect_duration_from_path(path: &str) -> Option<i64> {
+    match std::fs::File::open(path) {
+        Ok(mut file) => detect_duration_seconds_seekable(path, &mut file),
+        Err(e) => {
+            eprintln!("Failed to open audio file '{}': {}", path, e);
+            None
+        }
+    }
+}
+
+// ------------------
+// Helpers
+// ------------------
+
+fn path_extension_lower(path: &str) -> Option<String> {
+    std::path::Path::new(path)
+        .extension()
+        .and_then(|s| s.to_str())
+        .map(|s| s.to_ascii_lowercase())
+}
+
+fn sniff_and_parse<R: Read + Seek + ?Sized>(head: &[u8], r: &mut R) -> Option<Probe> {
+    // OggS
+    if ogg::is_ogg(head) {
+        if let Some(s) = ogg::parse_ogg(r) {
+            return Some(s);
+        }
+    }
+    // Formats with a fixed magic and everything needed in their headers
+    if amr::is_amr(head) {
+        return amr::parse_amr(r);
+    }
+    if flac::is_flac(head) || head.starts_with(b"ID3") {
+        if let Some(s) = flac::parse_flac(r) {
+            return Some(s);
+        }
+    }
+    if matroska::is_matroska(head) {
+        return matroska::parse_matroska(r);
+    }
+    if caf::is_caf(head) {
+        return caf::parse_caf(r);
+    }
+    // WAV RIFF/WAVE
+    if wav::is_wav(head) {
+        if let Some(s) = wav::parse_wav(r) {
+            return Some(s);
+        }
+    }
+    // ADTS AAC: 0xFFF syncword with layer 0 (MP3 frames have the same sync bits)
+    if aac::is_adts(head) && (head[1] & 0x06) == 0 {
+        if let Some(s) = aac::parse_aac_adts(r) {
+            return Some(s);
+        }
+    }
+    // MP4 (box walk, then a scan for 'moov') or MP3 (frame parsing). Both may read through
+    // the whole file, so try the one the header points at first: a forward-only reader
+    // can't come back for the other.
+    if mp4::looks_like_box(head) {
+        mp4::parse_mp4(r).or_else(|| mp3::parse_mp3(r))
+    } else {
+        mp3::parse_mp3(r).or_else(|| mp4::parse_mp4(r))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_unsupported_format() {
+        let data = b"not audio data";
+        let mut cursor = Cursor::new(data);
+        let result = detect_duration_seconds("test.bin", &mut cursor);
+        assert_eq!(result, None);
+    }
+
+    #[test]
+    fn test_empty_data() {
+        let data = b"";
+        let mut cursor = Cursor::new(data);
+        let result = detect_duration_seconds("test.mp3", &mut cursor);
+        assert_eq!(result, None);
+    }
+
+    #[test]
+    fn test_mp4_mislabeled_fixture_sniff() {
+        // Only run if the fixture exists
+        let p = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/test-audio/messenger/audio_clip_824795835818430.wav");
+        if !p.exists() {
+            return;
+        }
+        let data = std::fs::read(&p).unwrap();
+        // Force sniff path
+        let mut cursor = Cursor::new(data);
+        let dur = detect_duration_seconds("file.bin", &mut cursor);
+        if dur.is_none() {
+            // Drill down: ensure we can find moov and mvhd
+            let mut cursor = Cursor::new(std::fs::read(&p).unwrap());
+            let head = reader::read_up_to(&mut cursor, SNIFF_BYTES).unwrap();
+            let sniff = sniff_and_parse(&head, &mut cursor);
+            assert!(sniff.is_some(), "sniff_and_parse returned None");
+            assert!(sniff.unwrap().seconds > 1.0, "sniff duration too small");
+            let moov = mp4::find_box_anywhere(&mut cursor, 0, b"moov");
+            assert!(moov.is_some(), "no moov in file");
+            let d2 = mp4::parse_moov(&mut cursor, &moov.unwrap()).seconds;
+            assert!(d2.is_some(), "no duration from moov");
+        }
+        assert!(dur.is_some(), "expected duration, got None for {:?}", p);
+    }
+
+    /// A reader that can't seek, like a deflated ZIP entry.
+    struct Unseekable(Cursor<Vec<u8>>);
+
+    impl Read for Unseekable {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            self.0.read(buf)
+        }
+    }
+
+    /// MP4 with `moov` after a large `mdat`, so a forward-only reader has to skip to it.
+    fn moov_at_end_mp4(mdat_len: usize) -> Vec<u8> {
+        fn boxed(typ: &[u8; 4], body: &[u8]) -> Vec<u8> {
+            let mut b = ((body.len() + 8) as u32).to_be_bytes().to_vec();
+            b.extend_from_slice(typ);
+            b.extend_from_slice(body);
+            b
+        }
+        let mut mvhd = vec![0u8; 100];
+        mvhd[12..16].copy_from_slice(&1000u32.to_be_bytes());
+        mvhd[16..20].copy_from_slice(&7_500u32.to_be_bytes());
+        let mut file = boxed(b"ftyp", b"M4A \0\0\0\0");
+        file.extend(boxed(b"mdat", &vec![0xAB; mdat_len]));
+        file.extend(boxed(b"moov", &boxed(b"mvhd", &mvhd)));
+        file
+    }
+
+    #[test]
+    fn test_moov_at_end_forward_only() {
+        let data = moov_at_end_mp4(1 << 20);
+        let mut seekable = Cursor::new(data.clone());
+        assert_eq!(
+            detect_duration_seconds_seekable("a.m4a", &mut seekable),
+            Some(8)
+        );
+        let mut unseekable = Unseekable(Cursor::new(data));
+        assert_eq!(detect_duration_seconds("a.bin", &mut unseekable), Some(8));
+    }
+
+    #[test]
+    fn test_forward_seek_rewinds_only_within_kept_bytes() {
+        let data: Vec<u8> = (0..1_000_000u32).map(|i| i as u8).collect();
+        let mut r = ForwardSeek::new(Unseekable(Cursor::new(data))).unwrap();
+        let mut b = [0u8; 4];
+        r.seek(std::io::SeekFrom::Start(500_000)).unwrap();
+        r.read_exact(&mut b).unwrap();
+        assert_eq!(b, [32, 33, 34, 35]);
+        // Short rewind into the recently read bytes, and back to the start.
+        r.seek(std::io::SeekFrom::Start(500_001)).unwrap();
+        r.read_exact(&mut b).unwrap();
+        assert_eq!(b, [33, 34, 35, 36]);
+        r.seek(std::io::SeekFrom::Start(1)).unwrap();
+        r.read_exact(&mut b).unwrap();
+        assert_eq!(b, [1, 2, 3, 4]);
+        // Anything else behind the stream position is gone.
+        assert!(r.seek(std::io::SeekFrom::Start(200_000)).is_err());
+    }
+}
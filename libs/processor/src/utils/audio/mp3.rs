@@ -0,0 +1,279 @@
+//! MP3: frame headers, with the Xing/Info/VBRI frame count when present.
+
+use std::io::{Read, Seek};
+
+use super::reader::{find_forward, read_array, read_up_to, seek_to, skip_id3v2};
+use super::{average_bitrate, Probe};
+
+// MP3 duration: parse frames; prefer Xing/Info/VBRI frame count, else count frames
+pub(super) fn parse_mp3<R: Read + Seek + ?Sized>(r: &mut R) -> Option<Probe> {
+    // Skip ID3v2 if present
+    let offset = skip_id3v2(r)?;
+
+    // Scan to first valid frame header
+    seek_to(r, offset)?;
+    let first_pos = find_forward(r, 4, |w| Mp3Header::parse(w).is_some())?;
+    let header = Mp3Header::parse(&read_array::<4, _>(r)?)?;
+
+    // Try Xing/Info or VBRI headers for total frames
+    let summary =
+        parse_xing_info(r, first_pos, &header).or_else(|| parse_vbri(r, first_pos, &header));
+    if let Some((frames, bytes)) = summary {
+        let total_samples = frames as u64 * header.samples_per_frame() as u64;
+        let seconds = total_samples as f64 / header.sample_rate as f64;
+        let bitrate = match bytes {
+            Some(bytes) => average_bitrate(bytes as u64, seconds),
+            None => Some(header.bitrate_kbps as u32 * 1000),
+        };
+        return Some(header.probe(seconds, bitrate));
+    }
+
+    // Fallback: count frames by iterating
+    let mut pos = first_pos;
+    let mut frames: u64 = 0;
+    let mut bytes: u64 = 0;
+    loop {
+        seek_to(r, pos)?;
+        let Some(b) = read_array::<4, _>(r) else {
+            break;
+        };
+        if let Some(h) = Mp3Header::parse(&b) {
+            let flen = h.frame_length()? as u64;
+            if flen < 4 {
+                break;
+            }
+            frames += 1;
+            bytes += flen;
+            pos += flen;
+        } else {
+            // Possibly hit ID3v1 at end
+            if &b[0..3] == b"TAG" {
+                break;
+            }
+            // Resync on the next frame header
+            seek_to(r, pos)?;
+            match find_forward(r, 4, |w| {
+                Mp3Header::parse(w).is_some() || &w[0..3] == b"TAG"
+            }) {
+                Some(at) => pos = at,
+                None => break,
+            }
+        }
+    }
+    if frames == 0 || header.sample_rate == 0 {
+        return None;
+    }
+    let total_samples = frames * header.samples_per_frame() as u64;
+    let seconds = total_samples as f64 / header.sample_rate as f64;
+    Some(header.probe(seconds, average_bitrate(bytes, seconds)))
+}
+
+/// Frame count and (if present) stream byte count from a Xing/Info header.
+fn parse_xing_info<R: Read + Seek + ?Sized>(
+    r: &mut R,
+    first_pos: u64,
+    h: &Mp3Header,
+) -> Option<(u32, Option<u32>)> {
+    // After header (+ CRC if present) + side info, check for 'Xing' or 'Info'
+    let crc = if !h.protection_bit { 2 } else { 0 };
+    let side = h.side_info_len() as u64;
+    seek_to(r, first_pos + 4 + crc + side)?;
+    let b = read_up_to(r, 16)?;
+    if b.len() < 12 {
+        return None;
+    }
+    let tag = &b[0..4];
+    if tag != b"Xing" && tag != b"Info" {
+        return None;
+    }
+    let flags = u32::from_be_bytes([b[4], b[5], b[6], b[7]]);
+    if (flags & 0x1) == 0 {
+        return None;
+    }
+    let frames = u32::from_be_bytes([b[8], b[9], b[10], b[11]]);
+    let bytes = if (flags & 0x2) != 0 && b.len() >= 16 {
+        Some(u32::from_be_bytes([b[12], b[13], b[14], b[15]]))
+    } else {
+        None
+    };
+    Some((frames, bytes))
+}
+
+/// Frame and byte counts from a VBRI header.
+fn parse_vbri<R: Read + Seek + ?Sized>(
+    r: &mut R,
+    first_pos: u64,
+    h: &Mp3Header,
+) -> Option<(u32, Option<u32>)> {
+    // VBRI is located 32 bytes after header (common placement)
+    let crc = if !h.protection_bit { 2 } else { 0 };
+    seek_to(r, first_pos + 4 + crc + 32)?;
+    let b = read_array::<26, _>(r)?;
+    if &b[0..4] != b"VBRI" {
+        return None;
+    }
+    // bytes at offset 10, frames at offset 14 from 'VBRI'
+    let bytes = u32::from_be_bytes([b[10], b[11], b[12], b[13]]);
+    let frames = u32::from_be_bytes([b[14], b[15], b[16], b[17]]);
+    Some((frames, Some(bytes)))
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Mp3Header {
+    version_id: u8,       // 0=2.5, 1=reserved, 2=2, 3=1
+    layer: u8,            // 1=III, 2=II, 3=I
+    protection_bit: bool, // true => no CRC, false => CRC present
+    bitrate_kbps: u16,
+    sample_rate: u32,
+    padding: bool,
+    channel_mode: u8, // 3=mono
+}
+
+impl Mp3Header {
+    fn parse(h: &[u8]) -> Option<Mp3Header> {
+        if h.len() < 4 {
+            return None;
+        }
+        let b0 = h[0];
+        let b1 = h[1];
+        let b2 = h[2];
+        let b3 = h[3];
+        if b0 != 0xFF || (b1 & 0xE0) != 0xE0 {
+            return None;
+        }
+        let version_id = (b1 >> 3) & 0x03; // 00=2.5, 01=reserved,10=2,11=1
+        let layer_bits = (b1 >> 1) & 0x03; // 01=III, 10=II, 11=I
+        if version_id == 0x01 || layer_bits == 0x00 {
+            return None;
+        }
+        let layer = match layer_bits {
+            0b01 => 3,
+            0b10 => 2,
+            0b11 => 1,
+            _ => return None,
+        };
+        let protection_bit = (b1 & 0x01) != 0x00; // 0 => CRC present
+        let bitrate_index = (b2 >> 4) & 0x0F;
+        let sample_rate_index = (b2 >> 2) & 0x03;
+        if bitrate_index == 0 || bitrate_index == 0x0F || sample_rate_index == 0x03 {
+            return None;
+        }
+        let padding = ((b2 >> 1) & 0x01) != 0;
+        let channel_mode = (b3 >> 6) & 0x03;
+
+        let sample_rate = match version_id {
+            0b11 => [44100, 48000, 32000][sample_rate_index as usize], // MPEG1
+            0b10 => [22050, 24000, 16000][sample_rate_index as usize], // MPEG2
+            0b00 => [11025, 12000, 8000][sample_rate_index as usize],  // MPEG2.5
+            _ => return None,
+        } as u32;
+
+        // Bitrate table by version+layer
+        let bitrate_kbps = match (version_id, layer_bits) {
+            (0b11, 0b01) => [
+                0, 32, 40, 48, 56, 64, 80, 96, 112, 128, 160, 192, 224, 256, 320, 0,
+            ][bitrate_index as usize], // MPEG1 L3
+            (0b10, 0b01) | (0b00, 0b01) => [
+                0, 8, 16, 24, 32, 40, 48, 56, 64, 80, 96, 112, 128, 144, 160, 0,
+            ][bitrate_index as usize], // MPEG2/2.5 L3
+            // For completeness, Layer I/II (not typical for .mp3)
+            (0b11, 0b10) => [
+                0, 32, 48, 56, 64, 80, 96, 112, 128, 160, 192, 224, 256, 320, 384, 0,
+            ][bitrate_index as usize],
+            (0b10, 0b10) | (0b00, 0b10) => [
+                0, 8, 16, 24, 32, 40, 48, 56, 64, 80, 96, 112, 128, 144, 160, 0,
+            ][bitrate_index as usize],
+            (0b11, 0b11) => [
+                0, 32, 64, 96, 128, 160, 192, 224, 256, 288, 320, 352, 384, 416, 448, 0,
+            ][bitrate_index as usize],
+            (0b10, 0b11) | (0b00, 0b11) => [
+                0, 32, 48, 56, 64, 80, 96, 112, 128, 144, 160, 176, 192, 224, 256, 0,
+            ][bitrate_index as usize],
+            _ => 0,
+        } as u16;
+        if bitrate_kbps == 0 {
+            return None;
+        }
+
+        Some(Mp3Header {
+            version_id,
+            layer,
+            protection_bit,
+            bitrate_kbps,
+            sample_rate,
+            padding,
+            channel_mode,
+        })
+    }
+
+    fn probe(&self, seconds: f64, bitrate: Option<u32>) -> Probe {
+        let codec = match self.layer {
+            1 => "mp1",
+            2 => "mp2",
+            _ => "mp3",
+        };
+        Probe {
+            sample_rate: Some(self.sample_rate),
+            channels: Some(if self.channel_mode == 0b11 { 1 } else { 2 }),
+            bitrate,
+            ..Probe::new(seconds, codec)
+        }
+    }
+
+    fn samples_per_frame(&self) -> u32 {
+        match self.layer {
+            1 => 384,  // Layer I
+            2 => 1152, // Layer II
+            3 => match self.version_id {
+                0b11 => 1152,
+                _ => 576,
+            }, // Layer III
+            _ => 0,
+        }
+    }
+
+    fn side_info_len(&self) -> usize {
+        // For Layer III only (common for MP3)
+        if self.layer == 3 {
+            // Layer III only
+            let mono = self.channel_mode == 0b11;
+            match self.version_id {
+                0b11 => {
+                    if mono {
+                        17
+                    } else {
+                        32
+                    }
+                } // MPEG1
+                _ => {
+                    if mono {
+                        9
+                    } else {
+                        17
+                    }
+                } // MPEG2/2.5
+            }
+        } else {
+            0
+        }
+    }
+
+    fn frame_length(&self) -> Option<usize> {
+        let bps = (self.bitrate_kbps as u32) * 1000;
+        let spf = self.samples_per_frame();
+        if self.sample_rate == 0 {
+            return None;
+        }
+        let base = (spf as u64 * bps as u64) / (8 * self.sample_rate as u64);
+        let pad_units = if self.padding {
+            if self.layer == 1 {
+                4
+            } else {
+                1
+            }
+        } else {
+            0
+        };
+        Some((base as usize) + pad_units)
+    }
+}
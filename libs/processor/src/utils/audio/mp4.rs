@@ -0,0 +1,604 @@
+//! MP4/M4A (ISO BMFF): `moov`/`mvhd`, `mehd` or the audio track's `mdhd`, with `sidx` as a
+//! fallback for fragmented files. The same walk records the first video track's size and
+//! codec for [`crate::utils::video`].
+//!
+//! Boxes are walked by their headers: `mdat` and other payloads are skipped, and only the
+//! few header boxes needed are read, so `moov` may sit anywhere in the file.
+
+use std::io::{Read, Seek};
+
+use super::reader::{find_forward, position, read_array, read_up_to, seek_to};
+use super::Probe;
+
+/// Bytes read from full boxes like `mvhd`/`mdhd`; enough for version 1 layouts.
+const FULL_BOX_PREFIX: u64 = 32;
+
+/// Size of a version 1 `tkhd` payload, which ends with the track width and height.
+const TKHD_BYTES: u64 = 96;
+
+/// Header of a box: its type and where its payload starts and ends (`None` = end of stream).
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct BoxHeader {
+    pub typ: [u8; 4],
+    pub body: u64,
+    pub end: Option<u64>,
+}
+
+impl BoxHeader {
+    pub fn len(&self) -> Option<u64> {
+        self.end.map(|e| e - self.body)
+    }
+}
+
+/// Read the box header at the current position. `limit` is the end of the enclosing box;
+/// boxes overflowing it are rejected.
+pub(crate) fn next_box<R: Read + Seek + ?Sized>(
+    r: &mut R,
+    limit: Option<u64>,
+) -> Option<BoxHeader> {
+    let start = position(r)?;
+    if limit.is_some_and(|l| start + 8 > l) {
+        return None;
+    }
+    let h = read_array::<8, _>(r)?;
+    let size = u32::from_be_bytes([h[0], h[1], h[2], h[3]]) as u64;
+    let typ = [h[4], h[5], h[6], h[7]];
+    let (header, total) = match size {
+        1 => (16, Some(u64::from_be_bytes(read_array::<8, _>(r)?))),
+        0 => (8, None),
+        n => (8, Some(n)),
+    };
+    if total.is_some_and(|t| t < header) {
+        return None;
+    }
+    let end = total.map(|t| start + t).or(limit);
+    if let (Some(e), Some(l)) = (end, limit) {
+        if e > l {
+            return None;
+        }
+    }
+    Some(BoxHeader {
+        typ,
+        body: start + header,
+        end,
+    })
+}
+
+/// Move past `b`; `false` once the stream has nothing after it.
+pub(crate) fn skip_box<R: Seek + ?Sized>(r: &mut R, b: &BoxHeader) -> bool {
+    b.end.is_some_and(|e| seek_to(r, e).is_some())
+}
+
+/// Read the start of `b`'s payload, at most `max` bytes.
+pub(crate) fn read_body<R: Read + Seek + ?Sized>(
+    r: &mut R,
+    b: &BoxHeader,
+    max: u64,
+) -> Option<Vec<u8>> {
+    seek_to(r, b.body)?;
+    read_up_to(r, b.len().map_or(max, |l| l.min(max)))
+}
+
+/// Whether `head` starts with something shaped like a box header (printable 4cc type).
+pub(crate) fn looks_like_box(head: &[u8]) -> bool {
+    head.len() >= 8
+        && head[4..8]
+            .iter()
+            .all(|b| b.is_ascii_graphic() || *b == b' ')
+}
+
+/// What `moov` tells: the movie duration and the first audio and video tracks' details.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Moov {
+    pub seconds: Option<f64>,
+    pub audio: Option<AudioTrack>,
+    pub video: Option<VideoTrack>,
+}
+
+/// The first audio track with a usable `mdhd`.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct AudioTrack {
+    pub seconds: f64,
+    pub codec: Option<&'static str>,
+    pub sample_rate: Option<u32>,
+    pub channels: Option<u16>,
+    pub bitrate: Option<u32>,
+}
+
+/// The first video track: displayed size and codec.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct VideoTrack {
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    pub codec: Option<&'static str>,
+}
+
+/// A track `mdia` identified by its handler.
+enum Track {
+    Audio(AudioTrack),
+    Video(VideoTrack),
+}
+
+impl Moov {
+    fn probe(self, seconds: f64) -> Probe {
+        let track = self.audio.unwrap_or_default();
+        Probe {
+            seconds,
+            codec: track.codec,
+            sample_rate: track.sample_rate,
+            channels: track.channels,
+            bitrate: track.bitrate,
+        }
+    }
+}
+
+// MP4/M4A duration: find moov/mvhd or mdhd for audio track
+pub(super) fn parse_mp4<R: Read + Seek + ?Sized>(r: &mut R) -> Option<Probe> {
+    let (seconds, moov) = parse_movie(r)?;
+    Some(moov.probe(seconds))
+}
+
+/// The movie's duration in seconds and what its `moov` holds.
+pub(crate) fn parse_movie<R: Read + Seek + ?Sized>(r: &mut R) -> Option<(f64, Moov)> {
+    seek_to(r, 0)?;
+    // Top-level box scan
+    let mut moov: Option<Moov> = None;
+    let mut sidx: Option<Option<f64>> = None;
+    while let Some(b) = next_box(r, None) {
+        match &b.typ {
+            b"moov" if moov.is_none() => {
+                let m = parse_moov(r, &b);
+                // If moov-derived duration looks implausibly small (e.g., fragmented case),
+                // a sidx-derived duration is preferred, so keep walking for one.
+                if let Some(s) = m.seconds.filter(|&s| s > 1.0) {
+                    return Some((s, m));
+                }
+                moov = Some(m);
+                if sidx.is_some() {
+                    break;
+                }
+            }
+            b"sidx" if sidx.is_none() => {
+                sidx = Some(parse_sidx_total_duration(r, &b));
+                if moov.is_some() {
+                    break;
+                }
+            }
+            _ => {}
+        }
+        if !skip_box(r, &b) {
+            break;
+        }
+    }
+    if let Some(m) = moov {
+        let s_sidx = match sidx {
+            Some(s_sidx) => s_sidx,
+            None => find_box_anywhere(r, 0, b"sidx").and_then(|b| parse_sidx_total_duration(r, &b)),
+        };
+        let seconds = s_sidx.or(m.seconds)?;
+        return Some((seconds, m));
+    }
+
+    // Fallback: scan for a 'moov' box anywhere in the file
+    if let Some(b) = find_box_anywhere(r, 0, b"moov") {
+        let m = parse_moov(r, &b);
+        if let Some(s) = m.seconds {
+            return Some((s, m));
+        }
+    }
+    // Final fallback: single sidx box total duration
+    let b = find_box_anywhere(r, 0, b"sidx")?;
+    let seconds = parse_sidx_total_duration(r, &b)?;
+    Some((seconds, Moov::default()))
+}
+
+pub(super) fn parse_moov<R: Read + Seek + ?Sized>(r: &mut R, moov: &BoxHeader) -> Moov {
+    let mut mvhd: Option<Vec<u8>> = None;
+    let mut mehd: Option<Vec<u8>> = None;
+    let mut audio: Option<AudioTrack> = None;
+    let mut video: Option<VideoTrack> = None;
+    if seek_to(r, moov.body).is_none() {
+        return Moov::default();
+    }
+    while let Some(b) = next_box(r, moov.end) {
+        match &b.typ {
+            b"mvhd" if mvhd.is_none() => mvhd = read_body(r, &b, FULL_BOX_PREFIX),
+            b"mvex" if mehd.is_none() => {
+                mehd = child_box(r, &b, b"mehd").and_then(|c| read_body(r, &c, FULL_BOX_PREFIX));
+            }
+            b"trak" if audio.is_none() || video.is_none() => match parse_trak(r, &b) {
+                Some(Track::Audio(t)) if audio.is_none() => audio = Some(t),
+                Some(Track::Video(t)) if video.is_none() => video = Some(t),
+                _ => {}
+            },
+            _ => {}
+        }
+        if !skip_box(r, &b) {
+            break;
+        }
+    }
+
+    let seconds = moov_seconds(mvhd.as_deref(), mehd.as_deref())
+        .or_else(|| audio.as_ref().map(|t| t.seconds));
+    Moov {
+        seconds,
+        audio,
+        video,
+    }
+}
+
+fn moov_seconds(mvhd: Option<&[u8]>, mehd: Option<&[u8]>) -> Option<f64> {
+    let body = mvhd?;
+    if let Some(s) = parse_mvhd(body) {
+        return Some(s);
+    }
+    // If fragmented MP4: mvex/mehd may carry total fragment duration in movie timescale
+    let ts = parse_mvhd_timescale(body)?;
+    let frags = parse_mehd_duration(mehd?)?;
+    if ts > 0 && frags > 0 {
+        return Some(frags as f64 / ts as f64);
+    }
+    None
+}
+
+fn parse_trak<R: Read + Seek + ?Sized>(r: &mut R, trak: &BoxHeader) -> Option<Track> {
+    let mut tkhd: Option<Vec<u8>> = None;
+    seek_to(r, trak.body)?;
+    while let Some(b) = next_box(r, trak.end) {
+        match &b.typ {
+            // tkhd precedes mdia, and mdia is the last box needed.
+            b"tkhd" => tkhd = read_body(r, &b, TKHD_BYTES),
+            b"mdia" => return parse_mdia(r, &b, tkhd.as_deref()),
+            _ => {}
+        }
+        if !skip_box(r, &b) {
+            break;
+        }
+    }
+    None
+}
+
+fn parse_mdia<R: Read + Seek + ?Sized>(
+    r: &mut R,
+    mdia: &BoxHeader,
+    tkhd: Option<&[u8]>,
+) -> Option<Track> {
+    let mut handler: Option<[u8; 4]> = None;
+    let mut mdhd_dur: Option<f64> = None;
+    let mut entry: Option<Vec<u8>> = None;
+    seek_to(r, mdia.body)?;
+    while let Some(b) = next_box(r, mdia.end) {
+        if &b.typ == b"hdlr" {
+            let body = read_body(r, &b, 12)?;
+            // handler_type at offset 8
+            if body.len() >= 12 {
+                handler = Some([body[8], body[9], body[10], body[11]]);
+            }
+        } else if &b.typ == b"mdhd" {
+            if let Some(s) = parse_mdhd(&read_body(r, &b, FULL_BOX_PREFIX)?) {
+                mdhd_dur = Some(s);
+            }
+        } else if &b.typ == b"minf" {
+            entry = read_sample_entry(r, &b);
+        }
+        if !skip_box(r, &b) {
+            break;
+        }
+    }
+    match &handler? {
+        b"soun" => {
+            let mut track = AudioTrack::default();
+            if let Some(entry) = &entry {
+                parse_audio_sample_entry(entry, &mut track);
+            }
+            track.seconds = mdhd_dur?;
+            Some(Track::Audio(track))
+        }
+        b"vide" => {
+            let mut track = VideoTrack::default();
+            if let Some(entry) = &entry {
+                parse_video_sample_entry(entry, &mut track);
+            }
+            // Prefer the displayed size over the coded one.
+            if let Some((width, height)) = tkhd.and_then(parse_tkhd_size) {
+                track.width = Some(width);
+                track.height = Some(height);
+            }
+            Some(Track::Video(track))
+        }
+        _ => None,
+    }
+}
+
+/// Bytes of the first sample description read; enough for the audio sample entry and a
+/// following `esds`.
+const SAMPLE_ENTRY_PREFIX: u64 = 256;
+
+/// The start of `minf/stbl/stsd`'s first sample entry (from its box header on), if it is
+/// long enough for an audio or video sample entry.
+fn read_sample_entry<R: Read + Seek + ?Sized>(r: &mut R, minf: &BoxHeader) -> Option<Vec<u8>> {
+    let stbl = child_box(r, minf, b"stbl")?;
+    let stsd = child_box(r, &stbl, b"stsd")?;
+    let body = read_body(r, &stsd, SAMPLE_ENTRY_PREFIX)?;
+    // version/flags, entry_count, then the first entry box
+    if body.len() < 8 + 8 + 28 {
+        return None;
+    }
+    Some(body[8..].to_vec())
+}
+
+/// Codec, channels and sample rate from an audio sample entry.
+fn parse_audio_sample_entry(entry: &[u8], track: &mut AudioTrack) {
+    let format = [entry[4], entry[5], entry[6], entry[7]];
+    // AudioSampleEntry: 6 reserved + data_reference_index, 8 reserved, then
+    // channelcount(2), samplesize(2), pre_defined(2), reserved(2), samplerate(16.16)
+    let fields = &entry[8..];
+    track.channels = Some(u16::from_be_bytes([fields[16], fields[17]]));
+    track.sample_rate = Some(u16::from_be_bytes([fields[24], fields[25]]) as u32);
+    track.codec = codec_for_sample_entry(&format);
+    // 3GPP leaves channelcount unused for AMR, which is always mono.
+    if matches!(track.codec, Some("amr_nb") | Some("amr_wb")) {
+        track.channels = Some(1);
+    }
+    if &format == b"mp4a" {
+        if let Some(at) = memmem(&fields[28..], b"esds") {
+            if let Some((object_type, bitrate)) = parse_esds(&fields[28 + at + 4..]) {
+                if object_type == 0x69 || object_type == 0x6B {
+                    track.codec = Some("mp3");
+                }
+                track.bitrate = Some(bitrate).filter(|&b| b > 0);
+            }
+        }
+    }
+}
+
+/// Codec and coded size from a visual sample entry.
+fn parse_video_sample_entry(entry: &[u8], track: &mut VideoTrack) {
+    let format = [entry[4], entry[5], entry[6], entry[7]];
+    // VisualSampleEntry: 6 reserved + data_reference_index, pre_defined(2), reserved(2),
+    // pre_defined(12), then width(2), height(2)
+    let fields = &entry[8..];
+    track.width = Some(u16::from_be_bytes([fields[24], fields[25]]) as u32).filter(|&w| w > 0);
+    track.height = Some(u16::from_be_bytes([fields[26], fields[27]]) as u32).filter(|&h| h > 0);
+    track.codec = codec_for_video_entry(&format);
+}
+
+/// Displayed width and height from a `tkhd` payload: the 16.16 track size, swapped when the
+/// matrix rotates by 90 or 270 degrees (as in portrait phone recordings).
+fn parse_tkhd_size(body: &[u8]) -> Option<(u32, u32)> {
+    // Matrix after the version-dependent times, track id, duration and 16 bytes of
+    // layer/alternate group/volume/reserved; width and height follow it.
+    let matrix_at = if *body.first()? == 1 { 52 } else { 40 };
+    let m = body.get(matrix_at..matrix_at + 44)?;
+    let word = |at: usize| [m[at], m[at + 1], m[at + 2], m[at + 3]];
+    let (a, b) = (i32::from_be_bytes(word(0)), i32::from_be_bytes(word(4)));
+    let width = u32::from_be_bytes(word(36)) >> 16;
+    let height = u32::from_be_bytes(word(40)) >> 16;
+    if width == 0 || height == 0 {
+        return None;
+    }
+    // a = cos, b = sin of the rotation
+    if a == 0 && b != 0 {
+        Some((height, width))
+    } else {
+        Some((width, height))
+    }
+}
+
+/// The first `typ` child of `parent`.
+pub(crate) fn child_box<R: Read + Seek + ?Sized>(
+    r: &mut R,
+    parent: &BoxHeader,
+    typ: &[u8; 4],
+) -> Option<BoxHeader> {
+    seek_to(r, parent.body)?;
+    while let Some(c) = next_box(r, parent.end) {
+        if &c.typ == typ {
+            return Some(c);
+        }
+        if !skip_box(r, &c) {
+            break;
+        }
+    }
+    None
+}
+
+fn codec_for_sample_entry(format: &[u8; 4]) -> Option<&'static str> {
+    Some(match format {
+        b"mp4a" => "aac",
+        b"samr" => "amr_nb",
+        b"sawb" => "amr_wb",
+        b"Opus" => "opus",
+        b"fLaC" => "flac",
+        b"alac" => "alac",
+        b".mp3" => "mp3",
+        b"ac-3" => "ac3",
+        b"ec-3" => "eac3",
+        b"lpcm" | b"sowt" | b"twos" | b"ipcm" | b"raw " => "pcm",
+        b"ulaw" => "pcm_mulaw",
+        b"alaw" => "pcm_alaw",
+        _ => return None,
+    })
+}
+
+fn codec_for_video_entry(format: &[u8; 4]) -> Option<&'static str> {
+    Some(match format {
+        b"avc1" | b"avc3" => "h264",
+        b"hvc1" | b"hev1" | b"dvh1" | b"dvhe" => "hevc",
+        b"mp4v" => "mpeg4",
+        b"s263" | b"h263" => "h263",
+        b"vp08" => "vp8",
+        b"vp09" => "vp9",
+        b"av01" => "av1",
+        b"jpeg" | b"mjpa" | b"mjpb" => "mjpeg",
+        b"apch" | b"apcn" | b"apcs" | b"apco" | b"ap4h" | b"ap4x" => "prores",
+        _ => return None,
+    })
+}
+
+/// Object type and average bitrate from an `esds` payload (after the box header).
+fn parse_esds(esds: &[u8]) -> Option<(u8, u32)> {
+    // version/flags, then ES_Descriptor (tag 3) containing DecoderConfigDescriptor (tag 4)
+    let mut pos = 4;
+    let (tag, len_end) = descriptor_header(esds, pos)?;
+    if tag != 0x03 {
+        return None;
+    }
+    pos = len_end;
+    let flags = *esds.get(pos + 2)?;
+    pos += 3;
+    if flags & 0x80 != 0 {
+        pos += 2; // dependsOn_ES_ID
+    }
+    if flags & 0x40 != 0 {
+        pos += 1 + *esds.get(pos)? as usize; // URL
+    }
+    if flags & 0x20 != 0 {
+        pos += 2; // OCR_ES_Id
+    }
+    let (tag, body) = descriptor_header(esds, pos)?;
+    if tag != 0x04 {
+        return None;
+    }
+    // objectTypeIndication(1), streamType(1), bufferSizeDB(3), maxBitrate(4), avgBitrate(4)
+    let d = esds.get(body..body + 13)?;
+    Some((d[0], u32::from_be_bytes([d[9], d[10], d[11], d[12]])))
+}
+
+/// Tag of the descriptor at `pos` and where its payload starts.
+fn descriptor_header(buf: &[u8], pos: usize) -> Option<(u8, usize)> {
+    let tag = *buf.get(pos)?;
+    let mut at = pos + 1;
+    // Size: up to 4 bytes, 7 bits each, high bit = more follows
+    for _ in 0..4 {
+        let b = *buf.get(at)?;
+        at += 1;
+        if b & 0x80 == 0 {
+            return Some((tag, at));
+        }
+    }
+    None
+}
+
+fn memmem(hay: &[u8], needle: &[u8]) -> Option<usize> {
+    hay.windows(needle.len()).position(|w| w == needle)
+}
+
+/// Timescale and duration of an `mvhd` or `mdhd` payload (same layout up to the duration).
+fn parse_header_times(body: &[u8]) -> Option<(u32, u64)> {
+    if body.len() < 20 {
+        return None;
+    }
+    let version = body[0];
+    if version == 1 {
+        if body.len() < 32 {
+            return None;
+        }
+        let timescale = u32::from_be_bytes([body[20], body[21], body[22], body[23]]);
+        let duration = u64::from_be_bytes([
+            body[24], body[25], body[26], body[27], body[28], body[29], body[30], body[31],
+        ]);
+        Some((timescale, duration))
+    } else {
+        let timescale = u32::from_be_bytes([body[12], body[13], body[14], body[15]]);
+        let duration = u32::from_be_bytes([body[16], body[17], body[18], body[19]]) as u64;
+        Some((timescale, duration))
+    }
+}
+
+fn parse_mvhd(body: &[u8]) -> Option<f64> {
+    let (timescale, duration) = parse_header_times(body)?;
+    if timescale == 0 {
+        return None;
+    }
+    Some(duration as f64 / timescale as f64)
+}
+
+fn parse_mdhd(body: &[u8]) -> Option<f64> {
+    parse_mvhd(body)
+}
+
+fn parse_mvhd_timescale(body: &[u8]) -> Option<u32> {
+    if body.len() < 16 {
+        return None;
+    }
+    let version = body[0];
+    if version == 1 {
+        if body.len() < 24 {
+            return None;
+        }
+        Some(u32::from_be_bytes([body[20], body[21], body[22], body[23]]))
+    } else {
+        Some(u32::from_be_bytes([body[12], body[13], body[14], body[15]]))
+    }
+}
+
+fn parse_mehd_duration(body: &[u8]) -> Option<u64> {
+    if body.len() < 8 {
+        return None;
+    }
+    let version = body[0];
+    if version == 1 {
+        if body.len() < 12 {
+            return None;
+        }
+        Some(u64::from_be_bytes([
+            body[4], body[5], body[6], body[7], body[8], body[9], body[10], body[11],
+        ]))
+    } else {
+        Some(u32::from_be_bytes([body[4], body[5], body[6], body[7]]) as u64)
+    }
+}
+
+/// Find a box by 4cc type anywhere after `from` by scanning for the type marker and reading
+/// the size immediately before it. The reader is left at the box payload.
+pub(super) fn find_box_anywhere<R: Read + Seek + ?Sized>(
+    r: &mut R,
+    from: u64,
+    typ: &[u8; 4],
+) -> Option<BoxHeader> {
+    // The size field precedes the type, so the box can't start before `from`.
+    seek_to(r, from + 4)?;
+    let at = find_forward(r, 4, |w| w == typ)?;
+    seek_to(r, at - 4)?;
+    next_box(r, None)
+}
+
+// Parse duration from a sidx box: sum of subsegment_durations / timescale.
+fn parse_sidx_total_duration<R: Read + Seek + ?Sized>(r: &mut R, sidx: &BoxHeader) -> Option<f64> {
+    seek_to(r, sidx.body)?;
+    let mut left = sidx.len().unwrap_or(u64::MAX);
+    let mut take = |n: u64| -> Option<()> {
+        left = left.checked_sub(n)?;
+        Some(())
+    };
+    take(12)?;
+    let head = read_array::<12, _>(r)?;
+    let version = head[0];
+    // flags = head[1..4]
+    let timescale = u32::from_be_bytes([head[8], head[9], head[10], head[11]]);
+    if timescale == 0 {
+        return None;
+    }
+    // earliest_presentation_time & first_offset depend on version, then 2 reserved bytes
+    let skip = if version == 0 { 8 } else { 16 } + 2;
+    take(skip)?;
+    seek_to(r, sidx.body + 12 + skip)?;
+    take(2)?;
+    let ref_count = u16::from_be_bytes(read_array::<2, _>(r)?);
+    let mut total: u64 = 0;
+    for _ in 0..ref_count {
+        take(12)?;
+        let entry = read_array::<12, _>(r)?;
+        let ref_type_size = u32::from_be_bytes([entry[0], entry[1], entry[2], entry[3]]);
+        let sub_dur = u32::from_be_bytes([entry[4], entry[5], entry[6], entry[7]]) as u64;
+        // Only add if reference_type==0 (media data)
+        if (ref_type_size >> 31) == 0 {
+            total = total.saturating_add(sub_dur);
+        }
+    }
+    if total == 0 {
+        return None;
+    }
+    Some(total as f64 / timescale as f64)
+}
@@ -0,0 +1,192 @@
+//! Ogg Opus and Vorbis: final granule position over the stream's sample rate (48 kHz minus
+//! the `OpusHead` pre-skip for Opus, the identification header's rate for Vorbis).
+//!
+//! The codec header comes from the first page. When the reader can seek from the end, only the
+//! tail of the file is read to find the last granule; otherwise page headers are walked
+//! and payloads skipped.
+
+use std::io::{Read, Seek, SeekFrom};
+
+use super::reader::{find_forward, read_array, read_up_to, seek_to};
+use super::Probe;
+
+/// Bytes read from the end of the file when looking for the last page.
+const TAIL_BYTES: u64 = 64 * 1024;
+
+const EOS: u8 = 0x04;
+const BOS: u8 = 0x02;
+
+pub(super) fn is_ogg(head: &[u8]) -> bool {
+    head.len() >= 4 && &head[0..4] == b"OggS"
+}
+
+/// Fixed part of an Ogg page header.
+struct PageHeader {
+    header_type: u8,
+    granule: u64,
+    page_segments: usize,
+}
+
+impl PageHeader {
+    fn parse(h: &[u8]) -> Option<PageHeader> {
+        if h.len() < 27 || !is_ogg(h) || h[4] != 0 {
+            return None;
+        }
+        Some(PageHeader {
+            header_type: h[5],
+            granule: u64::from_le_bytes([h[6], h[7], h[8], h[9], h[10], h[11], h[12], h[13]]),
+            page_segments: h[26] as usize,
+        })
+    }
+}
+
+/// Read the page at the current position; returns its header and payload length, leaving
+/// the reader at the payload.
+fn read_page<R: Read + Seek + ?Sized>(r: &mut R) -> Option<(PageHeader, u64)> {
+    let page = PageHeader::parse(&read_array::<27, _>(r)?)?;
+    let segments = read_up_to(r, page.page_segments as u64)?;
+    if segments.len() < page.page_segments {
+        return None;
+    }
+    let payload_len = segments.iter().map(|&b| b as u64).sum();
+    Some((page, payload_len))
+}
+
+/// Codec details and granule timebase of the stream, from its first packet.
+#[derive(Clone, Copy, Debug)]
+struct Stream {
+    codec: Option<&'static str>,
+    rate: u32,
+    pre_skip: u16,
+    channels: Option<u16>,
+    bitrate: Option<u32>,
+}
+
+/// Bytes of the first packet needed to read an `OpusHead` or Vorbis identification header.
+const ID_HEADER_BYTES: u64 = 30;
+
+impl Stream {
+    fn from_first_packet(packet: &[u8]) -> Option<Stream> {
+        if packet.len() >= 19 && &packet[0..8] == b"OpusHead" {
+            // pre-skip at bytes 10..12 (little endian); granules always count 48 kHz samples
+            let pre_skip = u16::from_le_bytes([packet[10], packet[11]]);
+            return Some(Stream {
+                codec: Some("opus"),
+                rate: 48000,
+                pre_skip,
+                channels: Some(packet[9] as u16),
+                bitrate: None,
+            });
+        }
+        if packet.len() >= 16 && &packet[0..7] == b"\x01vorbis" {
+            let rate = u32::from_le_bytes([packet[12], packet[13], packet[14], packet[15]]);
+            let nominal = packet
+                .get(20..24)
+                .map(|b| i32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+                .filter(|&b| b > 0);
+            return Some(Stream {
+                codec: Some("vorbis"),
+                rate,
+                pre_skip: 0,
+                channels: Some(packet[11] as u16),
+                bitrate: nominal.map(|b| b as u32),
+            });
+        }
+        None
+    }
+
+    fn probe(self, granule: u64) -> Option<Probe> {
+        let samples = granule.checked_sub(self.pre_skip as u64)?;
+        if self.rate == 0 {
+            return None;
+        }
+        Some(Probe {
+            seconds: samples as f64 / self.rate as f64,
+            codec: self.codec,
+            sample_rate: Some(self.rate),
+            channels: self.channels,
+            bitrate: self.bitrate,
+        })
+    }
+}
+
+// Ogg duration: final granule (- pre-skip) / sample rate
+pub(super) fn parse_ogg<R: Read + Seek + ?Sized>(r: &mut R) -> Option<Probe> {
+    seek_to(r, 0)?;
+    let (first, payload_len) = read_page(r)?;
+    // Streams without a recognised codec header are timed like Opus, as before Vorbis
+    // support.
+    let mut stream = Stream {
+        codec: None,
+        rate: 48000,
+        pre_skip: 0,
+        channels: None,
+        bitrate: None,
+    };
+    if (first.header_type & BOS) != 0 {
+        let packet = read_up_to(r, payload_len.min(ID_HEADER_BYTES))?;
+        if let Some(s) = Stream::from_first_packet(&packet) {
+            stream = s;
+        }
+    }
+    if (first.header_type & EOS) != 0 {
+        return stream.probe(first.granule);
+    }
+
+    if let Ok(len) = r.seek(SeekFrom::End(0)) {
+        if let Some(granule) = last_granule_in_tail(r, len) {
+            return stream.probe(granule);
+        }
+    }
+    walk_pages(r, 27 + first.page_segments as u64 + payload_len, stream)
+}
+
+/// Granule of the last EOS page (or else the last page) starting in the file's tail.
+fn last_granule_in_tail<R: Read + Seek + ?Sized>(r: &mut R, len: u64) -> Option<u64> {
+    let start = len.saturating_sub(TAIL_BYTES);
+    seek_to(r, start)?;
+    let tail = read_up_to(r, TAIL_BYTES)?;
+    let mut last = None;
+    let mut last_eos = None;
+    let mut at = 0;
+    while let Some(off) = memmem(&tail[at..], b"OggS") {
+        let page_at = at + off;
+        // A granule of -1 marks a page on which no packet ends.
+        if let Some(page) = PageHeader::parse(&tail[page_at..]).filter(|p| p.granule != u64::MAX) {
+            last = Some(page.granule);
+            if (page.header_type & EOS) != 0 {
+                last_eos = Some(page.granule);
+            }
+        }
+        at = page_at + 4;
+    }
+    last_eos.or(last)
+}
+
+/// Walk pages from `pos` to the first EOS page (or end of stream).
+fn walk_pages<R: Read + Seek + ?Sized>(r: &mut R, mut pos: u64, stream: Stream) -> Option<Probe> {
+    let mut last_granule: Option<u64> = None;
+    loop {
+        seek_to(r, pos)?;
+        // Small resync: scan for next 'OggS' if alignment lost
+        match find_forward(r, 4, is_ogg) {
+            Some(at) => pos = at,
+            None => break,
+        }
+        let Some((page, payload_len)) = read_page(r) else {
+            break;
+        };
+        if (page.header_type & EOS) != 0 {
+            return stream.probe(page.granule);
+        }
+        // Track granule in case EOS not flagged (shouldn't happen)
+        last_granule = Some(page.granule);
+        pos += 27 + page.page_segments as u64 + payload_len;
+    }
+    // If we exited without EOS but have granule, compute anyway
+    stream.probe(last_granule?)
+}
+
+fn memmem(hay: &[u8], needle: &[u8]) -> Option<usize> {
+    hay.windows(needle.len()).position(|w| w == needle)
+}
@@ -0,0 +1,215 @@
+//! Byte-level reading helpers shared by the format parsers.
+//!
+//! Parsers take `Read + Seek` and only ever hold a few header bytes (or a bounded window)
+//! in memory. [`ForwardSeek`] adapts forward-only streams such as compressed ZIP entries.
+
+use std::io::{self, Read, Seek, SeekFrom};
+
+/// Bytes of the stream start that [`ForwardSeek`] keeps so sniffing can rewind.
+const HEAD_BYTES: usize = 64 * 1024;
+
+/// Most recently read bytes [`ForwardSeek`] keeps for short rewinds (resyncs, header
+/// re-reads); must cover a [`find_forward`] window.
+const LOOKBACK_BYTES: usize = 16 * 1024;
+
+/// Window size used by [`find_forward`].
+const SCAN_CHUNK: usize = 4096;
+
+/// `Read + Seek` over a forward-only stream (e.g. a deflated ZIP entry).
+///
+/// The first [`HEAD_BYTES`] and the last [`LOOKBACK_BYTES`] read are kept, so positions
+/// inside them can be revisited; seeking further forward skips (decompresses and discards)
+/// bytes, and seeking further back fails with [`io::ErrorKind::Unsupported`]. Seeking
+/// relative to the end is not supported either.
+pub struct ForwardSeek<R> {
+    inner: R,
+    head: Vec<u8>,
+    /// Bytes read from `inner` just before `inner_pos`.
+    recent: Vec<u8>,
+    /// Logical position of the reader.
+    pos: u64,
+    /// Position of `inner`, never below `head.len()`.
+    inner_pos: u64,
+}
+
+impl<R: Read> ForwardSeek<R> {
+    pub fn new(mut inner: R) -> io::Result<Self> {
+        let mut head = Vec::new();
+        (&mut inner)
+            .take(HEAD_BYTES as u64)
+            .read_to_end(&mut head)?;
+        let inner_pos = head.len() as u64;
+        Ok(Self {
+            inner,
+            head,
+            recent: Vec::new(),
+            pos: 0,
+            inner_pos,
+        })
+    }
+
+    fn recent_start(&self) -> u64 {
+        self.inner_pos - self.recent.len() as u64
+    }
+
+    fn rewind_error() -> io::Error {
+        io::Error::new(
+            io::ErrorKind::Unsupported,
+            "cannot rewind a forward-only stream that far",
+        )
+    }
+}
+
+impl<R: Read> Read for ForwardSeek<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        if let Some(rest) = self.head.get(self.pos as usize..).filter(|r| !r.is_empty()) {
+            let n = rest.len().min(buf.len());
+            buf[..n].copy_from_slice(&rest[..n]);
+            self.pos += n as u64;
+            return Ok(n);
+        }
+        if self.pos < self.inner_pos {
+            if self.pos < self.recent_start() {
+                return Err(Self::rewind_error());
+            }
+            let rest = &self.recent[(self.pos - self.recent_start()) as usize..];
+            let n = rest.len().min(buf.len());
+            buf[..n].copy_from_slice(&rest[..n]);
+            self.pos += n as u64;
+            return Ok(n);
+        }
+        if self.pos > self.inner_pos {
+            let gap = self.pos - self.inner_pos;
+            let skipped = io::copy(&mut (&mut self.inner).take(gap), &mut io::sink())?;
+            self.inner_pos += skipped;
+            self.recent.clear();
+            if skipped < gap {
+                return Ok(0);
+            }
+        }
+        let n = self.inner.read(buf)?;
+        self.inner_pos += n as u64;
+        self.pos += n as u64;
+        self.recent.extend_from_slice(&buf[..n]);
+        if self.recent.len() > 2 * LOOKBACK_BYTES {
+            let excess = self.recent.len() - LOOKBACK_BYTES;
+            self.recent.drain(..excess);
+        }
+        Ok(n)
+    }
+}
+
+impl<R: Read> Seek for ForwardSeek<R> {
+    fn seek(&mut self, to: SeekFrom) -> io::Result<u64> {
+        let target = match to {
+            SeekFrom::Start(p) => Some(p),
+            SeekFrom::Current(d) => self.pos.checked_add_signed(d),
+            SeekFrom::End(_) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::Unsupported,
+                    "forward-only stream has no known end",
+                ))
+            }
+        };
+        let target = target.ok_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidInput, "seek before start of stream")
+        })?;
+        if target >= self.head.len() as u64 && target < self.recent_start() {
+            return Err(Self::rewind_error());
+        }
+        // Forward gaps are skipped lazily on the next read.
+        self.pos = target;
+        Ok(target)
+    }
+}
+
+pub(crate) fn read_array<const N: usize, R: Read + ?Sized>(r: &mut R) -> Option<[u8; N]> {
+    let mut buf = [0u8; N];
+    r.read_exact(&mut buf).ok()?;
+    Some(buf)
+}
+
+/// Read up to `max` bytes (fewer at end of stream).
+pub(crate) fn read_up_to<R: Read + ?Sized>(r: &mut R, max: u64) -> Option<Vec<u8>> {
+    let mut buf = Vec::new();
+    r.take(max).read_to_end(&mut buf).ok()?;
+    Some(buf)
+}
+
+pub(crate) fn position<R: Seek + ?Sized>(r: &mut R) -> Option<u64> {
+    r.stream_position().ok()
+}
+
+pub(crate) fn seek_to<R: Seek + ?Sized>(r: &mut R, pos: u64) -> Option<()> {
+    r.seek(SeekFrom::Start(pos)).ok().map(|_| ())
+}
+
+/// Whether the byte just before `end` exists, i.e. the stream is at least `end` bytes long.
+/// Leaves the reader positioned at `end`.
+pub(super) fn reaches<R: Read + Seek + ?Sized>(r: &mut R, end: u64) -> bool {
+    end > 0 && seek_to(r, end - 1).is_some() && read_array::<1, _>(r).is_some()
+}
+
+/// Scan forward from the current position for the first offset where `matches` accepts the
+/// next `width` bytes, reading a small window at a time. On success the reader is left at the
+/// match and its position is returned.
+pub(super) fn find_forward<R: Read + Seek + ?Sized>(
+    r: &mut R,
+    width: usize,
+    matches: impl Fn(&[u8]) -> bool,
+) -> Option<u64> {
+    let mut base = position(r)?;
+    let mut window = Vec::with_capacity(SCAN_CHUNK + width);
+    loop {
+        let got = read_up_to(r, (SCAN_CHUNK + width - window.len()) as u64)?;
+        let eof = got.is_empty();
+        window.extend_from_slice(&got);
+        if window.len() < width {
+            return None;
+        }
+        if let Some(i) = (0..=window.len() - width).find(|&i| matches(&window[i..i + width])) {
+            let found = base + i as u64;
+            seek_to(r, found)?;
+            return Some(found);
+        }
+        if eof {
+            return None;
+        }
+        // Keep the last `width - 1` bytes so matches across windows are not missed.
+        let keep = width - 1;
+        let drop = window.len() - keep;
+        window.drain(..drop);
+        base += drop as u64;
+    }
+}
+
+pub(super) fn synchsafe_to_u32(b: &[u8]) -> u32 {
+    ((b[0] as u32 & 0x7F) << 21)
+        | ((b[1] as u32 & 0x7F) << 14)
+        | ((b[2] as u32 & 0x7F) << 7)
+        | (b[3] as u32 & 0x7F)
+}
+
+/// Offset just past an ID3v2 tag at the start of the stream, 0 if there is none.
+pub(super) fn skip_id3v2<R: Read + Seek + ?Sized>(r: &mut R) -> Option<u64> {
+    seek_to(r, 0)?;
+    let Some(h) = read_array::<10, _>(r) else {
+        return Some(0);
+    };
+    if &h[0..3] != b"ID3" {
+        return Some(0);
+    }
+    let mut skip = 10 + synchsafe_to_u32(&h[6..10]) as u64;
+    if (h[5] & 0x10) != 0 {
+        // footer present
+        skip += 10;
+    }
+    // The tag must be followed by at least one byte of audio.
+    if !reaches(r, skip + 1) {
+        return None;
+    }
+    Some(skip)
+}
@@ -0,0 +1,78 @@
+//! WAV (RIFF/WAVE): `fmt ` and `data` chunk headers; sample data is skipped.
+
+use std::io::{Read, Seek};
+
+use super::reader::{position, reaches, read_array, read_up_to, seek_to};
+use super::Probe;
+
+pub(super) fn is_wav(head: &[u8]) -> bool {
+    head.len() >= 12 && &head[0..4] == b"RIFF" && &head[8..12] == b"WAVE"
+}
+
+pub(super) fn parse_wav<R: Read + Seek + ?Sized>(r: &mut R) -> Option<Probe> {
+    seek_to(r, 0)?;
+    let header = read_array::<12, _>(r)?;
+    if !is_wav(&header) {
+        return None;
+    }
+
+    let mut sample_rate: Option<u32> = None;
+    let mut block_align: Option<u16> = None;
+    let mut codec = "pcm";
+    let mut channels: u16 = 0;
+    let mut byte_rate: u32 = 0;
+    let mut data_size: Option<u32> = None;
+
+    while let Some(chunk) = read_array::<8, _>(r) {
+        let id = &chunk[0..4];
+        let size = u32::from_le_bytes([chunk[4], chunk[5], chunk[6], chunk[7]]);
+        let body = position(r)?;
+        match id {
+            b"fmt " if size >= 16 => {
+                let fmt = read_up_to(r, 16)?;
+                if fmt.len() < 16 {
+                    break;
+                }
+                let audio_format = u16::from_le_bytes([fmt[0], fmt[1]]);
+                let sr = u32::from_le_bytes([fmt[4], fmt[5], fmt[6], fmt[7]]);
+                let ba = u16::from_le_bytes([fmt[12], fmt[13]]);
+                // We only support PCM/IEEE float via data-size method
+                if audio_format == 1 || audio_format == 3 {
+                    sample_rate = Some(sr);
+                    block_align = Some(ba);
+                    codec = if audio_format == 3 {
+                        "pcm_float"
+                    } else {
+                        "pcm"
+                    };
+                    channels = u16::from_le_bytes([fmt[2], fmt[3]]);
+                    byte_rate = u32::from_le_bytes([fmt[8], fmt[9], fmt[10], fmt[11]]);
+                }
+            }
+            b"data" => {
+                // A truncated data chunk doesn't tell the length.
+                if !reaches(r, body + size as u64) {
+                    break;
+                }
+                data_size = Some(size);
+            }
+            _ => {}
+        }
+        // Chunks are word-aligned; sizes are even, but if odd, skip pad byte
+        let size = size as u64;
+        seek_to(r, body + size + (size & 1))?;
+    }
+
+    match (sample_rate, block_align, data_size) {
+        (Some(sr), Some(ba), Some(ds)) if sr > 0 && ba > 0 => {
+            let total_samples = (ds as u64) / (ba as u64);
+            Some(Probe {
+                sample_rate: Some(sr),
+                channels: Some(channels),
+                bitrate: byte_rate.checked_mul(8),
+                ..Probe::new(total_samples as f64 / sr as f64, codec)
+            })
+        }
+        _ => None,
+    }
+}
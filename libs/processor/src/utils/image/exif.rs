@@ -0,0 +1,212 @@
+//! EXIF (TIFF structure) fields used for images: `Orientation` from IFD0 and
+//! `DateTimeOriginal`/`OffsetTimeOriginal` from the Exif IFD.
+
+/// Upper bound on the EXIF block kept in memory; `APP1` segments can't be larger anyway.
+pub(super) const MAX_EXIF_BYTES: u64 = 64 * 1024;
+
+const ORIENTATION: u16 = 0x0112;
+const EXIF_IFD: u16 = 0x8769;
+const DATE_TIME_ORIGINAL: u16 = 0x9003;
+const OFFSET_TIME_ORIGINAL: u16 = 0x9011;
+
+const TYPE_ASCII: u16 = 2;
+const TYPE_SHORT: u16 = 3;
+const TYPE_LONG: u16 = 4;
+
+#[derive(Clone, Debug, Default)]
+pub(super) struct Exif {
+    pub taken_at: Option<String>,
+    /// Quarter turns the image is displayed rotated by (from `Orientation` 5 to 8).
+    pub quarter_turns: u8,
+}
+
+/// Parse a TIFF-structured EXIF block, optionally preceded by the `Exif\0\0` marker.
+pub(super) fn parse_exif(data: &[u8]) -> Exif {
+    let tiff = data.strip_prefix(b"Exif\0\0").unwrap_or(data);
+    parse_tiff(tiff).unwrap_or_default()
+}
+
+struct Tiff<'a> {
+    data: &'a [u8],
+    little_endian: bool,
+}
+
+/// One IFD entry: type, count and the 4-byte value/offset field.
+struct Entry {
+    typ: u16,
+    count: u32,
+    value: [u8; 4],
+}
+
+impl Tiff<'_> {
+    fn u16_at(&self, at: usize) -> Option<u16> {
+        let b = self.data.get(at..at + 2)?;
+        Some(if self.little_endian {
+            u16::from_le_bytes([b[0], b[1]])
+        } else {
+            u16::from_be_bytes([b[0], b[1]])
+        })
+    }
+
+    fn u32_at(&self, at: usize) -> Option<u32> {
+        let b = self.data.get(at..at + 4)?;
+        let b = [b[0], b[1], b[2], b[3]];
+        Some(if self.little_endian {
+            u32::from_le_bytes(b)
+        } else {
+            u32::from_be_bytes(b)
+        })
+    }
+
+    /// Entries of the IFD at `offset` whose tag is in `tags`.
+    fn entries(&self, offset: usize, tags: &[u16]) -> Vec<(u16, Entry)> {
+        let mut found = Vec::new();
+        let Some(count) = self.u16_at(offset) else {
+            return found;
+        };
+        for i in 0..count as usize {
+            let at = offset + 2 + i * 12;
+            let (Some(tag), Some(typ), Some(n), Some(v)) = (
+                self.u16_at(at),
+                self.u16_at(at + 2),
+                self.u32_at(at + 4),
+                self.data.get(at + 8..at + 12),
+            ) else {
+                break;
+            };
+            if tags.contains(&tag) {
+                found.push((
+                    tag,
+                    Entry {
+                        typ,
+                        count: n,
+                        value: [v[0], v[1], v[2], v[3]],
+                    },
+                ));
+            }
+        }
+        found
+    }
+
+    fn short(&self, e: &Entry) -> Option<u16> {
+        if e.typ != TYPE_SHORT || e.count < 1 {
+            return None;
+        }
+        Tiff {
+            data: &e.value,
+            little_endian: self.little_endian,
+        }
+        .u16_at(0)
+    }
+
+    fn long(&self, e: &Entry) -> Option<u32> {
+        match e.typ {
+            TYPE_LONG => Tiff {
+                data: &e.value,
+                little_endian: self.little_endian,
+            }
+            .u32_at(0),
+            _ => self.short(e).map(u32::from),
+        }
+    }
+
+    /// An ASCII value without its trailing NULs.
+    fn ascii(&self, e: &Entry) -> Option<String> {
+        if e.typ != TYPE_ASCII {
+            return None;
+        }
+        let len = e.count as usize;
+        let bytes = if len <= 4 {
+            &e.value[..len]
+        } else {
+            let at = self.long(&Entry {
+                typ: TYPE_LONG,
+                count: 1,
+                value: e.value,
+            })? as usize;
+            self.data.get(at..at + len)?
+        };
+        std::str::from_utf8(bytes)
+            .ok()
+            .map(|s| s.trim_end_matches('\0').to_string())
+    }
+}
+
+fn parse_tiff(data: &[u8]) -> Option<Exif> {
+    let little_endian = match data.get(0..2)? {
+        b"II" => true,
+        b"MM" => false,
+        _ => return None,
+    };
+    let tiff = Tiff {
+        data,
+        little_endian,
+    };
+    if tiff.u16_at(2)? != 42 {
+        return None;
+    }
+    let ifd0 = tiff.u32_at(4)? as usize;
+    let mut exif = Exif::default();
+    let mut exif_ifd = None;
+    for (tag, e) in tiff.entries(ifd0, &[ORIENTATION, EXIF_IFD]) {
+        match tag {
+            // 5-8: transposed, rotated 90 degrees clockwise, transversed, rotated 270
+            ORIENTATION if (5..=8).contains(&tiff.short(&e).unwrap_or(1)) => exif.quarter_turns = 1,
+            EXIF_IFD => exif_ifd = tiff.long(&e),
+            _ => {}
+        }
+    }
+    let mut date = None;
+    let mut offset = None;
+    for (tag, e) in tiff.entries(
+        exif_ifd? as usize,
+        &[DATE_TIME_ORIGINAL, OFFSET_TIME_ORIGINAL],
+    ) {
+        match tag {
+            DATE_TIME_ORIGINAL => date = tiff.ascii(&e).and_then(|d| format_date_time(&d)),
+            OFFSET_TIME_ORIGINAL => offset = tiff.ascii(&e).filter(|o| is_offset(o)),
+            _ => {}
+        }
+    }
+    exif.taken_at = date.map(|d| match offset {
+        Some(o) => format!("{}{}", d, o),
+        None => d,
+    });
+    Some(exif)
+}
+
+/// `YYYY:MM:DD HH:MM:SS` as `YYYY-MM-DDTHH:MM:SS`; `None` for blank or malformed values
+/// (cameras write spaces or zeros when the clock is unset).
+fn format_date_time(s: &str) -> Option<String> {
+    let b = s.as_bytes();
+    if b.len() < 19 {
+        return None;
+    }
+    let digits_ok = [0, 1, 2, 3, 5, 6, 8, 9, 11, 12, 14, 15, 17, 18]
+        .iter()
+        .all(|&i| b[i].is_ascii_digit());
+    let separators_ok =
+        b[4] == b':' && b[7] == b':' && b[10] == b' ' && b[13] == b':' && b[16] == b':';
+    if !digits_ok || !separators_ok || &s[0..4] == "0000" {
+        return None;
+    }
+    Some(format!(
+        "{}-{}-{}T{}",
+        &s[0..4],
+        &s[5..7],
+        &s[8..10],
+        &s[11..19]
+    ))
+}
+
+/// `+HH:MM` or `-HH:MM`.
+fn is_offset(s: &str) -> bool {
+    let b = s.as_bytes();
+    b.len() == 6
+        && (b[0] == b'+' || b[0] == b'-')
+        && b[1].is_ascii_digit()
+        && b[2].is_ascii_digit()
+        && b[3] == b':'
+        && b[4].is_ascii_digit()
+        && b[5].is_ascii_digit()
+}
@@ -0,0 +1,108 @@
+//! GIF: logical screen size, then every block is walked (sub-block data skipped) to count
+//! image frames and sum the delays of their graphic control extensions.
+
+use std::io::{Read, Seek};
+
+use super::ImageInfo;
+use crate::utils::audio::reader::{position, read_array, seek_to};
+
+const EXTENSION: u8 = 0x21;
+const IMAGE: u8 = 0x2C;
+const GRAPHIC_CONTROL: u8 = 0xF9;
+
+/// Delays under this many centiseconds are shown as [`DEFAULT_DELAY_CS`] by browsers.
+const MIN_DELAY_CS: u16 = 2;
+const DEFAULT_DELAY_CS: u16 = 10;
+
+pub(super) fn is_gif(head: &[u8]) -> bool {
+    head.starts_with(b"GIF87a") || head.starts_with(b"GIF89a")
+}
+
+/// Bytes of a color table with the size bits of `packed`, if its flag (high bit) is set.
+fn color_table_bytes(packed: u8) -> u64 {
+    if packed & 0x80 != 0 {
+        3 << ((packed & 0x07) + 1)
+    } else {
+        0
+    }
+}
+
+/// Skip a chain of data sub-blocks (length byte, data) up to the zero-length terminator.
+fn skip_sub_blocks<R: Read + Seek + ?Sized>(r: &mut R) -> Option<()> {
+    loop {
+        let [len] = read_array::<1, _>(r)?;
+        if len == 0 {
+            return Some(());
+        }
+        let at = position(r)?;
+        seek_to(r, at + len as u64)?;
+    }
+}
+
+pub(super) fn parse_gif<R: Read + Seek + ?Sized>(r: &mut R) -> Option<ImageInfo> {
+    seek_to(r, 6)?;
+    // Logical screen descriptor: width(2), height(2), packed(1), background(1), aspect(1)
+    let screen = read_array::<7, _>(r)?;
+    let mut info = ImageInfo::new("gif");
+    info.set_size(
+        u16::from_le_bytes([screen[0], screen[1]]) as u32,
+        u16::from_le_bytes([screen[2], screen[3]]) as u32,
+        0,
+    );
+    let mut at = position(r)? + color_table_bytes(screen[4]);
+    seek_to(r, at)?;
+
+    let mut frames: u32 = 0;
+    let mut duration_cs: i64 = 0;
+    // Delay from the graphic control extension applying to the next image.
+    let mut delay: Option<u16> = None;
+    // A truncated file keeps the frames counted so far.
+    while let Some([block]) = read_array::<1, _>(r) {
+        match block {
+            EXTENSION => {
+                let Some([label]) = read_array::<1, _>(r) else {
+                    break;
+                };
+                if label == GRAPHIC_CONTROL {
+                    // size(1) = 4, packed(1), delay(2), transparent index(1), terminator(1)
+                    let Some(gce) = read_array::<6, _>(r) else {
+                        break;
+                    };
+                    delay = Some(u16::from_le_bytes([gce[2], gce[3]]));
+                    if gce[5] != 0 && skip_sub_blocks(r).is_none() {
+                        break;
+                    }
+                } else if skip_sub_blocks(r).is_none() {
+                    break;
+                }
+            }
+            IMAGE => {
+                // left(2), top(2), width(2), height(2), packed(1), then the LZW code size
+                let Some(desc) = read_array::<9, _>(r) else {
+                    break;
+                };
+                at = position(r)? + color_table_bytes(desc[8]) + 1;
+                if seek_to(r, at).is_none() || skip_sub_blocks(r).is_none() {
+                    break;
+                }
+                frames += 1;
+                let cs = delay.take().unwrap_or(0);
+                duration_cs += if cs < MIN_DELAY_CS {
+                    DEFAULT_DELAY_CS
+                } else {
+                    cs
+                } as i64;
+            }
+            // The trailer (0x3B), or anything unexpected
+            _ => break,
+        }
+    }
+    if frames == 0 {
+        return None;
+    }
+    info.frame_count = Some(frames);
+    if frames > 1 {
+        info.duration_ms = Some(duration_cs * 10);
+    }
+    Some(info)
+}
@@ -0,0 +1,257 @@
+//! HEIC/HEIF/AVIF (ISO BMFF): `meta` names the primary item (`pitm`). Its size comes from
+//! the `ispe` property associated with it in `iprp/ipma`, turned by `irot`. EXIF comes from
+//! the first item of type `Exif`, located through `iloc`.
+
+use std::io::{Read, Seek};
+
+use super::exif::{parse_exif, MAX_EXIF_BYTES};
+use super::ImageInfo;
+use crate::utils::audio::mp4::{next_box, read_body, skip_box};
+use crate::utils::audio::reader::{read_up_to, seek_to};
+
+/// Upper bound on the `meta` box kept in memory; real ones take a few kilobytes.
+const MAX_META_BYTES: u64 = 1024 * 1024;
+
+/// Format named by the `ftyp` major brand, if it is a HEIF image brand.
+pub(super) fn heif_format(head: &[u8]) -> Option<&'static str> {
+    if head.len() < 12 || &head[4..8] != b"ftyp" {
+        return None;
+    }
+    match &head[8..12] {
+        b"heic" | b"heix" | b"heim" | b"heis" | b"hevc" | b"hevx" | b"hevm" | b"hevs" => {
+            Some("heic")
+        }
+        b"avif" | b"avis" => Some("avif"),
+        b"mif1" | b"msf1" => Some("heif"),
+        _ => None,
+    }
+}
+
+pub(super) fn parse_heif<R: Read + Seek + ?Sized>(
+    r: &mut R,
+    format: &'static str,
+) -> Option<ImageInfo> {
+    seek_to(r, 0)?;
+    let mut meta = None;
+    while let Some(b) = next_box(r, None) {
+        if &b.typ == b"meta" {
+            meta = read_body(r, &b, MAX_META_BYTES);
+            break;
+        }
+        if !skip_box(r, &b) {
+            break;
+        }
+    }
+    let meta = Meta::parse(meta.as_deref()?)?;
+
+    let mut info = ImageInfo::new(format);
+    if let Some((width, height)) = meta.primary_size {
+        info.set_size(width, height, meta.quarter_turns);
+    }
+    if let Some((offset, length)) = meta.exif {
+        if seek_to(r, offset).is_some() {
+            let data = read_up_to(r, length.min(MAX_EXIF_BYTES))?;
+            // exif_tiff_header_offset(4), then the block
+            if data.len() > 4 {
+                let skip = u32::from_be_bytes([data[0], data[1], data[2], data[3]]) as usize;
+                if let Some(tiff) = data.get(4 + skip..) {
+                    // The orientation is already in irot.
+                    info.taken_at = parse_exif(tiff).taken_at;
+                }
+            }
+        }
+    }
+    Some(info)
+}
+
+/// What the `meta` box says about the primary image and the EXIF item.
+#[derive(Default)]
+struct Meta {
+    primary_size: Option<(u32, u32)>,
+    quarter_turns: u8,
+    /// File offset and length of the EXIF item.
+    exif: Option<(u64, u64)>,
+}
+
+/// Child boxes of an in-memory box payload: `(type, payload)`.
+fn boxes(mut buf: &[u8]) -> impl Iterator<Item = ([u8; 4], &[u8])> {
+    std::iter::from_fn(move || {
+        if buf.len() < 8 {
+            return None;
+        }
+        let size = u32::from_be_bytes([buf[0], buf[1], buf[2], buf[3]]) as usize;
+        let typ = [buf[4], buf[5], buf[6], buf[7]];
+        let (header, total) = match size {
+            0 => (8, buf.len()),
+            1 => (16, be(buf.get(8..16)?) as usize),
+            n => (8, n),
+        };
+        if total < header || total > buf.len() {
+            return None;
+        }
+        let body = &buf[header..total];
+        buf = &buf[total..];
+        Some((typ, body))
+    })
+}
+
+/// Big-endian unsigned integer of 1 to 8 bytes (0 bytes read as 0).
+fn be(b: &[u8]) -> u64 {
+    b.iter().fold(0, |acc, &x| (acc << 8) | x as u64)
+}
+
+/// Cursor over a payload reading big-endian fields.
+struct Fields<'a> {
+    buf: &'a [u8],
+    at: usize,
+}
+
+impl Fields<'_> {
+    fn take(&mut self, n: usize) -> Option<u64> {
+        let v = be(self.buf.get(self.at..self.at + n)?);
+        self.at += n;
+        Some(v)
+    }
+}
+
+impl Meta {
+    fn parse(meta: &[u8]) -> Option<Meta> {
+        // Full box: version/flags first
+        let children = meta.get(4..)?;
+        let mut primary = None;
+        let mut exif_item = None;
+        let mut ipco: Option<&[u8]> = None;
+        let mut ipma: Option<&[u8]> = None;
+        let mut iloc: Option<&[u8]> = None;
+        for (typ, body) in boxes(children) {
+            match &typ {
+                b"pitm" => {
+                    let width = if *body.first()? == 0 { 2 } else { 4 };
+                    primary = Some(be(body.get(4..4 + width)?) as u32);
+                }
+                b"iinf" => exif_item = find_exif_item(body),
+                b"iprp" => {
+                    for (typ, body) in boxes(body) {
+                        match &typ {
+                            b"ipco" => ipco = Some(body),
+                            b"ipma" => ipma = Some(body),
+                            _ => {}
+                        }
+                    }
+                }
+                b"iloc" => iloc = Some(body),
+                _ => {}
+            }
+        }
+
+        let mut out = Meta::default();
+        if let (Some(primary), Some(ipco), Some(ipma)) = (primary, ipco, ipma) {
+            let properties: Vec<([u8; 4], &[u8])> = boxes(ipco).collect();
+            for index in associations(ipma, primary).unwrap_or_default() {
+                let Some((typ, body)) = index.checked_sub(1).and_then(|i| properties.get(i)) else {
+                    continue;
+                };
+                match typ {
+                    // version/flags, width(4), height(4)
+                    b"ispe" if body.len() >= 12 => {
+                        out.primary_size = Some((be(&body[4..8]) as u32, be(&body[8..12]) as u32));
+                    }
+                    // angle * 90 degrees anti-clockwise in the low two bits
+                    b"irot" if !body.is_empty() => out.quarter_turns = body[0] & 0x03,
+                    _ => {}
+                }
+            }
+        }
+        if let (Some(item), Some(iloc)) = (exif_item, iloc) {
+            out.exif = locate(iloc, item);
+        }
+        Some(out)
+    }
+}
+
+/// Id of the first item of type `Exif` in an `iinf` payload.
+fn find_exif_item(iinf: &[u8]) -> Option<u32> {
+    let count_bytes = if *iinf.first()? == 0 { 2 } else { 4 };
+    for (typ, infe) in boxes(iinf.get(4 + count_bytes..)?) {
+        if &typ != b"infe" {
+            continue;
+        }
+        // version/flags, item_ID (2 or 4), item_protection_index(2), item_type(4)
+        let id_bytes = match *infe.first()? {
+            2 => 2,
+            3 => 4,
+            _ => continue,
+        };
+        let mut f = Fields { buf: infe, at: 4 };
+        let id = f.take(id_bytes)? as u32;
+        f.take(2)?;
+        if infe.get(f.at..f.at + 4)? == b"Exif" {
+            return Some(id);
+        }
+    }
+    None
+}
+
+/// 1-based property indexes associated with `item` in an `ipma` payload.
+fn associations(ipma: &[u8], item: u32) -> Option<Vec<usize>> {
+    let version = *ipma.first()?;
+    let wide_index = ipma.get(3)? & 0x01 != 0;
+    let mut f = Fields { buf: ipma, at: 4 };
+    let entries = f.take(4)?;
+    for _ in 0..entries {
+        let id = f.take(if version < 1 { 2 } else { 4 })? as u32;
+        let count = f.take(1)?;
+        let mut indexes = Vec::new();
+        for _ in 0..count {
+            // essential bit, then a 7- or 15-bit index
+            let index = if wide_index {
+                f.take(2)? & 0x7FFF
+            } else {
+                f.take(1)? & 0x7F
+            };
+            indexes.push(index as usize);
+        }
+        if id == item {
+            return Some(indexes);
+        }
+    }
+    None
+}
+
+/// File offset and length of `item`'s first extent from an `iloc` payload; `None` unless
+/// it is stored in the file itself (construction method 0).
+fn locate(iloc: &[u8], item: u32) -> Option<(u64, u64)> {
+    let version = *iloc.first()?;
+    let mut f = Fields { buf: iloc, at: 4 };
+    let sizes = f.take(1)?;
+    let (offset_size, length_size) = ((sizes >> 4) as usize, (sizes & 0x0F) as usize);
+    let sizes = f.take(1)?;
+    let base_offset_size = (sizes >> 4) as usize;
+    let index_size = if version >= 1 {
+        (sizes & 0x0F) as usize
+    } else {
+        0
+    };
+    let count = f.take(if version < 2 { 2 } else { 4 })?;
+    for _ in 0..count {
+        let id = f.take(if version < 2 { 2 } else { 4 })? as u32;
+        let construction = if version >= 1 { f.take(2)? & 0x0F } else { 0 };
+        f.take(2)?; // data_reference_index
+        let base = f.take(base_offset_size)?;
+        let extents = f.take(2)?;
+        let mut first = None;
+        for _ in 0..extents {
+            f.take(index_size)?;
+            let offset = f.take(offset_size)?;
+            let length = f.take(length_size)?;
+            first.get_or_insert((base + offset, length));
+        }
+        if id == item {
+            let (offset, length) = first?;
+            // A zero length means "to the end of the file".
+            let length = if length == 0 { MAX_EXIF_BYTES } else { length };
+            return (construction == 0).then_some((offset, length));
+        }
+    }
+    None
+}
@@ -0,0 +1,67 @@
+//! JPEG: size from the first `SOFn` frame header, EXIF from an `APP1` segment before it.
+
+use std::io::{Read, Seek};
+
+use super::exif::{parse_exif, MAX_EXIF_BYTES};
+use super::ImageInfo;
+use crate::utils::audio::reader::{position, read_array, read_up_to, seek_to};
+
+const SOI: u8 = 0xD8;
+const EOI: u8 = 0xD9;
+const SOS: u8 = 0xDA;
+const APP1: u8 = 0xE1;
+
+pub(super) fn is_jpeg(head: &[u8]) -> bool {
+    head.starts_with(&[0xFF, SOI, 0xFF])
+}
+
+/// Start-of-frame markers (baseline, progressive, lossless, arithmetic); `C4`, `C8` and
+/// `CC` share the range but are other segments.
+fn is_sof(marker: u8) -> bool {
+    (0xC0..=0xCF).contains(&marker) && !matches!(marker, 0xC4 | 0xC8 | 0xCC)
+}
+
+pub(super) fn parse_jpeg<R: Read + Seek + ?Sized>(r: &mut R) -> Option<ImageInfo> {
+    seek_to(r, 2)?;
+    let mut info = ImageInfo::new("jpeg");
+    let mut quarter_turns = 0;
+    loop {
+        let [ff, mut marker] = read_array::<2, _>(r)?;
+        if ff != 0xFF {
+            return None;
+        }
+        // Any number of 0xFF fill bytes may precede a marker.
+        while marker == 0xFF {
+            [marker] = read_array::<1, _>(r)?;
+        }
+        // Standalone markers carry no length.
+        if marker == 0x01 || (0xD0..=0xD7).contains(&marker) {
+            continue;
+        }
+        if marker == SOS || marker == EOI {
+            // Image data without a frame header before it.
+            return None;
+        }
+        let length = u16::from_be_bytes(read_array::<2, _>(r)?) as u64;
+        if length < 2 {
+            return None;
+        }
+        let body = position(r)?;
+        if marker == APP1 && info.taken_at.is_none() {
+            let data = read_up_to(r, (length - 2).min(MAX_EXIF_BYTES))?;
+            if data.starts_with(b"Exif\0\0") {
+                let exif = parse_exif(&data);
+                info.taken_at = exif.taken_at;
+                quarter_turns = exif.quarter_turns;
+            }
+        } else if is_sof(marker) {
+            // precision(1), height(2), width(2)
+            let f = read_array::<5, _>(r)?;
+            let height = u16::from_be_bytes([f[1], f[2]]) as u32;
+            let width = u16::from_be_bytes([f[3], f[4]]) as u32;
+            info.set_size(width, height, quarter_turns);
+            return Some(info);
+        }
+        seek_to(r, body + length - 2)?;
+    }
+}
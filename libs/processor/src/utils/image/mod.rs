@@ -0,0 +1,95 @@
+//! Image format, dimensions and capture time without external dependencies.
+//!
+//! The format is sniffed from the magic bytes, never taken from the file name. Supported
+//! formats:
+//! - JPEG (`SOFn` frame header, EXIF from `APP1`)
+//! - PNG (`IHDR`, EXIF from `eXIf`)
+//! - WebP (`VP8 `/`VP8L`/`VP8X`, `EXIF`, animation frames from `ANMF`)
+//! - HEIC/HEIF/AVIF (`ispe` and `irot` of the primary item, EXIF from its `Exif` item)
+//! - GIF (logical screen, frame count and delays)
+//!
+//! Like the audio parsers, these read headers and skip payloads; forward-only streams are
+//! wrapped in a [`ForwardSeek`].
+
+mod exif;
+mod gif;
+mod heif;
+mod jpeg;
+mod png;
+mod webp;
+
+use std::io::{Read, Seek};
+
+use crate::utils::audio::reader::{read_up_to, seek_to};
+use crate::utils::audio::ForwardSeek;
+
+/// Bytes looked at when sniffing the format from the stream start.
+const SNIFF_BYTES: u64 = 16;
+
+/// Format, displayed size and capture time of an image, as far as its headers tell.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ImageInfo {
+    /// Sniffed format: `"jpeg"`, `"png"`, `"webp"`, `"heic"`, `"heif"`, `"avif"` or `"gif"`.
+    pub format: &'static str,
+    /// Width in pixels as displayed, i.e. after the EXIF orientation or HEIF rotation.
+    pub width: Option<u32>,
+    /// Height in pixels as displayed.
+    pub height: Option<u32>,
+    /// EXIF `DateTimeOriginal` as `YYYY-MM-DDTHH:MM:SS` local time, followed by the
+    /// `OffsetTimeOriginal` offset when the file has one.
+    pub taken_at: Option<String>,
+    /// Frames of an animation (GIF, animated WebP); `None` for other formats.
+    pub frame_count: Option<u32>,
+    /// Length of one loop of an animation in milliseconds.
+    pub duration_ms: Option<i64>,
+}
+
+impl ImageInfo {
+    /// Details of an image of `format` with nothing else known yet.
+    pub fn new(format: &'static str) -> ImageInfo {
+        ImageInfo {
+            format,
+            ..ImageInfo::default()
+        }
+    }
+
+    /// Set the stored size, swapped when the image is displayed rotated by 90 or 270
+    /// degrees.
+    fn set_size(&mut self, width: u32, height: u32, quarter_turns: u8) {
+        let (width, height) = if quarter_turns % 2 == 1 {
+            (height, width)
+        } else {
+            (width, height)
+        };
+        self.width = Some(width).filter(|&w| w > 0);
+        self.height = Some(height).filter(|&h| h > 0);
+    }
+}
+
+/// Detect format, size and capture time of an image from a forward-only stream (e.g. a ZIP
+/// entry).
+///
+/// Returns `None` for unsupported formats and files whose header can't be parsed.
+pub fn detect_image_info<R: Read + ?Sized>(reader: &mut R) -> Option<ImageInfo> {
+    let mut reader = ForwardSeek::new(reader).ok()?;
+    detect_image_info_seekable(&mut reader)
+}
+
+/// [`detect_image_info`] for seekable readers.
+pub fn detect_image_info_seekable<R: Read + Seek + ?Sized>(reader: &mut R) -> Option<ImageInfo> {
+    seek_to(reader, 0)?;
+    let head = read_up_to(reader, SNIFF_BYTES)?;
+    if jpeg::is_jpeg(&head) {
+        jpeg::parse_jpeg(reader)
+    } else if png::is_png(&head) {
+        png::parse_png(reader)
+    } else if webp::is_webp(&head) {
+        webp::parse_webp(reader)
+    } else if gif::is_gif(&head) {
+        gif::parse_gif(reader)
+    } else if let Some(format) = heif::heif_format(&head) {
+        heif::parse_heif(reader, format)
+    } else {
+        None
+    }
+}
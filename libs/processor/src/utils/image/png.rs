@@ -0,0 +1,48 @@
+//! PNG: size from `IHDR`, EXIF from an `eXIf` chunk before the image data.
+
+use std::io::{Read, Seek};
+
+use super::exif::{parse_exif, MAX_EXIF_BYTES};
+use super::ImageInfo;
+use crate::utils::audio::reader::{position, read_array, read_up_to, seek_to};
+
+const SIGNATURE: &[u8; 8] = b"\x89PNG\r\n\x1a\n";
+
+pub(super) fn is_png(head: &[u8]) -> bool {
+    head.starts_with(SIGNATURE)
+}
+
+pub(super) fn parse_png<R: Read + Seek + ?Sized>(r: &mut R) -> Option<ImageInfo> {
+    seek_to(r, SIGNATURE.len() as u64)?;
+    let mut info = ImageInfo::new("png");
+    let mut size = None;
+    let mut quarter_turns = 0;
+    // Chunk: length(4), type(4), data, CRC(4)
+    while let Some(h) = read_array::<8, _>(r) {
+        let length = u32::from_be_bytes([h[0], h[1], h[2], h[3]]) as u64;
+        let body = position(r)?;
+        match &h[4..8] {
+            b"IHDR" => {
+                let d = read_array::<8, _>(r)?;
+                size = Some((
+                    u32::from_be_bytes([d[0], d[1], d[2], d[3]]),
+                    u32::from_be_bytes([d[4], d[5], d[6], d[7]]),
+                ));
+            }
+            b"eXIf" => {
+                let exif = parse_exif(&read_up_to(r, length.min(MAX_EXIF_BYTES))?);
+                info.taken_at = exif.taken_at;
+                quarter_turns = exif.quarter_turns;
+            }
+            // eXIf must come before the image data.
+            b"IDAT" | b"IEND" => break,
+            _ => {}
+        }
+        if seek_to(r, body + length + 4).is_none() {
+            break;
+        }
+    }
+    let (width, height) = size?;
+    info.set_size(width, height, quarter_turns);
+    Some(info)
+}
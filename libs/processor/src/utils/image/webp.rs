@@ -0,0 +1,79 @@
+//! WebP (RIFF): size from the `VP8 `, `VP8L` or `VP8X` chunk, EXIF from `EXIF`, and the
+//! frames and their durations from `ANMF` chunks of animations.
+
+use std::io::{Read, Seek};
+
+use super::exif::{parse_exif, MAX_EXIF_BYTES};
+use super::ImageInfo;
+use crate::utils::audio::reader::{position, read_array, read_up_to, seek_to};
+
+pub(super) fn is_webp(head: &[u8]) -> bool {
+    head.len() >= 12 && &head[0..4] == b"RIFF" && &head[8..12] == b"WEBP"
+}
+
+fn u24_le(b: &[u8]) -> u32 {
+    u32::from_le_bytes([b[0], b[1], b[2], 0])
+}
+
+pub(super) fn parse_webp<R: Read + Seek + ?Sized>(r: &mut R) -> Option<ImageInfo> {
+    seek_to(r, 12)?;
+    let mut info = ImageInfo::new("webp");
+    let mut size = None;
+    let mut quarter_turns = 0;
+    let mut frames: u32 = 0;
+    let mut duration_ms: i64 = 0;
+    while let Some(h) = read_array::<8, _>(r) {
+        let length = u32::from_le_bytes([h[4], h[5], h[6], h[7]]) as u64;
+        let body = position(r)?;
+        match &h[0..4] {
+            b"VP8X" => {
+                // flags(1), reserved(3), canvas width - 1 (24), canvas height - 1 (24)
+                let d = read_array::<10, _>(r)?;
+                size = Some((u24_le(&d[4..7]) + 1, u24_le(&d[7..10]) + 1));
+            }
+            b"VP8 " if size.is_none() => {
+                // frame tag(3), start code 9D 01 2A, then 14-bit width and height
+                let d = read_array::<10, _>(r)?;
+                if d[3..6] != [0x9D, 0x01, 0x2A] {
+                    return None;
+                }
+                size = Some((
+                    (u16::from_le_bytes([d[6], d[7]]) & 0x3FFF) as u32,
+                    (u16::from_le_bytes([d[8], d[9]]) & 0x3FFF) as u32,
+                ));
+            }
+            b"VP8L" if size.is_none() => {
+                // signature 0x2F, then 14-bit width - 1 and height - 1
+                let d = read_array::<5, _>(r)?;
+                if d[0] != 0x2F {
+                    return None;
+                }
+                let bits = u32::from_le_bytes([d[1], d[2], d[3], d[4]]);
+                size = Some(((bits & 0x3FFF) + 1, ((bits >> 14) & 0x3FFF) + 1));
+            }
+            b"EXIF" => {
+                let exif = parse_exif(&read_up_to(r, length.min(MAX_EXIF_BYTES))?);
+                info.taken_at = exif.taken_at;
+                quarter_turns = exif.quarter_turns;
+            }
+            b"ANMF" => {
+                // x(3), y(3), width - 1 (3), height - 1 (3), duration(3), flags(1)
+                let d = read_array::<16, _>(r)?;
+                frames += 1;
+                duration_ms += u24_le(&d[12..15]) as i64;
+            }
+            _ => {}
+        }
+        // Chunks are padded to an even size.
+        if seek_to(r, body + length + (length & 1)).is_none() {
+            break;
+        }
+    }
+    let (width, height) = size?;
+    info.set_size(width, height, quarter_turns);
+    if frames > 0 {
+        info.frame_count = Some(frames);
+        info.duration_ms = Some(duration_ms);
+    }
+    Some(info)
+}
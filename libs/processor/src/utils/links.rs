@@ -0,0 +1,129 @@
+//! URLs in message text and the domains they point to.
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+/// `http(s)://` URLs and bare `www.` hosts, up to the next whitespace or delimiter that
+/// can't be part of a URL.
+static URL_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"(?i)\b(?:https?://|www\.)[^\s<>"'`\u{201c}\u{201d}]+"#).expect("url regex")
+});
+
+/// Punctuation that ends a sentence rather than a URL when it comes last.
+const TRAILING_PUNCTUATION: &[char] = &['.', ',', ';', ':', '!', '?', '*', '_'];
+
+/// Host prefixes dropped from domains, so `www.` and mobile sites count as the site.
+const HOST_PREFIXES: &[&str] = &["www.", "m.", "mobile."];
+
+/// URLs in `text`, in order of appearance.
+pub fn find_urls(text: &str) -> Vec<&str> {
+    URL_RE
+        .find_iter(text)
+        .map(|m| trim_url(m.as_str()))
+        .filter(|url| url_domain(url).is_some())
+        .collect()
+}
+
+/// Strip trailing sentence punctuation and closing brackets without an opening one in the
+/// URL (`(see https://example.com/a_(b))` keeps the inner pair).
+fn trim_url(mut url: &str) -> &str {
+    loop {
+        let Some(last) = url.chars().last() else {
+            return url;
+        };
+        let unbalanced = |open: char| url.matches(open).count() < url.matches(last).count();
+        let strip = TRAILING_PUNCTUATION.contains(&last)
+            || (last == ')' && unbalanced('('))
+            || (last == ']' && unbalanced('['))
+            || (last == '}' && unbalanced('{'));
+        if !strip {
+            return url;
+        }
+        url = &url[..url.len() - last.len_utf8()];
+    }
+}
+
+/// Normalized domain of a URL: the lowercased host without port, credentials, trailing
+/// dot or a `www.`/`m.`/`mobile.` prefix. `None` when the URL has no dotted host.
+pub fn url_domain(url: &str) -> Option<String> {
+    let rest = match url.find("://") {
+        Some(at) => &url[at + 3..],
+        None => url,
+    };
+    let authority = rest.split(['/', '?', '#']).next().unwrap_or_default();
+    let host = authority.rsplit('@').next().unwrap_or_default();
+    let host = host.split(':').next().unwrap_or_default();
+    let mut host = host.trim_end_matches('.').to_lowercase();
+    while let Some(stripped) = HOST_PREFIXES
+        .iter()
+        .find_map(|p| host.strip_prefix(p).filter(|h| h.contains('.')))
+    {
+        host = stripped.to_string();
+    }
+    let valid = host.contains('.')
+        && host
+            .split('.')
+            .all(|label| !label.is_empty() && !label.starts_with('-'));
+    valid.then_some(host)
+}
+
+/// Query parameters map sites put a `lat,lon` pair in (Bing's `where1` is what Facebook
+/// uses for shared locations; `cp` separates the pair with `~`).
+const COORDINATE_PARAMS: &[&str] = &["where1", "cp", "q", "query", "ll", "sll", "center"];
+
+/// Latitude and longitude of a map link (Bing, Google or Apple Maps, or any `maps.` host),
+/// read from its query or from a Google-style `/@lat,lon,zoom` path segment.
+pub fn map_coordinates(url: &str) -> Option<(f64, f64)> {
+    let domain = url_domain(url)?;
+    let rest = url.split_once("://").map_or(url, |(_, rest)| rest);
+    let (path, query) = match rest.split_once('?') {
+        Some((path, query)) => (path, query.split('#').next().unwrap_or_default()),
+        None => (rest.split('#').next().unwrap_or_default(), ""),
+    };
+    if !domain.starts_with("maps.") && !path.contains("/maps") {
+        return None;
+    }
+    query
+        .split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .filter(|(key, _)| COORDINATE_PARAMS.contains(&key.to_ascii_lowercase().as_str()))
+        .find_map(|(_, value)| parse_coordinates(&percent_decode(value)))
+        .or_else(|| {
+            path.split('/')
+                .find_map(|segment| segment.strip_prefix('@'))
+                .and_then(parse_coordinates)
+        })
+}
+
+/// A `lat,lon` (or `lat~lon`) pair in range; anything after a further comma is ignored.
+fn parse_coordinates(value: &str) -> Option<(f64, f64)> {
+    let mut parts = value.split([',', '~']).map(str::trim);
+    let latitude: f64 = parts.next()?.parse().ok()?;
+    let longitude: f64 = parts.next()?.parse().ok()?;
+    ((-90.0..=90.0).contains(&latitude) && (-180.0..=180.0).contains(&longitude))
+        .then_some((latitude, longitude))
+}
+
+/// Decode `%XX` escapes and `+` in a query value; invalid escapes are kept as they are.
+fn percent_decode(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = (bytes[i] == b'%')
+            .then(|| value.get(i + 1..i + 3))
+            .flatten()
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match (bytes[i], escaped) {
+            (_, Some(byte)) => {
+                out.push(byte);
+                i += 3;
+                continue;
+            }
+            (b'+', None) => out.push(b' '),
+            (byte, None) => out.push(byte),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
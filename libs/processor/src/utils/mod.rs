@@ -4,5 +4,8 @@
 
 pub mod archive;
 pub mod audio;
+pub mod image;
+pub mod links;
+pub mod video;
 
 pub use archive::*;
@@ -0,0 +1,55 @@
+//! Video duration, resolution and codec without external dependencies.
+//!
+//! Supports MP4/MOV (ISO BMFF, including 3GP and M4V), reusing the box walking of the audio
+//! module's MP4 parser: the duration comes from `mvhd` (or `sidx` for fragmented files), the
+//! displayed size from the first video track's `tkhd` and the codec from its sample entry.
+//! Only headers are read, so forward-only streams such as ZIP entries work too.
+
+use std::io::{Read, Seek, SeekFrom};
+
+use crate::utils::audio::{mp4, ForwardSeek};
+
+/// Duration, displayed size and codec of a video file, as far as its headers tell.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct VideoInfo {
+    /// Duration in milliseconds (rounded), always positive.
+    pub duration_ms: i64,
+    /// Width in pixels as displayed, i.e. after the track's rotation.
+    pub width: Option<u32>,
+    /// Height in pixels as displayed.
+    pub height: Option<u32>,
+    /// Codec, e.g. `"h264"`, `"hevc"`, `"vp9"` or `"mpeg4"`.
+    pub codec: Option<&'static str>,
+}
+
+/// Detect duration, size and codec of an MP4/MOV video from a forward-only stream (e.g. a
+/// ZIP entry). Files with `moov` at the end are read through once.
+///
+/// Returns `None` for other containers, unparsable files and durations under half a
+/// millisecond.
+pub fn detect_video_info<R: Read + ?Sized>(reader: &mut R) -> Option<VideoInfo> {
+    let mut reader = ForwardSeek::new(reader).ok()?;
+    detect_video_info_seekable(&mut reader)
+}
+
+/// [`detect_video_info`] for seekable readers.
+pub fn detect_video_info_seekable<R: Read + Seek + ?Sized>(reader: &mut R) -> Option<VideoInfo> {
+    reader.seek(SeekFrom::Start(0)).ok()?;
+    let mut head = [0u8; 8];
+    reader.read_exact(&mut head).ok()?;
+    if !mp4::looks_like_box(&head) {
+        return None;
+    }
+    let (seconds, moov) = mp4::parse_movie(reader)?;
+    let duration_ms = (seconds * 1000.0).round() as i64;
+    if duration_ms <= 0 {
+        return None;
+    }
+    let track = moov.video.unwrap_or_default();
+    Some(VideoInfo {
+        duration_ms,
+        width: track.width,
+        height: track.height,
+        codec: track.codec,
+    })
+}
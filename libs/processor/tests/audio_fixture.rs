@@ -1,3 +1,5 @@
+mod common;
+
 use std::path::PathBuf;
 
 #[test]
@@ -49,3 +51,142 @@ fn audio_duration_messenger_mislabeled_wav_fixture() {
         path
     );
 }
+
+#[test]
+fn audio_duration_fixtures_seekable_and_zip_entry_agree() {
+    // The same bytes probed from a seekable file and from a deflated (forward-only) ZIP entry.
+    let dir: PathBuf = [
+        env!("CARGO_MANIFEST_DIR"),
+        "tests",
+        "test-audio",
+        "messenger",
+    ]
+    .iter()
+    .collect();
+    let mut names: Vec<String> = std::fs::read_dir(&dir)
+        .expect("list messenger fixtures")
+        .map(|e| e.unwrap().file_name().to_string_lossy().into_owned())
+        .collect();
+    names.sort();
+    let contents: Vec<Vec<u8>> = names
+        .iter()
+        .map(|n| std::fs::read(dir.join(n)).unwrap())
+        .collect();
+    let entries: Vec<(&str, &[u8])> = names
+        .iter()
+        .map(String::as_str)
+        .zip(contents.iter().map(Vec::as_slice))
+        .collect();
+    let zip_path = common::write_zip("audio_fixtures", &entries);
+    let mut archive = zip::ZipArchive::new(std::fs::File::open(&zip_path).unwrap()).unwrap();
+
+    for name in &names {
+        let mut file = std::fs::File::open(dir.join(name)).unwrap();
+        let seekable = processor::utils::audio::detect_duration_seconds_seekable(name, &mut file);
+        let mut entry = archive.by_name(name).unwrap();
+        let from_zip = processor::utils::audio::detect_duration_seconds(name, &mut entry);
+        assert!(seekable.is_some(), "no duration for {}", name);
+        assert_eq!(seekable, from_zip, "{}", name);
+    }
+}
+
+#[test]
+fn audio_info_messenger_mp4_fixture() {
+    let path: PathBuf = [
+        env!("CARGO_MANIFEST_DIR"),
+        "tests",
+        "test-audio",
+        "messenger",
+        "audioclip15773984540003506_573016540158892.mp4",
+    ]
+    .iter()
+    .collect();
+
+    let mut f = std::fs::File::open(&path).expect("open messenger mp4 fixture");
+    let info = processor::utils::audio::detect_audio_info_seekable(path.to_str().unwrap(), &mut f)
+        .expect("info for messenger mp4 fixture");
+    // ffprobe: aac (LC), 44100 Hz, mono, 3.506 s
+    assert_eq!(info.duration_ms, 3506);
+    assert_eq!(info.codec, Some("aac"));
+    assert_eq!(info.sample_rate, Some(44100));
+    assert_eq!(info.channels, Some(1));
+    assert_eq!(info.whole_seconds(), Some(4));
+}
+
+#[test]
+fn imported_audio_stores_milliseconds_and_codec() {
+    let clip = std::fs::read(
+        [
+            env!("CARGO_MANIFEST_DIR"),
+            "tests",
+            "test-audio",
+            "messenger",
+            "audioclip15773984540003506_573016540158892.mp4",
+        ]
+        .iter()
+        .collect::<PathBuf>(),
+    )
+    .unwrap();
+    let fb_uri = "your_facebook_activity/messages/inbox/bob_1/audio/clip.mp4";
+    let fb = format!(
+        r#"{{"participants":[{{"name":"Alice"}},{{"name":"Bob"}}],
+  "messages":[
+    {{"sender_name":"Bob","timestamp_ms":1700000002000,"audio_files":[{{"uri":"{}"}}]}},
+    {{"sender_name":"Alice","timestamp_ms":1700000001000,"audio_files":[{{"uri":"missing.mp4"}}]}}],
+  "title":"Bob","thread_path":"inbox/bob_1"}}"#,
+        fb_uri
+    );
+    let e2e = r#"{"participants":["Alice","Bob"],"threadName":"Bob_2","messages":[
+        {"senderName":"Bob","timestamp":1700000003,"text":"",
+         "media":[{"uri":"media/voice.m4a"}]}]}"#;
+    let zips = vec![
+        common::write_zip(
+            "imported_audio_fb",
+            &[
+                (
+                    "your_facebook_activity/messages/inbox/bob_1/message_1.json",
+                    fb.as_bytes(),
+                ),
+                (fb_uri, &clip),
+            ],
+        ),
+        common::write_zip(
+            "imported_audio_e2e",
+            &[
+                ("bob_2.json", e2e.as_bytes()),
+                ("media/pic.jpg", b"not a real image"),
+            ],
+        ),
+        // The E2E clip lives in another part of the export and is found through the file index.
+        common::write_zip("imported_audio_media", &[("media/voice.m4a", &clip)]),
+    ];
+    let db = common::fresh_db("imported_audio");
+    processor::importers::messenger::import_messenger_exports_with_options(
+        zips,
+        &db,
+        &Default::default(),
+    )
+    .expect("import");
+
+    let rows: Vec<String> = common::dump_rows(&db)
+        .into_iter()
+        .filter(|r| r.starts_with("audio|"))
+        .map(|r| {
+            r.split_once('|')
+                .unwrap()
+                .1
+                .split_once('|')
+                .unwrap()
+                .1
+                .to_string()
+        })
+        .collect();
+    assert_eq!(
+        rows,
+        [
+            "missing.mp4|Null|Null|Null".to_string(),
+            format!("{}|Integer(4)|Integer(3506)|aac", fb_uri),
+            "media/voice.m4a|Integer(4)|Integer(3506)|aac".to_string(),
+        ]
+    );
+}
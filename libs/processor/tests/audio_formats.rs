@@ -0,0 +1,130 @@
+mod common;
+
+use std::io::Cursor;
+use std::path::PathBuf;
+
+use processor::utils::audio::{
+    detect_audio_info_seekable, detect_duration_seconds, detect_duration_seconds_seekable,
+};
+
+// Fixtures in tests/test-audio/formats carry real container/codec headers around silent
+// (zeroed) payloads, with known lengths:
+const FIXTURES: &[(&str, i64)] = &[
+    ("voice_nb.amr", 3),  // 150 AMR-NB 12.2k frames
+    ("voice_wb.awb", 2),  // 120 AMR-WB frames (2.4 s), some NO_DATA
+    ("music.ogg", 6),     // Vorbis 22.05 kHz, final granule 132300
+    ("clip.flac", 5),     // STREAMINFO 72000 samples at 16 kHz (4.5 s)
+    ("voice.webm", 7),    // Info/Duration 7250 ms, unknown-size segment and cluster
+    ("voice_aac.caf", 4), // AAC packets, pakt 176400 valid frames at 44.1 kHz
+    ("tone_pcm.caf", 2),  // 8 kHz 8-bit LPCM, length from the data chunk
+    ("voice.3gp", 5),     // 3GP with an AMR track, mvhd 5.2 s
+];
+
+fn fixture(name: &str) -> PathBuf {
+    [
+        env!("CARGO_MANIFEST_DIR"),
+        "tests",
+        "test-audio",
+        "formats",
+        name,
+    ]
+    .iter()
+    .collect()
+}
+
+#[test]
+fn audio_formats_by_extension() {
+    for &(name, expected) in FIXTURES {
+        let mut f = std::fs::File::open(fixture(name)).expect("open fixture");
+        assert_eq!(
+            detect_duration_seconds_seekable(name, &mut f),
+            Some(expected),
+            "{}",
+            name
+        );
+    }
+}
+
+#[test]
+fn audio_formats_sniffed_by_magic() {
+    for &(name, expected) in FIXTURES {
+        let data = std::fs::read(fixture(name)).expect("read fixture");
+        let mut cursor = Cursor::new(data);
+        assert_eq!(
+            detect_duration_seconds("file.bin", &mut cursor),
+            Some(expected),
+            "{}",
+            name
+        );
+    }
+}
+
+#[test]
+fn audio_formats_from_zip_entries() {
+    let contents: Vec<Vec<u8>> = FIXTURES
+        .iter()
+        .map(|(name, _)| std::fs::read(fixture(name)).unwrap())
+        .collect();
+    let entries: Vec<(&str, &[u8])> = FIXTURES
+        .iter()
+        .map(|(name, _)| *name)
+        .zip(contents.iter().map(Vec::as_slice))
+        .collect();
+    let zip_path = common::write_zip("audio_formats", &entries);
+    let mut archive = zip::ZipArchive::new(std::fs::File::open(&zip_path).unwrap()).unwrap();
+    for &(name, expected) in FIXTURES {
+        let mut entry = archive.by_name(name).unwrap();
+        assert_eq!(
+            detect_duration_seconds(name, &mut entry),
+            Some(expected),
+            "{}",
+            name
+        );
+    }
+}
+
+#[test]
+fn audio_formats_truncated_headers() {
+    for &(name, _) in FIXTURES {
+        let data = std::fs::read(fixture(name)).unwrap();
+        // Only the magic bytes: no parser should panic or invent a length.
+        let mut cursor = Cursor::new(data[..8].to_vec());
+        assert_eq!(detect_duration_seconds(name, &mut cursor), None, "{}", name);
+    }
+}
+
+#[test]
+fn audio_formats_report_stream_details() {
+    // (name, duration_ms, codec, sample_rate, channels)
+    let expected: &[(&str, i64, &str, u32, u16)] = &[
+        ("voice_nb.amr", 3000, "amr_nb", 8000, 1),
+        ("voice_wb.awb", 2400, "amr_wb", 16000, 1),
+        ("music.ogg", 6000, "vorbis", 22050, 1),
+        ("clip.flac", 4500, "flac", 16000, 1),
+        ("voice.webm", 7250, "opus", 48000, 1),
+        ("voice_aac.caf", 4000, "aac", 44100, 1),
+        ("tone_pcm.caf", 2000, "pcm", 8000, 1),
+        ("voice.3gp", 5200, "amr_nb", 8000, 1),
+    ];
+    for &(name, ms, codec, sample_rate, channels) in expected {
+        let mut f = std::fs::File::open(fixture(name)).unwrap();
+        let info = detect_audio_info_seekable(name, &mut f).expect(name);
+        assert_eq!(info.duration_ms, ms, "{}", name);
+        assert_eq!(info.codec, Some(codec), "{}", name);
+        assert_eq!(info.sample_rate, Some(sample_rate), "{}", name);
+        assert_eq!(info.channels, Some(channels), "{}", name);
+        assert_eq!(
+            info.whole_seconds(),
+            detect_duration_seconds_seekable(name, &mut f),
+            "{}",
+            name
+        );
+    }
+    // Bitrates straight from the headers: Vorbis nominal rate, 8 kHz 8-bit LPCM.
+    let bitrate = |name: &str| {
+        let mut f = std::fs::File::open(fixture(name)).unwrap();
+        detect_audio_info_seekable(name, &mut f).unwrap().bitrate
+    };
+    assert_eq!(bitrate("music.ogg"), Some(64000));
+    assert_eq!(bitrate("tone_pcm.caf"), Some(64000));
+}
@@ -0,0 +1,548 @@
+mod common;
+
+use std::path::Path;
+
+use processor::database::{
+    Attachment, Call, Contact, ConversationEvent, ConversationMeta, ConversationType, EventKind,
+    Link, Location, MessageDb, MessageEdit, MessageRecord, Poll, PollOption, Sticker, UnsentReason,
+    WriteBatch,
+};
+use processor::utils::audio::AudioInfo;
+use processor::utils::image::ImageInfo;
+use processor::utils::video::VideoInfo;
+
+/// Export with one conversation of two people; returns their person ids.
+fn setup(batch: &mut WriteBatch<'_>) -> (i64, i64) {
+    let export_id = batch
+        .insert_export("messenger:facebook", None, None)
+        .unwrap();
+    let canon = batch
+        .insert_canonical_conversation(ConversationType::DM, Some("Bob"))
+        .unwrap();
+    let conv = batch
+        .insert_conversation(
+            ConversationType::DM,
+            Some("Bob"),
+            &ConversationMeta::default(),
+            export_id,
+            canon,
+        )
+        .unwrap();
+    let mut person = |name: &str| {
+        let canon = batch.insert_canonical_person(Some(name), None).unwrap();
+        batch.insert_person(conv, Some(name), None, canon).unwrap()
+    };
+    (person("Alice"), person("Bob"))
+}
+
+fn records(alice: i64, bob: i64, count: usize) -> Vec<MessageRecord> {
+    (0..count)
+        .map(|i| MessageRecord {
+            sender_id: if i.is_multiple_of(2) { alice } else { bob },
+            sent_at: 1_700_000_000 + i as i64,
+            unsent: i.is_multiple_of(17),
+            texts: vec![format!("message {}", i)],
+            reactions: if i.is_multiple_of(5) {
+                vec![(bob, "x".to_string()), (alice, "y".to_string())]
+            } else {
+                Vec::new()
+            },
+            ..Default::default()
+        })
+        .collect()
+}
+
+/// Bulk insert the records `build` makes for Alice and Bob into a fresh database and dump
+/// the rows of the given kinds.
+fn bulk_rows(
+    name: &str,
+    kinds: &[&str],
+    build: impl FnOnce(i64, i64) -> Vec<MessageRecord>,
+) -> Vec<String> {
+    let path = common::fresh_db(name);
+    let mut db = MessageDb::open(&path).unwrap();
+    let mut batch = db.begin_write().unwrap();
+    let (alice, bob) = setup(&mut batch);
+    batch.insert_messages_bulk(&build(alice, bob)).unwrap();
+    batch.commit().unwrap();
+    common::dump_rows(&path)
+        .into_iter()
+        .filter(|r| kinds.iter().any(|k| r.starts_with(&format!("{}|", k))))
+        .collect()
+}
+
+fn insert_row_by_row(batch: &mut WriteBatch<'_>, records: &[MessageRecord]) {
+    for r in records {
+        let id = batch
+            .insert_message(r.sender_id, r.sent_at, r.unsent)
+            .unwrap();
+        for t in &r.texts {
+            batch.add_message_text(id, t).unwrap();
+        }
+        for (reactor, reaction) in &r.reactions {
+            batch.insert_reaction(*reactor, id, reaction).unwrap();
+        }
+    }
+}
+
+fn write(db_path: &Path, bulk: bool) {
+    let mut db = MessageDb::open(db_path).unwrap();
+    let mut batch = db.begin_write().unwrap();
+    let (alice, bob) = setup(&mut batch);
+    // Enough rows to span several multi-row statements.
+    let records = records(alice, bob, 1000);
+    if bulk {
+        let ids = batch.insert_messages_bulk(&records[..400]).unwrap();
+        assert_eq!(ids, 1..401);
+        batch.insert_messages_bulk(&records[400..]).unwrap();
+    } else {
+        insert_row_by_row(&mut batch, &records);
+    }
+    batch.commit().unwrap();
+}
+
+#[test]
+fn bulk_insert_matches_row_by_row_insert() {
+    let row_by_row = common::fresh_db("bulk_insert_rows");
+    let bulk = common::fresh_db("bulk_insert_bulk");
+    write(&row_by_row, false);
+    write(&bulk, true);
+
+    assert_eq!(common::dump_rows(&bulk), common::dump_rows(&row_by_row));
+    assert_eq!(
+        common::query_i64(&bulk, "SELECT COUNT(*) FROM message"),
+        1000
+    );
+}
+
+#[test]
+fn deferred_indexes_and_import_pragmas_are_restored() {
+    let path = common::fresh_db("bulk_insert_restore");
+    let index_count = "SELECT COUNT(*) FROM sqlite_master WHERE type = 'index' AND sql IS NOT NULL";
+    let indexes_before = common::query_i64(&path, index_count);
+
+    let mut db = MessageDb::open(&path).unwrap();
+    let cache_size = |db: &MessageDb| -> i64 {
+        db.conn()
+            .pragma_query_value(None, "cache_size", |r| r.get(0))
+            .unwrap()
+    };
+    let cache_before = cache_size(&db);
+    let tuning = db.tune_for_import().unwrap();
+    assert_ne!(cache_size(&db), cache_before);
+
+    let mut batch = db.begin_write().unwrap();
+    assert!(batch.message_tables_empty().unwrap());
+    let deferred = batch.defer_indexes().unwrap();
+    let (alice, bob) = setup(&mut batch);
+    batch
+        .insert_messages_bulk(&records(alice, bob, 10))
+        .unwrap();
+    deferred.restore(&mut batch).unwrap();
+    assert!(!batch.message_tables_empty().unwrap());
+    batch.commit().unwrap();
+    tuning.restore(&db).unwrap();
+
+    assert_eq!(cache_size(&db), cache_before);
+    assert_eq!(common::query_i64(&path, index_count), indexes_before);
+}
+
+#[test]
+fn bulk_insert_writes_media() {
+    let path = common::fresh_db("bulk_insert_media");
+    let mut db = MessageDb::open(&path).unwrap();
+    let mut batch = db.begin_write().unwrap();
+    let (alice, bob) = setup(&mut batch);
+    let image = ImageInfo {
+        width: Some(640),
+        height: Some(480),
+        taken_at: Some("2023-11-14T22:13:20".to_string()),
+        ..ImageInfo::new("jpeg")
+    };
+    let gif = ImageInfo {
+        width: Some(200),
+        height: Some(100),
+        frame_count: Some(12),
+        duration_ms: Some(1200),
+        ..ImageInfo::new("gif")
+    };
+    let video = VideoInfo {
+        duration_ms: 1500,
+        width: Some(1280),
+        height: Some(720),
+        codec: Some("h264"),
+    };
+    let audio = AudioInfo {
+        duration_ms: 2250,
+        codec: Some("aac"),
+        ..AudioInfo::default()
+    };
+    batch
+        .insert_messages_bulk(&[
+            MessageRecord {
+                sender_id: alice,
+                sent_at: 1_700_000_001,
+                images: vec![
+                    Attachment {
+                        stored_path: Some("ab/ab1.jpg".to_string()),
+                        ..Attachment::new("photos/1.jpg", Some(image))
+                    },
+                    Attachment::new("photos/2.jpg", None),
+                ],
+                gifs: vec![Attachment::new("gifs/1.gif", Some(gif))],
+                ..Default::default()
+            },
+            MessageRecord {
+                sender_id: bob,
+                sent_at: 1_700_000_002,
+                videos: vec![
+                    Attachment::new("videos/1.mp4", Some(video)),
+                    Attachment::new("videos/1.webm", None),
+                ],
+                audios: vec![Attachment {
+                    stored_path: Some("cd/cd2.m4a".to_string()),
+                    ..Attachment::new("audio/2.m4a", Some(audio))
+                }],
+                ..Default::default()
+            },
+        ])
+        .unwrap();
+    batch.commit().unwrap();
+
+    let rows: Vec<String> = common::dump_rows(&path)
+        .into_iter()
+        .filter(|r| {
+            ["image|", "gif|", "video|", "audio|"]
+                .iter()
+                .any(|k| r.starts_with(k))
+        })
+        .collect();
+    assert_eq!(
+        rows,
+        [
+            "image|Integer(1)|photos/1.jpg|jpeg|Integer(640)|Integer(480)|2023-11-14T22:13:20",
+            "image|Integer(1)|photos/2.jpg|Null|Null|Null|Null",
+            "gif|Integer(1)|gifs/1.gif|Integer(200)|Integer(100)|Integer(12)|Integer(1200)",
+            "video|Integer(2)|videos/1.mp4|Integer(1500)|Integer(1280)|Integer(720)|h264",
+            "video|Integer(2)|videos/1.webm|Null|Null|Null|Null",
+            "audio|Integer(2)|audio/2.m4a|Integer(2)|Integer(2250)|aac",
+        ]
+    );
+    for sql in [
+        "SELECT COUNT(*) FROM message_image WHERE stored_path = 'ab/ab1.jpg'",
+        "SELECT COUNT(*) FROM message_audio WHERE stored_path = 'cd/cd2.m4a'",
+    ] {
+        assert_eq!(common::query_i64(&path, sql), 1, "{}", sql);
+    }
+}
+
+#[test]
+fn bulk_insert_writes_calls() {
+    let rows = bulk_rows("bulk_insert_calls", &["call"], |alice, bob| {
+        vec![
+            MessageRecord {
+                sender_id: alice,
+                sent_at: 1_700_000_001,
+                call: Some(Call {
+                    duration_seconds: 125,
+                    missed: false,
+                    video: true,
+                }),
+                ..Default::default()
+            },
+            MessageRecord {
+                sender_id: bob,
+                sent_at: 1_700_000_002,
+                texts: vec!["no call here".to_string()],
+                ..Default::default()
+            },
+            MessageRecord {
+                sender_id: bob,
+                sent_at: 1_700_000_003,
+                call: Some(Call {
+                    duration_seconds: 0,
+                    missed: true,
+                    video: false,
+                }),
+                ..Default::default()
+            },
+        ]
+    });
+    assert_eq!(
+        rows,
+        [
+            "call|Integer(1)|Integer(125)|Integer(0)|video",
+            "call|Integer(3)|Integer(0)|Integer(1)|audio",
+        ]
+    );
+}
+
+#[test]
+fn bulk_insert_writes_links() {
+    let rows = bulk_rows("bulk_insert_links", &["link"], |alice, bob| {
+        vec![
+            MessageRecord {
+                sender_id: alice,
+                sent_at: 1_700_000_001,
+                links: vec![
+                    Link::new("https://www.Example.com/a?b=c", None),
+                    Link::new("not a url", Some("just text".to_string())),
+                ],
+                ..Default::default()
+            },
+            MessageRecord {
+                sender_id: bob,
+                sent_at: 1_700_000_002,
+                links: vec![Link::new(
+                    "https://m.youtube.com/watch?v=x",
+                    Some("A video".to_string()),
+                )],
+                ..Default::default()
+            },
+        ]
+    });
+    assert_eq!(
+        rows,
+        [
+            "link|Integer(1)|https://www.Example.com/a?b=c|Null|example.com",
+            "link|Integer(1)|not a url|just text|Null",
+            "link|Integer(2)|https://m.youtube.com/watch?v=x|A video|youtube.com",
+        ]
+    );
+}
+
+#[test]
+fn bulk_insert_writes_stickers() {
+    let rows = bulk_rows("bulk_insert_stickers", &["sticker"], |alice, bob| {
+        vec![
+            MessageRecord {
+                sender_id: alice,
+                sent_at: 1_700_000_001,
+                stickers: vec![Sticker {
+                    image: Attachment::new(
+                        "stickers/wave.webp",
+                        Some(ImageInfo {
+                            width: Some(120),
+                            height: Some(120),
+                            frame_count: Some(4),
+                            ..ImageInfo::new("webp")
+                        }),
+                    ),
+                    is_like: false,
+                }],
+                ..Default::default()
+            },
+            MessageRecord {
+                sender_id: bob,
+                sent_at: 1_700_000_002,
+                stickers: vec![Sticker {
+                    image: Attachment::new("stickers/369239263222822.png", None),
+                    is_like: true,
+                }],
+                ..Default::default()
+            },
+        ]
+    });
+    assert_eq!(
+        rows,
+        [
+            "sticker|Integer(1)|stickers/wave.webp|webp|Integer(120)|Integer(120)|Integer(4)|Integer(0)",
+            "sticker|Integer(2)|stickers/369239263222822.png|Null|Null|Null|Null|Integer(1)",
+        ]
+    );
+}
+
+#[test]
+fn bulk_insert_writes_conversation_events() {
+    let rows = bulk_rows("bulk_insert_events", &["event"], |alice, bob| {
+        vec![
+            MessageRecord {
+                sender_id: alice,
+                sent_at: 1_700_000_001,
+                events: vec![
+                    ConversationEvent {
+                        kind: EventKind::MemberAdded,
+                        subject_name: Some("Carol".to_string()),
+                        value: None,
+                        raw_text: Some("Alice added Carol to the group.".to_string()),
+                    },
+                    ConversationEvent {
+                        kind: EventKind::MemberAdded,
+                        subject_name: Some("Dan".to_string()),
+                        value: None,
+                        raw_text: Some("Alice added Dan to the group.".to_string()),
+                    },
+                ],
+                ..Default::default()
+            },
+            MessageRecord {
+                sender_id: bob,
+                sent_at: 1_700_000_002,
+                events: vec![ConversationEvent {
+                    kind: EventKind::GroupRenamed,
+                    subject_name: None,
+                    value: Some("Trip".to_string()),
+                    raw_text: None,
+                }],
+                ..Default::default()
+            },
+        ]
+    });
+    assert_eq!(
+        rows,
+        [
+            "event|Integer(1)|member_added|Carol|Null",
+            "event|Integer(1)|member_added|Dan|Null",
+            "event|Integer(2)|group_renamed|Null|Trip",
+        ]
+    );
+}
+
+#[test]
+fn bulk_insert_writes_edits_and_unsent_reasons() {
+    let rows = bulk_rows("bulk_insert_edits", &["message", "edit"], |alice, bob| {
+        vec![
+            MessageRecord {
+                sender_id: alice,
+                sent_at: 1_700_000_001,
+                edit_count: 2,
+                edits: vec![
+                    MessageEdit {
+                        previous_text: "see you at 6".to_string(),
+                        sent_at: Some(1_700_000_000),
+                    },
+                    MessageEdit {
+                        previous_text: "see you at 7".to_string(),
+                        sent_at: None,
+                    },
+                ],
+                texts: vec!["see you at 8".to_string()],
+                ..Default::default()
+            },
+            MessageRecord {
+                sender_id: bob,
+                sent_at: 1_700_000_002,
+                edit_count: 1,
+                texts: vec!["ok!".to_string()],
+                ..Default::default()
+            },
+            MessageRecord {
+                sender_id: bob,
+                sent_at: 1_700_000_003,
+                unsent: true,
+                unsent_reason: Some(UnsentReason::Sender),
+                ..Default::default()
+            },
+            MessageRecord {
+                sender_id: alice,
+                sent_at: 1_700_000_004,
+                unsent: true,
+                unsent_reason: Some(UnsentReason::MessengerKidParent),
+                ..Default::default()
+            },
+            MessageRecord {
+                sender_id: alice,
+                sent_at: 1_700_000_005,
+                unsent: true,
+                ..Default::default()
+            },
+        ]
+    });
+    assert_eq!(
+        rows,
+        [
+            "message|Integer(1)|Integer(1)|Integer(1700000001)|Integer(0)|Null|Integer(1)|Integer(2)|Null",
+            "message|Integer(2)|Integer(2)|Integer(1700000002)|Integer(0)|Null|Integer(1)|Integer(1)|Null",
+            "message|Integer(3)|Integer(2)|Integer(1700000003)|Integer(1)|Null|Integer(0)|Integer(0)|sender",
+            "message|Integer(4)|Integer(1)|Integer(1700000004)|Integer(1)|Null|Integer(0)|Integer(0)|messenger_kid_parent",
+            "message|Integer(5)|Integer(1)|Integer(1700000005)|Integer(1)|Null|Integer(0)|Integer(0)|Null",
+            "edit|Integer(1)|see you at 6|Integer(1700000000)",
+            "edit|Integer(1)|see you at 7|Null",
+        ]
+    );
+}
+
+#[test]
+fn bulk_insert_writes_polls_locations_and_contacts() {
+    let kinds = ["poll", "location", "contact"];
+    let rows = bulk_rows("bulk_insert_structured", &kinds, |alice, bob| {
+        vec![
+            MessageRecord {
+                sender_id: alice,
+                sent_at: 1_700_000_001,
+                poll: Some(Poll {
+                    question: "Where to?".to_string(),
+                    options: vec![
+                        PollOption {
+                            text: "Beach".to_string(),
+                            vote_count: 0,
+                            voter_ids: vec![alice, bob],
+                        },
+                        PollOption {
+                            text: "Hills".to_string(),
+                            vote_count: 3,
+                            voter_ids: vec![bob],
+                        },
+                        PollOption {
+                            text: "Home".to_string(),
+                            vote_count: 0,
+                            voter_ids: Vec::new(),
+                        },
+                    ],
+                }),
+                ..Default::default()
+            },
+            MessageRecord {
+                sender_id: bob,
+                sent_at: 1_700_000_002,
+                poll: Some(Poll {
+                    question: "Pizza?".to_string(),
+                    options: Vec::new(),
+                }),
+                location: Some(Location {
+                    latitude: 50.06,
+                    longitude: 19.94,
+                    name: Some("Kraków".to_string()),
+                    is_live: true,
+                }),
+                ..Default::default()
+            },
+            MessageRecord {
+                sender_id: alice,
+                sent_at: 1_700_000_003,
+                location: Some(Location {
+                    latitude: -33.8568,
+                    longitude: 151.2153,
+                    name: None,
+                    is_live: false,
+                }),
+                contacts: vec![
+                    Contact {
+                        name: Some("Eve".to_string()),
+                        phone_number: Some("+48 600 100 200".to_string()),
+                        email: None,
+                    },
+                    Contact {
+                        name: None,
+                        phone_number: None,
+                        email: Some("x@example.com".to_string()),
+                    },
+                ],
+                ..Default::default()
+            },
+        ]
+    });
+    assert_eq!(
+        rows,
+        [
+            "poll|Integer(1)|Where to?|Beach|Integer(2)|1,2",
+            "poll|Integer(1)|Where to?|Hills|Integer(3)|2",
+            "poll|Integer(1)|Where to?|Home|Integer(0)|Null",
+            "poll|Integer(2)|Pizza?|Null|Null|Null",
+            "location|Integer(2)|Real(50.06)|Real(19.94)|Kraków|Integer(1)",
+            "location|Integer(3)|Real(-33.8568)|Real(151.2153)|Null|Integer(0)",
+            "contact|Integer(3)|Eve|+48 600 100 200|Null",
+            "contact|Integer(3)|Null|Null|x@example.com",
+        ]
+    );
+}
@@ -0,0 +1,72 @@
+mod common;
+
+use processor::importers::messenger::ImportOptions;
+use rusqlite::Connection;
+
+// Newest first, as Facebook writes them.
+const THREAD: &str = r#"{
+  "participants": [{"name": "Alice"}, {"name": "Bob"}],
+  "messages": [
+    {"sender_name": "Bob", "timestamp_ms": 1700000006000, "call_duration": 0},
+    {"sender_name": "Alice", "timestamp_ms": 1700000005000,
+     "content": "Nieodebrane połączenie wideo od Alice.", "call_duration": 0},
+    {"sender_name": "Bob", "timestamp_ms": 1700000004000,
+     "content": "The video chat ended.", "call_duration": 95},
+    {"sender_name": "Alice", "timestamp_ms": 1700000003000,
+     "content": "Bob missed your call.", "call_duration": 0},
+    {"sender_name": "Bob", "timestamp_ms": 1700000002000,
+     "content": "Bob called you.", "call_duration": 1800},
+    {"sender_name": "Alice", "timestamp_ms": 1700000001000, "content": "call me?"}
+  ],
+  "title": "Bob",
+  "thread_path": "inbox/bob_1"
+}"#;
+
+fn export_zip(name: &str) -> Vec<std::path::PathBuf> {
+    vec![common::write_zip(
+        name,
+        &[(
+            "your_facebook_activity/messages/inbox/bob_1/message_1.json",
+            THREAD.as_bytes(),
+        )],
+    )]
+}
+
+#[test]
+fn facebook_calls_are_imported() {
+    let (db, _) = common::import_in_every_mode("calls", &ImportOptions::default(), export_zip);
+    // A call without any text still gets its row.
+    assert_eq!(common::query_i64(&db, "SELECT COUNT(*) FROM message"), 6);
+
+    let conn = Connection::open(&db).unwrap();
+    let mut stmt = conn
+        .prepare(
+            "SELECT m.sent_at, c.duration_seconds, c.missed, c.call_type
+               FROM message_call c JOIN message m ON m.id = c.message_id
+              ORDER BY m.id",
+        )
+        .unwrap();
+    let calls: Vec<String> = stmt
+        .query_map([], |r| {
+            Ok(format!(
+                "{}|{}|{}|{}",
+                r.get::<_, i64>(0)?,
+                r.get::<_, i64>(1)?,
+                r.get::<_, bool>(2)?,
+                r.get::<_, String>(3)?
+            ))
+        })
+        .unwrap()
+        .map(Result::unwrap)
+        .collect();
+    assert_eq!(
+        calls,
+        [
+            "1700000002|1800|false|audio",
+            "1700000003|0|true|audio",
+            "1700000004|95|false|video",
+            "1700000005|0|true|video",
+            "1700000006|0|true|audio",
+        ]
+    );
+}
@@ -0,0 +1,125 @@
+mod common;
+
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use processor::error::ImportError;
+use processor::importers::messenger::{
+    import_messenger_exports_with_control, rollback_export, unfinished_exports, ImportOptions,
+};
+use processor::progress::{
+    CancellationToken, GlobalCallbacks, ImportPhase, ProgressSink, ProgressSnapshot,
+};
+
+const THREAD_A: &str = r#"{"participants":[{"name":"Alice"},{"name":"Bob"}],
+  "messages":[
+    {"sender_name":"Bob","timestamp_ms":1700000002000,"content":"two"},
+    {"sender_name":"Alice","timestamp_ms":1700000001000,"content":"one"}],
+  "title":"Bob","thread_path":"inbox/bob_1"}"#;
+const THREAD_B: &str = r#"{"participants":[{"name":"Alice"},{"name":"Carol"}],
+  "messages":[{"sender_name":"Carol","timestamp_ms":1700000003000,"content":"three"}],
+  "title":"Carol","thread_path":"inbox/carol_2"}"#;
+
+fn fixture_zip(name: &str) -> PathBuf {
+    common::write_zip(
+        name,
+        &[
+            (
+                "your_facebook_activity/messages/inbox/bob_1/message_1.json",
+                THREAD_A.as_bytes(),
+            ),
+            (
+                "your_facebook_activity/messages/inbox/carol_2/message_1.json",
+                THREAD_B.as_bytes(),
+            ),
+        ],
+    )
+}
+
+/// Cancels the import once the first thread file is done, like the OS killing the app.
+struct CancelAfterFirstThread(CancellationToken);
+
+impl ProgressSink for CancelAfterFirstThread {
+    fn report(&self, snapshot: &ProgressSnapshot) {
+        if snapshot.items_processed == 1 && snapshot.phase == ImportPhase::Insert {
+            self.0.cancel();
+        }
+    }
+}
+
+fn checkpointed(resume: bool) -> ImportOptions {
+    ImportOptions {
+        checkpoint: true,
+        checkpoint_messages: 1,
+        resume,
+        ..Default::default()
+    }
+}
+
+fn interrupted_import(name: &str) -> (PathBuf, PathBuf) {
+    let zip = fixture_zip(name);
+    let db = common::fresh_db(name);
+    let cancel = CancellationToken::new();
+    let result = import_messenger_exports_with_control(
+        vec![zip.clone()],
+        &db,
+        &checkpointed(false),
+        &cancel,
+        Arc::new(CancelAfterFirstThread(cancel.clone())),
+    );
+    assert_eq!(result, Err(ImportError::Cancelled));
+    (zip, db)
+}
+
+fn count(db: &Path, table: &str) -> i64 {
+    common::query_i64(db, &format!("SELECT COUNT(*) FROM {}", table))
+}
+
+#[test]
+fn interrupted_import_keeps_finished_threads_and_resumes() {
+    let (zip, db) = interrupted_import("checkpoint_resume");
+
+    // The first thread survived the interruption and is journaled.
+    assert_eq!(count(&db, "message"), 2);
+    assert_eq!(count(&db, "import_journal WHERE completed = 1"), 1);
+    let unfinished = unfinished_exports(&db).expect("unfinished");
+    assert_eq!(unfinished.len(), 1);
+
+    let report = import_messenger_exports_with_control(
+        vec![zip],
+        &db,
+        &checkpointed(true),
+        &CancellationToken::new(),
+        Arc::new(GlobalCallbacks),
+    )
+    .expect("resume");
+
+    assert_eq!(report.export_ids, unfinished);
+    assert_eq!(count(&db, "export"), 1);
+    assert_eq!(count(&db, "export WHERE status = 'complete'"), 1);
+    assert_eq!(count(&db, "conversation"), 2);
+    assert_eq!(count(&db, "message"), 3);
+    // People of the resumed export were reused, not duplicated.
+    assert_eq!(count(&db, "person"), 4);
+}
+
+#[test]
+fn interrupted_import_rolls_back_by_export_id() {
+    let (_zip, db) = interrupted_import("checkpoint_rollback");
+
+    let export_id = unfinished_exports(&db).expect("unfinished")[0];
+    rollback_export(&db, export_id).expect("rollback");
+
+    for table in [
+        "export",
+        "conversation",
+        "canonical_conversation",
+        "person",
+        "canonical_person",
+        "message",
+        "message_text",
+        "import_journal",
+    ] {
+        assert_eq!(count(&db, table), 0, "{} not empty", table);
+    }
+}
@@ -0,0 +1,187 @@
+//! Shared helpers for importer integration tests.
+//!
+//! The schema is owned by the mobile app's drizzle migrations, so test databases are
+//! built by replaying those SQL files in journal order.
+
+#![allow(dead_code)]
+
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use processor::importers::messenger::{import_messenger_exports_with_options, ImportOptions};
+use rusqlite::types::ValueRef;
+use rusqlite::Connection;
+use zip::write::FileOptions;
+
+fn scratch_dir() -> PathBuf {
+    // Keep artifacts inside the crate's target dir, like the audio tests do.
+    let mut p = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    p.push("target");
+    p.push("test-import");
+    fs::create_dir_all(&p).ok();
+    p
+}
+
+fn migrations_dir() -> PathBuf {
+    [
+        env!("CARGO_MANIFEST_DIR"),
+        "..",
+        "..",
+        "apps",
+        "mobile-client",
+        "drizzle",
+    ]
+    .iter()
+    .collect()
+}
+
+/// Create an empty database at `target/test-import/<name>.db` with all migrations applied.
+pub fn fresh_db(name: &str) -> PathBuf {
+    let path = scratch_dir().join(format!("{}.db", name));
+    for suffix in ["", "-wal", "-shm"] {
+        let _ = fs::remove_file(format!("{}{}", path.display(), suffix));
+    }
+
+    let journal: serde_json::Value = serde_json::from_str(
+        &fs::read_to_string(migrations_dir().join("meta").join("_journal.json"))
+            .expect("read drizzle journal"),
+    )
+    .expect("parse drizzle journal");
+
+    let conn = Connection::open(&path).expect("create test db");
+    for entry in journal["entries"].as_array().expect("journal entries") {
+        let tag = entry["tag"].as_str().expect("journal tag");
+        let sql = fs::read_to_string(migrations_dir().join(format!("{}.sql", tag)))
+            .expect("read migration");
+        for statement in sql.split("--> statement-breakpoint") {
+            if !statement.trim().is_empty() {
+                conn.execute_batch(statement)
+                    .unwrap_or_else(|e| panic!("migration {} failed: {}", tag, e));
+            }
+        }
+    }
+    path
+}
+
+/// Write a ZIP archive at `target/test-import/<name>.zip` containing the given entries.
+pub fn write_zip(name: &str, entries: &[(&str, &[u8])]) -> PathBuf {
+    let path = scratch_dir().join(format!("{}.zip", name));
+    let file = fs::File::create(&path).expect("create test zip");
+    let mut zip = zip::ZipWriter::new(file);
+    for (entry, data) in entries {
+        zip.start_file(*entry, FileOptions::default())
+            .expect("start zip entry");
+        zip.write_all(data).expect("write zip entry");
+    }
+    zip.finish().expect("finish test zip");
+    path
+}
+
+/// Run a single-value query against the test database.
+pub fn query_i64(db: &Path, sql: &str) -> i64 {
+    let conn = Connection::open(db).expect("open test db");
+    conn.query_row(sql, [], |r| r.get(0)).expect("query")
+}
+
+/// Every imported row that matters, as comparable strings.
+pub fn dump_rows(db: &Path) -> Vec<String> {
+    let conn = Connection::open(db).expect("open db");
+    let mut rows = Vec::new();
+    for sql in [
+        "SELECT 'person', id, conversation_id, name, is_participant FROM person ORDER BY id",
+        "SELECT 'membership', person_id, joined_at, left_at FROM person_membership
+         ORDER BY person_id, id",
+        "SELECT 'message', id, sender, sent_at, unsent, reply_to_message_id, edited, edit_count,
+                unsent_reason FROM message ORDER BY id",
+        "SELECT 'edit', message_id, previous_text, sent_at FROM message_edit
+         ORDER BY message_id, id",
+        "SELECT 'text', message_id, text FROM message_text ORDER BY message_id, id",
+        "SELECT 'image', message_id, image_uri, format, width, height, taken_at FROM message_image
+         ORDER BY message_id, id",
+        "SELECT 'gif', message_id, gif_uri, width, height, frame_count, length_ms FROM message_gif
+         ORDER BY message_id, id",
+        "SELECT 'sticker', message_id, sticker_uri, format, width, height, frame_count, is_like
+         FROM message_sticker ORDER BY message_id, id",
+        "SELECT 'video', message_id, video_uri, length_ms, width, height, codec FROM message_video
+         ORDER BY message_id, id",
+        "SELECT 'audio', message_id, audio_uri, length_seconds, length_ms, codec FROM message_audio
+         ORDER BY message_id, id",
+        "SELECT 'call', message_id, duration_seconds, missed, call_type FROM message_call
+         ORDER BY message_id, id",
+        "SELECT 'link', message_id, url, share_text, domain FROM message_link
+         ORDER BY message_id, id",
+        "SELECT 'poll', p.message_id, p.question, o.text, o.vote_count,
+                (SELECT GROUP_CONCAT(voter_id) FROM message_poll_vote WHERE option_id = o.id)
+           FROM message_poll p LEFT JOIN message_poll_option o ON o.poll_id = p.id
+          ORDER BY p.message_id, p.id, o.id",
+        "SELECT 'location', message_id, latitude, longitude, name, is_live FROM message_location
+         ORDER BY message_id, id",
+        "SELECT 'contact', message_id, name, phone_number, email FROM message_contact
+         ORDER BY message_id, id",
+        "SELECT 'source', message_id, export_id, entry_name, message_index FROM message_source
+         ORDER BY message_id",
+        "SELECT 'event', message_id, kind, subject_name, value FROM conversation_event
+         ORDER BY message_id, id",
+        "SELECT 'reaction', message_id, reactor_id, reaction FROM reaction
+         ORDER BY message_id, id",
+    ] {
+        let mut stmt = conn.prepare(sql).expect("prepare");
+        let columns = stmt.column_count();
+        let mapped = stmt
+            .query_map([], |r| {
+                (0..columns)
+                    .map(|i| {
+                        r.get_ref(i).map(|v| match v {
+                            ValueRef::Text(t) => String::from_utf8_lossy(t).into_owned(),
+                            other => format!("{:?}", other),
+                        })
+                    })
+                    .collect::<Result<Vec<_>, _>>()
+            })
+            .expect("query");
+        for row in mapped {
+            rows.push(row.expect("row").join("|"));
+        }
+    }
+    rows
+}
+
+/// Import the archives `zips` writes for a name into fresh databases three ways: sequentially,
+/// with parallel parsing and streamed. Asserts that all three hold the same rows and returns
+/// the sequential database (`<name>.db`) with its archives.
+pub fn import_in_every_mode(
+    name: &str,
+    options: &ImportOptions,
+    zips: impl Fn(&str) -> Vec<PathBuf>,
+) -> (PathBuf, Vec<PathBuf>) {
+    let import = |name: &str, options: ImportOptions| {
+        let db = fresh_db(name);
+        let archives = zips(name);
+        import_messenger_exports_with_options(archives.clone(), &db, &options)
+            .unwrap_or_else(|e| panic!("{}: {}", name, e));
+        (db, archives)
+    };
+    let sequential = import(name, options.clone());
+    let expected = dump_rows(&sequential.0);
+    for (mode, options) in [
+        (
+            "parallel",
+            ImportOptions {
+                parse_threads: 4,
+                ..options.clone()
+            },
+        ),
+        (
+            "streaming",
+            ImportOptions {
+                streaming: true,
+                ..options.clone()
+            },
+        ),
+    ] {
+        let (db, _) = import(&format!("{}_{}", name, mode), options);
+        assert_eq!(dump_rows(&db), expected, "{} differs from sequential", mode);
+    }
+    sequential
+}
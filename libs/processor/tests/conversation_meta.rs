@@ -0,0 +1,122 @@
+mod common;
+
+use processor::analytics::{most_shared_domains, StatsFilter};
+use processor::database::{ArchiveCategory, MessageDb};
+use processor::importers::messenger::{import_messenger_exports_with_options, ImportOptions};
+
+fn export_zips(name: &str) -> Vec<std::path::PathBuf> {
+    let inbox = r#"{"participants":[{"name":"Alice"},{"name":"Bob"},{"name":"Dan"}],
+  "messages":[
+    {"sender_name":"Bob","timestamp_ms":1700000001000,"share":{"link":"https://example.com/a"}}],
+  "title":"Friends","is_still_participant":false,"thread_path":"inbox/friends_1",
+  "joinable_mode":{"mode":1,"link":"https://m.me/j/AbCdEf/"}}"#;
+    let request = r#"{"participants":[{"name":"Alice"},{"name":"Spammer"}],
+  "messages":[
+    {"sender_name":"Spammer","timestamp_ms":1700000002000,"share":{"link":"https://spam.net/x"}}],
+  "title":"Spammer","thread_path":"message_requests/spammer_2"}"#;
+    let e2e = r#"{"participants":["Alice","Carol"],"threadName":"Carol_7","messages":[
+        {"senderName":"Carol","timestamp":1700000003,"text":"see https://example.org"}]}"#;
+    vec![
+        common::write_zip(
+            &format!("{}_fb", name),
+            &[
+                (
+                    "your_facebook_activity/messages/inbox/friends_1/message_1.json",
+                    inbox.as_bytes(),
+                ),
+                (
+                    "your_facebook_activity/messages/message_requests/spammer_2/message_1.json",
+                    request.as_bytes(),
+                ),
+            ],
+        ),
+        common::write_zip(
+            &format!("{}_e2e", name),
+            &[("carol_7.json", e2e.as_bytes()), ("media/x.jpg", b"x")],
+        ),
+    ]
+}
+
+#[test]
+fn conversation_metadata_is_stored() {
+    for parse_threads in [1, 4] {
+        let name = format!("conversation_meta_{}", parse_threads);
+        let db_path = common::fresh_db(&name);
+        let options = ImportOptions {
+            parse_threads,
+            ..Default::default()
+        };
+        import_messenger_exports_with_options(export_zips(&name), &db_path, &options).unwrap();
+
+        let db = MessageDb::open(&db_path).unwrap();
+        let mut stmt = db
+            .conn()
+            .prepare(
+                "SELECT name, platform_thread_id, owner_left, invite_link, archive_category
+                   FROM conversation ORDER BY id",
+            )
+            .unwrap();
+        let rows: Vec<String> = stmt
+            .query_map([], |r| {
+                Ok(format!(
+                    "{}|{}|{}|{}|{}",
+                    r.get::<_, String>(0)?,
+                    r.get::<_, String>(1)?,
+                    r.get::<_, bool>(2)?,
+                    r.get::<_, Option<String>>(3)?.unwrap_or_default(),
+                    r.get::<_, Option<String>>(4)?.unwrap_or_default()
+                ))
+            })
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(
+            rows,
+            [
+                "Friends|inbox/friends_1|true|https://m.me/j/AbCdEf/|inbox",
+                "Spammer|message_requests/spammer_2|false||message_requests",
+                "Carol|Carol_7|false||",
+            ]
+        );
+    }
+}
+
+#[test]
+fn stats_can_leave_out_message_requests() {
+    let db_path = common::fresh_db("conversation_meta_filter");
+    import_messenger_exports_with_options(
+        export_zips("conversation_meta_filter"),
+        &db_path,
+        &ImportOptions::default(),
+    )
+    .unwrap();
+    let db = MessageDb::open(&db_path).unwrap();
+    let domains = |filter: &StatsFilter| -> Vec<String> {
+        most_shared_domains(&db, filter, 10)
+            .unwrap()
+            .into_iter()
+            .map(|d| d.domain)
+            .collect()
+    };
+
+    assert_eq!(
+        domains(&StatsFilter::default()),
+        ["example.com", "example.org", "spam.net"]
+    );
+    let no_requests = StatsFilter {
+        exclude_categories: vec![ArchiveCategory::MessageRequests],
+        ..Default::default()
+    };
+    assert_eq!(domains(&no_requests), ["example.com", "example.org"]);
+    let e2e_only = StatsFilter {
+        exclude_categories: ArchiveCategory::ALL.to_vec(),
+        ..Default::default()
+    };
+    assert_eq!(domains(&e2e_only), ["example.org"]);
+
+    assert_eq!(
+        "filtered_threads".parse::<ArchiveCategory>().unwrap(),
+        ArchiveCategory::FilteredThreads
+    );
+    assert!("archive".parse::<ArchiveCategory>().is_err());
+}
@@ -0,0 +1,129 @@
+mod common;
+
+use processor::analytics::{most_edited, most_unsent, StatsFilter};
+use processor::database::MessageDb;
+use processor::importers::messenger::{import_messenger_exports_with_options, ImportOptions};
+
+fn export_zips(name: &str) -> Vec<std::path::PathBuf> {
+    // Newest first, as Facebook writes them.
+    let fb = r#"{"participants":[{"name":"Ann"},{"name":"Bob"}],
+  "messages":[
+    {"sender_name":"Bob","timestamp_ms":1700000004000,"is_unsent":true},
+    {"sender_name":"Ann","timestamp_ms":1700000003000,
+     "is_unsent_image_by_messenger_kid_parent":true},
+    {"sender_name":"Bob","timestamp_ms":1700000002000,"content":"hi",
+     "is_unsent_image_by_messenger_kid_parent":false},
+    {"sender_name":"Ann","timestamp_ms":1700000001000,"is_unsent":true}],
+  "title":"Bob","thread_path":"inbox/bob_1"}"#;
+    let e2e = r#"{"participants":["Ann","Cat"],"threadName":"Cat_2","messages":[
+        {"senderName":"Cat","timestamp":1700000010000,"text":"see you at 8",
+         "isEdited":true,"editHistory":[
+           {"text":"see you at 6","timestamp":1700000009000},
+           {"text":"see you at 7"}]},
+        {"senderName":"Ann","timestamp":1700000011000,"text":"ok!","isEdited":true},
+        {"senderName":"Cat","timestamp":1700000012000,"text":"","isUnsent":true},
+        {"senderName":"Cat","timestamp":1700000013000,"text":"typo","isEdited":true}]}"#;
+    vec![
+        common::write_zip(
+            &format!("{}_fb", name),
+            &[(
+                "your_facebook_activity/messages/inbox/bob_1/message_1.json",
+                fb.as_bytes(),
+            )],
+        ),
+        common::write_zip(
+            &format!("{}_e2e", name),
+            &[("cat_2.json", e2e.as_bytes()), ("media/x.jpg", b"x")],
+        ),
+    ]
+}
+
+#[test]
+fn edits_and_unsent_reasons_are_imported() {
+    let (db_path, _) =
+        common::import_in_every_mode("edits", &ImportOptions::default(), export_zips);
+
+    let db = MessageDb::open(&db_path).unwrap();
+    let mut stmt = db
+        .conn()
+        .prepare(
+            "SELECT id, unsent, edited, edit_count, COALESCE(unsent_reason, '-')
+               FROM message ORDER BY id",
+        )
+        .unwrap();
+    let rows: Vec<String> = stmt
+        .query_map([], |r| {
+            Ok(format!(
+                "{}|{}|{}|{}|{}",
+                r.get::<_, i64>(0)?,
+                r.get::<_, bool>(1)?,
+                r.get::<_, bool>(2)?,
+                r.get::<_, i64>(3)?,
+                r.get::<_, String>(4)?
+            ))
+        })
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(
+        rows,
+        [
+            "1|true|false|0|sender",
+            "2|false|false|0|-",
+            "3|true|false|0|messenger_kid_parent",
+            "4|true|false|0|sender",
+            "5|false|true|2|-",
+            "6|false|true|1|-",
+            "7|true|false|0|sender",
+            "8|false|true|1|-",
+        ]
+    );
+    let edits: Vec<String> = common::dump_rows(&db_path)
+        .into_iter()
+        .filter(|r| r.starts_with("edit|"))
+        .collect();
+    assert_eq!(
+        edits,
+        [
+            "edit|Integer(5)|see you at 6|Integer(1700000009)",
+            "edit|Integer(5)|see you at 7|Null",
+        ]
+    );
+}
+
+#[test]
+fn most_edited_and_most_unsent() {
+    let db_path = common::fresh_db("edits_stats");
+    import_messenger_exports_with_options(
+        export_zips("edits_stats"),
+        &db_path,
+        &ImportOptions::default(),
+    )
+    .unwrap();
+    let db = MessageDb::open(&db_path).unwrap();
+
+    let edited: Vec<(String, i64, i64)> = most_edited(&db, &StatsFilter::default(), 10)
+        .unwrap()
+        .into_iter()
+        .map(|e| (e.name.unwrap(), e.edited_messages, e.edits))
+        .collect();
+    assert_eq!(
+        edited,
+        [("Cat".to_string(), 2, 3), ("Ann".to_string(), 1, 1)]
+    );
+
+    let unsent: Vec<(String, i64, i64)> = most_unsent(&db, &StatsFilter::default(), 10)
+        .unwrap()
+        .into_iter()
+        .map(|u| (u.name.unwrap(), u.unsent, u.messages))
+        .collect();
+    // Ann's image removed by a parent does not count against her.
+    assert_eq!(
+        unsent,
+        [
+            ("Ann".to_string(), 1, 2),
+            ("Bob".to_string(), 1, 2),
+            ("Cat".to_string(), 1, 3),
+        ]
+    );
+}
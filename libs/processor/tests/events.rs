@@ -0,0 +1,202 @@
+mod common;
+
+use processor::database::{ConversationEvent, EventKind};
+use processor::importers::messenger::utils::classify_event;
+use processor::importers::messenger::ImportOptions;
+
+/// `kind|subject|value` of the events of a text message from `sender`.
+fn events(sender: &str, text: &str) -> Vec<String> {
+    classify_event(sender, None, &[], Some(text))
+        .into_iter()
+        .map(|e: ConversationEvent| {
+            format!(
+                "{}|{}|{}",
+                e.kind.as_str(),
+                e.subject_name.as_deref().unwrap_or("-"),
+                e.value.as_deref().unwrap_or("-")
+            )
+        })
+        .collect()
+}
+
+#[test]
+fn english_and_polish_templates_are_classified() {
+    let cases: &[(&str, &str, &[&str])] = &[
+        (
+            "Ann Lee",
+            "Ann Lee added Bob Smith, Carol and 2 others to the group.",
+            &["member_added|Bob Smith|-", "member_added|Carol|-"],
+        ),
+        (
+            "Ann Lee",
+            "Ann Lee removed you from the group.",
+            &["member_removed|-|-"],
+        ),
+        (
+            "Ann Lee",
+            "Ann Lee left the group.",
+            &["member_left|Ann Lee|-"],
+        ),
+        (
+            "Ann Lee",
+            "Ann Lee joined the group via an invite link.",
+            &["member_added|Ann Lee|-"],
+        ),
+        (
+            "Ann Lee",
+            "You named the group Trip 2024.",
+            &["group_renamed|-|Trip 2024"],
+        ),
+        (
+            "Ann Lee",
+            "Ann Lee set the nickname for Bob Smith to Bobby.",
+            &["nickname_set|Bob Smith|Bobby"],
+        ),
+        (
+            "Ann Lee",
+            "Ann Lee set her own nickname to Annie.",
+            &["nickname_set|Ann Lee|Annie"],
+        ),
+        (
+            "Ann Lee",
+            "Ann Lee set your nickname to boss.",
+            &["nickname_set|-|boss"],
+        ),
+        (
+            "Ann Lee",
+            "Ann Lee changed the theme to Love.",
+            &["theme_changed|-|Love"],
+        ),
+        (
+            "Ann Lee",
+            "Ann Lee set the emoji to 🎉.",
+            &["emoji_changed|-|🎉"],
+        ),
+        (
+            "Ann Lee",
+            "Ann Lee started a video chat.",
+            &["call_started|-|-"],
+        ),
+        ("Ann Lee", "The video chat ended.", &["call_ended|-|-"]),
+        (
+            "Ola Nowak",
+            "Ola Nowak dodała Bartka i Cezarego do grupy.",
+            &["member_added|Bartka|-", "member_added|Cezarego|-"],
+        ),
+        (
+            "Ola Nowak",
+            "Ola Nowak usunął(ęła) Cię z grupy.",
+            &["member_removed|-|-"],
+        ),
+        (
+            "Ola Nowak",
+            "Ola Nowak opuściła grupę.",
+            &["member_left|Ola Nowak|-"],
+        ),
+        (
+            "Ola Nowak",
+            "Ola Nowak nazwała grupę Wakacje.",
+            &["group_renamed|-|Wakacje"],
+        ),
+        (
+            "Ola Nowak",
+            "Ty zmieniłeś motyw na Miłość.",
+            &["theme_changed|-|Miłość"],
+        ),
+        (
+            "Ola Nowak",
+            "Ola Nowak ustawiła pseudonim użytkownika Jan Kowalski na Janek.",
+            &["nickname_set|Jan Kowalski|Janek"],
+        ),
+        (
+            "Ola Nowak",
+            "Ola Nowak ustawiła emoji na 👍.",
+            &["emoji_changed|-|👍"],
+        ),
+        (
+            "Ola Nowak",
+            "Połączenie wideo zakończyło się.",
+            &["call_ended|-|-"],
+        ),
+        // Ordinary messages.
+        ("Ann Lee", "I left the group chat open, sorry", &[]),
+        ("Ann Lee", "Bob left the group.", &[]),
+        ("Ann Lee", "added some stuff to the group", &[]),
+    ];
+    for (sender, text, expected) in cases {
+        assert_eq!(events(sender, text), *expected, "{}", text);
+    }
+
+    // Facebook `Subscribe`/`Unsubscribe` messages list the people concerned.
+    let kinds = |t, users: &[&str]| -> Vec<(EventKind, String)> {
+        classify_event("Ann Lee", Some(t), users, Some("whatever"))
+            .into_iter()
+            .map(|e| (e.kind, e.subject_name.unwrap()))
+            .collect()
+    };
+    assert_eq!(
+        kinds("Subscribe", &["Bob", "Carol"]),
+        [
+            (EventKind::MemberAdded, "Bob".to_string()),
+            (EventKind::MemberAdded, "Carol".to_string())
+        ]
+    );
+    assert_eq!(
+        kinds("Unsubscribe", &["Ann Lee", "Bob"]),
+        [
+            (EventKind::MemberLeft, "Ann Lee".to_string()),
+            (EventKind::MemberRemoved, "Bob".to_string())
+        ]
+    );
+}
+
+#[test]
+fn event_messages_are_imported_without_text() {
+    // Newest first, as Facebook writes them.
+    let fb = r#"{"participants":[{"name":"Ann"},{"name":"Bob"},{"name":"Carol"}],
+  "messages":[
+    {"sender_name":"Carol","timestamp_ms":1700000005000,"content":"Carol left the group."},
+    {"sender_name":"Bob","timestamp_ms":1700000004000,"content":"Bob called you.",
+     "call_duration":60},
+    {"sender_name":"Bob","timestamp_ms":1700000003000,"content":"Bob named the group Trip.",
+     "type":"Generic"},
+    {"sender_name":"Ann","timestamp_ms":1700000002000,"content":"Ann added Carol to the group.",
+     "type":"Subscribe","users":[{"name":"Carol"}]},
+    {"sender_name":"Ann","timestamp_ms":1700000001000,"content":"hello"}],
+  "title":"Trip","thread_path":"inbox/trip_1"}"#;
+    let e2e = r#"{"participants":["Ann","Dan"],"threadName":"Dan_2","messages":[
+        {"senderName":"Dan","timestamp":1700000006,"text":"Dan zmienił motyw na Miłość."},
+        {"senderName":"Dan","timestamp":1700000007,"text":"tak"}]}"#;
+    let zips = |name: &str| {
+        vec![
+            common::write_zip(
+                &format!("{}_fb", name),
+                &[(
+                    "your_facebook_activity/messages/inbox/trip_1/message_1.json",
+                    fb.as_bytes(),
+                )],
+            ),
+            common::write_zip(
+                &format!("{}_e2e", name),
+                &[("dan_2.json", e2e.as_bytes()), ("media/x.jpg", b"x")],
+            ),
+        ]
+    };
+
+    let (db, _) = common::import_in_every_mode("events", &ImportOptions::default(), zips);
+    let rows: Vec<String> = common::dump_rows(&db)
+        .into_iter()
+        .filter(|r| r.starts_with("text|") || r.starts_with("event|"))
+        .collect();
+    assert_eq!(
+        rows,
+        [
+            "text|Integer(1)|hello",
+            "text|Integer(7)|tak",
+            "event|Integer(2)|member_added|Carol|Null",
+            "event|Integer(3)|group_renamed|Null|Trip",
+            "event|Integer(5)|member_left|Carol|Null",
+            "event|Integer(6)|theme_changed|Null|Miłość",
+        ]
+    );
+}
@@ -0,0 +1,224 @@
+mod common;
+
+use std::io::Cursor;
+use std::path::PathBuf;
+
+use processor::utils::image::{detect_image_info, detect_image_info_seekable, ImageInfo};
+
+// Fixtures in tests/test-image carry real headers around placeholder pixel data:
+// - rotated.jpg: 640x480 with EXIF orientation 6 (rotated 90 degrees), DateTimeOriginal
+//   and OffsetTimeOriginal in a big-endian TIFF block
+// - exif.png: 300x200 with a little-endian `eXIf` chunk without an offset
+// - animated.webp: `VP8X` 320x240 canvas with three `ANMF` frames of 100, 100 and 200 ms
+// - rotated.heic: primary item 4032x3024 with `irot` 90 degrees and an `Exif` item in `mdat`
+// - animated.gif: 120x80, three frames with delays of 10, 20 and 0 (shown as 10) centiseconds
+fn fixtures() -> Vec<(&'static str, ImageInfo)> {
+    vec![
+        (
+            "rotated.jpg",
+            ImageInfo {
+                width: Some(480),
+                height: Some(640),
+                taken_at: Some("2023-07-14T18:30:05+02:00".to_string()),
+                ..ImageInfo::new("jpeg")
+            },
+        ),
+        (
+            "exif.png",
+            ImageInfo {
+                width: Some(300),
+                height: Some(200),
+                taken_at: Some("2021-02-03T04:05:06".to_string()),
+                ..ImageInfo::new("png")
+            },
+        ),
+        (
+            "animated.webp",
+            ImageInfo {
+                width: Some(320),
+                height: Some(240),
+                frame_count: Some(3),
+                duration_ms: Some(400),
+                ..ImageInfo::new("webp")
+            },
+        ),
+        (
+            "rotated.heic",
+            ImageInfo {
+                width: Some(3024),
+                height: Some(4032),
+                taken_at: Some("2024-01-02T03:04:05-05:00".to_string()),
+                ..ImageInfo::new("heic")
+            },
+        ),
+        (
+            "animated.gif",
+            ImageInfo {
+                width: Some(120),
+                height: Some(80),
+                frame_count: Some(3),
+                duration_ms: Some(400),
+                ..ImageInfo::new("gif")
+            },
+        ),
+    ]
+}
+
+fn fixture(name: &str) -> PathBuf {
+    [env!("CARGO_MANIFEST_DIR"), "tests", "test-image", name]
+        .iter()
+        .collect()
+}
+
+#[test]
+fn image_info_from_files() {
+    for (name, expected) in fixtures() {
+        let mut f = std::fs::File::open(fixture(name)).unwrap();
+        assert_eq!(
+            detect_image_info_seekable(&mut f),
+            Some(expected),
+            "{}",
+            name
+        );
+    }
+}
+
+#[test]
+fn image_info_from_zip_entries() {
+    let fixtures = fixtures();
+    let contents: Vec<Vec<u8>> = fixtures
+        .iter()
+        .map(|(name, _)| std::fs::read(fixture(name)).unwrap())
+        .collect();
+    let entries: Vec<(&str, &[u8])> = fixtures
+        .iter()
+        .map(|(name, _)| *name)
+        .zip(contents.iter().map(Vec::as_slice))
+        .collect();
+    let zip_path = common::write_zip("image_info", &entries);
+    let mut archive = zip::ZipArchive::new(std::fs::File::open(&zip_path).unwrap()).unwrap();
+    for (name, expected) in fixtures {
+        let mut entry = archive.by_name(name).unwrap();
+        assert_eq!(detect_image_info(&mut entry), Some(expected), "{}", name);
+    }
+}
+
+#[test]
+fn image_format_is_sniffed_not_named() {
+    // Only the bytes decide the format; there is no name to go by.
+    let png = std::fs::read(fixture("exif.png")).unwrap();
+    assert_eq!(
+        detect_image_info(&mut Cursor::new(png)).map(|i| i.format),
+        Some("png")
+    );
+    assert_eq!(
+        detect_image_info(&mut Cursor::new(b"not a real image".to_vec())),
+        None
+    );
+    // A still GIF has one frame and no animation length.
+    let still = b"GIF89a\x02\x00\x01\x00\x00\x00\x00\x2c\x00\x00\x00\x00\x02\x00\x01\x00\x00\x02\x02\x4c\x01\x00\x3b";
+    assert_eq!(
+        detect_image_info(&mut Cursor::new(still.to_vec())),
+        Some(ImageInfo {
+            width: Some(2),
+            height: Some(1),
+            frame_count: Some(1),
+            ..ImageInfo::new("gif")
+        })
+    );
+}
+
+#[test]
+fn imported_images_store_format_size_and_animation() {
+    let read = |name: &str| std::fs::read(fixture(name)).unwrap();
+    let (jpg, png, webp, heic, gif) = (
+        read("rotated.jpg"),
+        read("exif.png"),
+        read("animated.webp"),
+        read("rotated.heic"),
+        read("animated.gif"),
+    );
+    let dir = "your_facebook_activity/messages/inbox/bob_1";
+    let fb = format!(
+        r#"{{"participants":[{{"name":"Alice"}},{{"name":"Bob"}}],
+  "messages":[
+    {{"sender_name":"Bob","timestamp_ms":1700000002000,
+      "photos":[{{"uri":"{dir}/photos/rotated.jpg"}},{{"uri":"{dir}/photos/elsewhere.png"}}]}},
+    {{"sender_name":"Alice","timestamp_ms":1700000001000,"gifs":[{{"uri":"{dir}/gifs/a.gif"}}]}}],
+  "title":"Bob","thread_path":"inbox/bob_1"}}"#
+    );
+    let e2e = r#"{"participants":["Alice","Bob"],"threadName":"Bob_2","messages":[
+        {"senderName":"Bob","timestamp":1700000003,"text":"",
+         "media":[{"uri":"media/photo.heic"},{"uri":"media/anim.webp"},
+                  {"uri":"media/broken.jpg"},{"uri":"media/anim.gif"}]}]}"#;
+    let fb_json = format!("{}/message_1.json", dir);
+    let fb_photo = format!("{}/photos/rotated.jpg", dir);
+    let fb_gif = format!("{}/gifs/a.gif", dir);
+    // The second FB photo lives in another archive and resolves through the file index.
+    let fb_elsewhere = format!("{}/photos/elsewhere.png", dir);
+    let zips = vec![
+        common::write_zip(
+            "imported_images_fb",
+            &[
+                (fb_json.as_str(), fb.as_bytes()),
+                (fb_photo.as_str(), &jpg),
+                (fb_gif.as_str(), &gif),
+            ],
+        ),
+        common::write_zip("imported_images_fb_media", &[(fb_elsewhere.as_str(), &png)]),
+        common::write_zip(
+            "imported_images_e2e",
+            &[
+                ("bob_2.json", e2e.as_bytes()),
+                ("media/photo.heic", &heic),
+                ("media/anim.webp", &webp),
+                ("media/broken.jpg", b"not a real image"),
+                ("media/anim.gif", &gif),
+            ],
+        ),
+    ];
+    for parse_threads in [1, 4] {
+        let db = common::fresh_db(&format!("imported_images_{}", parse_threads));
+        let options = processor::importers::messenger::ImportOptions {
+            parse_threads,
+            ..Default::default()
+        };
+        processor::importers::messenger::import_messenger_exports_with_options(
+            zips.clone(),
+            &db,
+            &options,
+        )
+        .expect("import");
+
+        let rows: Vec<String> = common::dump_rows(&db)
+            .into_iter()
+            .filter(|r| r.starts_with("image|") || r.starts_with("gif|"))
+            .map(|r| {
+                let mut parts = r.splitn(3, '|');
+                format!("{}|{}", parts.next().unwrap(), parts.nth(1).unwrap())
+            })
+            .collect();
+        assert_eq!(
+            rows,
+            [
+                format!(
+                    "image|{}|jpeg|Integer(480)|Integer(640)|2023-07-14T18:30:05+02:00",
+                    fb_photo
+                ),
+                format!(
+                    "image|{}|png|Integer(300)|Integer(200)|2021-02-03T04:05:06",
+                    fb_elsewhere
+                ),
+                "image|media/photo.heic|heic|Integer(3024)|Integer(4032)|2024-01-02T03:04:05-05:00"
+                    .to_string(),
+                "image|media/anim.webp|webp|Integer(320)|Integer(240)|Null".to_string(),
+                "image|media/broken.jpg|Null|Null|Null|Null".to_string(),
+                format!(
+                    "gif|{}|Integer(120)|Integer(80)|Integer(3)|Integer(400)",
+                    fb_gif
+                ),
+                "gif|media/anim.gif|Integer(120)|Integer(80)|Integer(3)|Integer(400)".to_string(),
+            ]
+        );
+    }
+}
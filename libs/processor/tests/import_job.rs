@@ -0,0 +1,97 @@
+mod common;
+
+use std::sync::Arc;
+
+use processor::error::ImportError;
+use processor::ffi::{
+    processor_import_job_cancel, processor_import_job_free, processor_import_job_poll,
+    processor_string_free, ProcessorJobState,
+};
+use processor::importers::messenger::{
+    import_messenger_exports_with_control, ImportJob, ImportOptions, JobState,
+};
+use processor::progress::{self, CancellationToken, GlobalCallbacks, ImportPhase};
+
+const THREAD: &str = r#"{"participants":[{"name":"Alice"},{"name":"Bob"}],
+  "messages":[{"sender_name":"Bob","timestamp_ms":1700000000000,"content":"hi"}],
+  "title":"Bob","thread_path":"inbox/bob_1"}"#;
+
+fn fixture_zip(name: &str) -> std::path::PathBuf {
+    common::write_zip(
+        name,
+        &[(
+            "your_facebook_activity/messages/inbox/bob_1/message_1.json",
+            THREAD.as_bytes(),
+        )],
+    )
+}
+
+#[test]
+fn job_runs_independently_of_global_cancel() {
+    let zip = fixture_zip("job_independent");
+    let db = common::fresh_db("job_independent");
+
+    // A cancel aimed at legacy, process-wide imports must not reach the job.
+    progress::request_cancel();
+    let job = ImportJob::start(vec![zip], db.clone(), ImportOptions::default()).expect("start");
+    let report = loop {
+        let status = job.poll();
+        if status.state != JobState::Running {
+            assert_eq!(status.state, JobState::Succeeded, "{:?}", status.error);
+            assert_eq!(status.progress.phase, ImportPhase::PostProcess);
+            assert_eq!(status.progress.items_processed, 1);
+            break status.report.expect("report");
+        }
+        std::thread::yield_now();
+    };
+    progress::clear_cancel();
+
+    assert_eq!(report.export_ids.len(), 1);
+    assert_eq!(common::query_i64(&db, "SELECT COUNT(*) FROM message"), 1);
+    assert_eq!(job.wait().expect("outcome"), report);
+}
+
+#[test]
+fn cancelled_token_aborts_only_its_import() {
+    let cancelled = CancellationToken::new();
+    cancelled.cancel();
+    let db_a = common::fresh_db("job_cancelled_a");
+    let db_b = common::fresh_db("job_cancelled_b");
+
+    let result = import_messenger_exports_with_control(
+        vec![fixture_zip("job_cancelled_a")],
+        &db_a,
+        &ImportOptions::default(),
+        &cancelled,
+        Arc::new(GlobalCallbacks),
+    );
+    assert_eq!(result, Err(ImportError::Cancelled));
+    assert_eq!(common::query_i64(&db_a, "SELECT COUNT(*) FROM message"), 0);
+
+    import_messenger_exports_with_control(
+        vec![fixture_zip("job_cancelled_b")],
+        &db_b,
+        &ImportOptions::default(),
+        &CancellationToken::new(),
+        Arc::new(GlobalCallbacks),
+    )
+    .expect("independent import");
+    assert_eq!(common::query_i64(&db_b, "SELECT COUNT(*) FROM message"), 1);
+}
+
+#[test]
+fn null_job_handle_is_rejected() {
+    let mut status = std::ptr::null_mut();
+    unsafe {
+        processor_import_job_cancel(std::ptr::null());
+        let state = processor_import_job_poll(std::ptr::null(), &mut status);
+        assert_eq!(state, ProcessorJobState::Failed);
+        let json = std::ffi::CStr::from_ptr(status)
+            .to_str()
+            .unwrap()
+            .to_owned();
+        processor_string_free(status);
+        assert!(json.contains("invalid_argument"), "{json}");
+        processor_import_job_free(std::ptr::null_mut());
+    }
+}
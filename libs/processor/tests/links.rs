@@ -0,0 +1,164 @@
+mod common;
+
+use processor::analytics::{
+    most_shared_domains, top_link_sharers, DomainCount, LinkSharer, StatsFilter,
+};
+use processor::database::MessageDb;
+use processor::importers::messenger::{import_messenger_exports_with_options, ImportOptions};
+use processor::utils::links::{find_urls, url_domain};
+
+#[test]
+fn urls_are_found_and_domains_normalized() {
+    assert_eq!(
+        find_urls("see https://example.com/a_(b)), www.Example.org. and (http://x.io/p?q=1)!"),
+        [
+            "https://example.com/a_(b)",
+            "www.Example.org",
+            "http://x.io/p?q=1"
+        ]
+    );
+    assert!(find_urls("no links here, just e.g. and i.e.").is_empty());
+    assert!(find_urls("https://localhost/path").is_empty());
+
+    for (url, domain) in [
+        ("https://www.YouTube.com/watch?v=1", Some("youtube.com")),
+        ("http://m.facebook.com/story.php", Some("facebook.com")),
+        (
+            "https://user:pw@news.example.co.uk:8080/x",
+            Some("news.example.co.uk"),
+        ),
+        ("www.example.org", Some("example.org")),
+        ("https://m.me/bob", Some("m.me")),
+        ("https://example.com./", Some("example.com")),
+        ("not a url", None),
+    ] {
+        assert_eq!(url_domain(url).as_deref(), domain, "{}", url);
+    }
+}
+
+fn export_zips(name: &str) -> Vec<std::path::PathBuf> {
+    // Newest first, as Facebook writes them.
+    let fb = r#"{"participants":[{"name":"Alice"},{"name":"Bob"}],
+  "messages":[
+    {"sender_name":"Bob","timestamp_ms":1700000004000,"share":{"share_text":"A post"}},
+    {"sender_name":"Bob","timestamp_ms":1700000003000,
+     "share":{"link":"https://m.youtube.com/watch?v=2"}},
+    {"sender_name":"Alice","timestamp_ms":1700000002000,"content":"watch this",
+     "share":{"link":"https://www.youtube.com/watch?v=1","share_text":"Funny cats"}},
+    {"sender_name":"Alice","timestamp_ms":1700000001000,"content":"hi"}],
+  "title":"Bob","thread_path":"inbox/bob_1"}"#;
+    let e2e = r#"{"participants":["Alice","Carol"],"threadName":"Carol_2","messages":[
+        {"senderName":"Carol","timestamp":1700000005,
+         "text":"look https://example.com/a, and www.example.com/b."},
+        {"senderName":"Alice","timestamp":1700000006,"text":"nice"}]}"#;
+    vec![
+        common::write_zip(
+            &format!("{}_fb", name),
+            &[(
+                "your_facebook_activity/messages/inbox/bob_1/message_1.json",
+                fb.as_bytes(),
+            )],
+        ),
+        common::write_zip(
+            &format!("{}_e2e", name),
+            &[
+                ("carol_2.json", e2e.as_bytes()),
+                ("media/pic.jpg", b"not a real image"),
+            ],
+        ),
+    ]
+}
+
+#[test]
+fn links_are_imported_apart_from_text() {
+    for parse_threads in [1, 4] {
+        let name = format!("links_import_{}", parse_threads);
+        let db = common::fresh_db(&name);
+        let options = ImportOptions {
+            parse_threads,
+            ..Default::default()
+        };
+        import_messenger_exports_with_options(export_zips(&name), &db, &options).unwrap();
+
+        let rows: Vec<String> = common::dump_rows(&db)
+            .into_iter()
+            .filter(|r| r.starts_with("text|") || r.starts_with("link|"))
+            .collect();
+        assert_eq!(
+            rows,
+            [
+                "text|Integer(1)|hi",
+                "text|Integer(2)|watch this",
+                "text|Integer(4)|A post",
+                "text|Integer(5)|look https://example.com/a, and www.example.com/b.",
+                "text|Integer(6)|nice",
+                "link|Integer(2)|https://www.youtube.com/watch?v=1|Funny cats|youtube.com",
+                "link|Integer(3)|https://m.youtube.com/watch?v=2|Null|youtube.com",
+                "link|Integer(5)|https://example.com/a|Null|example.com",
+                "link|Integer(5)|www.example.com/b|Null|example.com",
+            ]
+        );
+    }
+}
+
+#[test]
+fn link_stats_rank_domains_and_sharers() {
+    let db_path = common::fresh_db("links_stats");
+    import_messenger_exports_with_options(
+        export_zips("links_stats"),
+        &db_path,
+        &ImportOptions::default(),
+    )
+    .unwrap();
+    let db = MessageDb::open(&db_path).unwrap();
+    let all = StatsFilter::default();
+
+    let domain = |domain: &str, links, sharers| DomainCount {
+        domain: domain.to_string(),
+        links,
+        sharers,
+    };
+    assert_eq!(
+        most_shared_domains(&db, &all, 10).unwrap(),
+        [domain("example.com", 2, 1), domain("youtube.com", 2, 2)]
+    );
+    assert_eq!(
+        most_shared_domains(&db, &all, 1).unwrap(),
+        [domain("example.com", 2, 1)]
+    );
+
+    let sharers: Vec<(String, i64, i64)> = top_link_sharers(&db, &all, 10)
+        .unwrap()
+        .into_iter()
+        .map(|s: LinkSharer| (s.name.unwrap(), s.links, s.domains))
+        .collect();
+    assert_eq!(
+        sharers,
+        [
+            ("Carol".to_string(), 2, 1),
+            ("Alice".to_string(), 1, 1),
+            ("Bob".to_string(), 1, 1),
+        ]
+    );
+
+    let fb_conversation = common::query_i64(
+        &db_path,
+        "SELECT canonical_conversation_id FROM conversation WHERE name = 'Bob'",
+    );
+    let fb_only = StatsFilter {
+        canonical_conversation_id: Some(fb_conversation),
+        ..Default::default()
+    };
+    assert_eq!(
+        most_shared_domains(&db, &fb_only, 10).unwrap(),
+        [domain("youtube.com", 2, 2)]
+    );
+    let before_bob_shared = StatsFilter {
+        until: Some(1_700_000_003),
+        ..Default::default()
+    };
+    assert_eq!(
+        most_shared_domains(&db, &before_bob_shared, 10).unwrap(),
+        [domain("youtube.com", 1, 1)]
+    );
+}
@@ -0,0 +1,132 @@
+mod common;
+
+use processor::importers::messenger::utils::{build_file_index, MAX_OPEN_ARCHIVES};
+use processor::importers::messenger::{
+    import_messenger_exports_with_options, ImportOptions, MissingMedia,
+};
+use processor::progress::CancellationToken;
+
+/// `s` as Facebook writes it in thread files: each UTF-8 byte as one Latin-1 character.
+fn mojibake(s: &str) -> String {
+    s.bytes().map(char::from).collect()
+}
+
+#[test]
+fn resolver_normalizes_lookups() {
+    let photo = "messages/inbox/ola_1/photos/Zdjęcie_1.JPG";
+    let zip = common::write_zip(
+        "resolver_normalizes_lookups",
+        &[
+            ("messages/inbox/ola_1/message_1.json", b"{}"),
+            (photo, b"photo"),
+            ("./media/voice.opus", b"voice"),
+        ],
+    );
+    let index = build_file_index(&[zip], &CancellationToken::new()).unwrap();
+    assert_eq!(index.len(), 2, "thread JSON is not an attachment");
+
+    for uri in [
+        photo.to_string(),
+        format!("./{}", photo),
+        mojibake(photo),
+        photo.to_lowercase(),
+        mojibake(&photo.to_uppercase()),
+    ] {
+        let loc = index.resolve(&uri).unwrap_or_else(|| panic!("{}", uri));
+        assert_eq!(loc.entry_name, photo, "{}", uri);
+    }
+    for uri in ["media/voice.opus", "./media/voice.opus", "MEDIA/Voice.opus"] {
+        assert_eq!(index.resolve(uri).unwrap().entry_name, "./media/voice.opus");
+    }
+    assert!(!index.contains("messages/inbox/ola_1/photos/other.jpg"));
+    assert!(!index.contains("messages/inbox/ola_1/message_1.json"));
+}
+
+#[test]
+fn resolver_reads_from_more_archives_than_it_keeps_open() {
+    let zips: Vec<_> = (0..MAX_OPEN_ARCHIVES + 3)
+        .map(|i| {
+            let name = format!("media/{}.jpg", i);
+            let body = format!("archive {}", i);
+            common::write_zip(
+                &format!("resolver_lru_{}", i),
+                &[(name.as_str(), body.as_bytes())],
+            )
+        })
+        .collect();
+    let mut index = build_file_index(&zips, &CancellationToken::new()).unwrap();
+    // Every pass cycles through more archives than stay open.
+    for _ in 0..2 {
+        for i in (0..zips.len()).rev() {
+            let body = index
+                .with_file(&format!("media/{}.jpg", i), |r| {
+                    let mut s = String::new();
+                    r.read_to_string(&mut s).unwrap();
+                    s
+                })
+                .unwrap();
+            assert_eq!(body, format!("archive {}", i));
+            let loc = index.resolve(&format!("media/{}.jpg", i)).unwrap();
+            assert_eq!(index.archive_path(loc), zips[i]);
+        }
+    }
+}
+
+#[test]
+fn import_reports_missing_media_once() {
+    let dir = "your_facebook_activity/messages/inbox/bob_1";
+    let thread = format!(
+        r#"{{"participants":[{{"name":"Alice"}},{{"name":"Bob"}}],
+  "messages":[
+    {{"sender_name":"Bob","timestamp_ms":1700000003000,
+      "sticker":{{"uri":"your_facebook_activity/messages/stickers_used/like.png"}}}},
+    {{"sender_name":"Bob","timestamp_ms":1700000002000,
+      "photos":[{{"uri":"{dir}/photos/here.jpg"}},{{"uri":"{dir}/photos/gone.jpg"}}],
+      "audio_files":[{{"uri":"{dir}/audio/gone.mp4"}}]}},
+    {{"sender_name":"Alice","timestamp_ms":1700000001000,
+      "sticker":{{"uri":"your_facebook_activity/messages/stickers_used/like.png"}}}}],
+  "title":"Bob","thread_path":"inbox/bob_1"}}"#
+    );
+    let json_path = format!("{}/message_1.json", dir);
+    let here = format!("{}/photos/here.jpg", dir);
+    let zip = common::write_zip(
+        "import_reports_missing_media",
+        &[
+            (json_path.as_str(), thread.as_bytes()),
+            (here.as_str(), b"not a real image"),
+        ],
+    );
+    for parse_threads in [1, 4] {
+        let db = common::fresh_db(&format!("import_reports_missing_media_{}", parse_threads));
+        let options = ImportOptions {
+            parse_threads,
+            ..Default::default()
+        };
+        let report =
+            import_messenger_exports_with_options(vec![zip.clone()], &db, &options).unwrap();
+        let missing = |kind, uri: &str| MissingMedia {
+            uri: uri.to_string(),
+            kind,
+        };
+        assert_eq!(
+            report.missing_media,
+            [
+                missing(
+                    "sticker",
+                    "your_facebook_activity/messages/stickers_used/like.png"
+                ),
+                missing("image", &format!("{}/photos/gone.jpg", dir)),
+                missing("audio", &format!("{}/audio/gone.mp4", dir)),
+            ]
+        );
+        // Rows of missing attachments are imported all the same.
+        assert_eq!(
+            common::query_i64(&db, "SELECT COUNT(*) FROM message_image"),
+            2
+        );
+        assert_eq!(
+            common::query_i64(&db, "SELECT COUNT(*) FROM message_sticker"),
+            2
+        );
+    }
+}
@@ -0,0 +1,205 @@
+mod common;
+
+use std::path::{Path, PathBuf};
+
+use processor::importers::messenger::utils::MediaStoreOptions;
+use processor::importers::messenger::{
+    import_messenger_exports_with_options, ImportOptions, ImportReport,
+};
+use rusqlite::Connection;
+use sha2::{Digest, Sha256};
+
+const DIR: &str = "your_facebook_activity/messages/inbox/bob_1";
+
+/// Store name of `content` saved with `extension`.
+fn stored_name(content: &[u8], extension: &str) -> String {
+    let hash: String = Sha256::digest(content)
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect();
+    format!("{}/{}.{}", &hash[..2], hash, extension)
+}
+
+/// One thread sending the same photo twice under different names, a bigger photo, a
+/// video and a voice clip, all in one archive.
+fn export_zip() -> PathBuf {
+    let thread = format!(
+        r#"{{"participants":[{{"name":"Alice"}},{{"name":"Bob"}}],
+  "messages":[
+    {{"sender_name":"Bob","timestamp_ms":1700000004000,"audio_files":[{{"uri":"{DIR}/audio/voice.m4a"}}]}},
+    {{"sender_name":"Bob","timestamp_ms":1700000003000,"videos":[{{"uri":"{DIR}/videos/clip.mp4"}}]}},
+    {{"sender_name":"Alice","timestamp_ms":1700000002000,
+      "photos":[{{"uri":"{DIR}/photos/big.png"}},{{"uri":"{DIR}/photos/copy.JPG"}}]}},
+    {{"sender_name":"Bob","timestamp_ms":1700000001000,"photos":[{{"uri":"{DIR}/photos/cat.jpg"}}]}}],
+  "title":"Bob","thread_path":"inbox/bob_1"}}"#
+    );
+    let json_path = format!("{}/message_1.json", DIR);
+    let entries: Vec<(String, Vec<u8>)> = vec![
+        (json_path, thread.into_bytes()),
+        (format!("{}/photos/cat.jpg", DIR), b"cat photo".to_vec()),
+        (format!("{}/photos/copy.JPG", DIR), b"cat photo".to_vec()),
+        (format!("{}/photos/big.png", DIR), vec![7; 4000]),
+        (format!("{}/videos/clip.mp4", DIR), b"video bytes".to_vec()),
+        (format!("{}/audio/voice.m4a", DIR), b"voice bytes".to_vec()),
+    ];
+    let entries: Vec<(&str, &[u8])> = entries
+        .iter()
+        .map(|(name, body)| (name.as_str(), body.as_slice()))
+        .collect();
+    common::write_zip("media_store_export", &entries)
+}
+
+fn import(name: &str, parse_threads: usize, store: MediaStoreOptions) -> (PathBuf, ImportReport) {
+    let db = common::fresh_db(name);
+    let options = ImportOptions {
+        parse_threads,
+        media_store: Some(store),
+        ..Default::default()
+    };
+    let report =
+        import_messenger_exports_with_options(vec![export_zip()], &db, &options).expect("import");
+    (db, report)
+}
+
+fn store_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("chat_stats_media_{}", name));
+    let _ = std::fs::remove_dir_all(&dir);
+    dir
+}
+
+/// `uri|stored_path` of every attachment, in import order.
+fn stored_paths(db: &Path) -> Vec<String> {
+    let conn = Connection::open(db).unwrap();
+    let mut rows = Vec::new();
+    for (table, column) in [
+        ("message_image", "image_uri"),
+        ("message_video", "video_uri"),
+        ("message_audio", "audio_uri"),
+    ] {
+        let sql = format!(
+            "SELECT {}, coalesce(stored_path, '-') FROM {} ORDER BY message_id, id",
+            column, table
+        );
+        let mut stmt = conn.prepare(&sql).unwrap();
+        let mapped = stmt
+            .query_map([], |r| {
+                Ok(format!(
+                    "{}|{}",
+                    r.get::<_, String>(0)?.rsplit('/').next().unwrap(),
+                    r.get::<_, String>(1)?
+                ))
+            })
+            .unwrap();
+        rows.extend(mapped.map(Result::unwrap));
+    }
+    rows
+}
+
+#[test]
+fn media_is_extracted_once_per_content() {
+    for parse_threads in [1, 4] {
+        let dir = store_dir(&format!("all_{}", parse_threads));
+        let store = MediaStoreOptions {
+            dir: dir.clone(),
+            ..Default::default()
+        };
+        let (db, report) = import(
+            &format!("media_store_all_{}", parse_threads),
+            parse_threads,
+            store,
+        );
+
+        let cat = stored_name(b"cat photo", "jpg");
+        let big = stored_name(&[7; 4000], "png");
+        let video = stored_name(b"video bytes", "mp4");
+        let voice = stored_name(b"voice bytes", "m4a");
+        assert_eq!(
+            stored_paths(&db),
+            [
+                format!("cat.jpg|{}", cat),
+                format!("big.png|{}", big),
+                // Same content as cat.jpg: the stored copy is shared.
+                format!("copy.JPG|{}", cat),
+                format!("clip.mp4|{}", video),
+                format!("voice.m4a|{}", voice),
+            ]
+        );
+        for path in [&cat, &big, &video, &voice] {
+            assert!(dir.join(path).is_file(), "{}", path);
+        }
+        assert_eq!(
+            std::fs::read(dir.join(&cat)).unwrap(),
+            b"cat photo".to_vec()
+        );
+        assert_eq!(report.stored_media_bytes, 9 + 4000 + 11 + 11);
+
+        // A second import finds everything stored already and adds nothing.
+        let store = MediaStoreOptions {
+            dir: dir.clone(),
+            ..Default::default()
+        };
+        let (again, report) = import(
+            &format!("media_store_again_{}", parse_threads),
+            parse_threads,
+            store,
+        );
+        assert_eq!(stored_paths(&again), stored_paths(&db));
+        assert_eq!(report.stored_media_bytes, 0);
+    }
+}
+
+#[test]
+fn media_store_respects_budget_and_kinds() {
+    let dir = store_dir("budget");
+    let store = MediaStoreOptions {
+        dir: dir.clone(),
+        // Fits the small files but not big.png.
+        max_bytes: 1000,
+        exclude: vec!["audio".to_string()],
+        ..Default::default()
+    };
+    let (db, report) = import("media_store_budget", 1, store);
+    assert_eq!(
+        stored_paths(&db),
+        [
+            format!("cat.jpg|{}", stored_name(b"cat photo", "jpg")),
+            "big.png|-".to_string(),
+            format!("copy.JPG|{}", stored_name(b"cat photo", "jpg")),
+            format!("clip.mp4|{}", stored_name(b"video bytes", "mp4")),
+            "voice.m4a|-".to_string(),
+        ]
+    );
+    assert_eq!(report.stored_media_bytes, 9 + 11);
+
+    let store = MediaStoreOptions {
+        dir: store_dir("only_video"),
+        include: vec!["video".to_string()],
+        ..Default::default()
+    };
+    let (db, _) = import("media_store_only_video", 1, store);
+    let stored: Vec<String> = stored_paths(&db)
+        .into_iter()
+        .filter(|r| !r.ends_with("|-"))
+        .collect();
+    assert_eq!(
+        stored,
+        [format!("clip.mp4|{}", stored_name(b"video bytes", "mp4"))]
+    );
+
+    let db = common::fresh_db("media_store_bad_kind");
+    let options = ImportOptions {
+        media_store: Some(MediaStoreOptions {
+            dir: store_dir("bad_kind"),
+            include: vec!["photo".to_string()],
+            ..Default::default()
+        }),
+        ..Default::default()
+    };
+    let err = import_messenger_exports_with_options(vec![export_zip()], &db, &options)
+        .expect_err("unknown kind");
+    assert!(
+        err.to_string().contains("Unknown media kind 'photo'"),
+        "{}",
+        err
+    );
+}
@@ -0,0 +1,109 @@
+mod common;
+
+use processor::analytics::{memberships, participation_rates, StatsFilter};
+use processor::database::MessageDb;
+use processor::importers::messenger::{import_messenger_exports_with_options, ImportOptions};
+
+/// A group where Carol leaves, Dan is added, and Bob is removed and added back. Eve is not
+/// listed and never shows up in an event.
+fn export_zip(name: &str) -> std::path::PathBuf {
+    // Newest first, as Facebook writes them.
+    let fb = r#"{"participants":[{"name":"Ann"},{"name":"Bob"},{"name":"Dan"}],
+  "messages":[
+    {"sender_name":"Bob","timestamp_ms":1008000,"content":"back"},
+    {"sender_name":"Ann","timestamp_ms":1007000,"content":"Ann added Bob to the group."},
+    {"sender_name":"Ann","timestamp_ms":1006000,"content":"Ann removed Bob from the group."},
+    {"sender_name":"Dan","timestamp_ms":1005000,"content":"thanks"},
+    {"sender_name":"Ann","timestamp_ms":1004000,"content":"Ann added Dan to the group.",
+     "type":"Subscribe","users":[{"name":"Dan"}]},
+    {"sender_name":"Carol","timestamp_ms":1003000,"content":"Carol left the group."},
+    {"sender_name":"Eve","timestamp_ms":1002000,"content":"sup"},
+    {"sender_name":"Bob","timestamp_ms":1002000,"content":"yo"},
+    {"sender_name":"Carol","timestamp_ms":1001000,"content":"hey"},
+    {"sender_name":"Ann","timestamp_ms":1000000,"content":"hi"}],
+  "title":"Trip","thread_path":"inbox/trip_1"}"#;
+    common::write_zip(
+        name,
+        &[(
+            "your_facebook_activity/messages/inbox/trip_1/message_1.json",
+            fb.as_bytes(),
+        )],
+    )
+}
+
+type Interval = (String, Option<i64>, Option<i64>);
+
+fn intervals(db_path: &std::path::Path) -> Vec<Interval> {
+    let conversation = common::query_i64(
+        db_path,
+        "SELECT canonical_conversation_id FROM conversation WHERE name = 'Trip'",
+    );
+    let db = MessageDb::open(db_path).unwrap();
+    memberships(&db, conversation)
+        .unwrap()
+        .into_iter()
+        .map(|m| (m.name.unwrap(), m.joined_at, m.left_at))
+        .collect()
+}
+
+#[test]
+fn memberships_follow_participants_and_events() {
+    let (db_path, _) =
+        common::import_in_every_mode("membership", &ImportOptions::default(), |name| {
+            vec![export_zip(name)]
+        });
+    assert_eq!(
+        common::query_i64(&db_path, "SELECT COUNT(*) FROM person WHERE is_participant"),
+        3
+    );
+    let s = |name: &str, joined_at, left_at| (name.to_string(), joined_at, left_at);
+    assert_eq!(
+        intervals(&db_path),
+        [
+            s("Ann", None, None),
+            s("Bob", None, Some(1006)),
+            s("Carol", None, Some(1003)),
+            s("Eve", None, Some(1002)),
+            s("Dan", Some(1004), None),
+            s("Bob", Some(1007), None),
+        ]
+    );
+}
+
+#[test]
+fn participation_is_normalized_by_membership() {
+    let db_path = common::fresh_db("membership_rates");
+    import_messenger_exports_with_options(
+        vec![export_zip("membership_rates")],
+        &db_path,
+        &ImportOptions::default(),
+    )
+    .unwrap();
+    let db = MessageDb::open(&db_path).unwrap();
+
+    let rates: Vec<(String, i64, i64)> = participation_rates(&db, &StatsFilter::default(), 10)
+        .unwrap()
+        .into_iter()
+        .map(|p| (p.name.unwrap(), p.messages, p.group_messages))
+        .collect();
+    let r = |name: &str, messages, group_messages| (name.to_string(), messages, group_messages);
+    assert_eq!(
+        rates,
+        [
+            r("Dan", 1, 2),
+            r("Bob", 2, 6),
+            r("Carol", 1, 4),
+            r("Eve", 1, 4),
+            r("Ann", 1, 6),
+        ]
+    );
+
+    let after_carol_left = StatsFilter {
+        since: Some(1004),
+        ..Default::default()
+    };
+    let top = participation_rates(&db, &after_carol_left, 1).unwrap();
+    assert_eq!(top.len(), 1);
+    assert_eq!(top[0].name.as_deref(), Some("Bob"));
+    assert_eq!(top[0].share, 0.5);
+}
@@ -0,0 +1,106 @@
+mod common;
+
+use processor::database::{MessageDb, MessageSource};
+use processor::importers::messenger::{
+    import_messenger_exports_with_options, message_source_json, ImportOptions,
+};
+use serde_json::json;
+
+/// A Facebook export (newest first) and an E2E export (oldest first), each with a malformed
+/// message between two good ones.
+fn export_zips(name: &str) -> Vec<std::path::PathBuf> {
+    let fb = r#"{"participants":[{"name":"Ann"},{"name":"Bob"}],"messages":[
+        {"sender_name":"Bob","timestamp_ms":1700000003000,"content":"later"},
+        {"sender_name":"Ann","timestamp_ms":"soon","content":"broken"},
+        {"sender_name":"Ann","timestamp_ms":1700000001000,"content":"first"}],
+      "title":"Bob","thread_path":"inbox/bob_1"}"#;
+    let e2e = r#"{"participants":["Ann","Eve"],"threadName":"Eve_2","messages":[
+        {"senderName":"Eve","timestamp":1700000010,"text":"hi"},
+        {"senderName":"Ann","timestamp":"now","text":"broken"},
+        {"senderName":"Ann","timestamp":1700000012,"text":"hello"}]}"#;
+    vec![
+        common::write_zip(
+            &format!("{}_fb", name),
+            &[(
+                "your_facebook_activity/messages/inbox/bob_1/message_1.json",
+                fb.as_bytes(),
+            )],
+        ),
+        common::write_zip(
+            &format!("{}_e2e", name),
+            &[("eve_2.json", e2e.as_bytes()), ("media/x.jpg", b"x")],
+        ),
+    ]
+}
+
+#[test]
+fn sources_point_at_the_original_json() {
+    let options = ImportOptions {
+        lenient: true,
+        record_sources: true,
+        ..Default::default()
+    };
+    let (db, zips) = common::import_in_every_mode("source", &options, export_zips);
+
+    let sources: Vec<String> = common::dump_rows(&db)
+        .into_iter()
+        .filter(|r| r.starts_with("source|"))
+        .collect();
+    assert_eq!(
+        sources,
+        [
+            "source|Integer(1)|Integer(1)|your_facebook_activity/messages/inbox/bob_1/message_1.json|Integer(2)",
+            "source|Integer(2)|Integer(1)|your_facebook_activity/messages/inbox/bob_1/message_1.json|Integer(0)",
+            "source|Integer(3)|Integer(2)|eve_2.json|Integer(0)",
+            "source|Integer(4)|Integer(2)|eve_2.json|Integer(2)",
+        ]
+    );
+    assert_eq!(
+        common::query_i64(
+            &db,
+            &format!(
+                "SELECT COUNT(*) FROM message_source WHERE archive_path = '{}'",
+                zips[1].display()
+            )
+        ),
+        2
+    );
+
+    assert_eq!(
+        MessageDb::open(&db).unwrap().message_source(3).unwrap(),
+        Some(MessageSource {
+            export_id: 2,
+            archive_path: zips[1].display().to_string(),
+            entry_name: "eve_2.json".to_string(),
+            message_index: 0,
+        })
+    );
+    assert_eq!(
+        message_source_json(&db, 2).unwrap(),
+        Some(json!({"sender_name": "Bob", "timestamp_ms": 1700000003000i64, "content": "later"}))
+    );
+    assert_eq!(
+        message_source_json(&db, 4).unwrap(),
+        Some(json!({"senderName": "Ann", "timestamp": 1700000012, "text": "hello"}))
+    );
+}
+
+#[test]
+fn sources_are_only_recorded_on_request() {
+    let db = common::fresh_db("source_off");
+    import_messenger_exports_with_options(
+        export_zips("source_off"),
+        &db,
+        &ImportOptions {
+            lenient: true,
+            ..Default::default()
+        },
+    )
+    .unwrap();
+    assert_eq!(common::query_i64(&db, "SELECT COUNT(*) FROM message"), 4);
+    assert_eq!(
+        common::query_i64(&db, "SELECT COUNT(*) FROM message_source"),
+        0
+    );
+    assert_eq!(message_source_json(&db, 1).unwrap(), None);
+}
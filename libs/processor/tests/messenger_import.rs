@@ -0,0 +1,105 @@
+mod common;
+
+use processor::error::ImportErrorKind;
+use processor::importers::messenger::{
+    import_messenger_exports, import_messenger_exports_with_options, ImportOptions,
+};
+
+const GOOD_THREAD: &str = r#"{
+  "participants": [{"name": "Alice"}, {"name": "Bob"}],
+  "messages": [
+    {"sender_name": "Bob", "timestamp_ms": 1700000002000, "content": "second"},
+    {"sender_name": "Alice", "timestamp_ms": 1700000001000, "content": "first",
+     "is_geoblocked_for_viewer": false}
+  ],
+  "title": "Bob",
+  "is_still_participant": true,
+  "thread_path": "inbox/bob_123"
+}"#;
+
+// One message has `timestamp_ms` as a string, which no longer matches the schema.
+const THREAD_WITH_BAD_MESSAGE: &str = r#"{
+  "participants": [{"name": "Alice"}, {"name": "Carol"}],
+  "messages": [
+    {"sender_name": "Carol", "timestamp_ms": "yesterday", "content": "broken"},
+    {"sender_name": "Alice", "timestamp_ms": 1700000003000, "content": "kept"}
+  ],
+  "title": "Carol",
+  "thread_path": "inbox/carol_456"
+}"#;
+
+const TRUNCATED_THREAD: &str = r#"{"participants": [{"name": "Alice"}], "messages": ["#;
+
+fn fixture_zip(name: &str) -> std::path::PathBuf {
+    common::write_zip(
+        name,
+        &[
+            (
+                "your_facebook_activity/messages/inbox/bob_123/message_1.json",
+                GOOD_THREAD.as_bytes(),
+            ),
+            (
+                "your_facebook_activity/messages/inbox/carol_456/message_1.json",
+                THREAD_WITH_BAD_MESSAGE.as_bytes(),
+            ),
+            (
+                "your_facebook_activity/messages/inbox/dave_789/message_1.json",
+                TRUNCATED_THREAD.as_bytes(),
+            ),
+        ],
+    )
+}
+
+#[test]
+fn strict_import_reports_schema_mismatch_with_path() {
+    let db = common::fresh_db("strict_schema_mismatch");
+    let zip = fixture_zip("strict_schema_mismatch");
+
+    let err = import_messenger_exports(vec![zip], &db).expect_err("strict import must fail");
+    assert_eq!(err.kind(), ImportErrorKind::SchemaMismatch);
+    assert!(
+        err.to_string().contains("carol_456/message_1.json"),
+        "{}",
+        err
+    );
+    assert_eq!(common::query_i64(&db, "SELECT COUNT(*) FROM message"), 0);
+}
+
+#[test]
+fn lenient_import_quarantines_bad_threads_and_messages() {
+    let db = common::fresh_db("lenient_quarantine");
+    let zip = fixture_zip("lenient_quarantine");
+
+    let options = ImportOptions {
+        lenient: true,
+        ..Default::default()
+    };
+    let report = import_messenger_exports_with_options(vec![zip], &db, &options)
+        .expect("lenient import succeeds");
+
+    assert_eq!(report.export_ids.len(), 1);
+    assert_eq!(report.skipped.len(), 2, "{:?}", report.skipped);
+
+    let bad_message = report
+        .skipped
+        .iter()
+        .find(|s| s.path.contains("carol_456"))
+        .expect("bad message recorded");
+    assert_eq!(bad_message.message_index, Some(0));
+    assert_eq!(bad_message.error.kind(), ImportErrorKind::SchemaMismatch);
+
+    let bad_thread = report
+        .skipped
+        .iter()
+        .find(|s| s.path.contains("dave_789"))
+        .expect("truncated thread recorded");
+    assert_eq!(bad_thread.message_index, None);
+    assert_eq!(bad_thread.error.kind(), ImportErrorKind::JsonParse);
+
+    // Two messages from Bob's thread plus the surviving one from Carol's.
+    assert_eq!(common::query_i64(&db, "SELECT COUNT(*) FROM message"), 3);
+    assert_eq!(
+        common::query_i64(&db, "SELECT COUNT(*) FROM conversation"),
+        2
+    );
+}
@@ -0,0 +1,113 @@
+mod common;
+
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use processor::error::ImportError;
+use processor::importers::messenger::{
+    import_messenger_exports_with_control, import_messenger_exports_with_options, ImportOptions,
+};
+use processor::progress::{CancellationToken, ImportPhase, ProgressSink, ProgressSnapshot};
+
+fn fb_thread(i: usize) -> String {
+    format!(
+        r#"{{"participants":[{{"name":"Alice"}},{{"name":"Friend {i}"}}],
+  "messages":[
+    {{"sender_name":"Friend {i}","timestamp_ms":{t2},"content":"reply {i}",
+      "reactions":[{{"reaction":"x","actor":"Alice"}}]}},
+    {{"sender_name":"Alice","timestamp_ms":{t1},"content":"hello {i}"}}],
+  "title":"Friend {i}","thread_path":"inbox/friend_{i}"}}"#,
+        i = i,
+        t1 = 1_700_000_000_000u64 + i as u64 * 10_000,
+        t2 = 1_700_000_005_000u64 + i as u64 * 10_000,
+    )
+}
+
+// One broken thread in the middle exercises lenient skipping order.
+fn fixture_zips(name: &str) -> Vec<PathBuf> {
+    let threads: Vec<(String, String)> = (0..12)
+        .map(|i| {
+            let body = if i == 5 {
+                r#"{"participants":[{"name":"Alice"}],"messages":["#.to_string()
+            } else {
+                fb_thread(i)
+            };
+            (
+                format!(
+                    "your_facebook_activity/messages/inbox/friend_{}/message_1.json",
+                    i
+                ),
+                body,
+            )
+        })
+        .collect();
+    let entries: Vec<(&str, &[u8])> = threads
+        .iter()
+        .map(|(path, body)| (path.as_str(), body.as_bytes()))
+        .collect();
+    let e2e = r#"{"participants":["Alice","Bob"],"threadName":"Bob_1","messages":[
+        {"senderName":"Bob","timestamp":1700000001,"text":"e2e"}]}"#;
+    vec![
+        common::write_zip(&format!("{}_fb", name), &entries),
+        common::write_zip(
+            &format!("{}_e2e", name),
+            &[
+                ("bob_1.json", e2e.as_bytes()),
+                ("media/clip.mp4", b"not a real video"),
+            ],
+        ),
+    ]
+}
+
+fn import(name: &str, parse_threads: usize) -> Vec<String> {
+    let db = common::fresh_db(name);
+    let options = ImportOptions {
+        lenient: true,
+        parse_threads,
+        ..Default::default()
+    };
+    let report =
+        import_messenger_exports_with_options(fixture_zips(name), &db, &options).expect("import");
+    assert_eq!(report.skipped.len(), 1);
+    common::dump_rows(&db)
+}
+
+#[test]
+fn parallel_import_matches_sequential_import() {
+    let sequential = import("parallel_equivalence_sequential", 1);
+    let parallel = import("parallel_equivalence_parallel", 4);
+
+    assert!(sequential.iter().any(|r| r.contains("Friend 11")));
+    assert_eq!(parallel, sequential);
+}
+
+/// Cancels once a few threads have been written.
+struct CancelMidway(CancellationToken);
+
+impl ProgressSink for CancelMidway {
+    fn report(&self, snapshot: &ProgressSnapshot) {
+        if snapshot.phase == ImportPhase::Insert && snapshot.items_processed == 3 {
+            self.0.cancel();
+        }
+    }
+}
+
+#[test]
+fn parallel_import_stops_on_cancel() {
+    let db = common::fresh_db("parallel_cancel");
+    let cancel = CancellationToken::new();
+    let options = ImportOptions {
+        parse_threads: 4,
+        ..Default::default()
+    };
+    let result = import_messenger_exports_with_control(
+        fixture_zips("parallel_cancel"),
+        &db,
+        &options,
+        &cancel,
+        Arc::new(CancelMidway(cancel.clone())),
+    );
+
+    assert_eq!(result, Err(ImportError::Cancelled));
+    assert_eq!(common::query_i64(&db, "SELECT COUNT(*) FROM message"), 0);
+}
@@ -0,0 +1,103 @@
+mod common;
+
+use std::ffi::CStr;
+use std::sync::Mutex;
+
+use processor::importers::messenger::import_messenger_exports;
+use processor::progress::{set_progress_event_callback, ImportPhase, ProgressEvent};
+
+#[derive(Debug, Clone)]
+struct Seen {
+    phase: ImportPhase,
+    current_file: Option<String>,
+    thread_name: Option<String>,
+    items_processed: u32,
+    items_total: u32,
+    bytes_processed: u64,
+    bytes_total: u64,
+}
+
+static EVENTS: Mutex<Vec<Seen>> = Mutex::new(Vec::new());
+
+unsafe extern "C" fn record(event: *const ProgressEvent) {
+    let event = &*event;
+    let text = |p: *const std::os::raw::c_char| {
+        (!p.is_null()).then(|| CStr::from_ptr(p).to_string_lossy().into_owned())
+    };
+    EVENTS.lock().unwrap().push(Seen {
+        phase: event.phase,
+        current_file: text(event.current_file),
+        thread_name: text(event.thread_name),
+        items_processed: event.items_processed,
+        items_total: event.items_total,
+        bytes_processed: event.bytes_processed,
+        bytes_total: event.bytes_total,
+    });
+}
+
+const THREAD_A: &str = r#"{"participants":[{"name":"Alice"},{"name":"Bob"}],
+  "messages":[{"sender_name":"Bob","timestamp_ms":1700000000000,"content":"hi"}],
+  "title":"Bob","thread_path":"inbox/bob_1"}"#;
+const THREAD_B: &str = r#"{"participants":[{"name":"Alice"},{"name":"Carol"}],
+  "messages":[{"sender_name":"Carol","timestamp_ms":1700000001000,"content":"hey"}],
+  "title":"Carol","thread_path":"inbox/carol_2"}"#;
+
+#[test]
+fn events_walk_phases_with_fixed_totals() {
+    let zip = common::write_zip(
+        "progress_events",
+        &[
+            (
+                "your_facebook_activity/messages/inbox/bob_1/message_1.json",
+                THREAD_A.as_bytes(),
+            ),
+            (
+                "your_facebook_activity/messages/inbox/carol_2/message_1.json",
+                THREAD_B.as_bytes(),
+            ),
+        ],
+    );
+    let db = common::fresh_db("progress_events");
+
+    set_progress_event_callback(Some(record));
+    import_messenger_exports(vec![zip], &db).expect("import");
+    set_progress_event_callback(None);
+
+    let events = EVENTS.lock().unwrap().clone();
+    let expected_bytes = (THREAD_A.len() + THREAD_B.len()) as u64;
+
+    let order = |phase: ImportPhase| phase as u8;
+    let mut last_phase = 0;
+    let mut last_processed = (0, 0);
+    for e in events.iter().filter(|e| e.items_total > 0) {
+        // Totals are known once scanning ends and never change afterwards.
+        assert_eq!(e.items_total, 2);
+        assert_eq!(e.bytes_total, expected_bytes);
+        assert!((e.items_processed, e.bytes_processed) >= last_processed);
+        last_processed = (e.items_processed, e.bytes_processed);
+        // Parse/insert alternate per thread; otherwise phases only move forward.
+        if !matches!(e.phase, ImportPhase::Parse | ImportPhase::Insert) {
+            assert!(
+                order(e.phase) >= last_phase,
+                "phase went backwards: {:?}",
+                e
+            );
+        }
+        last_phase = last_phase.max(order(e.phase));
+    }
+
+    let last = events.last().expect("events");
+    assert_eq!(last.phase, ImportPhase::PostProcess);
+    assert_eq!(
+        (last.items_processed, last.bytes_processed),
+        (2, expected_bytes)
+    );
+
+    assert!(events.iter().any(|e| e.phase == ImportPhase::Index));
+    assert!(events.iter().any(|e| e.phase == ImportPhase::Parse
+        && e.current_file.as_deref()
+            == Some("your_facebook_activity/messages/inbox/carol_2/message_1.json")));
+    assert!(events
+        .iter()
+        .any(|e| e.phase == ImportPhase::Insert && e.thread_name.as_deref() == Some("Carol")));
+}
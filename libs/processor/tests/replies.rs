@@ -0,0 +1,105 @@
+mod common;
+
+use std::collections::HashMap;
+
+use processor::analytics::{reply_chain_depths, reply_pairs, StatsFilter};
+use processor::database::{ConversationMeta, ConversationType, MessageDb, MessageRecord};
+
+/// A group of Ann, Dan and Eve with two reply chains and a message that replies to nothing
+/// here, then a DM between Ann and Eve whose reply was written before its target.
+fn write_replies(name: &str) -> std::path::PathBuf {
+    let path = common::fresh_db(name);
+    let mut db = MessageDb::open(&path).unwrap();
+    let mut batch = db.begin_write().unwrap();
+    let export_id = batch.insert_export("messenger:e2e", None, None).unwrap();
+    let mut canonical = HashMap::new();
+    for name in ["Ann", "Dan", "Eve"] {
+        let id = batch.insert_canonical_person(Some(name), None).unwrap();
+        canonical.insert(name, id);
+    }
+    let mut conversation = |ctype, title: &str, names: &[&'static str]| {
+        let canon = batch
+            .insert_canonical_conversation(ctype, Some(title))
+            .unwrap();
+        let conv = batch
+            .insert_conversation(
+                ctype,
+                Some(title),
+                &ConversationMeta::default(),
+                export_id,
+                canon,
+            )
+            .unwrap();
+        names
+            .iter()
+            .map(|&name| {
+                let person = batch
+                    .insert_person(conv, Some(name), None, canonical[name])
+                    .unwrap();
+                (name, person)
+            })
+            .collect::<HashMap<_, _>>()
+    };
+    let group = conversation(ConversationType::Group, "Dan", &["Ann", "Dan", "Eve"]);
+    let dm = conversation(ConversationType::DM, "Eve", &["Ann", "Eve"]);
+
+    let message = |sender_id, sent_at, reply_to_message_id| MessageRecord {
+        sender_id,
+        sent_at,
+        reply_to_message_id,
+        ..Default::default()
+    };
+    batch
+        .insert_messages_bulk(&[
+            message(group["Ann"], 1_700_000_001, None),
+            message(group["Dan"], 1_700_000_002, Some(1)),
+            message(group["Eve"], 1_700_000_003, Some(2)),
+            message(group["Ann"], 1_700_000_004, Some(3)),
+            message(group["Dan"], 1_700_000_005, None),
+            message(group["Ann"], 1_700_000_006, Some(5)),
+            message(group["Dan"], 1_700_000_007, Some(6)),
+            message(group["Eve"], 1_700_000_008, None),
+            message(dm["Eve"], 1_700_000_020, Some(10)),
+            message(dm["Ann"], 1_700_000_010, None),
+        ])
+        .unwrap();
+    batch.commit().unwrap();
+    path
+}
+
+#[test]
+fn reply_pairs_and_chain_depths() {
+    let db_path = write_replies("replies_stats");
+    let db = MessageDb::open(&db_path).unwrap();
+
+    let pairs: Vec<(String, String, i64)> = reply_pairs(&db, &StatsFilter::default(), 10)
+        .unwrap()
+        .into_iter()
+        .map(|p| (p.from_name.unwrap(), p.to_name.unwrap(), p.replies))
+        .collect();
+    let p = |from: &str, to: &str, replies| (from.to_string(), to.to_string(), replies);
+    assert_eq!(
+        pairs,
+        [
+            p("Dan", "Ann", 2),
+            p("Ann", "Dan", 1),
+            p("Ann", "Eve", 1),
+            p("Eve", "Ann", 1),
+            p("Eve", "Dan", 1),
+        ]
+    );
+
+    let depths: Vec<(i64, i64)> = reply_chain_depths(&db, &StatsFilter::default())
+        .unwrap()
+        .into_iter()
+        .map(|d| (d.depth, d.chains))
+        .collect();
+    assert_eq!(depths, [(1, 1), (2, 1), (3, 1)]);
+
+    let later = StatsFilter {
+        since: Some(1_700_000_005),
+        ..Default::default()
+    };
+    let depths = reply_chain_depths(&db, &later).unwrap();
+    assert_eq!(depths.iter().map(|d| d.depth).collect::<Vec<_>>(), [1, 2]);
+}
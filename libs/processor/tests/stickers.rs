@@ -0,0 +1,118 @@
+mod common;
+
+use processor::analytics::{media_sent_by_person, StatsFilter};
+use processor::database::MessageDb;
+use processor::importers::messenger::utils::{is_like_sticker, is_sticker_uri};
+use processor::importers::messenger::{import_messenger_exports_with_options, ImportOptions};
+
+const LIKE: &str = "your_facebook_activity/messages/stickers_used/\
+                    39178562_1505197616293642_5411344281094848512_n_369239263222822.png";
+const CAT: &str = "your_facebook_activity/messages/stickers_used/851582_1234567890_n.png";
+
+#[test]
+fn stickers_are_recognized_by_path() {
+    assert!(is_sticker_uri(CAT));
+    assert!(is_sticker_uri("media/Stickers/abc.webp"));
+    assert!(is_sticker_uri("media/sticker_42.png"));
+    assert!(!is_sticker_uri("media/photo.jpg"));
+    assert!(!is_sticker_uri("messages/inbox/stick_1/photos/a.jpg"));
+
+    assert!(is_like_sticker(LIKE));
+    assert!(is_like_sticker(
+        "messages/stickers_used/851557_369239266556155_759568595_n.png"
+    ));
+    assert!(!is_like_sticker(CAT));
+}
+
+fn export_zips(name: &str) -> Vec<std::path::PathBuf> {
+    let dir = "your_facebook_activity/messages/inbox/bob_1";
+    // Newest first, as Facebook writes them.
+    let fb = format!(
+        r#"{{"participants":[{{"name":"Alice"}},{{"name":"Bob"}}],
+  "messages":[
+    {{"sender_name":"Bob","timestamp_ms":1700000003000,"sticker":{{"uri":"{CAT}"}}}},
+    {{"sender_name":"Bob","timestamp_ms":1700000002000,"sticker":{{"uri":"{LIKE}"}}}},
+    {{"sender_name":"Alice","timestamp_ms":1700000001000,"photos":[{{"uri":"{dir}/photos/a.jpg"}}]}}],
+  "title":"Bob","thread_path":"inbox/bob_1"}}"#
+    );
+    let e2e = r#"{"participants":["Alice","Carol"],"threadName":"Carol_2","messages":[
+        {"senderName":"Carol","timestamp":1700000004,"type":"sticker",
+         "media":[{"uri":"media/abc.webp"}]},
+        {"senderName":"Carol","timestamp":1700000005,"media":[{"uri":"media/stickers/x.png"}]},
+        {"senderName":"Alice","timestamp":1700000006,"media":[{"uri":"media/p.jpg"}]}]}"#;
+    let fb_json = format!("{}/message_1.json", dir);
+    vec![
+        common::write_zip(
+            &format!("{}_fb", name),
+            &[(fb_json.as_str(), fb.as_bytes())],
+        ),
+        common::write_zip(
+            &format!("{}_e2e", name),
+            &[("carol_2.json", e2e.as_bytes()), ("media/p.jpg", b"photo")],
+        ),
+    ]
+}
+
+#[test]
+fn stickers_are_imported_apart_from_photos() {
+    for parse_threads in [1, 4] {
+        let name = format!("stickers_import_{}", parse_threads);
+        let db_path = common::fresh_db(&name);
+        let options = ImportOptions {
+            parse_threads,
+            ..Default::default()
+        };
+        let report =
+            import_messenger_exports_with_options(export_zips(&name), &db_path, &options).unwrap();
+
+        let rows: Vec<String> = common::dump_rows(&db_path)
+            .into_iter()
+            .filter(|r| r.starts_with("image|") || r.starts_with("sticker|"))
+            .map(|r| {
+                let mut fields = r.split('|');
+                let kind = fields.next().unwrap();
+                let message = fields.next().unwrap();
+                let uri = fields.next().unwrap().rsplit('/').next().unwrap();
+                let is_like = if kind == "sticker" {
+                    fields.next_back().unwrap()
+                } else {
+                    "-"
+                };
+                format!("{}|{}|{}|{}", kind, message, uri, is_like)
+            })
+            .collect();
+        assert_eq!(
+            rows,
+            [
+                "image|Integer(1)|a.jpg|-",
+                "image|Integer(6)|p.jpg|-",
+                "sticker|Integer(2)|39178562_1505197616293642_5411344281094848512_n_369239263222822.png|Integer(1)",
+                "sticker|Integer(3)|851582_1234567890_n.png|Integer(0)",
+                "sticker|Integer(4)|abc.webp|Integer(0)",
+                "sticker|Integer(5)|x.png|Integer(0)",
+            ]
+        );
+        let missing_kinds: Vec<&str> = report.missing_media.iter().map(|m| m.kind).collect();
+        assert_eq!(
+            missing_kinds,
+            ["image", "sticker", "sticker", "sticker", "sticker"]
+        );
+
+        let db = MessageDb::open(&db_path).unwrap();
+        let sent: Vec<(String, i64, i64, i64)> =
+            media_sent_by_person(&db, &StatsFilter::default(), 10)
+                .unwrap()
+                .into_iter()
+                .map(|m| (m.name.unwrap(), m.photos, m.stickers, m.like_stickers))
+                .collect();
+        assert_eq!(
+            sent,
+            [
+                ("Bob".to_string(), 0, 2, 1),
+                ("Carol".to_string(), 0, 2, 0),
+                ("Alice".to_string(), 1, 0, 0),
+                ("Alice".to_string(), 1, 0, 0),
+            ]
+        );
+    }
+}
@@ -0,0 +1,144 @@
+mod common;
+
+use std::path::PathBuf;
+
+use processor::importers::messenger::{import_messenger_exports_with_options, ImportOptions};
+
+// Newest first, with the header after the messages, a reactor who is not a participant,
+// an unsent message, a geoblocked one and attachments.
+const FB_THREAD: &str = r#"{
+  "messages": [
+    {"sender_name": "Bob", "timestamp_ms": 1700000005000, "content": "last",
+     "reactions": [{"reaction": "â\u009d¤", "actor": "Eve"}]},
+    {"sender_name": "Alice", "timestamp_ms": 1700000004000, "is_unsent": true},
+    {"sender_name": "Mallory", "timestamp_ms": 1700000003000, "content": "hidden",
+     "is_geoblocked_for_viewer": true},
+    {"sender_name": "Dan", "timestamp_ms": 1700000002500},
+    {"sender_name": "Bob", "timestamp_ms": 1700000002000,
+     "photos": [{"uri": "photos/a.jpg"}, {"uri": "photos/b.jpg"}],
+     "share": {"link": "https://example.com"}},
+    {"sender_name": "Alice", "timestamp_ms": 1700000001000, "content": "first",
+     "reactions": [{"reaction": "x", "actor": "Carol"}, {"reaction": "y", "actor": "Bob"}]}
+  ],
+  "participants": [{"name": "Alice"}, {"name": "Bob"}],
+  "title": "Bob",
+  "thread_path": "inbox/bob_1"
+}"#;
+
+const E2E_THREAD: &str = r#"{
+  "participants": ["Alice", "Bob"],
+  "threadName": "Bob_2",
+  "messages": [
+    {"senderName": "Alice", "timestamp": 1700000001000, "text": "hi",
+     "reactions": [{"actor": "Carol", "reaction": "x"}]},
+    {"senderName": "Bob", "timestamp": 1700000002, "text": "",
+     "media": [{"uri": "media/clip.mp4"}, {"uri": "media/pic.jpg"}]},
+    {"senderName": "Bob", "timestamp": 1700000003, "isUnsent": true}
+  ]
+}"#;
+
+fn fixture_zips(name: &str) -> Vec<PathBuf> {
+    vec![
+        common::write_zip(
+            &format!("{}_fb", name),
+            &[(
+                "your_facebook_activity/messages/inbox/bob_1/message_1.json",
+                FB_THREAD.as_bytes(),
+            )],
+        ),
+        common::write_zip(
+            &format!("{}_e2e", name),
+            &[
+                ("bob_2.json", E2E_THREAD.as_bytes()),
+                ("media/clip.mp4", b"not a real video"),
+            ],
+        ),
+    ]
+}
+
+fn import(name: &str, streaming: bool) -> Vec<String> {
+    let db = common::fresh_db(name);
+    let options = ImportOptions {
+        streaming,
+        ..Default::default()
+    };
+    import_messenger_exports_with_options(fixture_zips(name), &db, &options).expect("import");
+    common::dump_rows(&db)
+}
+
+#[test]
+fn streaming_import_matches_buffered_import() {
+    let buffered = import("streaming_equivalence_buffered", false);
+    let streamed = import("streaming_equivalence_streamed", true);
+
+    assert!(buffered.iter().any(|r| r.contains("Eve")), "{:?}", buffered);
+    assert_eq!(streamed, buffered);
+}
+
+#[test]
+fn lenient_streaming_drops_a_thread_found_truncated_halfway() {
+    // Both truncated files have their header first, so their first message is written
+    // before the truncation is found.
+    let fb_truncated = r#"{"participants":[{"name":"Ann"},{"name":"Cid"}],"messages":[
+        {"sender_name":"Cid","timestamp_ms":1700000009000,"content":"gone"},
+        {"sender_name":"Ann","timestamp_ms":17000"#;
+    let e2e_truncated = r#"{"participants":["Ann","Dee"],"threadName":"Dee_3","messages":[
+        {"senderName":"Dee","timestamp":1700000010,"text":"gone"},
+        {"senderName":"Ann","#;
+    let zips = |name: &str| {
+        let mut zips = fixture_zips(name);
+        zips.push(common::write_zip(
+            &format!("{}_fb_truncated", name),
+            &[(
+                "your_facebook_activity/messages/inbox/cid_3/message_1.json",
+                fb_truncated.as_bytes(),
+            )],
+        ));
+        zips.push(common::write_zip(
+            &format!("{}_e2e_truncated", name),
+            &[
+                ("dee_3.json", e2e_truncated.as_bytes()),
+                ("media/x.jpg", b"x"),
+            ],
+        ));
+        zips
+    };
+    let mut dumps = Vec::new();
+    for (name, streaming) in [
+        ("streaming_truncated_buffered", false),
+        ("streaming_truncated_streamed", true),
+    ] {
+        let db = common::fresh_db(name);
+        let options = ImportOptions {
+            lenient: true,
+            streaming,
+            ..Default::default()
+        };
+        let report =
+            import_messenger_exports_with_options(zips(name), &db, &options).expect("import");
+        let skipped: Vec<_> = report
+            .skipped
+            .iter()
+            .map(|s| (s.path.clone(), s.message_index))
+            .collect();
+        assert_eq!(
+            skipped,
+            [
+                (
+                    "your_facebook_activity/messages/inbox/cid_3/message_1.json".to_string(),
+                    None
+                ),
+                ("dee_3.json".to_string(), None),
+            ],
+            "{}",
+            name
+        );
+        dumps.push(common::dump_rows(&db));
+    }
+    assert!(
+        !dumps[0].iter().any(|r| r.contains("gone")),
+        "{:?}",
+        dumps[0]
+    );
+    assert_eq!(dumps[1], dumps[0]);
+}
@@ -0,0 +1,127 @@
+mod common;
+
+use processor::analytics::{media_sent_by_person, StatsFilter};
+use processor::database::MessageDb;
+use processor::importers::messenger::{import_messenger_exports_with_options, ImportOptions};
+use processor::utils::links::map_coordinates;
+
+#[test]
+fn coordinates_are_read_from_map_links() {
+    for (url, expected) in [
+        (
+            "https://www.bing.com/maps/default.aspx?v=2&pc=FACEBK&mid=8100&where1=52.2297%2C+21.0122&FORM=FBKPL1&mkt=en-US",
+            Some((52.2297, 21.0122)),
+        ),
+        (
+            "https://maps.google.com/?q=-33.8568,151.2153",
+            Some((-33.8568, 151.2153)),
+        ),
+        (
+            "https://www.google.com/maps/place/Sydney/@-33.8688,151.2093,12z/data=x",
+            Some((-33.8688, 151.2093)),
+        ),
+        (
+            "https://www.bing.com/maps?cp=47.6~-122.3&lvl=11",
+            Some((47.6, -122.3)),
+        ),
+        ("https://maps.apple.com/?ll=48.8584,2.2945", Some((48.8584, 2.2945))),
+        // Not a map, no coordinates, or out of range.
+        ("https://example.com/?q=52.2,21.0", None),
+        ("https://maps.google.com/?q=Warsaw", None),
+        ("https://maps.google.com/?q=95.0,21.0", None),
+    ] {
+        assert_eq!(map_coordinates(url), expected, "{}", url);
+    }
+}
+
+/// A Facebook export with a poll notice, a shared map location and a poll notice from
+/// someone else than the sender, then an E2E export with a poll, a live location and contacts.
+fn export_zips(name: &str) -> Vec<std::path::PathBuf> {
+    let fb = r#"{"participants":[{"name":"Ann"},{"name":"Bob"}],
+  "messages":[
+    {"sender_name":"Bob","timestamp_ms":1700000003000,"content":"Bob created a poll: Pizza?."},
+    {"sender_name":"Ann","timestamp_ms":1700000002000,"content":"Ann sent a location.",
+     "share":{"link":"https://www.bing.com/maps/default.aspx?v=2&pc=FACEBK&mid=8100&where1=52.2297%2C+21.0122&FORM=FBKPL1","share_text":"Old Town"}},
+    {"sender_name":"Ann","timestamp_ms":1700000001000,"content":"Carol created a poll: not really"}],
+  "title":"Bob","thread_path":"inbox/bob_1"}"#;
+    let e2e = r#"{"participants":["Ann","Cat","Dan"],"threadName":"Trip_2","messages":[
+        {"senderName":"Cat","timestamp":1700000010,"text":"Where to?",
+         "poll":{"options":[
+           {"text":"Beach","voters":["Ann","Dan"]},
+           {"text":"Hills","voters":["Cat"],"voteCount":3},
+           {"text":"Home"}]}},
+        {"senderName":"Dan","timestamp":1700000011,"text":"",
+         "location":{"latitude":50.06,"longitude":19.94,"address":"Kraków","isLive":true}},
+        {"senderName":"Ann","timestamp":1700000012,"text":"call him",
+         "contacts":[{"name":"Eve","phoneNumber":"+48 600 100 200"},{"email":"x@example.com"}]}]}"#;
+    vec![
+        common::write_zip(
+            &format!("{}_fb", name),
+            &[(
+                "your_facebook_activity/messages/inbox/bob_1/message_1.json",
+                fb.as_bytes(),
+            )],
+        ),
+        common::write_zip(
+            &format!("{}_e2e", name),
+            &[("trip_2.json", e2e.as_bytes()), ("media/x.jpg", b"x")],
+        ),
+    ]
+}
+
+#[test]
+fn polls_locations_and_contacts_are_imported() {
+    let (db, _) =
+        common::import_in_every_mode("structured", &ImportOptions::default(), export_zips);
+    let rows: Vec<String> = common::dump_rows(&db)
+        .into_iter()
+        .filter(|r| {
+            ["text|", "link|", "poll|", "location|", "contact|"]
+                .iter()
+                .any(|p| r.starts_with(p))
+        })
+        .collect();
+    assert_eq!(
+        rows,
+        [
+            "text|Integer(1)|Carol created a poll: not really",
+            "text|Integer(2)|Ann sent a location.",
+            "text|Integer(6)|call him",
+            "poll|Integer(3)|Pizza?|Null|Null|Null",
+            "poll|Integer(4)|Where to?|Beach|Integer(2)|3,5",
+            "poll|Integer(4)|Where to?|Hills|Integer(3)|4",
+            "poll|Integer(4)|Where to?|Home|Integer(0)|Null",
+            "location|Integer(2)|Real(52.2297)|Real(21.0122)|Old Town|Integer(0)",
+            "location|Integer(5)|Real(50.06)|Real(19.94)|Kraków|Integer(1)",
+            "contact|Integer(6)|Eve|+48 600 100 200|Null",
+            "contact|Integer(6)|Null|Null|x@example.com",
+        ]
+    );
+}
+
+#[test]
+fn structured_attachments_are_counted_apart_from_text() {
+    let db_path = common::fresh_db("structured_stats");
+    import_messenger_exports_with_options(
+        export_zips("structured_stats"),
+        &db_path,
+        &ImportOptions::default(),
+    )
+    .unwrap();
+    let db = MessageDb::open(&db_path).unwrap();
+    let sent: Vec<(String, i64, i64, i64)> = media_sent_by_person(&db, &StatsFilter::default(), 10)
+        .unwrap()
+        .into_iter()
+        .map(|m| (m.name.unwrap(), m.polls, m.locations, m.contacts))
+        .collect();
+    assert_eq!(
+        sent,
+        [
+            ("Ann".to_string(), 0, 0, 2),
+            ("Ann".to_string(), 0, 1, 0),
+            ("Bob".to_string(), 1, 0, 0),
+            ("Cat".to_string(), 1, 0, 0),
+            ("Dan".to_string(), 0, 1, 0),
+        ]
+    );
+}
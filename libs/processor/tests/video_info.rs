@@ -0,0 +1,170 @@
+mod common;
+
+use std::io::Cursor;
+use std::path::PathBuf;
+
+use processor::utils::video::{detect_video_info, detect_video_info_seekable, VideoInfo};
+
+// Fixtures in tests/test-video carry real box structures around zeroed sample data:
+// - portrait.mp4: H.264 1920x1080 rotated by 90 degrees, 12.345 s, `moov` after a 200 kB
+//   `mdat`, audio track after the video track
+// - clip.mov: QuickTime, audio track first, then HEVC 1280x720 with a version 1 `tkhd`, 5 s
+const FIXTURES: &[(&str, VideoInfo)] = &[
+    (
+        "portrait.mp4",
+        VideoInfo {
+            duration_ms: 12345,
+            width: Some(1080),
+            height: Some(1920),
+            codec: Some("h264"),
+        },
+    ),
+    (
+        "clip.mov",
+        VideoInfo {
+            duration_ms: 5000,
+            width: Some(1280),
+            height: Some(720),
+            codec: Some("hevc"),
+        },
+    ),
+];
+
+fn fixture(name: &str) -> PathBuf {
+    [env!("CARGO_MANIFEST_DIR"), "tests", "test-video", name]
+        .iter()
+        .collect()
+}
+
+#[test]
+fn video_info_from_files() {
+    for (name, expected) in FIXTURES {
+        let mut f = std::fs::File::open(fixture(name)).unwrap();
+        assert_eq!(
+            detect_video_info_seekable(&mut f),
+            Some(*expected),
+            "{}",
+            name
+        );
+    }
+}
+
+#[test]
+fn video_info_from_zip_entries() {
+    let contents: Vec<Vec<u8>> = FIXTURES
+        .iter()
+        .map(|(name, _)| std::fs::read(fixture(name)).unwrap())
+        .collect();
+    let entries: Vec<(&str, &[u8])> = FIXTURES
+        .iter()
+        .map(|(name, _)| *name)
+        .zip(contents.iter().map(Vec::as_slice))
+        .collect();
+    let zip_path = common::write_zip("video_info", &entries);
+    let mut archive = zip::ZipArchive::new(std::fs::File::open(&zip_path).unwrap()).unwrap();
+    for (name, expected) in FIXTURES {
+        let mut entry = archive.by_name(name).unwrap();
+        assert_eq!(detect_video_info(&mut entry), Some(*expected), "{}", name);
+    }
+}
+
+#[test]
+fn video_info_without_video_track_or_container() {
+    // Audio-only MP4: the duration is known, the rest is not.
+    let path: PathBuf = [
+        env!("CARGO_MANIFEST_DIR"),
+        "tests",
+        "test-audio",
+        "messenger",
+        "audioclip15773984540003506_573016540158892.mp4",
+    ]
+    .iter()
+    .collect();
+    let mut f = std::fs::File::open(path).unwrap();
+    assert_eq!(
+        detect_video_info_seekable(&mut f),
+        Some(VideoInfo {
+            duration_ms: 3506,
+            ..VideoInfo::default()
+        })
+    );
+
+    let webm = [
+        env!("CARGO_MANIFEST_DIR"),
+        "tests",
+        "test-audio",
+        "formats",
+        "voice.webm",
+    ]
+    .iter()
+    .collect::<PathBuf>();
+    let mut f = std::fs::File::open(webm).unwrap();
+    assert_eq!(detect_video_info_seekable(&mut f), None);
+    assert_eq!(
+        detect_video_info(&mut Cursor::new(b"not a real video".to_vec())),
+        None
+    );
+}
+
+#[test]
+fn imported_videos_store_duration_size_and_codec() {
+    let portrait = std::fs::read(fixture("portrait.mp4")).unwrap();
+    let mov = std::fs::read(fixture("clip.mov")).unwrap();
+    let fb_uri = "your_facebook_activity/messages/inbox/bob_1/videos/portrait.mp4";
+    let fb = format!(
+        r#"{{"participants":[{{"name":"Alice"}},{{"name":"Bob"}}],
+  "messages":[{{"sender_name":"Bob","timestamp_ms":1700000002000,"videos":[{{"uri":"{}"}}]}}],
+  "title":"Bob","thread_path":"inbox/bob_1"}}"#,
+        fb_uri
+    );
+    let e2e = r#"{"participants":["Alice","Bob"],"threadName":"Bob_2","messages":[
+        {"senderName":"Bob","timestamp":1700000003,"text":"",
+         "media":[{"uri":"media/clip.mov"},{"uri":"media/broken.mp4"}]}]}"#;
+    let zips = vec![
+        common::write_zip(
+            "imported_videos_fb",
+            &[
+                (
+                    "your_facebook_activity/messages/inbox/bob_1/message_1.json",
+                    fb.as_bytes(),
+                ),
+                (fb_uri, &portrait),
+            ],
+        ),
+        common::write_zip(
+            "imported_videos_e2e",
+            &[
+                ("bob_2.json", e2e.as_bytes()),
+                ("media/clip.mov", &mov),
+                ("media/broken.mp4", b"not a real video"),
+            ],
+        ),
+    ];
+    for parse_threads in [1, 4] {
+        let db = common::fresh_db(&format!("imported_videos_{}", parse_threads));
+        let options = processor::importers::messenger::ImportOptions {
+            parse_threads,
+            ..Default::default()
+        };
+        processor::importers::messenger::import_messenger_exports_with_options(
+            zips.clone(),
+            &db,
+            &options,
+        )
+        .expect("import");
+
+        let rows: Vec<String> = common::dump_rows(&db)
+            .into_iter()
+            .filter(|r| r.starts_with("video|"))
+            .map(|r| r.splitn(3, '|').nth(2).unwrap().to_string())
+            .collect();
+        assert_eq!(
+            rows,
+            [
+                format!("{}|Integer(12345)|Integer(1080)|Integer(1920)|h264", fb_uri),
+                "media/clip.mov|Integer(5000)|Integer(1280)|Integer(720)|hevc".to_string(),
+                "media/broken.mp4|Null|Null|Null|Null".to_string(),
+            ]
+        );
+    }
+}